@@ -5,6 +5,7 @@ use libpaillier::unknown_order::BigNumber;
 use num_bigint::BigInt;
 use openssl::bn::{BigNum, BigNumContext, BigNumRef};
 use rug::{Complete, Integer};
+use tss_ecdsa::benchmarks::PaillierKeyPair;
 
 const PRIME_SIZE: usize = 1024;
 
@@ -13,7 +14,7 @@ criterion_group! {
     config = Criterion::default().sample_size(10);
     targets = compare_prime_gen
 }
-criterion_group!(benches, compare_modpow);
+criterion_group!(benches, compare_modpow, compare_paillier);
 criterion_main!(benches, slow_primegen);
 
 fn compare_prime_gen(c: &mut Criterion) {
@@ -105,10 +106,7 @@ fn modulus_string() -> String {
     "aae7348ef0b743475d5a64a4c27e0c717771a40da32eaabf729d5d3fbb794ebf3bbb5096474e8ca2f0d64bb0481d855f12aad504e94e39e6fc4c1a2b7a4c42649fb7b2a02d245713c289f287b6dda8396d2a8f8f3e02d7ee14b437e3ee5e450becf6f8f38c7c5a5cfffc4de26028f44ebaf63c8a78dd7045d0473fac663e66a0552ca6edc94153793069a5a53e39a6ac49bb08b74fa2e0ba83a6546877e745daf8eb30b87281c8c9ceecfcf3cd271eb6d000567fecada07729a296e9ec078c711f16f5f31c679b60e4f9d78a385b679cbcc6e93d52f656356b9d293d4e7b1f7c80e0444d18a2c150d5a358c7a01ca413d1a0471c737257d84d213d91ab24bd4d"
         .into()
     /*
-       [
-           "C3538E1FE5ED23EC1AFFB9A1FC9F8891EC5CD38062393A94789718B81CF6A1D1FE61B28197525D51C1767749D3488132239CCDCC3383DD9B050AC9E544964B8EA3895149369D4CA6226BD4AE618EB746D4EF4A7477EE08CBA9BE73E7887261678D27519BEE2237E128591DAD4E2EAAE43CF0802E3DDE681BAF7F78EC6BC15393",
-           "DFFD80ACE4F8800EC5C4544855DEE54317BCDFF3324BA93BFAA95AF94F8A7922E553C6D0CE1E23A0C05702047F687379208496B66023E6BD4E547CD0607D2B080C5C75C133DD16E93315E28AAD0438737C53D23C78D1CBED65C0B105EE81999589CA1A61252EF47E48E550D920B3E24DAB6E5E6D0286298BF3910EF31335F79F"
-       ]
+       factor_strings()
            .iter()
            .map(|s| BigNumber::from_slice(hex::decode(s).unwrap()))
            .reduce(|p1, p2| p1 * p2)
@@ -118,6 +116,64 @@ fn modulus_string() -> String {
     */
 }
 
+/// Get hex strings of the two safe primes whose product is `modulus_string()`.
+fn factor_strings() -> [&'static str; 2] {
+    [
+        "C3538E1FE5ED23EC1AFFB9A1FC9F8891EC5CD38062393A94789718B81CF6A1D1FE61B28197525D51C1767749D3488132239CCDCC3383DD9B050AC9E544964B8EA3895149369D4CA6226BD4AE618EB746D4EF4A7477EE08CBA9BE73E7887261678D27519BEE2237E128591DAD4E2EAAE43CF0802E3DDE681BAF7F78EC6BC15393",
+        "DFFD80ACE4F8800EC5C4544855DEE54317BCDFF3324BA93BFAA95AF94F8A7922E553C6D0CE1E23A0C05702047F687379208496B66023E6BD4E547CD0607D2B080C5C75C133DD16E93315E28AAD0438737C53D23C78D1CBED65C0B105EE81999589CA1A61252EF47E48E550D920B3E24DAB6E5E6D0286298BF3910EF31335F79F",
+    ]
+}
+
+/// Compare Paillier encryption under another party's key, which does a modular
+/// exponentiation mod `N²`, against encryption under a party's own key, which
+/// uses the factors `p` and `q` of `N` to work modulo `p²` and `q²` instead.
+/// Likewise, compare libpaillier's decryption against the library's, which
+/// recovers the plaintext modulo `p` and `q` separately.
+///
+/// Each operation goes through the library's own code path, and the results
+/// are checked to agree before they are benchmarked.
+fn compare_paillier(c: &mut Criterion) {
+    let mut rng = rand::thread_rng();
+    let mut plaintext_rng = rand::thread_rng();
+    let [p, q] = factor_strings().map(|s| BigNumber::from_slice(hex::decode(s).unwrap()));
+    let key = PaillierKeyPair::from_primes(&p, &q).unwrap();
+    let libpaillier_key = libpaillier::DecryptionKey::with_primes(&p, &q).unwrap();
+
+    // Sample plaintexts in `[0, N/2)`, so they are valid inputs to encryption
+    // and decrypt to the same value under both decryption methods.
+    let half_n: BigNumber = (key.modulus() - 1) / 2;
+    let mut plaintext = || BigNumber::from_rng(&half_n, &mut plaintext_rng);
+
+    let x = plaintext();
+    let ciphertext = key.encrypt(&mut rng, &x).unwrap();
+    assert_eq!(key.decrypt(&ciphertext).unwrap(), x);
+    let owner_ciphertext = key.encrypt_as_owner(&mut rng, &x).unwrap();
+    assert_eq!(key.decrypt(&owner_ciphertext).unwrap(), x);
+    let libpaillier_ciphertext = BigNumber::from_slice(ciphertext.to_bytes());
+    let decrypt_libpaillier =
+        || BigNumber::from_slice(libpaillier_key.decrypt(&libpaillier_ciphertext).unwrap());
+    assert_eq!(decrypt_libpaillier(), x);
+
+    c.bench_function("paillier encrypt", |b| {
+        b.iter_batched(
+            &mut plaintext,
+            |x| key.encrypt(&mut rng, &x).unwrap(),
+            BatchSize::SmallInput,
+        )
+    });
+    c.bench_function("paillier encrypt with factors", |b| {
+        b.iter_batched(
+            &mut plaintext,
+            |x| key.encrypt_as_owner(&mut rng, &x).unwrap(),
+            BatchSize::SmallInput,
+        )
+    });
+    c.bench_function("paillier decrypt", |b| b.iter(&decrypt_libpaillier));
+    c.bench_function("paillier decrypt with factors", |b| {
+        b.iter(|| key.decrypt(&ciphertext).unwrap())
+    });
+}
+
 fn to_ossl(val: String) -> BigNum {
     BigNum::from_hex_str(&val).unwrap()
 }
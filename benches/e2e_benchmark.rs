@@ -3,9 +3,9 @@ use criterion::{criterion_group, criterion_main, Criterion};
 use rand::{prelude::IteratorRandom, rngs::OsRng, CryptoRng, Rng, RngCore};
 use std::collections::HashMap;
 use tss_ecdsa::{
-    auxinfo::AuxInfoParticipant,
+    auxinfo::{self, AuxInfoParticipant},
     errors::Result,
-    keygen::KeygenParticipant,
+    keygen::{self, KeygenParticipant},
     messages::Message,
     presign::{self, PresignParticipant},
     Identifier, Participant, ParticipantConfig, ParticipantIdentifier, ProtocolParticipant,
@@ -104,25 +104,33 @@ fn run_benchmarks_for_given_size(c: &mut Criterion, num_players: usize) {
 
     // Benchmark keygen
     let keygen_sid = Identifier::random(&mut rng);
-    let keygen_inputs = std::iter::repeat(()).take(num_players).collect::<Vec<_>>();
+    let keygen_inputs = std::iter::repeat(keygen::Input::default())
+        .take(num_players)
+        .collect::<Vec<_>>();
     c.bench_function(&format!("Keygen with {num_players} nodes"), |b| {
         b.iter(|| run_subprotocol::<KeygenParticipant>(keygen_sid, keygen_inputs.clone()))
     });
 
     // Benchmark auxinfo
     let auxinfo_sid = Identifier::random(&mut rng);
-    let auxinfo_inputs = std::iter::repeat(()).take(num_players).collect::<Vec<_>>();
+    let auxinfo_inputs = std::iter::repeat(auxinfo::Input::default())
+        .take(num_players)
+        .collect::<Vec<_>>();
     c.bench_function(&format!("Auxinfo with {num_players} nodes"), |b| {
         b.iter(|| run_subprotocol::<AuxInfoParticipant>(auxinfo_sid, auxinfo_inputs.clone()))
     });
 
     // Prepare to benchmark presign:
     // 1. Run keygen and get outputs
-    let keygen_inputs = std::iter::repeat(()).take(num_players).collect();
+    let keygen_inputs = std::iter::repeat(keygen::Input::default())
+        .take(num_players)
+        .collect();
     let keygen_outputs = run_subprotocol::<KeygenParticipant>(keygen_sid, keygen_inputs).unwrap();
 
     // 2. Run auxinfo and get outputs
-    let auxinfo_inputs = std::iter::repeat(()).take(num_players).collect();
+    let auxinfo_inputs = std::iter::repeat(auxinfo::Input::default())
+        .take(num_players)
+        .collect();
     let auxinfo_outputs =
         run_subprotocol::<AuxInfoParticipant>(auxinfo_sid, auxinfo_inputs).unwrap();
 
@@ -36,8 +36,8 @@ use std::{
 use tracing::{debug, info, instrument, span, trace, Level};
 use tracing_subscriber::{self, EnvFilter};
 use tss_ecdsa::{
-    auxinfo::AuxInfoParticipant,
-    keygen::{KeygenParticipant, Output},
+    auxinfo::{self, AuxInfoParticipant},
+    keygen::{self, KeygenParticipant, Output},
     messages::Message,
     presign::{self, PresignParticipant},
     sign::{self, SignParticipant},
@@ -405,14 +405,14 @@ impl Worker {
 /// These functions fetch the required inputs from storage.
 impl Worker {
     fn new_keygen(&mut self, sid: SessionId, key_id: KeyId) -> anyhow::Result<()> {
-        self.new_sub_protocol::<KeygenParticipant>(sid, (), key_id)
+        self.new_sub_protocol::<KeygenParticipant>(sid, keygen::Input::default(), key_id)
     }
 
     fn new_auxinfo(&mut self, sid: SessionId, key_id: KeyId) -> anyhow::Result<()> {
         // Note: Missing inputs to aux-info see issues
         // #242 and #243.
         let _output: &Output = self.key_gen_material.retrieve(&key_id);
-        self.new_sub_protocol::<AuxInfoParticipant>(sid, (), key_id)
+        self.new_sub_protocol::<AuxInfoParticipant>(sid, auxinfo::Input::default(), key_id)
     }
 
     fn new_presign(&mut self, sid: SessionId, key_id: KeyId) -> anyhow::Result<()> {
@@ -425,7 +425,8 @@ impl Worker {
 
     fn new_sign(&mut self, sid: SessionId, key_id: KeyId) -> anyhow::Result<()> {
         let key_shares = self.key_gen_material.retrieve(&key_id).public_key_shares();
-        let record = self.presign_records.take(&key_id);
+        // Presign was run with the default of one record per session.
+        let record = self.presign_records.take(&key_id).remove(0);
 
         let inputs = sign::Input::new(b"hello world", record, key_shares.to_vec());
         self.new_sub_protocol::<SignParticipant>(sid, inputs, key_id)
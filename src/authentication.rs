@@ -0,0 +1,253 @@
+// Copyright (c) 2022-2023 Bolt Labs Holdings, Inc
+//
+// This source code is licensed under both the MIT license found in the
+// LICENSE-MIT file in the root directory of this source tree and the Apache
+// License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+// of this source tree.
+
+//! Signed envelopes that authenticate the sender of a [`Message`].
+//!
+//! The protocol requires every message to be authenticated, so that a
+//! participant can't claim to be someone else (see the
+//! [networking section](crate#-networking) of the crate documentation). This
+//! module provides an optional way to meet that requirement: each participant
+//! holds an ECDSA (secp256k1) identity key, the sender wraps each outgoing
+//! [`Message`] in a [`SignedMessage`], and the recipient checks it against a
+//! [`PeerDirectory`] of everyone's verifying keys before processing it.
+//!
+//! # 🔒 Key distribution
+//! The calling application is still responsible for generating the identity
+//! keys and distributing the verifying keys, e.g. using a PKI. All
+//! participants must agree on the [`PeerDirectory`]. The identity keys are
+//! long-lived and must be stored securely.
+
+use std::collections::HashMap;
+
+use k256::ecdsa::{
+    signature::{Signer, Verifier},
+    Signature, SigningKey, VerifyingKey,
+};
+use rand::{CryptoRng, RngCore};
+use serde::{Deserialize, Serialize};
+use tracing::error;
+
+use crate::{
+    errors::{CallerError, Result},
+    messages::Message,
+    Participant, ParticipantIdentifier, ProtocolParticipant,
+};
+
+/// Domain separator for the bytes signed by a [`SignedMessage`].
+const SIGNATURE_DOMAIN: &[u8] = b"tss-ecdsa signed message";
+
+/// A mapping from each [`ParticipantIdentifier`] to the verifying key of that
+/// participant's identity key.
+#[derive(Debug, Clone, Default)]
+pub struct PeerDirectory {
+    keys: HashMap<ParticipantIdentifier, VerifyingKey>,
+}
+
+impl PeerDirectory {
+    /// Create an empty directory.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add the verifying key for `participant`, replacing any existing key.
+    pub fn insert(&mut self, participant: ParticipantIdentifier, key: VerifyingKey) {
+        let _ = self.keys.insert(participant, key);
+    }
+
+    /// Get the verifying key for `participant`, if there is one.
+    pub fn get(&self, participant: ParticipantIdentifier) -> Option<&VerifyingKey> {
+        self.keys.get(&participant)
+    }
+}
+
+impl FromIterator<(ParticipantIdentifier, VerifyingKey)> for PeerDirectory {
+    fn from_iter<I: IntoIterator<Item = (ParticipantIdentifier, VerifyingKey)>>(iter: I) -> Self {
+        Self {
+            keys: iter.into_iter().collect(),
+        }
+    }
+}
+
+/// A [`Message`] together with a signature from its sender's identity key.
+///
+/// The signature covers the whole message, including the session ID, sender
+/// and recipient, so a signed message can't be redirected or replayed into a
+/// different session.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SignedMessage {
+    message: Message,
+    signature: Vec<u8>,
+}
+
+impl SignedMessage {
+    /// Sign `message` with the sender's identity key.
+    pub fn sign(message: Message, key: &SigningKey) -> Self {
+        let signature: Signature = key.sign(&Self::signed_bytes(&message));
+        Self {
+            message,
+            signature: signature.to_bytes().to_vec(),
+        }
+    }
+
+    /// Check that the message was signed by the identity key of the
+    /// participant named in its `from` field, and return the message.
+    ///
+    /// Fails if the sender isn't in `directory` or if the signature doesn't
+    /// verify.
+    pub fn verify(self, directory: &PeerDirectory) -> Result<Message> {
+        let sender = self.message.from();
+        let key = directory.get(sender).ok_or_else(|| {
+            error!("No identity key is known for the sender {}", sender);
+            CallerError::InvalidMessageSender
+        })?;
+        let signature = Signature::from_slice(&self.signature).map_err(|_| {
+            error!("Message from {} has a malformed signature", sender);
+            CallerError::BadMessageSignature
+        })?;
+        key.verify(&Self::signed_bytes(&self.message), &signature)
+            .map_err(|_| {
+                error!(
+                    "Message signature doesn't verify under the identity key of the claimed sender {}",
+                    sender
+                );
+                CallerError::BadMessageSignature
+            })?;
+        Ok(self.message)
+    }
+
+    /// The message, which has not been authenticated.
+    ///
+    /// This can be used to route the message, but must not be processed
+    /// without calling [`verify()`](SignedMessage::verify()).
+    pub fn unverified_message(&self) -> &Message {
+        &self.message
+    }
+
+    fn signed_bytes(message: &Message) -> Vec<u8> {
+        [SIGNATURE_DOMAIN, &message.to_bytes()].concat()
+    }
+}
+
+impl<P: ProtocolParticipant> Participant<P> {
+    /// Authenticate a signed message using `directory` and, if it's valid,
+    /// process it.
+    ///
+    /// This behaves like
+    /// [`process_single_message()`](Participant::process_single_message()),
+    /// except that messages whose signature doesn't match their claimed sender
+    /// are rejected before they reach the protocol. Messages the participant
+    /// addresses to itself (such as the
+    /// [`initialize_message()`](Participant::initialize_message())) don't
+    /// need to be signed and can be passed to `process_single_message`
+    /// directly.
+    pub fn process_signed_message<R: RngCore + CryptoRng>(
+        &mut self,
+        message: SignedMessage,
+        directory: &PeerDirectory,
+        rng: &mut R,
+    ) -> Result<(Option<P::Output>, Vec<Message>)> {
+        let message = message.verify(directory)?;
+        self.process_single_message(&message, rng)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        errors::InternalError,
+        keygen::{self, KeygenParticipant},
+        messages::{KeygenMessageType, MessageType},
+        utils::testing::init_testing,
+        Identifier, ParticipantConfig,
+    };
+
+    #[test]
+    fn signed_messages_are_checked_against_the_claimed_sender() -> Result<()> {
+        let rng = &mut init_testing();
+        let configs = ParticipantConfig::random_quorum(2, rng)?;
+        let keys: Vec<_> = configs.iter().map(|_| SigningKey::random(rng)).collect();
+        let directory: PeerDirectory = configs
+            .iter()
+            .zip(&keys)
+            .map(|(config, key)| (config.id(), *key.verifying_key()))
+            .collect();
+
+        let sid = Identifier::random(rng);
+        let message = Message::new(
+            MessageType::Keygen(KeygenMessageType::R1CommitHash),
+            sid,
+            configs[0].id(),
+            configs[1].id(),
+            &[0u8; 32],
+        )?;
+
+        // Messages signed by the sender verify
+        let signed = SignedMessage::sign(message.clone(), &keys[0]);
+        let verified = signed.clone().verify(&directory)?;
+        assert_eq!(verified.from(), configs[0].id());
+
+        // Messages signed by someone else don't
+        let forged = SignedMessage::sign(message.clone(), &keys[1]);
+        assert_eq!(
+            forged.verify(&directory).unwrap_err(),
+            InternalError::CallingApplicationMistake(CallerError::BadMessageSignature)
+        );
+
+        // Neither do modified messages
+        let mut tampered = signed;
+        tampered.message.unverified_bytes[0] ^= 1;
+        assert!(tampered.verify(&directory).is_err());
+
+        // Or messages from unknown senders
+        let unknown = SignedMessage::sign(message, &keys[0]);
+        assert!(unknown.verify(&PeerDirectory::new()).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn participants_reject_forged_messages() -> Result<()> {
+        let rng = &mut init_testing();
+        let configs = ParticipantConfig::random_quorum(2, rng)?;
+        let keys: Vec<_> = configs.iter().map(|_| SigningKey::random(rng)).collect();
+        let directory: PeerDirectory = configs
+            .iter()
+            .zip(&keys)
+            .map(|(config, key)| (config.id(), *key.verifying_key()))
+            .collect();
+
+        let sid = Identifier::random(rng);
+        let mut participants = configs
+            .iter()
+            .map(|config| {
+                Participant::<KeygenParticipant>::from_config(
+                    config.clone(),
+                    sid,
+                    keygen::Input::full_threshold(),
+                )
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let ready = participants[0].initialize_message()?;
+        let (_, messages) = participants[0].process_single_message(&ready, rng)?;
+        let message = messages
+            .into_iter()
+            .find(|message| message.to() == participants[1].id())
+            .unwrap();
+
+        // A message signed with the wrong key is rejected...
+        let forged = SignedMessage::sign(message.clone(), &keys[1]);
+        assert!(participants[1]
+            .process_signed_message(forged, &directory, rng)
+            .is_err());
+
+        // ...but the genuine one is processed
+        let signed = SignedMessage::sign(message, &keys[0]);
+        let _ = participants[1].process_signed_message(signed, &directory, rng)?;
+        Ok(())
+    }
+}
@@ -8,7 +8,7 @@
 
 use crate::{
     errors::{CallerError, InternalError, Result},
-    paillier::{DecryptionKey, EncryptionKey},
+    paillier::{DecryptionKey, EncryptionKey, NoncePool},
     ring_pedersen::VerifiedRingPedersen,
     utils::ParseBytes,
     zkp::ProofContext,
@@ -32,7 +32,7 @@ use zeroize::Zeroize;
 ///
 /// # 🔒 Storage requirements
 /// This type must be stored securely by the calling application.
-#[derive(Clone, PartialEq, Eq)]
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct AuxInfoPrivate {
     /// The participant's Paillier private key.
     decryption_key: DecryptionKey,
@@ -173,6 +173,12 @@ impl AuxInfoPublic {
         &self.pk
     }
 
+    /// Use the nonces in `pool` when encrypting under this participant's
+    /// Paillier key.
+    pub(crate) fn set_nonce_pool(&mut self, pool: NoncePool) {
+        self.pk.set_nonce_pool(pool)
+    }
+
     pub(crate) fn params(&self) -> &VerifiedRingPedersen {
         &self.params
     }
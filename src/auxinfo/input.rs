@@ -0,0 +1,160 @@
+// Copyright (c) 2022-2023 Bolt Labs Holdings, Inc
+//
+// This source code is licensed under both the MIT license found in the
+// LICENSE-MIT file in the root directory of this source tree and the Apache
+// License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+// of this source tree.
+
+use std::sync::Arc;
+
+use libpaillier::unknown_order::BigNumber;
+use rand::{CryptoRng, RngCore};
+use tracing::{error, info};
+
+use crate::{
+    auxinfo::{prime_pool::SafePrimePair, SafePrimePool},
+    errors::{InternalError, Result},
+    paillier::prime_gen,
+};
+
+/// Input needed for an
+/// [`AuxInfoParticipant`](crate::auxinfo::AuxInfoParticipant) to run.
+///
+/// By default, the participant generates the safe primes for its Paillier
+/// key during the run, which takes several seconds. Use
+/// [`Input::with_prime_pool()`] to take them from a [`SafePrimePool`] that
+/// was filled ahead of time, or [`Input::with_safe_primes()`] to use primes
+/// generated elsewhere.
+#[derive(Debug, Clone, Default)]
+pub struct Input {
+    primes: PrimeSource,
+}
+
+/// Where a participant gets the safe primes for its Paillier key.
+#[derive(Debug, Clone, Default)]
+enum PrimeSource {
+    /// Generate fresh primes during the run.
+    #[default]
+    Generate,
+    /// Take primes from a pool, or generate fresh ones if it's empty.
+    Pool(Arc<SafePrimePool>),
+    /// Use primes provided by the calling application.
+    Provided(SafePrimePair),
+}
+
+impl Input {
+    /// Creates a new [`Input`] that generates fresh safe primes during the
+    /// run.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a new [`Input`] that takes its safe primes from `pool`.
+    ///
+    /// If the pool is empty when the run starts, fresh primes are generated
+    /// as usual. If the pool is persisted, it must be stored again once the
+    /// participant has taken its primes; see the storage requirements on
+    /// [`SafePrimePool`].
+    pub fn with_prime_pool(pool: Arc<SafePrimePool>) -> Self {
+        Self {
+            primes: PrimeSource::Pool(pool),
+        }
+    }
+
+    /// Creates a new [`Input`] that uses the safe primes `p` and `q`, given as
+    /// big-endian bytes, for the participant's Paillier key.
+    ///
+    /// The primes must be distinct safe primes that are `PRIME_BITS` (1024)
+    /// bits long, and their product must be twice that long. This is checked
+    /// here, which takes a little while, since it tests the primality of both
+    /// primes and their Sophie Germain counterparts.
+    ///
+    /// # 🔒 Security requirements
+    /// The primes are the participant's Paillier secret key. They must be
+    /// generated with a cryptographically secure RNG, kept secret, and only
+    /// used for a single auxinfo run.
+    pub fn with_safe_primes(p: &[u8], q: &[u8]) -> Result<Self> {
+        let primes = SafePrimePair {
+            p: BigNumber::from_slice(p),
+            q: BigNumber::from_slice(q),
+        };
+        primes.validate()?;
+        Ok(Self {
+            primes: PrimeSource::Provided(primes),
+        })
+    }
+
+    /// Get the pair of safe primes to use for this participant's Paillier
+    /// key.
+    pub(crate) fn safe_primes<R: RngCore + CryptoRng>(
+        &self,
+        rng: &mut R,
+    ) -> Result<(BigNumber, BigNumber)> {
+        match &self.primes {
+            PrimeSource::Generate => {}
+            PrimeSource::Pool(pool) => match pool.take()? {
+                Some(primes) => return Ok(primes),
+                None => info!("Safe prime pool is empty; generating fresh primes"),
+            },
+            PrimeSource::Provided(primes) => return Ok((primes.p.clone(), primes.q.clone())),
+        }
+        prime_gen::get_random_safe_prime_pair(rng).map_err(|_| {
+            error!("Failed to generate a pair of safe primes");
+            InternalError::InternalInvariantFailed
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parameters::PRIME_BITS, utils::testing::init_testing};
+
+    #[test]
+    fn primes_come_from_the_pool_while_it_has_any() {
+        let rng = &mut init_testing();
+        let pool = Arc::new(SafePrimePool::new());
+        pool.fill(rng, 1).unwrap();
+        let input = Input::with_prime_pool(pool.clone());
+
+        let _ = input.safe_primes(rng).unwrap();
+        assert_eq!(pool.remaining().unwrap(), 0);
+
+        // An empty pool falls back to generating primes
+        let (p, q) = input.safe_primes(rng).unwrap();
+        assert_ne!(p, q);
+        assert!(Input::new().safe_primes(rng).is_ok());
+    }
+
+    #[test]
+    fn provided_primes_must_be_distinct_safe_primes() {
+        let rng = &mut init_testing();
+        let (p, q) = prime_gen::get_random_safe_prime_pair(rng).unwrap();
+
+        let input = Input::with_safe_primes(&p.to_bytes(), &q.to_bytes()).unwrap();
+        assert_eq!(input.safe_primes(rng).unwrap(), (p.clone(), q.clone()));
+
+        // The primes must be distinct
+        assert!(Input::with_safe_primes(&p.to_bytes(), &p.to_bytes()).is_err());
+
+        // They must be prime...
+        let composite: BigNumber = &p + 1;
+        assert!(Input::with_safe_primes(&composite.to_bytes(), &q.to_bytes()).is_err());
+
+        // ...and safe
+        let mut unsafe_prime: BigNumber = &p + 2;
+        loop {
+            let half: BigNumber = (&unsafe_prime - 1) / 2;
+            if unsafe_prime.is_prime() && !half.is_prime() {
+                break;
+            }
+            unsafe_prime = unsafe_prime + 2;
+        }
+        assert_eq!(unsafe_prime.bit_length(), PRIME_BITS);
+        assert!(Input::with_safe_primes(&unsafe_prime.to_bytes(), &q.to_bytes()).is_err());
+
+        // They must be the right size
+        let small = BigNumber::from(23);
+        assert!(Input::with_safe_primes(&small.to_bytes(), &q.to_bytes()).is_err());
+    }
+}
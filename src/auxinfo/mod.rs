@@ -11,6 +11,9 @@
 //! [`AuxInfoParticipant`] for
 //! more details.
 //!
+//! Most of the work of an auxinfo run is generating safe primes; a
+//! [`SafePrimePool`] can be used to do that ahead of time.
+//!
 //! [^cite]: Ran Canetti, Rosario Gennaro, Steven Goldfeder, Nikolaos
 //! Makriyannis, and Udi Peled. UC Non-Interactive, Proactive, Threshold ECDSA
 //! with Identifiable Aborts. [EPrint archive,
@@ -18,10 +21,14 @@
 
 mod auxinfo_commit;
 mod info;
+mod input;
 mod output;
 mod participant;
+mod prime_pool;
 mod proof;
 
 pub use info::{AuxInfoPrivate, AuxInfoPublic};
+pub use input::Input;
 pub use output::Output;
 pub use participant::AuxInfoParticipant;
+pub use prime_pool::SafePrimePool;
@@ -6,8 +6,10 @@ use crate::{
     auxinfo::info::{AuxInfoPrivate, AuxInfoPublic},
     errors::{CallerError, InternalError, Result},
     protocol::ParticipantIdentifier,
+    secret_store::{RecordType, SecretRecord},
 };
 use tracing::error;
+use zeroize::Zeroize;
 
 #[cfg(test)]
 use rand::{CryptoRng, RngCore};
@@ -99,6 +101,21 @@ impl Output {
     }
 }
 
+impl SecretRecord for Output {
+    const RECORD_TYPE: RecordType = RecordType::AuxInfoOutput;
+
+    fn to_record_bytes(&self) -> Result<Vec<u8>> {
+        serialize!(&(&self.public_auxinfo, &self.private_auxinfo))
+    }
+
+    fn from_record_bytes(mut bytes: Vec<u8>) -> Result<Self> {
+        let parts: Result<(Vec<AuxInfoPublic>, AuxInfoPrivate)> = deserialize!(&bytes);
+        bytes.zeroize();
+        let (public_auxinfo, private_auxinfo) = parts?;
+        Self::from_parts(public_auxinfo, private_auxinfo)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -14,15 +14,17 @@ use crate::{
         auxinfo_commit::{Commitment, CommitmentScheme},
         info::{AuxInfoPrivate, AuxInfoPublic, AuxInfoWitnesses},
         proof::{AuxInfoProof, CommonInput},
-        Output,
+        Input, Output,
     },
     broadcast::participant::{BroadcastOutput, BroadcastParticipant, BroadcastTag},
     errors::{CallerError, InternalError, Result},
     local_storage::LocalStorage,
     messages::{AuxinfoMessageType, Message, MessageType},
     paillier::DecryptionKey,
+    parallel,
     participant::{
-        Broadcast, InnerProtocolParticipant, ProcessOutcome, ProtocolParticipant, Status,
+        Broadcast, InnerProtocolParticipant, ParticipantState, ProcessOutcome, ProtocolParticipant,
+        Status,
     },
     protocol::{Identifier, ParticipantIdentifier, ProtocolType, SharedContext},
     ring_pedersen::VerifiedRingPedersen,
@@ -39,26 +41,32 @@ mod storage {
     pub(super) struct Private;
     impl TypeTag for Private {
         type Value = AuxInfoPrivate;
+        const NAME: &'static str = "auxinfo.Private";
     }
     pub(super) struct Public;
     impl TypeTag for Public {
         type Value = AuxInfoPublic;
+        const NAME: &'static str = "auxinfo.Public";
     }
     pub(super) struct Commit;
     impl TypeTag for Commit {
         type Value = Commitment;
+        const NAME: &'static str = "auxinfo.Commit";
     }
     pub(super) struct Decommit;
     impl TypeTag for Decommit {
         type Value = CommitmentScheme;
+        const NAME: &'static str = "auxinfo.Decommit";
     }
     pub(super) struct GlobalRid;
     impl TypeTag for GlobalRid {
+        const NAME: &'static str = "auxinfo.GlobalRid";
         type Value = [u8; 32];
     }
     pub(super) struct Witnesses;
     impl TypeTag for Witnesses {
         type Value = AuxInfoWitnesses;
+        const NAME: &'static str = "auxinfo.Witnesses";
     }
 }
 
@@ -66,7 +74,8 @@ mod storage {
 /// protocol[^cite].
 ///
 /// # Protocol input
-/// The protocol takes no input.
+/// An [`Input`], which says where the participant gets the safe primes for
+/// its Paillier key. By default they are generated during the run.
 ///
 /// # Protocol output
 /// Upon succesful completion, the participant outputs the following:
@@ -122,12 +131,14 @@ pub struct AuxInfoParticipant {
     local_storage: LocalStorage,
     /// Broadcast subprotocol handler
     broadcast_participant: BroadcastParticipant,
+    /// The input to the protocol
+    input: Input,
     /// The status of the protocol execution
     status: Status,
 }
 
 impl ProtocolParticipant for AuxInfoParticipant {
-    type Input = ();
+    type Input = Input;
     // The output type includes `AuxInfoPublic` material for all participants
     // (including ourselves) and `AuxInfoPrivate` for ourselves.
     type Output = Output;
@@ -143,12 +154,8 @@ impl ProtocolParticipant for AuxInfoParticipant {
             id,
             other_participant_ids: other_participant_ids.clone(),
             local_storage: Default::default(),
-            broadcast_participant: BroadcastParticipant::new(
-                sid,
-                id,
-                other_participant_ids,
-                input,
-            )?,
+            broadcast_participant: BroadcastParticipant::new(sid, id, other_participant_ids, ())?,
+            input,
             status: Status::NotReady,
         })
     }
@@ -173,6 +180,23 @@ impl ProtocolParticipant for AuxInfoParticipant {
         self.sid
     }
 
+    fn export_state(&self) -> Result<Vec<u8>> {
+        ParticipantState::export(
+            &self.status,
+            &self.local_storage,
+            Some(&self.broadcast_participant),
+        )
+    }
+
+    fn import_state(&mut self, state: &[u8]) -> Result<()> {
+        ParticipantState::import(
+            state,
+            &mut self.status,
+            &mut self.local_storage,
+            Some(&mut self.broadcast_participant),
+        )
+    }
+
     #[cfg_attr(feature = "flame_it", flame("auxinfo"))]
     #[instrument(skip_all, err(Debug))]
     fn process_message<R: RngCore + CryptoRng>(
@@ -218,6 +242,36 @@ impl ProtocolParticipant for AuxInfoParticipant {
     fn status(&self) -> &Status {
         &self.status
     }
+
+    fn waiting_on(&self) -> Vec<ParticipantIdentifier> {
+        if self.status != Status::Initialized {
+            return Vec::new();
+        }
+        let others = &self.other_participant_ids;
+
+        // Round one: the broadcast commitments
+        let missing_commits = self.local_storage.missing_ids::<storage::Commit>(others);
+        if !missing_commits.is_empty() {
+            return self
+                .broadcast_participant
+                .waiting_on_broadcasts(BroadcastTag::AuxinfoR1CommitHash, &missing_commits);
+        }
+
+        // Round two: the decommitments
+        let missing_decommits = self.local_storage.missing_ids::<storage::Decommit>(others);
+        if !missing_decommits.is_empty() {
+            return missing_decommits;
+        }
+
+        // Round three: the proofs for the auxinfo parameters
+        self.local_storage.missing_ids::<storage::Public>(others)
+    }
+
+    fn abort(&mut self) {
+        self.local_storage = LocalStorage::default();
+        self.broadcast_participant.abort();
+        self.status = Status::Aborted;
+    }
 }
 
 impl InnerProtocolParticipant for AuxInfoParticipant {
@@ -493,27 +547,37 @@ impl AuxInfoParticipant {
 
         let witness = self.local_storage.retrieve::<storage::Witnesses>(self.id)?;
         let product = &witness.p * &witness.q;
-        self.other_participant_ids
+        let shared_context = &self.retrieve_context();
+        // Grab the other participants' decommitment records from storage...
+        let verifiers = self
+            .other_participant_ids
             .iter()
             .map(|&pid| {
-                // Grab the other participant's decommitment record from storage...
                 let verifier_decommit = self.local_storage.retrieve::<storage::Decommit>(pid)?;
-                let setup_params = verifier_decommit.clone().into_public();
-                let params = setup_params.params();
-                let shared_context = &self.retrieve_context();
-                // ... and use its setup parameters in the proof.
-                let common_input =
-                    CommonInput::new(shared_context, sid, global_rid, params, &product);
-                let proof = AuxInfoProof::prove(rng, &common_input, &witness.p, &witness.q)?;
-                Message::new(
-                    MessageType::Auxinfo(AuxinfoMessageType::R3Proof),
-                    sid,
-                    self.id,
-                    pid,
-                    &proof,
-                )
+                Ok((pid, verifier_decommit.clone().into_public()))
             })
-            .collect::<Result<Vec<_>>>()
+            .collect::<Result<Vec<_>>>()?;
+        // ... and use their setup parameters in the proofs, which are
+        // independent of each other and so can be generated in parallel.
+        parallel::map_with_rng(rng, verifiers, |rng, (pid, setup_params)| -> Result<_> {
+            let common_input = CommonInput::new(
+                shared_context,
+                sid,
+                global_rid,
+                setup_params.params(),
+                &product,
+            );
+            let proof = AuxInfoProof::prove(rng, &common_input, &witness.p, &witness.q)?;
+            Message::new(
+                MessageType::Auxinfo(AuxinfoMessageType::R3Proof),
+                sid,
+                self.id,
+                pid,
+                &proof,
+            )
+        })
+        .into_iter()
+        .collect::<Result<Vec<_>>>()
     }
 
     /// Handle other participants' round three messages.
@@ -599,7 +663,8 @@ impl AuxInfoParticipant {
     ) -> Result<(AuxInfoPrivate, AuxInfoPublic, AuxInfoWitnesses)> {
         debug!("Creating new auxinfo.");
 
-        let (decryption_key, p, q) = DecryptionKey::new(rng).map_err(|_| {
+        let (p, q) = self.input.safe_primes(rng)?;
+        let decryption_key = DecryptionKey::from_primes(&p, &q).map_err(|_| {
             error!("Failed to create DecryptionKey");
             InternalError::InternalInvariantFailed
         })?;
@@ -617,20 +682,25 @@ impl AuxInfoParticipant {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::{utils::testing::init_testing, Identifier, ParticipantConfig};
+    use crate::{
+        auxinfo::SafePrimePool, secret_store::SecretRecord, utils::testing::init_testing,
+        Identifier, ParticipantConfig,
+    };
     use rand::{CryptoRng, Rng, RngCore};
     use std::collections::HashMap;
 
     impl AuxInfoParticipant {
         pub fn new_quorum<R: RngCore + CryptoRng>(
             sid: Identifier,
-            input: (),
+            input: Input,
             quorum_size: usize,
             rng: &mut R,
         ) -> Result<Vec<Self>> {
             ParticipantConfig::random_quorum(quorum_size, rng)?
                 .into_iter()
-                .map(|config| Self::new(sid, config.id(), config.other_ids().to_vec(), input))
+                .map(|config| {
+                    Self::new(sid, config.id(), config.other_ids().to_vec(), input.clone())
+                })
                 .collect::<Result<Vec<_>>>()
         }
 
@@ -714,12 +784,35 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn auxinfo_uses_primes_from_the_pool() -> Result<()> {
+        let mut rng = init_testing();
+        let pool = std::sync::Arc::new(SafePrimePool::new());
+        pool.fill(&mut rng, 1)?;
+
+        // Keep a copy of the pool so we know which primes it holds
+        let (p, q) = SafePrimePool::from_record_bytes(pool.to_record_bytes()?)?
+            .take()?
+            .unwrap();
+
+        let sid = Identifier::random(&mut rng);
+        let participant =
+            AuxInfoParticipant::new_quorum(sid, Input::with_prime_pool(pool.clone()), 2, &mut rng)?
+                .remove(0);
+        let (private, public, _) = participant.new_auxinfo(&mut rng)?;
+        assert_eq!(private.decryption_key().modulus(), &(&p * &q));
+        assert_eq!(public.pk().modulus(), &(&p * &q));
+        assert_eq!(pool.remaining()?, 0);
+        Ok(())
+    }
+
     #[test]
     fn test_run_auxinfo_protocol() -> Result<()> {
         let QUORUM_SIZE = 3;
         let mut rng = init_testing();
         let sid = Identifier::random(&mut rng);
-        let mut quorum = AuxInfoParticipant::new_quorum(sid, (), QUORUM_SIZE, &mut rng)?;
+        let mut quorum =
+            AuxInfoParticipant::new_quorum(sid, Input::default(), QUORUM_SIZE, &mut rng)?;
         let mut inboxes = HashMap::new();
         for participant in &quorum {
             let _ = inboxes.insert(participant.id, vec![]);
@@ -0,0 +1,232 @@
+// Copyright (c) 2022-2023 Bolt Labs Holdings, Inc
+//
+// This source code is licensed under both the MIT license found in the
+// LICENSE-MIT file in the root directory of this source tree and the Apache
+// License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+// of this source tree.
+
+use std::{
+    fmt::{Debug, Formatter},
+    sync::{Mutex, MutexGuard},
+};
+
+use libpaillier::unknown_order::BigNumber;
+use rand::{CryptoRng, RngCore};
+use serde::{Deserialize, Serialize};
+use tracing::error;
+use zeroize::{Zeroize, ZeroizeOnDrop};
+
+use crate::{
+    errors::{CallerError, InternalError, Result},
+    paillier::prime_gen,
+    parameters::PRIME_BITS,
+    secret_store::{RecordType, SecretRecord},
+};
+
+/// A pair of safe primes from which a Paillier modulus can be formed.
+#[derive(Clone, Serialize, Deserialize, ZeroizeOnDrop)]
+pub(super) struct SafePrimePair {
+    pub(super) p: BigNumber,
+    pub(super) q: BigNumber,
+}
+
+impl Debug for SafePrimePair {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str("SafePrimePair([redacted])")
+    }
+}
+
+impl SafePrimePair {
+    /// Check that `p` and `q` are distinct `PRIME_BITS`-long safe primes whose
+    /// product is `2 * PRIME_BITS` long.
+    pub(super) fn validate(&self) -> Result<()> {
+        let is_safe_prime = |x: &BigNumber| {
+            let half: BigNumber = (x - 1) / 2;
+            x.bit_length() == PRIME_BITS && x.is_prime() && half.is_prime()
+        };
+        if !is_safe_prime(&self.p) || !is_safe_prime(&self.q) {
+            error!("Paillier primes must be safe primes of {} bits", PRIME_BITS);
+            Err(CallerError::BadInput)?
+        }
+        if self.p == self.q {
+            error!("Paillier primes must be distinct");
+            Err(CallerError::BadInput)?
+        }
+        if (&self.p * &self.q).bit_length() != 2 * PRIME_BITS {
+            error!(
+                "The product of the Paillier primes must be {} bits",
+                2 * PRIME_BITS
+            );
+            Err(CallerError::BadInput)?
+        }
+        Ok(())
+    }
+}
+
+/// A pool of safe primes for [`auxinfo`](crate::auxinfo::AuxInfoParticipant)
+/// runs, generated ahead of time.
+///
+/// Almost all of the work of an auxinfo run is generating the two safe primes
+/// that make up the participant's Paillier modulus. The pool lets the calling
+/// application do that in advance, e.g. on a background thread with
+/// [`fill()`](SafePrimePool::fill()), and then pass the pool to the auxinfo
+/// [`Input`](crate::auxinfo::Input) so that the run itself only has to
+/// compute the proofs. Primes are stored and handed out in pairs whose
+/// product is a modulus of the correct length.
+///
+/// The pool is safe to share between threads; each pair is handed out to
+/// exactly one auxinfo run.
+///
+/// # 🔒 Storage requirements
+/// The primes are secret: anyone who learns them can decrypt everything
+/// encrypted under the resulting Paillier key. The pool can be persisted in a
+/// [`SecretStore`](crate::secret_store::SecretStore), but taking primes from
+/// it only updates the copy in memory. An auxinfo participant takes its pair
+/// when it processes its ready message, so the calling application must store
+/// the pool again (or delete it) after that and before sending any of the
+/// resulting messages. Otherwise, restoring the earlier copy after a restart
+/// would hand the same primes to another run.
+#[derive(Default)]
+pub struct SafePrimePool {
+    pairs: Mutex<Vec<SafePrimePair>>,
+}
+
+impl Debug for SafePrimePool {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        // Only show the number of pairs; the primes themselves are secret.
+        let mut debug = f.debug_struct("SafePrimePool");
+        match self.pairs.lock() {
+            Ok(pairs) => {
+                debug.field("available", &pairs.len());
+            }
+            Err(_) => {
+                debug.field("available", &"[poisoned]");
+            }
+        }
+        debug.finish()
+    }
+}
+
+impl SafePrimePool {
+    /// Create an empty pool.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn lock(&self) -> Result<MutexGuard<'_, Vec<SafePrimePair>>> {
+        self.pairs.lock().map_err(|_| {
+            error!("Safe prime pool lock was poisoned");
+            InternalError::InternalInvariantFailed
+        })
+    }
+
+    /// Generate `count` pairs of safe primes and add them to the pool.
+    ///
+    /// This is slow (seconds per pair), so it should usually be run in the
+    /// background. The pool is only locked while the finished pairs are
+    /// added, so auxinfo runs can keep taking primes in the meantime.
+    pub fn fill<R: RngCore + CryptoRng>(&self, rng: &mut R, count: usize) -> Result<()> {
+        let pairs = std::iter::repeat_with(|| {
+            prime_gen::get_random_safe_prime_pair(rng)
+                .map(|(p, q)| SafePrimePair { p, q })
+                .map_err(|_| {
+                    error!("Failed to generate a pair of safe primes");
+                    InternalError::InternalInvariantFailed
+                })
+        })
+        .take(count)
+        .collect::<Result<Vec<_>>>()?;
+        self.lock()?.extend(pairs);
+        Ok(())
+    }
+
+    /// The number of pairs of safe primes in the pool.
+    ///
+    /// This can be used to decide when to generate more in the background.
+    pub fn remaining(&self) -> Result<usize> {
+        Ok(self.lock()?.len())
+    }
+
+    /// Take a pair of safe primes from the pool, if there are any.
+    ///
+    /// This doesn't touch any persisted copy of the pool; see the storage
+    /// requirements on [`SafePrimePool`].
+    pub(crate) fn take(&self) -> Result<Option<(BigNumber, BigNumber)>> {
+        Ok(self
+            .lock()?
+            .pop()
+            .map(|pair| (pair.p.clone(), pair.q.clone())))
+    }
+}
+
+impl SecretRecord for SafePrimePool {
+    const RECORD_TYPE: RecordType = RecordType::SafePrimes;
+
+    fn to_record_bytes(&self) -> Result<Vec<u8>> {
+        serialize!(&*self.lock()?)
+    }
+
+    fn from_record_bytes(mut bytes: Vec<u8>) -> Result<Self> {
+        let pairs: Result<Vec<SafePrimePair>> = deserialize!(&bytes);
+        bytes.zeroize();
+        let pairs = pairs?;
+
+        if pairs.iter().any(|pair| pair.validate().is_err()) {
+            error!("Stored safe primes are not valid Paillier primes");
+            Err(CallerError::DeserializationFailed)?
+        }
+
+        Ok(Self {
+            pairs: Mutex::new(pairs),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::testing::init_testing;
+
+    #[test]
+    fn pairs_are_taken_once() {
+        let rng = &mut init_testing();
+        let pool = SafePrimePool::new();
+        pool.fill(rng, 3).unwrap();
+        assert_eq!(pool.remaining().unwrap(), 3);
+
+        let mut taken = Vec::new();
+        while let Some((p, q)) = pool.take().unwrap() {
+            assert_ne!(p, q);
+            assert_eq!((&p * &q).bit_length(), 2 * PRIME_BITS);
+            taken.push(p);
+        }
+        assert_eq!(taken.len(), 3);
+        assert_eq!(pool.remaining().unwrap(), 0);
+    }
+
+    #[test]
+    fn pool_round_trips_through_record_bytes() {
+        let rng = &mut init_testing();
+        let pool = SafePrimePool::new();
+        pool.fill(rng, 2).unwrap();
+
+        let bytes = pool.to_record_bytes().unwrap();
+        let restored = SafePrimePool::from_record_bytes(bytes).unwrap();
+        assert_eq!(restored.remaining().unwrap(), 2);
+        assert_eq!(restored.take().unwrap(), pool.take().unwrap());
+
+        // Primes of the wrong size are rejected
+        let small = BigNumber::from(23);
+        let bad = serialize!(&vec![SafePrimePair {
+            p: small.clone(),
+            q: small,
+        }])
+        .unwrap();
+        assert!(SafePrimePool::from_record_bytes(bad).is_err());
+
+        // So are numbers of the right size that aren't safe primes
+        let (p, q) = pool.take().unwrap().unwrap();
+        let bad = serialize!(&vec![SafePrimePair { p: p + 2, q }]).unwrap();
+        assert!(SafePrimePool::from_record_bytes(bad).is_err());
+    }
+}
@@ -0,0 +1,93 @@
+// Copyright (c) 2022-2023 Bolt Labs Holdings, Inc
+//
+// This source code is licensed under both the MIT license found in the
+// LICENSE-MIT file in the root directory of this source tree and the Apache
+// License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+// of this source tree.
+
+//! Thin wrappers that expose internal operations to the benchmarks.
+//!
+//! This module is only compiled with the `benchmarks` feature. It is not part
+//! of the public API and may change at any time.
+
+use crate::{
+    errors::{InternalError, Result},
+    paillier::{Ciphertext, DecryptionKey, EncryptionKey},
+};
+use libpaillier::unknown_order::BigNumber;
+use rand::{CryptoRng, RngCore};
+use tracing::error;
+
+/// A Paillier ciphertext produced by [`PaillierKeyPair`].
+pub struct PaillierCiphertext(Ciphertext);
+
+impl PaillierCiphertext {
+    /// Return the big-endian byte representation of the ciphertext.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.0.to_bytes()
+    }
+}
+
+/// A Paillier key pair, used to benchmark encryption under another party's
+/// key against the faster paths available to the key's owner.
+pub struct PaillierKeyPair {
+    decryption_key: DecryptionKey,
+    encryption_key: EncryptionKey,
+}
+
+impl PaillierKeyPair {
+    /// Create the key pair with factors `p` and `q`, which must be
+    /// 1024-bit safe primes.
+    pub fn from_primes(p: &BigNumber, q: &BigNumber) -> Result<Self> {
+        let decryption_key = DecryptionKey::from_primes(p, q).map_err(|err| {
+            error!("Failed to create a Paillier key pair: {err}");
+            InternalError::InternalInvariantFailed
+        })?;
+        let encryption_key = decryption_key.encryption_key();
+        Ok(Self {
+            decryption_key,
+            encryption_key,
+        })
+    }
+
+    /// Return the modulus `N` of the key pair.
+    pub fn modulus(&self) -> &BigNumber {
+        self.encryption_key.modulus()
+    }
+
+    /// Encrypt `x` with `EncryptionKey::encrypt()`, the way a party encrypts
+    /// under another party's key.
+    pub fn encrypt<R: RngCore + CryptoRng>(
+        &self,
+        rng: &mut R,
+        x: &BigNumber,
+    ) -> Result<PaillierCiphertext> {
+        let (c, _) = self.encryption_key.encrypt(rng, x).map_err(|err| {
+            error!("Failed to encrypt: {err}");
+            InternalError::InternalInvariantFailed
+        })?;
+        Ok(PaillierCiphertext(c))
+    }
+
+    /// Encrypt `x` with `DecryptionKey::encrypt()`, the way a party encrypts
+    /// under its own key.
+    pub fn encrypt_as_owner<R: RngCore + CryptoRng>(
+        &self,
+        rng: &mut R,
+        x: &BigNumber,
+    ) -> Result<PaillierCiphertext> {
+        let (c, _) = self.decryption_key.encrypt(rng, x).map_err(|err| {
+            error!("Failed to encrypt: {err}");
+            InternalError::InternalInvariantFailed
+        })?;
+        Ok(PaillierCiphertext(c))
+    }
+
+    /// Decrypt `c` with `DecryptionKey::decrypt()`.
+    pub fn decrypt(&self, c: &PaillierCiphertext) -> Result<BigNumber> {
+        self.decryption_key.decrypt(&c.0).map_err(|err| {
+            error!("Failed to decrypt: {err}");
+            InternalError::InternalInvariantFailed
+        })
+    }
+}
@@ -11,7 +11,9 @@ use crate::{
     errors::{CallerError, InternalError, Result},
     local_storage::LocalStorage,
     messages::{BroadcastMessageType, Message, MessageType},
-    participant::{InnerProtocolParticipant, ProcessOutcome, ProtocolParticipant},
+    participant::{
+        InnerProtocolParticipant, ParticipantState, ProcessOutcome, ProtocolParticipant,
+    },
     protocol::{ParticipantIdentifier, ProtocolType, SharedContext},
     run_only_once_per_tag, Identifier,
 };
@@ -19,7 +21,7 @@ use crate::{
 use crate::participant::Status;
 use rand::{CryptoRng, RngCore};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use tracing::{error, info, instrument};
 
 // Local storage data types.
@@ -30,6 +32,7 @@ mod storage {
     pub(super) struct Votes;
     impl TypeTag for Votes {
         type Value = HashMap<BroadcastIndex, Vec<u8>>;
+        const NAME: &'static str = "broadcast.Votes";
     }
 }
 
@@ -52,6 +55,8 @@ pub(crate) struct BroadcastParticipant {
 pub(crate) enum BroadcastTag {
     AuxinfoR1CommitHash,
     KeyGenR1CommitHash,
+    RefreshR1CommitHash,
+    ReshareR1Dealing,
     PresignR1Ciphertexts,
 }
 
@@ -113,6 +118,14 @@ impl ProtocolParticipant for BroadcastParticipant {
         self.sid
     }
 
+    fn export_state(&self) -> Result<Vec<u8>> {
+        ParticipantState::export(&self.status, &self.local_storage, None)
+    }
+
+    fn import_state(&mut self, state: &[u8]) -> Result<()> {
+        ParticipantState::import(state, &mut self.status, &mut self.local_storage, None)
+    }
+
     fn ready_type() -> MessageType {
         // I'm not totally confident since broadcast takes a different shape than the
         // other protocols, but this is definitely the first message in the
@@ -166,6 +179,34 @@ impl ProtocolParticipant for BroadcastParticipant {
     fn status(&self) -> &Status {
         &self.status
     }
+
+    fn waiting_on(&self) -> Vec<ParticipantIdentifier> {
+        // Report the participants holding up any broadcast that has started
+        if !self.local_storage.contains::<storage::Votes>(self.id) {
+            return Vec::new();
+        }
+        let broadcasts = match self.local_storage.retrieve::<storage::Votes>(self.id) {
+            Ok(votes) => votes
+                .keys()
+                .map(|idx| (idx.tag.clone(), idx.leader))
+                .collect::<HashSet<_>>(),
+            Err(_) => return Vec::new(),
+        };
+        let waiting = broadcasts
+            .into_iter()
+            .flat_map(|(tag, leader)| self.waiting_on_broadcasts(tag, &[leader]))
+            .collect::<Vec<_>>();
+        self.other_participant_ids
+            .iter()
+            .copied()
+            .filter(|pid| waiting.contains(pid))
+            .collect()
+    }
+
+    fn abort(&mut self) {
+        self.local_storage = LocalStorage::default();
+        self.status = Status::Aborted;
+    }
 }
 
 impl InnerProtocolParticipant for BroadcastParticipant {
@@ -190,6 +231,54 @@ impl InnerProtocolParticipant for BroadcastParticipant {
 }
 
 impl BroadcastParticipant {
+    /// The participants whose messages are needed to complete the broadcasts
+    /// with the given tag from each of the `leaders`.
+    ///
+    /// A broadcast completes once every other participant has voted for the
+    /// leader's message. If we haven't received the leader's own message, only
+    /// the leader is reported, since the other participants can't forward a
+    /// message they never received.
+    pub(crate) fn waiting_on_broadcasts(
+        &self,
+        tag: BroadcastTag,
+        leaders: &[ParticipantIdentifier],
+    ) -> Vec<ParticipantIdentifier> {
+        let votes = if self.local_storage.contains::<storage::Votes>(self.id) {
+            self.local_storage.retrieve::<storage::Votes>(self.id).ok()
+        } else {
+            None
+        };
+        let has_voted = |leader: ParticipantIdentifier, voter: ParticipantIdentifier| {
+            votes.map_or(false, |votes| {
+                votes.contains_key(&BroadcastIndex {
+                    tag: tag.clone(),
+                    leader,
+                    other_id: voter,
+                })
+            })
+        };
+
+        let waiting = leaders
+            .iter()
+            .flat_map(|&leader| {
+                if has_voted(leader, leader) {
+                    self.other_participant_ids
+                        .iter()
+                        .copied()
+                        .filter(|&voter| !has_voted(leader, voter))
+                        .collect()
+                } else {
+                    vec![leader]
+                }
+            })
+            .collect::<Vec<_>>();
+        self.other_participant_ids
+            .iter()
+            .copied()
+            .filter(|pid| waiting.contains(pid))
+            .collect()
+    }
+
     #[instrument(skip_all, err(Debug))]
     pub(crate) fn gen_round_one_msgs<R: RngCore + CryptoRng>(
         &mut self,
@@ -0,0 +1,128 @@
+// Copyright (c) 2022-2023 Bolt Labs Holdings, Inc
+//
+// This source code is licensed under both the MIT license found in the
+// LICENSE-MIT file in the root directory of this source tree and the Apache
+// License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+// of this source tree.
+
+use std::collections::HashMap;
+
+use async_trait::async_trait;
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
+use tracing::{error, warn};
+
+use crate::{
+    driver::Transport,
+    errors::{CallerError, Result},
+    messages::Message,
+    ParticipantIdentifier,
+};
+
+/// A [`Transport`] that connects participants in the same process using
+/// in-memory channels.
+///
+/// Messages are delivered in the order they are sent. A message sent to a
+/// participant whose transport has been dropped (e.g. because it already
+/// finished the protocol) is discarded.
+#[derive(Debug)]
+pub struct MemoryTransport {
+    id: ParticipantIdentifier,
+    senders: HashMap<ParticipantIdentifier, UnboundedSender<Message>>,
+    receiver: UnboundedReceiver<Message>,
+}
+
+impl MemoryTransport {
+    /// Create a transport for each of the given participants, all connected
+    /// to each other. The transports are returned in the same order as the
+    /// participants.
+    pub fn network(participants: &[ParticipantIdentifier]) -> Vec<Self> {
+        let (senders, receivers): (HashMap<_, _>, Vec<_>) = participants
+            .iter()
+            .map(|pid| {
+                let (sender, receiver) = unbounded_channel();
+                ((*pid, sender), receiver)
+            })
+            .unzip();
+
+        participants
+            .iter()
+            .zip(receivers)
+            .map(|(pid, receiver)| Self {
+                id: *pid,
+                senders: senders.clone(),
+                receiver,
+            })
+            .collect()
+    }
+
+    /// The participant this transport belongs to.
+    pub fn id(&self) -> ParticipantIdentifier {
+        self.id
+    }
+}
+
+#[async_trait]
+impl Transport for MemoryTransport {
+    async fn send(&mut self, message: Message) -> Result<()> {
+        let sender = self.senders.get(&message.to()).ok_or_else(|| {
+            error!(
+                "Tried to send a message to unknown participant {}",
+                message.to()
+            );
+            CallerError::TransportFailed
+        })?;
+        if sender.send(message).is_err() {
+            warn!("Discarding message to a participant that has left the network");
+        }
+        Ok(())
+    }
+
+    async fn receive(&mut self) -> Result<Message> {
+        // We hold a sender to ourselves, so the channel is never closed.
+        self.receiver.recv().await.ok_or_else(|| {
+            error!("In-memory transport channel was closed");
+            CallerError::TransportFailed.into()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{utils::testing::init_testing, Identifier};
+
+    #[tokio::test]
+    async fn messages_are_delivered_to_their_recipient() -> Result<()> {
+        let rng = &mut init_testing();
+        let pids = std::iter::repeat_with(|| ParticipantIdentifier::random(rng))
+            .take(3)
+            .collect::<Vec<_>>();
+        let mut transports = MemoryTransport::network(&pids);
+        assert!(transports.iter().zip(&pids).all(|(t, pid)| t.id() == *pid));
+
+        let sid = Identifier::random(rng);
+        let message = Message::new(
+            crate::messages::MessageType::Keygen(crate::messages::KeygenMessageType::Ready),
+            sid,
+            pids[0],
+            pids[2],
+            &[1u8, 2, 3],
+        )?;
+        transports[0].send(message.clone()).await?;
+        let received = transports[2].receive().await?;
+        assert_eq!(received.from(), pids[0]);
+        assert_eq!(received.unverified_bytes, message.unverified_bytes);
+
+        // Messages to unknown participants fail
+        let stranger = ParticipantIdentifier::random(rng);
+        let message = Message::new(
+            crate::messages::MessageType::Keygen(crate::messages::KeygenMessageType::Ready),
+            sid,
+            pids[0],
+            stranger,
+            &[0u8],
+        )?;
+        assert!(transports[0].send(message).await.is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,359 @@
+// Copyright (c) 2022-2023 Bolt Labs Holdings, Inc
+//
+// This source code is licensed under both the MIT license found in the
+// LICENSE-MIT file in the root directory of this source tree and the Apache
+// License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+// of this source tree.
+
+//! An async driver that runs a [`Participant`] to completion over a
+//! [`Transport`].
+//!
+//! The library itself doesn't do any networking: a [`Participant`] consumes
+//! and produces [`Message`]s, and the calling application must deliver them.
+//! This module connects the two for applications using
+//! [tokio](https://tokio.rs). The calling application implements
+//! [`Transport`] for its network, and [`run()`] takes care of starting the
+//! protocol, routing messages and returning the output. A participant can
+//! still be needed after it has its output, e.g. to answer the
+//! identification phase of presign for participants whose final check
+//! failed; [`serve()`] keeps it answering.
+//!
+//! [`MemoryTransport`] connects participants in the same process, which is
+//! useful for testing.
+//!
+//! This module is only available with the `async_driver` feature.
+//!
+//! # 🔒 Transport requirements
+//! The transport must satisfy the requirements on channels described in the
+//! [networking section](crate#-networking) of the crate documentation; in
+//! particular, it must authenticate the sender of each message.
+
+mod memory;
+
+pub use memory::MemoryTransport;
+
+use std::collections::VecDeque;
+
+use async_trait::async_trait;
+use rand::{CryptoRng, RngCore};
+use tracing::{info, instrument, warn};
+
+use crate::{
+    errors::{CallerError, InternalError, Result},
+    messages::Message,
+    Participant, ProtocolParticipant,
+};
+
+/// A channel that sends and receives [`Message`]s for a single participant.
+#[async_trait]
+pub trait Transport: Send {
+    /// Send a message to the participant it's addressed to
+    /// ([`Message::to()`]).
+    async fn send(&mut self, message: Message) -> Result<()>;
+
+    /// Wait for the next message addressed to this participant.
+    async fn receive(&mut self) -> Result<Message>;
+}
+
+/// Run the protocol for `participant` to completion, sending and receiving
+/// messages over `transport`.
+///
+/// This starts the protocol with
+/// [`initialize_message()`](Participant::initialize_message()) and then
+/// processes messages until the participant produces an output. Messages the
+/// participant addresses to itself are handled directly instead of going
+/// through the transport. Any messages produced alongside the output are sent
+/// before returning.
+///
+/// The transport must only deliver messages for the participant's session;
+/// any error from processing a message ends the run.
+///
+/// This returns as soon as the participant has its output, but the other
+/// participants may not be done yet, and some protocols need a finished
+/// participant to keep responding: in presign, participants whose final
+/// check fails run an identification phase that every participant must
+/// answer. Call [`serve()`] afterwards to keep answering them.
+#[instrument(skip_all, err(Debug))]
+pub async fn run<P, T, R>(
+    participant: &mut Participant<P>,
+    transport: &mut T,
+    rng: &mut R,
+) -> Result<P::Output>
+where
+    P: ProtocolParticipant,
+    T: Transport + ?Sized,
+    R: RngCore + CryptoRng,
+{
+    info!("Running participant {} to completion.", participant.id());
+
+    let mut local = VecDeque::from([participant.initialize_message()?]);
+    loop {
+        let message = match local.pop_front() {
+            Some(message) => message,
+            None => transport.receive().await?,
+        };
+
+        if let Some(output) = process(participant, transport, &mut local, &message, rng).await? {
+            return Ok(output);
+        }
+    }
+}
+
+/// Keep processing messages for a `participant` that [`run()`] has already
+/// run to completion, so that it can answer the other participants.
+///
+/// Messages that arrive for the finished session are ignored, except for the
+/// ones the protocol still handles, such as identification requests in
+/// presign. This never returns unless processing a message or the transport
+/// fails, so it should be run alongside other work and dropped (e.g. with
+/// `tokio::time::timeout`) once the other participants are done.
+#[instrument(skip_all, err(Debug))]
+pub async fn serve<P, T, R>(
+    participant: &mut Participant<P>,
+    transport: &mut T,
+    rng: &mut R,
+) -> Result<()>
+where
+    P: ProtocolParticipant,
+    T: Transport + ?Sized,
+    R: RngCore + CryptoRng,
+{
+    info!("Serving participant {} after completion.", participant.id());
+
+    let mut local = VecDeque::new();
+    loop {
+        let message = match local.pop_front() {
+            Some(message) => message,
+            None => transport.receive().await?,
+        };
+
+        match process(participant, transport, &mut local, &message, rng).await {
+            Ok(_) => {}
+            Err(InternalError::CallingApplicationMistake(
+                CallerError::ProtocolAlreadyTerminated,
+            )) => {
+                warn!(
+                    "Ignoring a {:?} message for a session that already terminated",
+                    message.message_type()
+                );
+            }
+            Err(err) => return Err(err),
+        }
+    }
+}
+
+/// Process a single message and route the resulting messages: messages the
+/// participant addresses to itself are queued in `local`, and the rest are
+/// sent over the transport.
+async fn process<P, T, R>(
+    participant: &mut Participant<P>,
+    transport: &mut T,
+    local: &mut VecDeque<Message>,
+    message: &Message,
+    rng: &mut R,
+) -> Result<Option<P::Output>>
+where
+    P: ProtocolParticipant,
+    T: Transport + ?Sized,
+    R: RngCore + CryptoRng,
+{
+    let (output, messages) = participant.process_single_message(message, rng)?;
+    for message in messages {
+        if message.to() == participant.id() {
+            local.push_back(message);
+        } else {
+            transport.send(message).await?;
+        }
+    }
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        auxinfo::{self, AuxInfoParticipant},
+        errors::InternalError,
+        keygen::{self, KeygenParticipant},
+        presign::{self, PresignParticipant},
+        sign::{self, SignParticipant},
+        utils::testing::init_testing,
+        Identifier, ParticipantConfig, ParticipantIdentifier,
+    };
+    use k256::ecdsa::signature::Verifier;
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+    use std::collections::HashMap;
+    use tokio::sync::mpsc::unbounded_channel;
+
+    /// A transport that corrupts the round three presign messages sent to
+    /// `victim`, if there is one.
+    struct CorruptingTransport {
+        inner: MemoryTransport,
+        victim: Option<ParticipantIdentifier>,
+    }
+
+    #[async_trait]
+    impl Transport for CorruptingTransport {
+        async fn send(&mut self, message: Message) -> Result<()> {
+            let message = if Some(message.to()) == self.victim {
+                presign::corrupt_delta(message)?
+            } else {
+                message
+            };
+            self.inner.send(message).await
+        }
+
+        async fn receive(&mut self) -> Result<Message> {
+            self.inner.receive().await
+        }
+    }
+
+    /// Run a protocol with every participant in its own task, connected by
+    /// in-memory transports, and collect the outputs.
+    async fn run_all<P>(
+        configs: &[ParticipantConfig],
+        mut inputs: HashMap<ParticipantIdentifier, P::Input>,
+        rng: &mut StdRng,
+    ) -> Result<HashMap<ParticipantIdentifier, P::Output>>
+    where
+        P: ProtocolParticipant + Send + 'static,
+        P::Input: Send,
+        P::Output: Send + 'static,
+    {
+        let sid = Identifier::random(rng);
+        let pids = configs
+            .iter()
+            .map(ParticipantConfig::id)
+            .collect::<Vec<_>>();
+        let tasks = MemoryTransport::network(&pids)
+            .into_iter()
+            .zip(configs)
+            .map(|(mut transport, config)| {
+                let input = inputs.remove(&config.id()).unwrap();
+                let mut participant = Participant::<P>::from_config(config.clone(), sid, input)?;
+                let mut rng = StdRng::from_seed(rng.gen());
+                Ok(tokio::spawn(async move {
+                    let output = run(&mut participant, &mut transport, &mut rng).await?;
+                    Ok::<_, InternalError>((participant.id(), output))
+                }))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut outputs = HashMap::new();
+        for task in tasks {
+            let (pid, output) = task.await.unwrap()?;
+            let _ = outputs.insert(pid, output);
+        }
+        Ok(outputs)
+    }
+
+    #[tokio::test]
+    async fn driver_runs_full_protocol() -> Result<()> {
+        let rng = &mut init_testing();
+        let configs = ParticipantConfig::random_quorum(3, rng)?;
+
+        let keygen_inputs = configs
+            .iter()
+            .map(|config| (config.id(), keygen::Input::full_threshold()))
+            .collect();
+        let keygen_outputs = run_all::<KeygenParticipant>(&configs, keygen_inputs, rng).await?;
+        let auxinfo_inputs = configs
+            .iter()
+            .map(|config| (config.id(), auxinfo::Input::default()))
+            .collect();
+        let auxinfo_outputs = run_all::<AuxInfoParticipant>(&configs, auxinfo_inputs, rng).await?;
+
+        let presign_inputs = configs
+            .iter()
+            .map(|config| {
+                let input = presign::Input::new(
+                    auxinfo_outputs[&config.id()].clone(),
+                    keygen_outputs[&config.id()].clone(),
+                )?;
+                Ok((config.id(), input))
+            })
+            .collect::<Result<_>>()?;
+        let mut presign_outputs =
+            run_all::<PresignParticipant>(&configs, presign_inputs, rng).await?;
+
+        let message = b"Testing the async driver";
+        let public_key_shares = keygen_outputs[&configs[0].id()].public_key_shares();
+        let sign_inputs = configs
+            .iter()
+            .map(|config| {
+                let record = presign_outputs.remove(&config.id()).unwrap().remove(0);
+                let input = sign::Input::new(message, record, public_key_shares.to_vec());
+                (config.id(), input)
+            })
+            .collect();
+        let signatures = run_all::<SignParticipant>(&configs, sign_inputs, rng).await?;
+
+        // Everyone produces the same, valid signature
+        let public_key = keygen_outputs[&configs[0].id()].public_key()?;
+        for signature in signatures.values() {
+            assert_eq!(signature, &signatures[&configs[0].id()]);
+            assert!(public_key.verify(message, signature.as_ref()).is_ok());
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn finished_participants_answer_identification() -> Result<()> {
+        let rng = &mut init_testing();
+        let configs = ParticipantConfig::random_quorum(3, rng)?;
+        let keygen_outputs = keygen::Output::simulate_set(&configs, rng);
+        let auxinfo_outputs = auxinfo::Output::simulate_set(&configs, rng);
+        let pids = configs
+            .iter()
+            .map(ParticipantConfig::id)
+            .collect::<Vec<_>>();
+
+        // The cheater only lies to the victim, so everyone else finishes and
+        // must keep serving for the victim to identify the cheater.
+        let (cheater, victim) = (pids[0], pids[1]);
+        let sid = Identifier::random(rng);
+        let (results_sender, mut results) = unbounded_channel();
+        let tasks = MemoryTransport::network(&pids)
+            .into_iter()
+            .zip(&configs)
+            .zip(keygen_outputs.into_iter().zip(auxinfo_outputs))
+            .map(|((transport, config), (keygen_output, auxinfo_output))| {
+                let input = presign::Input::new(auxinfo_output, keygen_output)?;
+                let mut participant =
+                    Participant::<PresignParticipant>::from_config(config.clone(), sid, input)?;
+                let mut transport = CorruptingTransport {
+                    inner: transport,
+                    victim: (config.id() == cheater).then_some(victim),
+                };
+                let mut rng = StdRng::from_seed(rng.gen());
+                let results_sender = results_sender.clone();
+                Ok(tokio::spawn(async move {
+                    let result = run(&mut participant, &mut transport, &mut rng).await;
+                    let finished = result.is_ok();
+                    results_sender.send((participant.id(), result)).unwrap();
+                    if finished {
+                        serve(&mut participant, &mut transport, &mut rng).await?;
+                    }
+                    Ok::<_, InternalError>(())
+                }))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut outcomes = HashMap::new();
+        while outcomes.len() < pids.len() {
+            let (pid, result) = results.recv().await.unwrap();
+            let _ = outcomes.insert(pid, result);
+        }
+        for task in tasks {
+            task.abort();
+        }
+
+        // The victim blames the cheater; everyone else got their records
+        assert_eq!(
+            outcomes.remove(&victim).unwrap().unwrap_err(),
+            InternalError::ProtocolError(Some(cheater))
+        );
+        assert!(outcomes.values().all(Result::is_ok));
+        Ok(())
+    }
+}
@@ -28,6 +28,12 @@ pub enum InternalError {
     InternalInvariantFailed,
     #[error("Unexpected state: {0:?} for protocol participant's status")]
     UnexpectedStatus(Status),
+    #[error("Participant {from} aborted the session: {reason}")]
+    SessionAborted {
+        from: ParticipantIdentifier,
+        blamed: Option<ParticipantIdentifier>,
+        reason: String,
+    },
 }
 
 /// Errors that are caused by incorrect behavior by the calling application.
@@ -57,6 +63,16 @@ pub enum CallerError {
     BadInput,
     #[error("Failed to deserialize bytes into the expected type")]
     DeserializationFailed,
+    #[error("Failed to read or write a record in the secret store; see logs for details")]
+    SecretStoreFailed,
+    #[error("The transport failed to send or receive a message; see logs for details")]
+    TransportFailed,
+    #[error("A message's signature did not verify under the identity key of its claimed sender")]
+    BadMessageSignature,
+    #[error(
+        "Received a message encoded with an unsupported wire format, or by a different release of this library"
+    )]
+    UnsupportedWireVersion,
 }
 
 macro_rules! serialize {
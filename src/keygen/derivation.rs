@@ -0,0 +1,213 @@
+// Copyright (c) 2022-2023 Bolt Labs Holdings, Inc
+//
+// This source code is licensed under both the MIT license found in the
+// LICENSE-MIT file in the root directory of this source tree and the Apache
+// License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+// of this source tree.
+
+//! [BIP32](https://github.com/bitcoin/bips/blob/master/bip-0032.mediawiki)
+//! non-hardened child key derivation.
+//!
+//! Non-hardened derivation only needs the parent public key and chain code, so
+//! it works for threshold keys: the child private key is the parent private
+//! key plus a public tweak, which the participants can add to their shares
+//! without interacting.
+
+use std::str::FromStr;
+
+use generic_array::GenericArray;
+use hmac::{Hmac, Mac};
+use k256::{elliptic_curve::PrimeField, Scalar};
+use sha2::Sha512;
+use tracing::error;
+
+use crate::{
+    errors::{CallerError, InternalError, Result},
+    utils::CurvePoint,
+};
+
+/// Child indices at or above this value denote hardened derivation.
+const HARDENED_OFFSET: u32 = 1 << 31;
+
+/// A BIP32 derivation path made up of non-hardened child indices.
+///
+/// Hardened derivation requires the parent private key, which no single
+/// participant holds, so it isn't supported.
+///
+/// A path can be parsed from the usual string form, e.g. `m/0/1`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct DerivationPath(Vec<u32>);
+
+impl DerivationPath {
+    /// Create a path from a list of child indices.
+    ///
+    /// Every index must be less than `2^31`.
+    pub fn new(indices: Vec<u32>) -> Result<Self> {
+        if indices.iter().any(|&index| index >= HARDENED_OFFSET) {
+            error!("Hardened derivation isn't supported for threshold keys");
+            Err(CallerError::BadInput)?
+        }
+        Ok(Self(indices))
+    }
+
+    /// Get the child indices in the path.
+    pub fn indices(&self) -> &[u32] {
+        &self.0
+    }
+}
+
+impl FromStr for DerivationPath {
+    type Err = InternalError;
+
+    fn from_str(path: &str) -> Result<Self> {
+        let mut components = path.split('/');
+        if components.next() != Some("m") {
+            error!("Derivation path must start with `m`");
+            Err(CallerError::BadInput)?
+        }
+        let indices = components
+            .map(|component| {
+                // Hardened components (e.g. `0'`) fail to parse here.
+                component.parse::<u32>().map_err(|_| {
+                    error!("Invalid derivation path component {:?}", component);
+                    CallerError::BadInput.into()
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Self::new(indices)
+    }
+}
+
+/// The result of deriving a child key from a parent public key.
+#[derive(Debug)]
+pub(crate) struct ChildKey {
+    /// The sum of the tweaks along the path. The child private key is the
+    /// parent private key plus this value.
+    pub(crate) tweak: Scalar,
+    /// The child public key.
+    pub(crate) public_key: CurvePoint,
+    /// The child chain code.
+    pub(crate) chain_code: [u8; 32],
+}
+
+/// Derive the child key at `path` from the parent `public_key` and
+/// `chain_code`.
+///
+/// In the (astronomically unlikely) case that a child index produces an
+/// invalid key, BIP32 says to move on to the next index; this returns an
+/// error so that the caller can do so.
+pub(crate) fn derive_child_key(
+    public_key: &CurvePoint,
+    chain_code: &[u8; 32],
+    path: &DerivationPath,
+) -> Result<ChildKey> {
+    path.indices().iter().try_fold(
+        ChildKey {
+            tweak: Scalar::ZERO,
+            public_key: *public_key,
+            chain_code: *chain_code,
+        },
+        |parent, &index| {
+            let mut mac = Hmac::<Sha512>::new_from_slice(&parent.chain_code).map_err(|_| {
+                error!("Failed to initialize HMAC with the chain code");
+                InternalError::InternalInvariantFailed
+            })?;
+            mac.update(&parent.public_key.to_bytes());
+            mac.update(&index.to_be_bytes());
+            let output = mac.finalize().into_bytes();
+            let (left, right) = output.split_at(32);
+
+            let tweak =
+                Option::<Scalar>::from(Scalar::from_repr(GenericArray::clone_from_slice(left)))
+                    .ok_or_else(|| {
+                        error!("Child index {} produces an invalid key", index);
+                        InternalError::from(CallerError::BadInput)
+                    })?;
+            let public_key = parent.public_key + CurvePoint::GENERATOR.multiply_by_scalar(&tweak);
+            if public_key == CurvePoint::IDENTITY {
+                error!("Child index {} produces an invalid key", index);
+                Err(CallerError::BadInput)?
+            }
+
+            let mut chain_code = [0u8; 32];
+            chain_code.copy_from_slice(right);
+            Ok(ChildKey {
+                tweak: parent.tweak + tweak,
+                public_key,
+                chain_code,
+            })
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::testing::init_testing;
+    use k256::elliptic_curve::Field;
+
+    #[test]
+    fn derivation_paths_parse() {
+        assert!("m".parse::<DerivationPath>().unwrap().indices().is_empty());
+        assert_eq!(
+            "m/0/2147483647".parse::<DerivationPath>().unwrap(),
+            DerivationPath::new(vec![0, 2147483647]).unwrap()
+        );
+
+        // Hardened and malformed paths are rejected
+        assert!("m/0'".parse::<DerivationPath>().is_err());
+        assert!("m/0h".parse::<DerivationPath>().is_err());
+        assert!("m/2147483648".parse::<DerivationPath>().is_err());
+        assert!("0/1".parse::<DerivationPath>().is_err());
+        assert!("m/".parse::<DerivationPath>().is_err());
+        assert!(DerivationPath::new(vec![HARDENED_OFFSET]).is_err());
+    }
+
+    #[test]
+    fn derivation_matches_bip32_test_vector() {
+        // Test vector 2 from BIP32, chain m/0.
+        let decode = |s: &str| hex::decode(s).unwrap();
+        let public_key = CurvePoint::try_from_bytes(&decode(
+            "03cbcaa9c98c877a26977d00825c956a238e8dddfbd322cce4f74b0b5bd6ace4a7",
+        ))
+        .unwrap();
+        let chain_code = decode("60499f801b896d83179a4374aeb7822aaeaceaa0db1f85ee3e904c4defbd9689")
+            .try_into()
+            .unwrap();
+
+        let child = derive_child_key(&public_key, &chain_code, &"m/0".parse().unwrap()).unwrap();
+        assert_eq!(
+            child.public_key.to_bytes(),
+            decode("02fc9e5af0ac8d9b3cecfe2a888e2117ba3d089d8585886c9c826b6b22a98d12ea")
+        );
+        assert_eq!(
+            child.chain_code.to_vec(),
+            decode("f0909affaa7ee7abe5dd4e100598d4dc53cd709d5a5c2cac40e7412f232f7c9c")
+        );
+    }
+
+    #[test]
+    fn derivation_composes_along_path() {
+        let rng = &mut init_testing();
+        let secret = Scalar::random(&mut *rng);
+        let public_key = CurvePoint::GENERATOR.multiply_by_scalar(&secret);
+        let chain_code = [7u8; 32];
+
+        let child = derive_child_key(&public_key, &chain_code, &"m/1/2".parse().unwrap()).unwrap();
+        let intermediate =
+            derive_child_key(&public_key, &chain_code, &"m/1".parse().unwrap()).unwrap();
+        let grandchild = derive_child_key(
+            &intermediate.public_key,
+            &intermediate.chain_code,
+            &"m/2".parse().unwrap(),
+        )
+        .unwrap();
+
+        assert_eq!(child.public_key, grandchild.public_key);
+        assert_eq!(child.chain_code, grandchild.chain_code);
+        assert_eq!(
+            CurvePoint::GENERATOR.multiply_by_scalar(&(secret + child.tweak)),
+            child.public_key
+        );
+    }
+}
@@ -0,0 +1,61 @@
+// Copyright (c) 2022-2023 Bolt Labs Holdings, Inc
+//
+// This source code is licensed under both the MIT license found in the
+// LICENSE-MIT file in the root directory of this source tree and the Apache
+// License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+// of this source tree.
+
+use crate::errors::{CallerError, Result};
+use tracing::error;
+
+/// Input needed for a [`KeygenParticipant`](crate::keygen::KeygenParticipant)
+/// to run.
+///
+/// By default, key generation is full-threshold: it produces additive key
+/// shares, and _every_ participant must take part in every signature. Use
+/// [`Input::with_threshold()`] to instead produce Shamir shares of the private
+/// key, so that any `threshold` participants can sign.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Input {
+    threshold: Option<usize>,
+}
+
+impl Input {
+    /// Creates a new [`Input`] for a full-threshold key generation.
+    pub fn full_threshold() -> Self {
+        Self::default()
+    }
+
+    /// Creates a new [`Input`] for a `threshold`-out-of-`n` key generation,
+    /// where `n` is the number of participants in the session.
+    ///
+    /// The `threshold` must be non-zero; it is checked against the number of
+    /// participants when the protocol is instantiated.
+    pub fn with_threshold(threshold: usize) -> Result<Self> {
+        if threshold == 0 {
+            error!("Tried to create a keygen input with a threshold of zero");
+            Err(CallerError::BadInput)?
+        }
+        Ok(Self {
+            threshold: Some(threshold),
+        })
+    }
+
+    /// The number of participants required to sign, if this is a threshold
+    /// key generation.
+    pub(crate) fn threshold(&self) -> Option<usize> {
+        self.threshold
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn threshold_must_be_nonzero() {
+        assert!(Input::with_threshold(0).is_err());
+        assert_eq!(Input::with_threshold(2).unwrap().threshold(), Some(2));
+        assert_eq!(Input::full_threshold().threshold(), None);
+    }
+}
@@ -40,6 +40,9 @@ pub(crate) struct KeygenDecommit {
     pub u_i: [u8; 32],
     pub pk: KeySharePublic,
     pub A: CurvePoint,
+    /// Feldman commitments to the sender's sharing polynomial, present only
+    /// in threshold mode.
+    pub coefficient_commitments: Option<Vec<CurvePoint>>,
 }
 
 impl KeygenDecommit {
@@ -50,6 +53,7 @@ impl KeygenDecommit {
         sender: &ParticipantIdentifier,
         pk: &KeySharePublic,
         sch_precom: &PiSchPrecommit,
+        coefficient_commitments: Option<Vec<CurvePoint>>,
     ) -> Self {
         let mut rid = [0u8; 32];
         let mut u_i = [0u8; 32];
@@ -62,6 +66,7 @@ impl KeygenDecommit {
             u_i,
             pk: pk.clone(),
             A: *sch_precom.precommitment(),
+            coefficient_commitments,
         }
     }
 
@@ -75,6 +80,23 @@ impl KeygenDecommit {
         &self.pk
     }
 
+    /// Check that the Feldman commitments match the expected threshold and
+    /// are consistent with the sender's public key share.
+    pub(crate) fn validate_commitments(&self, threshold: Option<usize>) -> Result<()> {
+        let valid = match (threshold, &self.coefficient_commitments) {
+            (None, None) => true,
+            (Some(threshold), Some(commitments)) => {
+                commitments.len() == threshold && commitments.first() == Some(self.pk.as_ref())
+            }
+            _ => false,
+        };
+        if !valid {
+            error!("decommitment contains malformed Feldman commitments");
+            return Err(InternalError::ProtocolError(Some(self.sender)));
+        }
+        Ok(())
+    }
+
     pub(crate) fn commit(&self) -> Result<KeygenCommit> {
         let mut transcript = Transcript::new(b"KeyGenR1");
         transcript.append_message(b"decom", &serialize!(&self)?);
@@ -8,6 +8,7 @@
 
 use crate::{
     errors::{CallerError, Result},
+    keygen::shamir::evaluate_commitments,
     utils::{k256_order, CurvePoint, ParseBytes},
     ParticipantIdentifier,
 };
@@ -25,7 +26,7 @@ const KEYSHARE_TAG: &[u8] = b"KeySharePrivate";
 ///
 /// # 🔒 Storage requirements
 /// This type must be stored securely by the calling application.
-#[derive(Clone, ZeroizeOnDrop, PartialEq, Eq)]
+#[derive(Clone, Serialize, Deserialize, ZeroizeOnDrop, PartialEq, Eq)]
 pub struct KeySharePrivate {
     x: BigNumber, // in the range [1, q)
 }
@@ -43,6 +44,15 @@ impl KeySharePrivate {
         KeySharePrivate { x: random_bn }
     }
 
+    /// Wrap a share computed by the protocol (e.g. a sum of Shamir shares).
+    ///
+    /// The share is reduced modulo the order of the curve.
+    pub(crate) fn from_bignumber(x: &BigNumber) -> Self {
+        KeySharePrivate {
+            x: x.nmod(&k256_order()),
+        }
+    }
+
     /// Computes the "raw" curve point corresponding to this private key.
     pub(crate) fn public_share(&self) -> Result<CurvePoint> {
         CurvePoint::GENERATOR.multiply_by_bignum(&self.x)
@@ -139,6 +149,39 @@ impl AsRef<BigNumber> for KeySharePrivate {
 pub struct KeySharePublic {
     participant: ParticipantIdentifier,
     X: CurvePoint,
+    /// Sharing data for a threshold key share; `None` for an additive
+    /// (full-threshold) key share.
+    shamir: Option<ShamirData>,
+}
+
+/// Public data describing a Shamir key share.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ShamirData {
+    /// The point at which the shared polynomial is evaluated to get this share.
+    evaluation_point: BigNumber,
+    /// Feldman commitments to each coefficient of the shared polynomial,
+    /// lowest degree first.
+    commitments: Vec<CurvePoint>,
+}
+
+impl ShamirData {
+    /// Get the point at which the shared polynomial is evaluated to get this
+    /// share.
+    pub fn evaluation_point(&self) -> &BigNumber {
+        &self.evaluation_point
+    }
+
+    /// Get the Feldman commitments to the shared polynomial.
+    ///
+    /// The first commitment is the public key.
+    pub(crate) fn commitments(&self) -> &[CurvePoint] {
+        &self.commitments
+    }
+
+    /// The number of shares required to reconstruct the secret.
+    pub fn threshold(&self) -> usize {
+        self.commitments.len()
+    }
 }
 
 impl KeySharePublic {
@@ -146,15 +189,40 @@ impl KeySharePublic {
         Self {
             participant,
             X: share,
+            shamir: None,
         }
     }
 
+    /// Form the public Shamir share of the given participant from the
+    /// commitments to the shared polynomial.
+    pub(crate) fn new_shamir(
+        participant: ParticipantIdentifier,
+        commitments: Vec<CurvePoint>,
+    ) -> Result<Self> {
+        let evaluation_point = participant.evaluation_point();
+        let share = evaluate_commitments(&commitments, &evaluation_point)?;
+        Ok(Self {
+            participant,
+            X: share,
+            shamir: Some(ShamirData {
+                evaluation_point,
+                commitments,
+            }),
+        })
+    }
+
     /// Get the ID of the participant who claims to hold the private share
     /// corresponding to this public key share.
     pub fn participant(&self) -> ParticipantIdentifier {
         self.participant
     }
 
+    /// Get the Shamir sharing data for this key share, if it is a threshold
+    /// key share.
+    pub fn shamir(&self) -> Option<&ShamirData> {
+        self.shamir.as_ref()
+    }
+
     /// Generate a new [`KeySharePrivate`] and [`KeySharePublic`].
     pub(crate) fn new_keyshare<R: RngCore + CryptoRng>(
         participant: ParticipantIdentifier,
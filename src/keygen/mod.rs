@@ -7,11 +7,18 @@
 // License, Version 2.0 found in the LICENSE-APACHE file in the root directory
 // of this source tree.
 
+mod derivation;
+mod input;
 mod keygen_commit;
 mod keyshare;
 mod output;
 mod participant;
+pub(crate) mod shamir;
 
-pub use keyshare::{KeySharePrivate, KeySharePublic};
+pub(crate) use derivation::derive_child_key;
+pub use derivation::DerivationPath;
+pub use input::Input;
+pub use keyshare::{KeySharePrivate, KeySharePublic, ShamirData};
+pub(crate) use output::validate_public_key_shares;
 pub use output::Output;
 pub use participant::KeygenParticipant;
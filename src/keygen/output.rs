@@ -9,13 +9,20 @@ use std::collections::HashSet;
 
 use crate::{
     errors::{CallerError, InternalError, Result},
-    keygen::keyshare::{KeySharePrivate, KeySharePublic},
-    utils::CurvePoint,
+    keygen::{
+        derivation::{derive_child_key, DerivationPath},
+        keyshare::{KeySharePrivate, KeySharePublic, ShamirData},
+        shamir::{evaluate_commitments, lagrange_coefficient_at_zero},
+    },
+    secret_store::{RecordType, SecretRecord},
+    utils::{k256_order, scalar_to_bn, CurvePoint},
     ParticipantIdentifier,
 };
 
 use k256::ecdsa::VerifyingKey;
+use libpaillier::unknown_order::BigNumber;
 use tracing::error;
+use zeroize::Zeroize;
 
 /// Output type from key generation, including all parties' public key shares,
 /// this party's private key share, and a bit of global randomness.
@@ -29,23 +36,139 @@ pub struct Output {
 impl Output {
     /// Construct the generated public key.
     pub fn public_key(&self) -> Result<VerifyingKey> {
-        // Add up all the key shares
-        let public_key_point = self
-            .public_key_shares
-            .iter()
-            .fold(CurvePoint::IDENTITY, |sum, share| sum + *share.as_ref());
-
-        VerifyingKey::from_encoded_point(&public_key_point.into()).map_err(|_| {
+        self.public_key_point().to_verifying_key().map_err(|_| {
             error!("Keygen output does not produce a valid public key.");
             InternalError::InternalInvariantFailed
         })
     }
 
+    /// Construct the generated public key as a [`CurvePoint`].
+    fn public_key_point(&self) -> CurvePoint {
+        match self.shamir_commitments() {
+            // The public key is the commitment to the constant term of the shared
+            // polynomial
+            Some(commitments) => commitments[0],
+            // Otherwise, add up all the key shares
+            None => self
+                .public_key_shares
+                .iter()
+                .fold(CurvePoint::IDENTITY, |sum, share| sum + *share.as_ref()),
+        }
+    }
+
+    /// Get the BIP32 chain code for the key.
+    ///
+    /// This is the random value that the participants agreed on during key
+    /// generation, and is used for [child key derivation](Output::derive_child).
+    /// Refreshing or resharing the key keeps it, so child keys don't change.
+    pub fn chain_code(&self) -> [u8; 32] {
+        self.rid
+    }
+
+    /// Derive the output for the child key at the given BIP32 derivation
+    /// `path`, using non-hardened derivation.
+    ///
+    /// The child output holds this participant's share of the child private
+    /// key and every participant's share of the child public key, and its
+    /// [chain code](Output::chain_code) is the child chain code, so deriving
+    /// along `m/a` and then `m/b` gives the same output as deriving along
+    /// `m/a/b`. It can be used in place of any other keygen output.
+    ///
+    /// The child private key is the parent private key plus a public tweak.
+    /// For threshold key shares, every share is shifted by the tweak; for
+    /// full-threshold key shares, the tweak is added to the share of the
+    /// participant with the smallest [`ParticipantIdentifier`].
+    pub fn derive_child(&self, path: &DerivationPath) -> Result<Self> {
+        let child = derive_child_key(&self.public_key_point(), &self.rid, path)?;
+        let tweak = scalar_to_bn(&child.tweak);
+        let tweak_point = CurvePoint::GENERATOR.multiply_by_scalar(&child.tweak);
+
+        let (public_key_shares, tweak_private_share) = match self.shamir_commitments() {
+            // Adding the tweak to the constant term of the shared polynomial
+            // shifts every share by the tweak
+            Some(commitments) => {
+                let mut commitments = commitments.to_vec();
+                commitments[0] = commitments[0] + tweak_point;
+                let public_key_shares = self
+                    .public_key_shares
+                    .iter()
+                    .map(|share| {
+                        KeySharePublic::new_shamir(share.participant(), commitments.clone())
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+                (public_key_shares, true)
+            }
+            None => {
+                let tweaked_pid = self
+                    .public_key_shares
+                    .iter()
+                    .map(KeySharePublic::participant)
+                    .min()
+                    .ok_or_else(|| {
+                        error!("Keygen output has no public key shares, but it was validated at construction");
+                        InternalError::InternalInvariantFailed
+                    })?;
+                let public_key_shares = self
+                    .public_key_shares
+                    .iter()
+                    .map(|share| {
+                        if share.participant() == tweaked_pid {
+                            KeySharePublic::new(tweaked_pid, *share.as_ref() + tweak_point)
+                        } else {
+                            share.clone()
+                        }
+                    })
+                    .collect();
+                (public_key_shares, self.private_pid()? == tweaked_pid)
+            }
+        };
+
+        let private_key_share = if tweak_private_share {
+            KeySharePrivate::from_bignumber(
+                &self
+                    .private_key_share
+                    .as_ref()
+                    .modadd(&tweak, &k256_order()),
+            )
+        } else {
+            self.private_key_share.clone()
+        };
+
+        Self::from_parts(public_key_shares, private_key_share, child.chain_code)
+    }
+
     /// Get the individual shares of the public key.
     pub fn public_key_shares(&self) -> &[KeySharePublic] {
         &self.public_key_shares
     }
 
+    /// Get the number of participants required to produce a signature.
+    ///
+    /// For a full-threshold key, this is the total number of participants.
+    pub fn threshold(&self) -> usize {
+        match self.shamir_commitments() {
+            Some(commitments) => commitments.len(),
+            None => self.public_key_shares.len(),
+        }
+    }
+
+    /// Returns whether the key shares are Shamir (threshold) shares rather
+    /// than additive (full-threshold) shares.
+    pub fn is_threshold(&self) -> bool {
+        self.shamir_commitments().is_some()
+    }
+
+    /// Get the Feldman commitments to the shared polynomial, if the key shares
+    /// are Shamir shares.
+    ///
+    /// By construction, these are the same for every public key share.
+    fn shamir_commitments(&self) -> Option<&[CurvePoint]> {
+        self.public_key_shares
+            .first()
+            .and_then(KeySharePublic::shamir)
+            .map(ShamirData::commitments)
+    }
+
     pub(crate) fn private_key_share(&self) -> &KeySharePrivate {
         &self.private_key_share
     }
@@ -67,6 +190,75 @@ impl Output {
         }
     }
 
+    /// Convert Shamir key shares into additive key shares for the given
+    /// quorum.
+    ///
+    /// Each quorum member's share is scaled by its Lagrange coefficient, so
+    /// that the private shares of the quorum sum to the private key and the
+    /// returned public shares sum to the public key. The `quorum` must contain
+    /// this participant, must be a subset of the participants in this output,
+    /// and must contain at least [`Output::threshold()`] participants.
+    pub(crate) fn to_additive_shares(&self, quorum: &[ParticipantIdentifier]) -> Result<Self> {
+        if !self.is_threshold() {
+            error!("Tried to convert additive key shares to additive key shares");
+            Err(InternalError::InternalInvariantFailed)?
+        }
+
+        let unique_quorum = quorum.iter().collect::<HashSet<_>>();
+        if unique_quorum.len() != quorum.len() || quorum.len() < self.threshold() {
+            error!(
+                "Quorum of {} participants is too small for a threshold of {}, or contains duplicates",
+                quorum.len(),
+                self.threshold()
+            );
+            Err(CallerError::BadInput)?
+        }
+
+        let quorum_shares = quorum
+            .iter()
+            .map(|pid| {
+                self.public_key_shares
+                    .iter()
+                    .find(|share| share.participant() == *pid)
+                    .ok_or_else(|| {
+                        error!("Quorum member {} does not hold a key share", pid);
+                        CallerError::BadInput.into()
+                    })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let points = quorum
+            .iter()
+            .map(ParticipantIdentifier::evaluation_point)
+            .collect::<Vec<_>>();
+
+        let my_point = self.private_pid()?.evaluation_point();
+        if !points.contains(&my_point) {
+            error!(
+                "Tried to convert key shares for a quorum that does not include this participant"
+            );
+            Err(CallerError::BadInput)?
+        }
+
+        let public_key_shares = quorum_shares
+            .iter()
+            .zip(&points)
+            .map(|(share, point)| {
+                let lambda = lagrange_coefficient_at_zero(point, &points)?;
+                Ok(KeySharePublic::new(
+                    share.participant(),
+                    share.as_ref().multiply_by_bignum(&lambda)?,
+                ))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let lambda = lagrange_coefficient_at_zero(&my_point, &points)?;
+        let private_key_share = KeySharePrivate::from_bignumber(
+            &lambda.modmul(self.private_key_share.as_ref(), &k256_order()),
+        );
+
+        Self::from_parts(public_key_shares, private_key_share, self.rid)
+    }
+
     /// This could be made public if appropriate
     #[cfg(test)]
     pub(crate) fn rid(&self) -> &[u8; 32] {
@@ -83,19 +275,15 @@ impl Output {
     /// - There is a valid key pair -- that is, the public key corresponding to
     ///   the private key share must be contained in the list of public shares.
     /// - The public key shares must be from a unique set of participants
+    /// - Either none of the public key shares are Shamir shares, or all of
+    ///   them are, with the same commitments, and each share is consistent with
+    ///   those commitments.
     pub fn from_parts(
         public_key_shares: Vec<KeySharePublic>,
         private_key_share: KeySharePrivate,
         rid: [u8; 32],
     ) -> Result<Self> {
-        let pids = public_key_shares
-            .iter()
-            .map(KeySharePublic::participant)
-            .collect::<HashSet<_>>();
-        if pids.len() != public_key_shares.len() {
-            error!("Tried to create a keygen output using a set of public material from non-unique participants");
-            Err(CallerError::BadInput)?
-        }
+        validate_public_key_shares(&public_key_shares)?;
 
         let expected_public_share = private_key_share.public_share()?;
         if !public_key_shares
@@ -127,10 +315,74 @@ impl Output {
     }
 }
 
+impl SecretRecord for Output {
+    const RECORD_TYPE: RecordType = RecordType::KeygenOutput;
+
+    fn to_record_bytes(&self) -> Result<Vec<u8>> {
+        serialize!(&(&self.public_key_shares, &self.private_key_share, &self.rid))
+    }
+
+    fn from_record_bytes(mut bytes: Vec<u8>) -> Result<Self> {
+        let parts: Result<(Vec<KeySharePublic>, KeySharePrivate, [u8; 32])> = deserialize!(&bytes);
+        bytes.zeroize();
+        let (public_key_shares, private_key_share, rid) = parts?;
+        Self::from_parts(public_key_shares, private_key_share, rid)
+    }
+}
+
+/// Check that a set of public key shares is well-formed:
+/// - The public key shares must be from a unique set of participants
+/// - Either none of the public key shares are Shamir shares, or all of them
+///   are, with the same commitments, and each share is consistent with those
+///   commitments.
+pub(crate) fn validate_public_key_shares(public_key_shares: &[KeySharePublic]) -> Result<()> {
+    let pids = public_key_shares
+        .iter()
+        .map(KeySharePublic::participant)
+        .collect::<HashSet<_>>();
+    if pids.len() != public_key_shares.len() {
+        error!("Tried to create a keygen output using a set of public material from non-unique participants");
+        Err(CallerError::BadInput)?
+    }
+
+    if let Some(first) = public_key_shares.first().and_then(KeySharePublic::shamir) {
+        let commitments = first.commitments();
+        for share in public_key_shares {
+            let consistent = match share.shamir() {
+                Some(shamir) => {
+                    shamir.commitments() == commitments
+                        && shamir.evaluation_point() == &share.participant().evaluation_point()
+                        && &evaluate_commitments(commitments, shamir.evaluation_point())?
+                            == share.as_ref()
+                }
+                None => false,
+            };
+            if !consistent {
+                error!("Tried to create a keygen output using inconsistent Shamir shares");
+                Err(CallerError::BadInput)?
+            }
+        }
+        if commitments.is_empty() || commitments.len() > public_key_shares.len() {
+            error!("Tried to create a keygen output with an invalid threshold");
+            Err(CallerError::BadInput)?
+        }
+    } else if public_key_shares
+        .iter()
+        .any(|share| share.shamir().is_some())
+    {
+        error!("Tried to create a keygen output mixing additive and Shamir shares");
+        Err(CallerError::BadInput)?
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::{utils::testing::init_testing, ParticipantConfig, ParticipantIdentifier};
+    use crate::{
+        keygen::shamir::Polynomial, utils::testing::init_testing, ParticipantConfig,
+        ParticipantIdentifier,
+    };
     use rand::{CryptoRng, Rng, RngCore};
 
     impl Output {
@@ -187,6 +439,117 @@ mod tests {
                 })
                 .collect()
         }
+
+        /// Simulate a consistent, valid output of a `threshold`-out-of-`n`
+        /// keygen run with the given participants.
+        ///
+        /// This produces output for every config in the provided set. The
+        /// `threshold` must be between 1 and the number of configs.
+        pub(crate) fn simulate_threshold_set(
+            configs: &[ParticipantConfig],
+            threshold: usize,
+            rng: &mut (impl CryptoRng + RngCore),
+        ) -> Vec<Self> {
+            let secret = KeySharePrivate::random(rng);
+            let polynomial = Polynomial::random(secret.as_ref(), threshold, rng).unwrap();
+            let commitments = polynomial.commit().unwrap();
+
+            let public_key_shares = configs
+                .iter()
+                .map(|config| KeySharePublic::new_shamir(config.id(), commitments.clone()).unwrap())
+                .collect::<Vec<_>>();
+
+            let rid = rng.gen();
+
+            configs
+                .iter()
+                .map(|config| {
+                    let private_key_share = KeySharePrivate::from_bignumber(
+                        &polynomial.evaluate(&config.id().evaluation_point()),
+                    );
+                    Self::from_parts(public_key_shares.clone(), private_key_share, rid).unwrap()
+                })
+                .collect()
+        }
+    }
+
+    #[test]
+    fn threshold_output_has_expected_public_key() -> Result<()> {
+        let rng = &mut init_testing();
+        let configs = ParticipantConfig::random_quorum(5, rng)?;
+        let outputs = Output::simulate_threshold_set(&configs, 3, rng);
+
+        for output in &outputs {
+            assert!(output.is_threshold());
+            assert_eq!(output.threshold(), 3);
+            assert_eq!(output.public_key()?, outputs[0].public_key()?);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn additive_conversion_preserves_public_key() -> Result<()> {
+        let rng = &mut init_testing();
+        let configs = ParticipantConfig::random_quorum(5, rng)?;
+        let outputs = Output::simulate_threshold_set(&configs, 3, rng);
+        let quorum = configs[1..4]
+            .iter()
+            .map(ParticipantConfig::id)
+            .collect::<Vec<_>>();
+
+        let additive_outputs = outputs[1..4]
+            .iter()
+            .map(|output| output.to_additive_shares(&quorum))
+            .collect::<Result<Vec<_>>>()?;
+
+        // The additive shares sum to the original public key
+        for output in &additive_outputs {
+            assert!(!output.is_threshold());
+            assert_eq!(output.public_key()?, outputs[0].public_key()?);
+        }
+
+        // Quorums that are too small or don't include the participant are rejected
+        assert!(outputs[1].to_additive_shares(&quorum[..2]).is_err());
+        assert!(outputs[0].to_additive_shares(&quorum).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn threshold_output_must_not_mix_additive_shares() {
+        let rng = &mut init_testing();
+        let configs = ParticipantConfig::random_quorum(4, rng).unwrap();
+        let output = Output::simulate_threshold_set(&configs, 2, rng)
+            .pop()
+            .unwrap();
+        let (mut public_key_shares, private_key_share, rid) = output.into_parts();
+
+        // Replace one of the other participants' shares with an additive share
+        let additive = KeySharePublic::new(
+            public_key_shares[0].participant(),
+            *public_key_shares[0].as_ref(),
+        );
+        public_key_shares[0] = additive;
+
+        assert!(Output::from_parts(public_key_shares, private_key_share, rid).is_err());
+    }
+
+    #[test]
+    fn threshold_output_shares_must_use_the_same_commitments() {
+        let rng = &mut init_testing();
+        let configs = ParticipantConfig::random_quorum(4, rng).unwrap();
+        let output = Output::simulate_threshold_set(&configs, 2, rng)
+            .pop()
+            .unwrap();
+        let (mut public_key_shares, private_key_share, rid) = output.into_parts();
+
+        // Replace one of the other participants' shares with a share of a
+        // different key
+        let other_output = Output::simulate_threshold_set(&configs, 2, rng)
+            .pop()
+            .unwrap();
+        public_key_shares[0] = other_output.public_key_shares()[0].clone();
+
+        assert!(Output::from_parts(public_key_shares, private_key_share, rid).is_err());
     }
 
     #[test]
@@ -248,4 +611,83 @@ mod tests {
             Output::from_parts(public_key_shares, private_key_shares.pop().unwrap(), rid).is_err()
         );
     }
+
+    #[test]
+    fn derived_outputs_hold_shares_of_the_child_key() -> Result<()> {
+        let rng = &mut init_testing();
+        let configs = ParticipantConfig::random_quorum(4, rng)?;
+        let path: DerivationPath = "m/3/1".parse()?;
+
+        // Full-threshold shares
+        let outputs = Output::simulate_set(&configs, rng);
+        let expected = derive_child_key(&outputs[0].public_key_point(), &outputs[0].rid, &path)?;
+        let children = outputs
+            .iter()
+            .map(|output| output.derive_child(&path))
+            .collect::<Result<Vec<_>>>()?;
+        let q = k256_order();
+        let secret = children.iter().fold(BigNumber::zero(), |sum, child| {
+            sum.modadd(child.private_key_share.as_ref(), &q)
+        });
+        assert_eq!(
+            CurvePoint::GENERATOR.multiply_by_bignum(&secret)?,
+            expected.public_key
+        );
+        for child in &children {
+            assert_eq!(child.public_key_point(), expected.public_key);
+            assert_eq!(child.chain_code(), expected.chain_code);
+            assert_eq!(child.public_key_shares, children[0].public_key_shares);
+        }
+
+        // Threshold shares: any threshold of child shares reconstructs the child key
+        let outputs = Output::simulate_threshold_set(&configs, 3, rng);
+        let expected = derive_child_key(&outputs[0].public_key_point(), &outputs[0].rid, &path)?;
+        let children = outputs
+            .iter()
+            .map(|output| output.derive_child(&path))
+            .collect::<Result<Vec<_>>>()?;
+        let quorum = configs[1..]
+            .iter()
+            .map(ParticipantConfig::id)
+            .collect::<Vec<_>>();
+        let secret = children[1..]
+            .iter()
+            .map(|child| child.to_additive_shares(&quorum))
+            .collect::<Result<Vec<_>>>()?
+            .iter()
+            .fold(BigNumber::zero(), |sum, child| {
+                sum.modadd(child.private_key_share.as_ref(), &q)
+            });
+        assert_eq!(
+            CurvePoint::GENERATOR.multiply_by_bignum(&secret)?,
+            expected.public_key
+        );
+        for child in &children {
+            assert!(child.is_threshold());
+            assert_eq!(child.public_key_point(), expected.public_key);
+            assert_eq!(child.public_key_shares, children[0].public_key_shares);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn derivation_composes_across_outputs() -> Result<()> {
+        let rng = &mut init_testing();
+        let configs = ParticipantConfig::random_quorum(3, rng)?;
+        let output = Output::simulate_threshold_set(&configs, 2, rng).remove(0);
+
+        let direct = output.derive_child(&"m/5/9".parse()?)?;
+        let stepwise = output
+            .derive_child(&"m/5".parse()?)?
+            .derive_child(&"m/9".parse()?)?;
+        assert_eq!(direct.public_key_shares, stepwise.public_key_shares);
+        assert_eq!(direct.private_key_share, stepwise.private_key_share);
+        assert_eq!(direct.rid, stepwise.rid);
+
+        // The empty path gives back the same output
+        let same = output.derive_child(&"m".parse()?)?;
+        assert_eq!(same.public_key_shares, output.public_key_shares);
+        assert_eq!(same.private_key_share, output.private_key_share);
+        Ok(())
+    }
 }
@@ -12,24 +12,26 @@ use crate::{
     broadcast::participant::{BroadcastOutput, BroadcastParticipant, BroadcastTag},
     errors::{CallerError, InternalError, Result},
     keygen::{
+        input::Input,
         keygen_commit::{KeygenCommit, KeygenDecommit},
         keyshare::{KeySharePrivate, KeySharePublic},
         output::Output,
+        shamir::{Polynomial, PrivateShare},
     },
     local_storage::LocalStorage,
     messages::{KeygenMessageType, Message, MessageType},
     participant::{
-        Broadcast, InnerProtocolParticipant, ProcessOutcome, ProtocolParticipant, Status,
+        Broadcast, InnerProtocolParticipant, ParticipantState, ProcessOutcome, ProtocolParticipant,
+        Status,
     },
     protocol::{ParticipantIdentifier, ProtocolType, SharedContext},
     run_only_once,
-    zkp::{
-        pisch::{CommonInput, PiSchPrecommit, PiSchProof, ProverSecret},
-        Proof,
-    },
+    utils::{k256_order, CurvePoint},
+    zkp::pisch::{CommonInput, PiSchPrecommit, PiSchProof, ProverSecret},
     Identifier,
 };
 
+use libpaillier::unknown_order::BigNumber;
 use merlin::Transcript;
 use rand::{CryptoRng, RngCore};
 use tracing::{error, info, instrument, warn};
@@ -41,33 +43,55 @@ mod storage {
     pub(super) struct Commit;
     impl TypeTag for Commit {
         type Value = KeygenCommit;
+        const NAME: &'static str = "keygen.Commit";
     }
     pub(super) struct Decommit;
     impl TypeTag for Decommit {
         type Value = KeygenDecommit;
+        const NAME: &'static str = "keygen.Decommit";
     }
     pub(super) struct SchnorrPrecom;
     impl TypeTag for SchnorrPrecom {
         type Value = PiSchPrecommit;
+        const NAME: &'static str = "keygen.SchnorrPrecom";
+    }
+    pub(super) struct SchnorrProof;
+    impl TypeTag for SchnorrProof {
+        type Value = PiSchProof;
+        const NAME: &'static str = "keygen.SchnorrProof";
     }
     pub(super) struct GlobalRid;
     impl TypeTag for GlobalRid {
+        const NAME: &'static str = "keygen.GlobalRid";
         type Value = [u8; 32];
     }
     pub(super) struct PrivateKeyshare;
     impl TypeTag for PrivateKeyshare {
         type Value = KeySharePrivate;
+        const NAME: &'static str = "keygen.PrivateKeyshare";
     }
     pub(super) struct PublicKeyshare;
     impl TypeTag for PublicKeyshare {
         type Value = KeySharePublic;
+        const NAME: &'static str = "keygen.PublicKeyshare";
+    }
+    pub(super) struct SharingPolynomial;
+    impl TypeTag for SharingPolynomial {
+        type Value = Polynomial;
+        const NAME: &'static str = "keygen.SharingPolynomial";
+    }
+    pub(super) struct ReceivedShare;
+    impl TypeTag for ReceivedShare {
+        type Value = PrivateShare;
+        const NAME: &'static str = "keygen.ReceivedShare";
     }
 }
 
 /// A [`ProtocolParticipant`] that runs the key generation protocol[^cite].
 ///
 /// # Protocol input
-/// The protocol takes no input.
+/// The protocol takes an [`Input`] which specifies whether to produce
+/// full-threshold (additive) key shares, or `t`-out-of-`n` (Shamir) key shares.
 ///
 /// # Protocol output
 /// Upon successful completion, the participant produces [`Output`], which
@@ -77,10 +101,20 @@ mod storage {
 /// - A single private key share for this participant; and
 /// - A random value, agreed on by all participants.
 ///
+/// In threshold mode, each public key share additionally carries the
+/// participant's evaluation point and the Feldman commitments to the shared
+/// polynomial.
+///
 /// # 🔒 Storage requirements
 /// The [private key share](KeySharePrivate) in the output requires secure
 /// persistent storage.
 ///
+/// # 🔒 Networking requirements
+/// In threshold mode, each participant sends a private Shamir share to every
+/// other participant in round two. These messages must be sent over a
+/// confidential channel, in addition to the usual authentication and
+/// integrity requirements.
+///
 /// # High-level protocol description
 /// The key generation protocol runs in four rounds:
 /// - In the first round, each participant broadcasts a commitment to (1) its
@@ -98,6 +132,15 @@ mod storage {
 ///   a global random value, produced with contributory randomness from all
 ///   parties.
 ///
+/// In threshold mode, each participant additionally samples a polynomial of
+/// degree `t-1` whose constant term is its key share. The round one commitment
+/// also covers Feldman commitments to the polynomial's coefficients, and in
+/// round two each participant privately sends every other participant its
+/// polynomial evaluated at their evaluation point. Each received evaluation is
+/// checked against the sender's Feldman commitments. The final private key
+/// share is the sum of all received evaluations, which is a Shamir share of the
+/// sum of all participants' constant terms.
+///
 /// [^cite]: Ran Canetti, Rosario Gennaro, Steven Goldfeder, Nikolaos
 /// Makriyannis, and Udi Peled. UC Non-Interactive, Proactive, Threshold ECDSA
 /// with Identifiable Aborts. [EPrint archive,
@@ -111,6 +154,8 @@ pub struct KeygenParticipant {
     /// A list of all other participant identifiers participating in the
     /// protocol
     other_participant_ids: Vec<ParticipantIdentifier>,
+    /// Input to the protocol
+    input: Input,
     /// Local storage for this participant to store secrets
     local_storage: LocalStorage,
     /// Broadcast subprotocol handler
@@ -120,7 +165,7 @@ pub struct KeygenParticipant {
 }
 
 impl ProtocolParticipant for KeygenParticipant {
-    type Input = ();
+    type Input = Input;
     type Output = Output;
 
     fn new(
@@ -129,17 +174,24 @@ impl ProtocolParticipant for KeygenParticipant {
         other_participant_ids: Vec<ParticipantIdentifier>,
         input: Self::Input,
     ) -> Result<Self> {
+        if let Some(threshold) = input.threshold() {
+            if threshold > other_participant_ids.len() + 1 {
+                error!(
+                    "Keygen threshold ({}) exceeds the number of participants ({})",
+                    threshold,
+                    other_participant_ids.len() + 1
+                );
+                Err(CallerError::BadInput)?
+            }
+        }
+
         Ok(Self {
             sid,
             id,
             other_participant_ids: other_participant_ids.clone(),
+            input,
             local_storage: Default::default(),
-            broadcast_participant: BroadcastParticipant::new(
-                sid,
-                id,
-                other_participant_ids,
-                input,
-            )?,
+            broadcast_participant: BroadcastParticipant::new(sid, id, other_participant_ids, ())?,
             status: Status::NotReady,
         })
     }
@@ -164,6 +216,23 @@ impl ProtocolParticipant for KeygenParticipant {
         self.sid
     }
 
+    fn export_state(&self) -> Result<Vec<u8>> {
+        ParticipantState::export(
+            &self.status,
+            &self.local_storage,
+            Some(&self.broadcast_participant),
+        )
+    }
+
+    fn import_state(&mut self, state: &[u8]) -> Result<()> {
+        ParticipantState::import(
+            state,
+            &mut self.status,
+            &mut self.local_storage,
+            Some(&mut self.broadcast_participant),
+        )
+    }
+
     #[cfg_attr(feature = "flame_it", flame("keygen"))]
     #[instrument(skip_all)]
     fn process_message<R: RngCore + CryptoRng>(
@@ -196,9 +265,14 @@ impl ProtocolParticipant for KeygenParticipant {
                 broadcast_outcome.convert(self, Self::handle_round_one_msg, rng)
             }
             MessageType::Keygen(KeygenMessageType::R2Decommit) => {
-                self.handle_round_two_msg(message)
+                self.handle_round_two_msg(rng, message)
+            }
+            MessageType::Keygen(KeygenMessageType::R2PrivateShare) => {
+                self.handle_round_two_private_share_msg(rng, message)
+            }
+            MessageType::Keygen(KeygenMessageType::R3Proof) => {
+                self.handle_round_three_msg(rng, message)
             }
-            MessageType::Keygen(KeygenMessageType::R3Proof) => self.handle_round_three_msg(message),
             message_type => {
                 error!(
                     "Incorrect MessageType given to KeygenParticipant. Got: {:?}",
@@ -212,6 +286,45 @@ impl ProtocolParticipant for KeygenParticipant {
     fn status(&self) -> &Status {
         &self.status
     }
+
+    fn waiting_on(&self) -> Vec<ParticipantIdentifier> {
+        if self.status != Status::Initialized {
+            return Vec::new();
+        }
+        let others = &self.other_participant_ids;
+
+        // Round one: the broadcast commitments
+        let missing_commits = self.local_storage.missing_ids::<storage::Commit>(others);
+        if !missing_commits.is_empty() {
+            return self
+                .broadcast_participant
+                .waiting_on_broadcasts(BroadcastTag::KeyGenR1CommitHash, &missing_commits);
+        }
+
+        // Round two: the decommitments and, in threshold mode, the private shares
+        let round_two = others
+            .iter()
+            .copied()
+            .filter(|pid| {
+                !self.local_storage.contains::<storage::Decommit>(*pid)
+                    || (self.input.threshold().is_some()
+                        && !self.local_storage.contains::<storage::ReceivedShare>(*pid))
+            })
+            .collect::<Vec<_>>();
+        if !round_two.is_empty() {
+            return round_two;
+        }
+
+        // Round three: the Schnorr proofs
+        self.local_storage
+            .missing_ids::<storage::SchnorrProof>(others)
+    }
+
+    fn abort(&mut self) {
+        self.local_storage = LocalStorage::default();
+        self.broadcast_participant.abort();
+        self.status = Status::Aborted;
+    }
 }
 
 impl InnerProtocolParticipant for KeygenParticipant {
@@ -276,9 +389,29 @@ impl KeygenParticipant {
 
         let (keyshare_private, keyshare_public) = KeySharePublic::new_keyshare(self.id(), rng)?;
 
+        // In threshold mode, share the private key share with a polynomial and
+        // commit to its coefficients.
+        let coefficient_commitments = match self.input.threshold() {
+            Some(threshold) => {
+                let polynomial = Polynomial::random(keyshare_private.as_ref(), threshold, rng)?;
+                let commitments = polynomial.commit()?;
+                self.local_storage
+                    .store::<storage::SharingPolynomial>(self.id, polynomial);
+                Some(commitments)
+            }
+            None => None,
+        };
+
         // This corresponds to `A_i` in the paper.
         let sch_precom = PiSchProof::precommit(rng)?;
-        let decom = KeygenDecommit::new(rng, &sid, &self.id, &keyshare_public, &sch_precom);
+        let decom = KeygenDecommit::new(
+            rng,
+            &sid,
+            &self.id,
+            &keyshare_public,
+            &sch_precom,
+            coefficient_commitments,
+        );
         // This corresponds to `V_i` in the paper.
         let com = decom.commit()?;
         let com_bytes = serialize!(&com)?;
@@ -354,7 +487,7 @@ impl KeygenParticipant {
             let round_two_outcomes = self
                 .fetch_messages(MessageType::Keygen(KeygenMessageType::R2Decommit))?
                 .iter()
-                .map(|msg| self.handle_round_two_msg(msg))
+                .map(|msg| self.handle_round_two_msg(rng, msg))
                 .collect::<Result<Vec<_>>>()?;
 
             ProcessOutcome::collect_with_messages(round_two_outcomes, round_one_messages)
@@ -366,7 +499,9 @@ impl KeygenParticipant {
 
     /// Generate the protocol's round two messages.
     ///
-    /// The outcome is an opening to the commitment generated in round one.
+    /// The outcome is an opening to the commitment generated in round one. In
+    /// threshold mode, it also includes a private share for each other
+    /// participant.
     #[cfg_attr(feature = "flame_it", flame("keygen"))]
     #[instrument(skip_all, err(Debug))]
     fn gen_round_two_msgs<R: RngCore + CryptoRng>(
@@ -399,6 +534,30 @@ impl KeygenParticipant {
             decom,
         )?;
         messages.extend_from_slice(&more_messages);
+
+        if self.input.threshold().is_some() {
+            let polynomial = self
+                .local_storage
+                .retrieve::<storage::SharingPolynomial>(self.id)?;
+            let share_messages = self
+                .other_participant_ids
+                .iter()
+                .map(|&pid| {
+                    let share = PrivateShare::new(polynomial.evaluate(&pid.evaluation_point()));
+                    Message::new(
+                        MessageType::Keygen(KeygenMessageType::R2PrivateShare),
+                        sid,
+                        self.id,
+                        pid,
+                        &share,
+                    )
+                })
+                .collect::<Result<Vec<_>>>()?;
+            let my_share = PrivateShare::new(polynomial.evaluate(&self.id.evaluation_point()));
+            messages.extend(share_messages);
+            self.local_storage
+                .store::<storage::ReceivedShare>(self.id, my_share);
+        }
         Ok(messages)
     }
 
@@ -407,8 +566,9 @@ impl KeygenParticipant {
     /// Here we check that the decommitments from each participant are valid.
     #[cfg_attr(feature = "flame_it", flame("keygen"))]
     #[instrument(skip_all, err(Debug))]
-    fn handle_round_two_msg(
+    fn handle_round_two_msg<R: RngCore + CryptoRng>(
         &mut self,
+        rng: &mut R,
         message: &Message,
     ) -> Result<ProcessOutcome<<Self as ProtocolParticipant>::Output>> {
         info!("Handling round two keygen message.");
@@ -429,13 +589,91 @@ impl KeygenParticipant {
             .local_storage
             .retrieve::<storage::Commit>(message.from())?;
         decom.verify(&message.id(), &message.from(), com)?;
+        decom.validate_commitments(self.input.threshold())?;
         self.local_storage
             .store::<storage::Decommit>(message.from(), decom);
 
-        // Check if we've received all the decommits
+        // Now that we have the sender's Feldman commitments, check any private
+        // share they sent us early.
+        for share_message in self.fetch_messages_by_sender(
+            MessageType::Keygen(KeygenMessageType::R2PrivateShare),
+            message.from(),
+        )? {
+            self.verify_private_share(&share_message)?;
+        }
+
+        self.maybe_finish_round_two(rng)
+    }
+
+    /// Handle the private share messages sent in round two in threshold
+    /// mode.
+    ///
+    /// Here we check that the share is consistent with the sender's Feldman
+    /// commitments.
+    #[cfg_attr(feature = "flame_it", flame("keygen"))]
+    #[instrument(skip_all, err(Debug))]
+    fn handle_round_two_private_share_msg<R: RngCore + CryptoRng>(
+        &mut self,
+        rng: &mut R,
+        message: &Message,
+    ) -> Result<ProcessOutcome<<Self as ProtocolParticipant>::Output>> {
+        info!("Handling round two keygen private share message.");
+
+        if self.input.threshold().is_none() {
+            error!("Received a private share during a full-threshold keygen");
+            return Err(InternalError::ProtocolError(Some(message.from())));
+        }
+
+        // We need the sender's decommitment to check the share against.
+        if !self
+            .local_storage
+            .contains::<storage::Decommit>(message.from())
+        {
+            self.stash_message(message)?;
+            return Ok(ProcessOutcome::Incomplete);
+        }
+        self.verify_private_share(message)?;
+
+        self.maybe_finish_round_two(rng)
+    }
+
+    /// Check a private share against the sender's Feldman commitments and
+    /// store it if it is valid.
+    ///
+    /// The sender's decommitment must already be stored.
+    fn verify_private_share(&mut self, message: &Message) -> Result<()> {
+        let share = PrivateShare::from_message(message)?;
+        let decom = self
+            .local_storage
+            .retrieve::<storage::Decommit>(message.from())?;
+        let commitments = decom.coefficient_commitments.as_ref().ok_or_else(|| {
+            error!("Sender's decommitment did not include Feldman commitments");
+            InternalError::ProtocolError(Some(message.from()))
+        })?;
+        if !share.verify(commitments, &self.id.evaluation_point())? {
+            error!("Private share does not match the sender's Feldman commitments");
+            return Err(InternalError::ProtocolError(Some(message.from())));
+        }
+        self.local_storage
+            .store::<storage::ReceivedShare>(message.from(), share);
+        Ok(())
+    }
+
+    /// Check whether round two is complete, and if so, generate round three
+    /// messages and handle any early round three messages.
+    fn maybe_finish_round_two<R: RngCore + CryptoRng>(
+        &mut self,
+        rng: &mut R,
+    ) -> Result<ProcessOutcome<<Self as ProtocolParticipant>::Output>> {
+        // Check if we've received all the decommits (and, in threshold mode, all
+        // the private shares)
         let r2_done = self
             .local_storage
-            .contains_for_all_ids::<storage::Decommit>(&self.all_participants());
+            .contains_for_all_ids::<storage::Decommit>(&self.all_participants())
+            && (self.input.threshold().is_none()
+                || self
+                    .local_storage
+                    .contains_for_all_ids::<storage::ReceivedShare>(&self.all_participants()));
 
         if r2_done {
             // Generate messages for round 3...
@@ -445,7 +683,7 @@ impl KeygenParticipant {
             let round_three_outcomes = self
                 .fetch_messages(MessageType::Keygen(KeygenMessageType::R3Proof))?
                 .iter()
-                .map(|msg| self.handle_round_three_msg(msg))
+                .map(|msg| self.handle_round_three_msg(rng, msg))
                 .collect::<Result<Vec<_>>>()?;
             ProcessOutcome::collect_with_messages(round_three_outcomes, round_three_messages)
         } else {
@@ -517,14 +755,15 @@ impl KeygenParticipant {
 
     /// Handle the protocol's round three messages.
     ///
-    /// Here we validate the Schnorr proofs from each participant. If these
-    /// pass, then we are assured that all public key shares are valid, and we
-    /// can terminate the protocol by outputting these alongside this
-    /// participant's own private key share.
+    /// Here we collect the Schnorr proofs from each participant, and once we
+    /// have all of them, batch-verify them. If these pass, then we are assured
+    /// that all public key shares are valid, and we can terminate the protocol
+    /// by outputting these alongside this participant's own private key share.
     #[cfg_attr(feature = "flame_it", flame("keygen"))]
     #[instrument(skip_all, err(Debug))]
-    fn handle_round_three_msg(
+    fn handle_round_three_msg<R: RngCore + CryptoRng>(
         &mut self,
+        rng: &mut R,
         message: &Message,
     ) -> Result<ProcessOutcome<<Self as ProtocolParticipant>::Output>> {
         info!("Handling round three keygen message.");
@@ -534,21 +773,53 @@ impl KeygenParticipant {
             return Ok(ProcessOutcome::Incomplete);
         }
         let proof = PiSchProof::from_message(message)?;
-        let global_rid = *self.local_storage.retrieve::<storage::GlobalRid>(self.id)?;
-        let decom = self
-            .local_storage
-            .retrieve::<storage::Decommit>(message.from())?;
+        self.local_storage
+            .store::<storage::SchnorrProof>(message.from(), proof);
 
-        let input = CommonInput::new(&decom.pk);
+        // Wait until we have the proofs from all the other participants, so
+        // that we can verify them together.
+        if !self
+            .local_storage
+            .contains_for_all_ids::<storage::SchnorrProof>(&self.other_participant_ids)
+        {
+            return Ok(ProcessOutcome::Incomplete);
+        }
 
-        let mut transcript = schnorr_proof_transcript(&global_rid)?;
-        proof.verify(input, &self.retrieve_context(), &mut transcript)?;
+        let global_rid = *self.local_storage.retrieve::<storage::GlobalRid>(self.id)?;
+        let transcript = schnorr_proof_transcript(&global_rid)?;
+        let proofs = self
+            .other_participant_ids
+            .iter()
+            .map(|pid| self.local_storage.remove::<storage::SchnorrProof>(*pid))
+            .collect::<Result<Vec<_>>>()?;
+        let decoms = self
+            .other_participant_ids
+            .iter()
+            .map(|pid| self.local_storage.retrieve::<storage::Decommit>(*pid))
+            .collect::<Result<Vec<_>>>()?;
+        let batch = self
+            .other_participant_ids
+            .iter()
+            .zip(proofs)
+            .zip(&decoms)
+            .map(|((pid, proof), decom)| {
+                (*pid, proof, CommonInput::new(&decom.pk), transcript.clone())
+            })
+            .collect();
+        PiSchProof::verify_batch(batch, &self.retrieve_context(), rng)?;
 
-        // Only if the proof verifies do we store the participant's public key
-        // share. This signals the end of the protocol for the participant.
-        let keyshare = decom.get_keyshare();
-        self.local_storage
-            .store::<storage::PublicKeyshare>(message.from(), keyshare.clone());
+        // Only if the proofs verify do we store the participants' public key
+        // shares. This signals the end of the protocol for the participant.
+        let keyshares = self
+            .other_participant_ids
+            .iter()
+            .copied()
+            .zip(decoms.into_iter().map(|decom| decom.get_keyshare().clone()))
+            .collect::<Vec<_>>();
+        for (pid, keyshare) in keyshares {
+            self.local_storage
+                .store::<storage::PublicKeyshare>(pid, keyshare);
+        }
 
         //check if we've stored all the public keyshares
         let keyshare_done = self
@@ -565,6 +836,14 @@ impl KeygenParticipant {
             let private_key_share = self
                 .local_storage
                 .remove::<storage::PrivateKeyshare>(self.id)?;
+
+            // In threshold mode, combine the dealt shares into Shamir shares of
+            // the joint key.
+            let (public_key_shares, private_key_share) = if self.input.threshold().is_some() {
+                self.combine_shamir_shares()?
+            } else {
+                (public_key_shares, private_key_share)
+            };
             self.status = Status::TerminatedSuccessfully;
 
             let output = Output::from_parts(public_key_shares, private_key_share, global_rid)?;
@@ -574,6 +853,61 @@ impl KeygenParticipant {
             Ok(ProcessOutcome::Incomplete)
         }
     }
+
+    /// Combine the private shares received from each participant into this
+    /// participant's final Shamir share, and compute the public Shamir shares
+    /// of every participant from the sum of all participants' Feldman
+    /// commitments.
+    fn combine_shamir_shares(&mut self) -> Result<(Vec<KeySharePublic>, KeySharePrivate)> {
+        let q = k256_order();
+        let all_participants = self.all_participants();
+
+        let private_share = all_participants
+            .iter()
+            .map(|pid| self.local_storage.remove::<storage::ReceivedShare>(*pid))
+            .collect::<Result<Vec<_>>>()?
+            .iter()
+            .fold(BigNumber::zero(), |sum, share| {
+                sum.modadd(share.as_ref(), &q)
+            });
+        let private_key_share = KeySharePrivate::from_bignumber(&private_share);
+        let _ = self
+            .local_storage
+            .remove::<storage::SharingPolynomial>(self.id)?;
+
+        // The joint polynomial is the sum of every participant's polynomial, so
+        // its commitments are the sums of the individual commitments.
+        let all_commitments = all_participants
+            .iter()
+            .map(|pid| {
+                let decom = self.local_storage.retrieve::<storage::Decommit>(*pid)?;
+                decom.coefficient_commitments.clone().ok_or_else(|| {
+                    error!("Stored decommitment is missing Feldman commitments");
+                    InternalError::InternalInvariantFailed
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let threshold = self
+            .input
+            .threshold()
+            .ok_or(InternalError::InternalInvariantFailed)?;
+        let joint_commitments = (0..threshold)
+            .map(|k| {
+                all_commitments
+                    .iter()
+                    .fold(CurvePoint::IDENTITY, |sum, commitments| {
+                        sum + commitments[k]
+                    })
+            })
+            .collect::<Vec<_>>();
+
+        let public_key_shares = all_participants
+            .iter()
+            .map(|&pid| KeySharePublic::new_shamir(pid, joint_commitments.clone()))
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok((public_key_shares, private_key_share))
+    }
 }
 
 /// Generate a [`Transcript`] for [`PiSchProof`].
@@ -587,82 +921,25 @@ fn schnorr_proof_transcript(global_rid: &[u8; 32]) -> Result<Transcript> {
 mod tests {
     use super::*;
     use crate::{
+        keygen::shamir::lagrange_coefficient_at_zero,
+        participant::testing::run_participants,
         utils::{testing::init_testing, CurvePoint},
         Identifier, ParticipantConfig,
     };
-    use rand::{CryptoRng, Rng, RngCore};
-    use std::collections::HashMap;
-    use tracing::debug;
+    use rand::{CryptoRng, RngCore};
 
     impl KeygenParticipant {
         pub fn new_quorum<R: RngCore + CryptoRng>(
             sid: Identifier,
+            input: Input,
             quorum_size: usize,
             rng: &mut R,
         ) -> Result<Vec<Self>> {
             ParticipantConfig::random_quorum(quorum_size, rng)?
                 .into_iter()
-                .map(|config| Self::new(sid, config.id(), config.other_ids().to_vec(), ()))
+                .map(|config| Self::new(sid, config.id(), config.other_ids().to_vec(), input))
                 .collect::<Result<Vec<_>>>()
         }
-
-        pub fn initialize_keygen_message(&self, keygen_identifier: Identifier) -> Result<Message> {
-            let empty: [u8; 0] = [];
-            Message::new(
-                MessageType::Keygen(KeygenMessageType::Ready),
-                keygen_identifier,
-                self.id,
-                self.id,
-                &empty,
-            )
-        }
-    }
-
-    /// Delivers all messages into their respective participant's inboxes.
-    fn deliver_all(
-        messages: &[Message],
-        inboxes: &mut HashMap<ParticipantIdentifier, Vec<Message>>,
-    ) {
-        for message in messages {
-            inboxes
-                .get_mut(&message.to())
-                .unwrap()
-                .push(message.clone());
-        }
-    }
-
-    fn is_keygen_done(quorum: &[KeygenParticipant]) -> bool {
-        for participant in quorum {
-            if *participant.status() != Status::TerminatedSuccessfully {
-                return false;
-            }
-        }
-        true
-    }
-
-    #[allow(clippy::type_complexity)]
-    fn process_messages<R: RngCore + CryptoRng>(
-        quorum: &mut Vec<KeygenParticipant>,
-        inboxes: &mut HashMap<ParticipantIdentifier, Vec<Message>>,
-        rng: &mut R,
-    ) -> Option<(usize, ProcessOutcome<Output>)> {
-        // Pick a random participant to process
-        let index = rng.gen_range(0..quorum.len());
-        let participant = quorum.get_mut(index).unwrap();
-
-        let inbox = inboxes.get_mut(&participant.id).unwrap();
-        if inbox.is_empty() {
-            // No messages to process for this participant, so pick another participant
-            return None;
-        }
-        let message = inbox.remove(rng.gen_range(0..inbox.len()));
-        debug!(
-            "processing participant: {}, with message type: {:?} from {}",
-            &participant.id,
-            &message.message_type(),
-            &message.from(),
-        );
-        Some((index, participant.process_message(rng, &message).unwrap()))
     }
 
     #[cfg_attr(feature = "flame_it", flame)]
@@ -677,45 +954,86 @@ mod tests {
     }
 
     #[test]
-    fn keygen_produces_valid_outputs() -> Result<()> {
-        let QUORUM_SIZE = 3;
+    fn threshold_must_not_exceed_quorum_size() -> Result<()> {
         let mut rng = init_testing();
         let sid = Identifier::random(&mut rng);
-        let mut quorum = KeygenParticipant::new_quorum(sid, QUORUM_SIZE, &mut rng)?;
-        let mut inboxes = HashMap::new();
-        for participant in &quorum {
-            let _ = inboxes.insert(participant.id, vec![]);
-        }
-        let mut outputs = std::iter::repeat_with(|| None)
-            .take(QUORUM_SIZE)
-            .collect::<Vec<_>>();
+        assert!(
+            KeygenParticipant::new_quorum(sid, Input::with_threshold(4)?, 3, &mut rng).is_err()
+        );
+        assert!(KeygenParticipant::new_quorum(sid, Input::with_threshold(3)?, 3, &mut rng).is_ok());
+        Ok(())
+    }
 
-        for participant in &quorum {
-            let inbox = inboxes.get_mut(&participant.id).unwrap();
-            inbox.push(participant.initialize_keygen_message(sid)?);
+    #[test]
+    fn threshold_keygen_produces_valid_outputs() -> Result<()> {
+        let QUORUM_SIZE = 4;
+        let THRESHOLD = 2;
+        let mut rng = init_testing();
+        let sid = Identifier::random(&mut rng);
+        let mut quorum = KeygenParticipant::new_quorum(
+            sid,
+            Input::with_threshold(THRESHOLD)?,
+            QUORUM_SIZE,
+            &mut rng,
+        )?;
+        let outputs = run_participants(&mut quorum, &mut rng)?;
+
+        // Every participant agrees on the public key and public key shares
+        let public_key = outputs[0].public_key()?;
+        for output in &outputs {
+            assert!(output.is_threshold());
+            assert_eq!(output.threshold(), THRESHOLD);
+            assert_eq!(output.public_key()?, public_key);
+            assert_eq!(output.public_key_shares(), outputs[0].public_key_shares());
         }
 
-        while !is_keygen_done(&quorum) {
-            let (index, outcome) = match process_messages(&mut quorum, &mut inboxes, &mut rng) {
-                None => continue,
-                Some(x) => x,
-            };
+        // Each participant's private share matches its public share
+        for (output, participant) in outputs.iter().zip(&quorum) {
+            let public_share = output
+                .public_key_shares()
+                .iter()
+                .find(|share| share.participant() == participant.id)
+                .unwrap();
+            let expected_public_share =
+                CurvePoint::GENERATOR.multiply_by_bignum(output.private_key_share().as_ref())?;
+            assert_eq!(public_share.as_ref(), &expected_public_share);
+        }
 
-            // Deliver messages and save outputs
-            match outcome {
-                ProcessOutcome::Incomplete => {}
-                ProcessOutcome::Processed(messages) => deliver_all(&messages, &mut inboxes),
-                ProcessOutcome::Terminated(output) => outputs[index] = Some(output),
-                ProcessOutcome::TerminatedForThisParticipant(output, messages) => {
-                    deliver_all(&messages, &mut inboxes);
-                    outputs[index] = Some(output);
-                }
-            }
+        // Any `THRESHOLD` participants can reconstruct the private key, which
+        // matches the public key
+        let q = k256_order();
+        for quorum_outputs in outputs.windows(THRESHOLD) {
+            let points = quorum_outputs
+                .iter()
+                .map(|output| Ok(output.private_pid()?.evaluation_point()))
+                .collect::<Result<Vec<_>>>()?;
+            let secret = quorum_outputs.iter().zip(&points).try_fold(
+                BigNumber::zero(),
+                |sum, (output, point)| {
+                    let lambda = lagrange_coefficient_at_zero(point, &points)?;
+                    Ok::<_, InternalError>(
+                        sum.modadd(&lambda.modmul(output.private_key_share().as_ref(), &q), &q),
+                    )
+                },
+            )?;
+            let secret_public = CurvePoint::GENERATOR.multiply_by_bignum(&secret)?;
+            assert_eq!(
+                k256::ecdsa::VerifyingKey::from_encoded_point(&secret_public.into()).unwrap(),
+                public_key
+            );
         }
 
-        // Make sure every player got an output
-        let outputs: Vec<_> = outputs.into_iter().flatten().collect();
-        assert_eq!(outputs.len(), QUORUM_SIZE);
+        Ok(())
+    }
+
+    #[test]
+    fn keygen_produces_valid_outputs() -> Result<()> {
+        let QUORUM_SIZE = 3;
+        let mut rng = init_testing();
+        let sid = Identifier::random(&mut rng);
+        let mut quorum =
+            KeygenParticipant::new_quorum(sid, Input::default(), QUORUM_SIZE, &mut rng)?;
+        let outputs = run_participants(&mut quorum, &mut rng)?;
 
         // Check returned outputs
         //
@@ -0,0 +1,271 @@
+// Copyright (c) 2022-2023 Bolt Labs Holdings, Inc
+//
+// This source code is licensed under both the MIT license found in the
+// LICENSE-MIT file in the root directory of this source tree and the Apache
+// License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+// of this source tree.
+
+//! Feldman-verifiable Shamir secret sharing over the scalar field of
+//! secp256k1.
+//!
+//! A dealer holding a secret `s` samples a random polynomial `f` of degree
+//! `t-1` with `f(0) = s`, publishes commitments `g^{a_k}` to each coefficient
+//! `a_k` of `f`, and gives `f(i)` to the party with evaluation point `i`. Any
+//! party can check its share against the commitments, and any `t` shares can
+//! be combined (using Lagrange coefficients) to recover `s`.
+
+use crate::{
+    errors::{InternalError, Result},
+    messages::{KeygenMessageType, Message, MessageType},
+    utils::{k256_order, random_positive_bn, CurvePoint},
+};
+use libpaillier::unknown_order::BigNumber;
+use rand::{CryptoRng, RngCore};
+use serde::{Deserialize, Serialize};
+use std::fmt::Debug;
+use tracing::error;
+use zeroize::ZeroizeOnDrop;
+
+/// A polynomial over the scalar field, used to deal Shamir shares.
+#[derive(Serialize, Deserialize, ZeroizeOnDrop)]
+pub(crate) struct Polynomial {
+    /// Coefficients of the polynomial, lowest degree first.
+    coefficients: Vec<BigNumber>,
+}
+
+impl Debug for Polynomial {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Polynomial")
+            .field("degree", &(self.coefficients.len() - 1))
+            .field("coefficients", &"[redacted]")
+            .finish()
+    }
+}
+
+impl Polynomial {
+    /// Sample a random polynomial with the given constant term, such that
+    /// `threshold` evaluations are required to recover the constant term.
+    ///
+    /// The `threshold` must be non-zero.
+    pub(crate) fn random<R: RngCore + CryptoRng>(
+        constant_term: &BigNumber,
+        threshold: usize,
+        rng: &mut R,
+    ) -> Result<Self> {
+        if threshold == 0 {
+            error!("Tried to sample a polynomial for a threshold of zero");
+            return Err(InternalError::InternalInvariantFailed);
+        }
+        let q = k256_order();
+        let coefficients = std::iter::once(constant_term.nmod(&q))
+            .chain(std::iter::repeat_with(|| random_positive_bn(rng, &q)).take(threshold - 1))
+            .collect();
+        Ok(Self { coefficients })
+    }
+
+    /// Evaluate the polynomial at the point `x`, modulo the order of the
+    /// curve.
+    pub(crate) fn evaluate(&self, x: &BigNumber) -> BigNumber {
+        let q = k256_order();
+        // Horner's method, starting from the highest-degree coefficient.
+        self.coefficients
+            .iter()
+            .rev()
+            .fold(BigNumber::zero(), |acc, coefficient| {
+                acc.modmul(x, &q).modadd(coefficient, &q)
+            })
+    }
+
+    /// Form Feldman commitments `g^{a_k}` to each coefficient of the
+    /// polynomial.
+    pub(crate) fn commit(&self) -> Result<Vec<CurvePoint>> {
+        self.coefficients
+            .iter()
+            .map(|coefficient| CurvePoint::GENERATOR.multiply_by_bignum(coefficient))
+            .collect()
+    }
+}
+
+/// Evaluate the polynomial committed to by `commitments` "in the exponent" at
+/// the point `x`.
+///
+/// If `commitments` are Feldman commitments to a polynomial `f`, this returns
+/// `g^{f(x)}`.
+pub(crate) fn evaluate_commitments(
+    commitments: &[CurvePoint],
+    x: &BigNumber,
+) -> Result<CurvePoint> {
+    // Horner's method, starting from the highest-degree commitment.
+    commitments
+        .iter()
+        .rev()
+        .try_fold(CurvePoint::IDENTITY, |acc, commitment| {
+            Ok(acc.multiply_by_bignum(x)? + *commitment)
+        })
+}
+
+/// Compute the Lagrange coefficient for interpolating the value at zero of a
+/// polynomial from its evaluations at `quorum_points`, for the evaluation at
+/// `point`.
+///
+/// The `point` must be contained in `quorum_points`, and `quorum_points` must
+/// not contain duplicates.
+pub(crate) fn lagrange_coefficient_at_zero(
+    point: &BigNumber,
+    quorum_points: &[BigNumber],
+) -> Result<BigNumber> {
+    if !quorum_points.contains(point) {
+        error!("Tried to compute a Lagrange coefficient for a point outside the quorum");
+        return Err(InternalError::InternalInvariantFailed);
+    }
+
+    let q = k256_order();
+    let (numerator, denominator) = quorum_points.iter().filter(|other| *other != point).fold(
+        (BigNumber::one(), BigNumber::one()),
+        |(numerator, denominator), other| {
+            (
+                numerator.modmul(other, &q),
+                denominator.modmul(&other.modsub(point, &q), &q),
+            )
+        },
+    );
+
+    let inverse = denominator.invert(&q).ok_or_else(|| {
+        error!("Quorum evaluation points were not distinct");
+        InternalError::InternalInvariantFailed
+    })?;
+    Ok(numerator.modmul(&inverse, &q))
+}
+
+/// A dealer's evaluation of its polynomial at the recipient's evaluation
+/// point.
+///
+/// This is sent privately from the dealer to the recipient.
+#[derive(Clone, Serialize, Deserialize, ZeroizeOnDrop)]
+pub(crate) struct PrivateShare {
+    share: BigNumber,
+}
+
+impl Debug for PrivateShare {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("PrivateShare([redacted])")
+    }
+}
+
+impl PrivateShare {
+    pub(crate) fn new(share: BigNumber) -> Self {
+        Self { share }
+    }
+
+    pub(crate) fn from_message(message: &Message) -> Result<Self> {
+        Self::from_message_of_type(
+            message,
+            MessageType::Keygen(KeygenMessageType::R2PrivateShare),
+        )
+    }
+
+    /// Parse a private share sent in a message of the given type.
+    pub(crate) fn from_message_of_type(
+        message: &Message,
+        message_type: MessageType,
+    ) -> Result<Self> {
+        message.check_type(message_type)?;
+        let private_share: PrivateShare = deserialize!(&message.unverified_bytes)?;
+        if private_share.share < BigNumber::zero() || private_share.share >= k256_order() {
+            error!("Received a private share that was out of range");
+            return Err(InternalError::ProtocolError(Some(message.from())));
+        }
+        Ok(private_share)
+    }
+
+    /// Check that this share is consistent with the dealer's Feldman
+    /// commitments at the given evaluation point.
+    pub(crate) fn verify(&self, commitments: &[CurvePoint], x: &BigNumber) -> Result<bool> {
+        let expected = evaluate_commitments(commitments, x)?;
+        self.verify_public(&expected)
+    }
+
+    /// Check that this share is the discrete log of `expected`.
+    pub(crate) fn verify_public(&self, expected: &CurvePoint) -> Result<bool> {
+        Ok(&CurvePoint::GENERATOR.multiply_by_bignum(&self.share)? == expected)
+    }
+}
+
+impl AsRef<BigNumber> for PrivateShare {
+    fn as_ref(&self) -> &BigNumber {
+        &self.share
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::testing::init_testing;
+
+    #[test]
+    fn shares_match_commitments() -> Result<()> {
+        let rng = &mut init_testing();
+        let q = k256_order();
+        let secret = random_positive_bn(rng, &q);
+        let polynomial = Polynomial::random(&secret, 4, rng)?;
+        let commitments = polynomial.commit()?;
+
+        assert_eq!(
+            commitments[0],
+            CurvePoint::GENERATOR.multiply_by_bignum(&secret)?
+        );
+
+        for _ in 0..10 {
+            let x = random_positive_bn(rng, &q);
+            let share = PrivateShare::new(polynomial.evaluate(&x));
+            assert!(share.verify(&commitments, &x)?);
+
+            // A share evaluated at the wrong point doesn't verify
+            let y = random_positive_bn(rng, &q);
+            assert!(!share.verify(&commitments, &y)?);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn any_threshold_shares_recover_secret() -> Result<()> {
+        let rng = &mut init_testing();
+        let q = k256_order();
+        let secret = random_positive_bn(rng, &q);
+        let threshold = 3;
+        let polynomial = Polynomial::random(&secret, threshold, rng)?;
+
+        let points = std::iter::repeat_with(|| random_positive_bn(rng, &q))
+            .take(5)
+            .collect::<Vec<_>>();
+
+        // Every window of `threshold` points recovers the secret
+        for quorum in points.windows(threshold) {
+            let recovered = quorum.iter().try_fold(BigNumber::zero(), |acc, x| {
+                let lambda = lagrange_coefficient_at_zero(x, quorum)?;
+                Ok::<_, InternalError>(acc.modadd(&lambda.modmul(&polynomial.evaluate(x), &q), &q))
+            })?;
+            assert_eq!(recovered, secret);
+        }
+
+        // Fewer than `threshold` points do not
+        let quorum = &points[..threshold - 1];
+        let recovered = quorum.iter().try_fold(BigNumber::zero(), |acc, x| {
+            let lambda = lagrange_coefficient_at_zero(x, quorum)?;
+            Ok::<_, InternalError>(acc.modadd(&lambda.modmul(&polynomial.evaluate(x), &q), &q))
+        })?;
+        assert_ne!(recovered, secret);
+        Ok(())
+    }
+
+    #[test]
+    fn lagrange_coefficient_requires_point_in_quorum() {
+        let rng = &mut init_testing();
+        let q = k256_order();
+        let points = std::iter::repeat_with(|| random_positive_bn(rng, &q))
+            .take(3)
+            .collect::<Vec<_>>();
+        let outsider = random_positive_bn(rng, &q);
+        assert!(lagrange_coefficient_at_zero(&outsider, &points).is_err());
+    }
+}
@@ -13,16 +13,28 @@
 //! implementation is more limited than the cited protocol in several important
 //! ways:
 //!
-//! 1. It is full-threshold: _all_ participants holding a share of the private
-//! key must collaborate to produce a signature.
+//! 1. It is full-threshold by default: _all_ participants holding a share of
+//! the private key must collaborate to produce a signature. Key generation can
+//! instead produce `t`-out-of-`n` Shamir shares (see [`keygen::Input`]), in
+//! which case any quorum of at least `t` share holders can presign and sign;
+//! each quorum member converts its share into an additive share using Lagrange
+//! interpolation when it starts presigning.
 //!
-//! 2. It does not implement key refresh. The paper ties this into the aux-info
-//! protocol, but we removed the components that are only used to update the
-//! participants' private key shares.
+//! 2. Key refresh is implemented as a separate subprotocol,
+//! [`refresh`](refresh::RefreshParticipant), rather than tied into the aux-info
+//! protocol as in the paper. It re-randomizes the participants' private key
+//! shares without changing the public key. A key can also be moved to a new
+//! set of participants, possibly with a new threshold, with
+//! [`reshare`](reshare::ReshareParticipant).
 //!
-//! 3. It does not implement identifiable abort. That is, the protocol will
-//! abort if a party misbehaves, but we did not implement the procedures for
-//! identifying which party was responsible.
+//! 3. Identifiable abort is only partially implemented. If the final check in
+//! [`presign`](presign::PresignParticipant) fails, the participants run the
+//! identification phase from the paper, and the resulting
+//! [`ProtocolError`](errors::InternalError::ProtocolError) names the party
+//! responsible. Similarly, if a signature fails to verify, signing names the
+//! party that sent a bad signature share. Elsewhere, the protocol will abort
+//! if a party misbehaves, but the error does not always identify which party
+//! was responsible.
 //!
 //!
 //! ## Background
@@ -43,6 +55,8 @@
 //! `Participant` is parameterized by the subprotocol that it runs:
 //! [`keygen`](`keygen::KeygenParticipant`),
 //! [`auxinfo`](auxinfo::AuxInfoParticipant),
+//! [`refresh`](refresh::RefreshParticipant),
+//! [`reshare`](reshare::ReshareParticipant),
 //! [`presign`](presign::PresignParticipant),
 //! [`sign`](sign::SignParticipant) or
 //! [`interactive_sign`](sign::InteractiveSignParticipant).
@@ -91,7 +105,9 @@
 //! each taking input and returning output. The calling application must persist
 //! outputs, provide them as input for subsequent protocol executions, and
 //! delete them at the end of their lifetimes. Some outputs are private values
-//! that must be stored securely. See [`Participant`] for more details.
+//! that must be stored securely. See [`Participant`] for more details. The
+//! [`secret_store`] module provides storage backends that the calling
+//! application can use or extend.
 //!
 //! 3. Identifier creation. To create a [`Participant`], the calling
 //! application must specify a session [`Identifier`] and
@@ -133,7 +149,20 @@
 //! receiving a message and signature over a channel, the calling application
 //! must check that the `from` field in the message matches the signing key used
 //! to generate the signature. This ensures that the sender is not lying about
-//! its identity.
+//! its identity. The [`authentication`] module provides signed message
+//! envelopes that the calling application can use for this.
+//!
+//! Some messages carry secret shares in the clear, and must also be sent over
+//! a channel that provides confidentiality, such as TLS between each pair of
+//! participants:
+//! - [`R2PrivateShare`](messages::KeygenMessageType::R2PrivateShare) messages,
+//!   which hold the private Shamir shares of threshold [`keygen`]. Keygen runs
+//!   before [`auxinfo`], so there are no Paillier keys yet to encrypt them
+//!   under.
+//! - [`R1PrivateShare`](messages::ReshareMessageType::R1PrivateShare)
+//!   messages, which hold the shares that [`reshare`] dealers send to the new
+//!   participants. New participants may not have run auxinfo, so these aren't
+//!   encrypted either.
 //!
 //! The protocol requires a UC-secure, synchronous, authenticated broadcast
 //! channel for use by the [`Participant`]s. Currently, the library handles this
@@ -143,6 +172,15 @@
 //! abort[^abort] rather than the stronger notion of identifiable abort that is
 //! achieved with an authenticated broadcast protocol.
 //!
+//! Messages should be encoded for the network with
+//! [`Message::to_bytes()`](messages::Message::to_bytes()). The encoding
+//! records the version of this library that produced it, and
+//! [`Message::try_from_bytes()`](messages::Message::try_from_bytes()) rejects
+//! messages from any other version, so all participants must run the same
+//! release. Applications using tokio can enable the `async_driver` feature and
+//! implement `driver::Transport` for their network to have a [`Participant`]
+//! run to completion over it.
+//!
 //!
 //! # ⚠️ Security warning
 //! The implementation in this crate has not been independently audited for
@@ -167,6 +205,10 @@
 //! application does not have to track where within a session the protocol
 //! execution is at a given time.
 //!
+//! With the `rayon` feature, the zero-knowledge proofs that a participant
+//! generates for, or receives from, different participants are handled in
+//! parallel, which speeds up `auxinfo` and `presign` for large quorums.
+//!
 //! [^cite]: Ran Canetti, Rosario Gennaro, Steven Goldfeder, Nikolaos
 //! Makriyannis, and Udi Peled. UC Non-Interactive, Proactive, Threshold ECDSA
 //! with Identifiable Aborts. [EPrint archive,
@@ -205,18 +247,28 @@ extern crate flamer;
 #[macro_use]
 pub mod errors;
 
+pub mod authentication;
 pub mod auxinfo;
+#[cfg(feature = "benchmarks")]
+#[doc(hidden)]
+pub mod benchmarks;
 mod broadcast;
+#[cfg(feature = "async_driver")]
+pub mod driver;
 pub mod keygen;
 mod local_storage;
 mod message_queue;
 pub mod messages;
 mod paillier;
+mod parallel;
 mod parameters;
 mod participant;
 pub mod presign;
 mod protocol;
+pub mod refresh;
+pub mod reshare;
 mod ring_pedersen;
+pub mod secret_store;
 pub mod sign;
 mod utils;
 mod zkp;
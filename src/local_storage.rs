@@ -10,22 +10,39 @@
 //! [`LocalStorage`] provides a means for storing values associated with a
 //! [`TypeTag`] and [`ParticipantIdentifier`]. Values can
 //! be either stored, retrieved, and looked up in the storage.
+//!
+//! The contents of a [`LocalStorage`] can be serialized with
+//! [`LocalStorage::export()`] and restored with [`LocalStorage::import()`], so
+//! that a protocol execution can be resumed after a restart. Restored values
+//! are kept in serialized form until they are first accessed, since their
+//! type is only known at that point.
 
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use tracing::error;
+use zeroize::{ZeroizeOnDrop, Zeroizing};
 
 use crate::{
     errors::{InternalError, Result},
     ParticipantIdentifier,
 };
 use std::{
-    any::{Any, TypeId},
+    any::Any,
+    borrow::Cow,
     collections::HashMap,
+    fmt::{Debug, Formatter},
+    sync::OnceLock,
 };
 
 /// A type implementing `TypeTag` can be used to store and retrieve
 /// values of type `<T as TypeTag>::Value`.
 pub(crate) trait TypeTag: 'static {
-    type Value: Send + Sync;
+    type Value: Send + Sync + Serialize + DeserializeOwned;
+    /// The name that entries for this tag are stored under when exported.
+    ///
+    /// This must be unique among the tags used by a protocol, and must not
+    /// change between releases, or exported storage can no longer be
+    /// imported.
+    const NAME: &'static str;
 }
 
 pub(crate) mod storage {
@@ -35,18 +52,148 @@ pub(crate) mod storage {
     pub(crate) struct MessageQueue;
     impl TypeTag for MessageQueue {
         type Value = crate::message_queue::MessageQueue;
+        const NAME: &'static str = "storage.MessageQueue";
     }
 
     pub(crate) struct ProgressStore;
     impl TypeTag for ProgressStore {
         type Value = HashSet<String>;
+        const NAME: &'static str = "storage.ProgressStore";
+    }
+}
+
+/// The key for a storage entry.
+///
+/// Entries are keyed by the [name](TypeTag::NAME) of their [`TypeTag`] rather
+/// than its [`TypeId`](std::any::TypeId), so that an exported entry can be
+/// matched up with its tag when it is imported.
+type Key = (ParticipantIdentifier, Cow<'static, str>);
+
+fn key<T: TypeTag>(participant_id: ParticipantIdentifier) -> Key {
+    (participant_id, Cow::Borrowed(T::NAME))
+}
+
+/// A stored value, along with the function that serializes it.
+struct StoredValue {
+    value: Box<dyn Any + Send + Sync>,
+    encode: fn(&(dyn Any + Send + Sync)) -> Result<Vec<u8>>,
+}
+
+impl StoredValue {
+    fn new<T: TypeTag>(value: T::Value) -> Self {
+        Self {
+            value: Box::new(value),
+            encode: encode::<T>,
+        }
     }
 }
 
+fn encode<T: TypeTag>(value: &(dyn Any + Send + Sync)) -> Result<Vec<u8>> {
+    let value = value.downcast_ref::<T::Value>().ok_or_else(|| {
+        error!(
+            "Could not downcast storage entry for serialization. Type: {:?}",
+            std::any::type_name::<T::Value>()
+        );
+        InternalError::InternalInvariantFailed
+    })?;
+    serialize!(value)
+}
+
+/// An entry in [`LocalStorage`].
+///
+/// An entry holds either a value, or (if it was imported and hasn't been
+/// accessed yet) the serialized form of a value.
+struct Entry {
+    value: OnceLock<StoredValue>,
+    encoded: Option<Zeroizing<Vec<u8>>>,
+}
+
+impl Debug for Entry {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        // Stored values may be secret, so don't print them.
+        f.debug_struct("Entry")
+            .field("decoded", &self.value.get().is_some())
+            .finish_non_exhaustive()
+    }
+}
+
+impl Entry {
+    fn new<T: TypeTag>(value: T::Value) -> Self {
+        Self {
+            value: OnceLock::from(StoredValue::new::<T>(value)),
+            encoded: None,
+        }
+    }
+
+    fn decode<T: TypeTag>(&self) -> Result<StoredValue> {
+        let encoded = self.encoded.as_ref().ok_or_else(|| {
+            error!("Storage entry has neither a value nor a serialized value");
+            InternalError::InternalInvariantFailed
+        })?;
+        let value: T::Value = deserialize!(encoded.as_slice()).map_err(|e| {
+            error!(
+                "Could not deserialize imported storage entry. Type: {:?}",
+                std::any::type_name::<T::Value>()
+            );
+            e
+        })?;
+        Ok(StoredValue::new::<T>(value))
+    }
+
+    fn get<T: TypeTag>(&self) -> Result<&StoredValue> {
+        match self.value.get() {
+            Some(value) => Ok(value),
+            None => {
+                let decoded = self.decode::<T>()?;
+                Ok(self.value.get_or_init(|| decoded))
+            }
+        }
+    }
+
+    fn get_mut<T: TypeTag>(&mut self) -> Result<&mut StoredValue> {
+        if self.value.get().is_none() {
+            let decoded = self.decode::<T>()?;
+            let _ = self.value.set(decoded);
+        }
+        self.value.get_mut().ok_or_else(|| {
+            error!("Storage entry was decoded but has no value");
+            InternalError::InternalInvariantFailed
+        })
+    }
+
+    fn into_value<T: TypeTag>(mut self) -> Result<StoredValue> {
+        match self.value.take() {
+            Some(value) => Ok(value),
+            None => self.decode::<T>(),
+        }
+    }
+
+    fn encode(&self) -> Result<Vec<u8>> {
+        match (self.value.get(), &self.encoded) {
+            (Some(value), _) => (value.encode)(&*value.value),
+            (None, Some(encoded)) => Ok(encoded.to_vec()),
+            (None, None) => {
+                error!("Storage entry has neither a value nor a serialized value");
+                Err(InternalError::InternalInvariantFailed)
+            }
+        }
+    }
+}
+
+/// The serialized form of an [`Entry`].
+#[derive(Serialize, Deserialize, ZeroizeOnDrop)]
+struct EncodedEntry {
+    #[zeroize(skip)]
+    participant_id: ParticipantIdentifier,
+    #[zeroize(skip)]
+    tag: String,
+    value: Vec<u8>,
+}
+
 /// A type for storing values local to a protocol.
 #[derive(Debug, Default)]
 pub(crate) struct LocalStorage {
-    storage: HashMap<(ParticipantIdentifier, TypeId), Box<dyn Any + Send + Sync>>,
+    storage: HashMap<Key, Entry>,
 }
 
 impl LocalStorage {
@@ -59,7 +206,7 @@ impl LocalStorage {
     ) {
         let _ = self
             .storage
-            .insert((participant_id, TypeId::of::<T>()), Box::new(value));
+            .insert(key::<T>(participant_id), Entry::new::<T>(value));
     }
 
     /// Retrieves a reference to a value via its [`TypeTag`] and
@@ -72,16 +219,20 @@ impl LocalStorage {
         participant_id: ParticipantIdentifier,
     ) -> Result<&T::Value> {
         self.storage
-            .get(&(participant_id, TypeId::of::<T>()))
-            .map(|any| {
-                any.downcast_ref::<T::Value>().ok_or_else(|| {
-                    error!(
-                        "Could not downcast storage entry. Type: {:?}, participant_id: {}",
-                        std::any::type_name::<T::Value>(),
-                        participant_id
-                    );
-                    InternalError::InternalInvariantFailed
-                })
+            .get(&key::<T>(participant_id))
+            .map(|entry| {
+                entry
+                    .get::<T>()?
+                    .value
+                    .downcast_ref::<T::Value>()
+                    .ok_or_else(|| {
+                        error!(
+                            "Could not downcast storage entry. Type: {:?}, participant_id: {}",
+                            std::any::type_name::<T::Value>(),
+                            participant_id
+                        );
+                        InternalError::InternalInvariantFailed
+                    })
             })
             .unwrap_or_else(|| {
                 error!(
@@ -103,7 +254,7 @@ impl LocalStorage {
         participant_id: ParticipantIdentifier,
     ) -> Result<T::Value> {
         self.storage
-            .remove(&(participant_id, TypeId::of::<T>()))
+            .remove(&key::<T>(participant_id))
             .ok_or_else(|| {
                 error!(
                     "Could not locate storage entry. Type: {:?}, participant_id: {}",
@@ -112,6 +263,8 @@ impl LocalStorage {
                 );
                 InternalError::InternalInvariantFailed
             })?
+            .into_value::<T>()?
+            .value
             .downcast::<T::Value>()
             .map_err(|_| {
                 error!(
@@ -130,8 +283,8 @@ impl LocalStorage {
         &mut self,
         participant_id: ParticipantIdentifier,
     ) -> Option<&mut T::Value> {
-        match self.storage.get_mut(&(participant_id, TypeId::of::<T>())) {
-            Some(any) => any.downcast_mut::<T::Value>(),
+        match self.storage.get_mut(&key::<T>(participant_id)) {
+            Some(entry) => entry.get_mut::<T>().ok()?.value.downcast_mut::<T::Value>(),
             None => None,
         }
     }
@@ -151,10 +304,123 @@ impl LocalStorage {
         true
     }
 
+    /// Returns the `participant_ids` that don't have a value for the given
+    /// [`TypeTag`], in the order they were provided.
+    pub(crate) fn missing_ids<T: TypeTag>(
+        &self,
+        participant_ids: &[ParticipantIdentifier],
+    ) -> Vec<ParticipantIdentifier> {
+        participant_ids
+            .iter()
+            .copied()
+            .filter(|pid| !self.contains::<T>(*pid))
+            .collect()
+    }
+
     /// Returns `true` if a value exists for the given [`TypeTag`]
     /// and [`ParticipantIdentifier`].
     pub(crate) fn contains<T: TypeTag>(&self, participant_id: ParticipantIdentifier) -> bool {
-        self.storage
-            .contains_key(&(participant_id, TypeId::of::<T>()))
+        self.storage.contains_key(&key::<T>(participant_id))
+    }
+
+    /// Serialize the contents of the storage.
+    ///
+    /// 🔒 The output may contain secret values and must be handled with care.
+    pub(crate) fn export(&self) -> Result<Vec<u8>> {
+        let entries = self
+            .storage
+            .iter()
+            .map(|((participant_id, tag), entry)| {
+                Ok(EncodedEntry {
+                    participant_id: *participant_id,
+                    tag: tag.to_string(),
+                    value: entry.encode()?,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        serialize!(&entries)
+    }
+
+    /// Restore storage serialized by [`LocalStorage::export()`].
+    pub(crate) fn import(bytes: &[u8]) -> Result<Self> {
+        let entries: Vec<EncodedEntry> = deserialize!(bytes)?;
+        let storage = entries
+            .into_iter()
+            .map(|mut entry| {
+                let key = (
+                    entry.participant_id,
+                    Cow::Owned(std::mem::take(&mut entry.tag)),
+                );
+                let entry = Entry {
+                    value: OnceLock::new(),
+                    encoded: Some(Zeroizing::new(std::mem::take(&mut entry.value))),
+                };
+                (key, entry)
+            })
+            .collect();
+        Ok(Self { storage })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::testing::init_testing;
+
+    struct Counter;
+    impl TypeTag for Counter {
+        type Value = u64;
+        const NAME: &'static str = "test.Counter";
+    }
+
+    struct Name;
+    impl TypeTag for Name {
+        type Value = String;
+        const NAME: &'static str = "test.Name";
+    }
+
+    #[test]
+    fn storage_survives_export_and_import() {
+        let rng = &mut init_testing();
+        let pid = ParticipantIdentifier::random(rng);
+        let other_pid = ParticipantIdentifier::random(rng);
+
+        let mut storage = LocalStorage::default();
+        storage.store::<Counter>(pid, 3);
+        storage.store::<Counter>(other_pid, 4);
+        storage.store::<Name>(pid, "alice".to_string());
+
+        let mut imported = LocalStorage::import(&storage.export().unwrap()).unwrap();
+        assert!(imported.contains_for_all_ids::<Counter>(&[pid, other_pid]));
+        assert!(!imported.contains::<Name>(other_pid));
+        assert_eq!(imported.retrieve::<Counter>(pid).unwrap(), &3);
+        assert_eq!(imported.retrieve::<Name>(pid).unwrap(), "alice");
+
+        // Values can be modified and removed after import, and exporting again
+        // picks up the changes
+        *imported.retrieve_mut::<Counter>(other_pid).unwrap() += 1;
+        assert_eq!(imported.remove::<Name>(pid).unwrap(), "alice");
+        let reimported = LocalStorage::import(&imported.export().unwrap()).unwrap();
+        assert_eq!(reimported.retrieve::<Counter>(pid).unwrap(), &3);
+        assert_eq!(reimported.retrieve::<Counter>(other_pid).unwrap(), &5);
+        assert!(!reimported.contains::<Name>(pid));
+    }
+
+    #[test]
+    fn imported_values_must_have_the_right_type() {
+        let rng = &mut init_testing();
+        let pid = ParticipantIdentifier::random(rng);
+
+        let mut storage = LocalStorage::default();
+        storage.store::<Counter>(pid, 3);
+
+        // Rename the entry, so that it's imported under the wrong tag
+        let mut entries: Vec<EncodedEntry> = deserialize!(&storage.export().unwrap()).unwrap();
+        assert_eq!(entries[0].tag, Counter::NAME);
+        entries[0].tag = Name::NAME.to_string();
+        let imported = LocalStorage::import(&serialize!(&entries).unwrap()).unwrap();
+
+        assert!(imported.contains::<Name>(pid));
+        assert!(imported.retrieve::<Name>(pid).is_err());
     }
 }
@@ -18,10 +18,11 @@ use crate::{
     messages::{Message, MessageType},
     ParticipantIdentifier,
 };
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
 /// A type for storing a queue of [`Message`]s by [`MessageType`].
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub(crate) struct MessageQueue(HashMap<MessageType, Vec<Message>>);
 
 impl MessageQueue {
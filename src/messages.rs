@@ -12,8 +12,9 @@
 //! passed between participants
 
 use crate::{
-    errors::{InternalError, Result},
+    errors::{CallerError, InternalError, Result},
     protocol::{Identifier, ParticipantIdentifier},
+    utils::ParseBytes,
 };
 use serde::{Deserialize, Serialize};
 use std::fmt::Debug;
@@ -24,18 +25,29 @@ use tracing::{error, instrument, trace};
 /////////////////
 
 /// An enum consisting of all message types
+///
+/// Message types are serialized as a fixed numeric code, so that adding or
+/// reordering variants doesn't change the encoding of existing types.
 #[derive(Debug, Copy, Clone, Hash, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(into = "u16", try_from = "u16")]
 pub enum MessageType {
     /// Auxinfo messages
     Auxinfo(AuxinfoMessageType),
     /// Keygen messages
     Keygen(KeygenMessageType),
+    /// Key refresh messages
+    Refresh(RefreshMessageType),
+    /// Key resharing messages
+    Reshare(ReshareMessageType),
     /// Presign messages
     Presign(PresignMessageType),
     /// Sign message
     Sign(SignMessageType),
     /// Broadcast messages
     Broadcast(BroadcastMessageType),
+    /// Sent by a participant that has abandoned the session, see
+    /// [`Participant::abort()`](crate::Participant::abort())
+    Abort,
 }
 
 /// An enum consisting of all auxinfo message types
@@ -61,11 +73,40 @@ pub enum KeygenMessageType {
     R1CommitHash,
     /// The information committed to in Round 1
     R2Decommit,
+    /// A private Shamir share of the sender's key share, sent only in
+    /// threshold mode
+    R2PrivateShare,
     /// A proof of knowledge of the discrete log of the value decommitted in
     /// Round 2
     R3Proof,
 }
 
+/// An enum consisting of all key refresh message types
+#[derive(Debug, Copy, Clone, Hash, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RefreshMessageType {
+    /// Signal to self that we're ready to run the protocol
+    Ready,
+    /// A hash commitment to the public updates and associated proofs
+    R1CommitHash,
+    /// The information committed to in Round 1
+    R2Decommit,
+    /// An encrypted private update for the recipient, along with proofs of
+    /// knowledge of the discrete log of each public update decommitted in
+    /// Round 2
+    R3EncryptedUpdate,
+}
+
+/// An enum consisting of all key resharing message types
+#[derive(Debug, Copy, Clone, Hash, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ReshareMessageType {
+    /// Signal to self that we're ready to run the protocol
+    Ready,
+    /// A dealer's commitments to the shares of its key share
+    R1Dealing,
+    /// A private share of the sender's key share for a new participant
+    R1PrivateShare,
+}
+
 /// An enum consisting of all presign message types
 #[derive(Debug, Copy, Clone, Hash, PartialEq, Eq, Serialize, Deserialize)]
 pub enum PresignMessageType {
@@ -79,6 +120,8 @@ pub enum PresignMessageType {
     RoundTwo,
     /// Third round of presigning
     RoundThree,
+    /// Identification phase, run if the final presigning check fails
+    Identification,
 }
 
 /// Messages sent during the signing protocol
@@ -99,6 +142,125 @@ pub enum BroadcastMessageType {
     Redisperse,
 }
 
+impl From<MessageType> for u16 {
+    /// The stable code for each [`MessageType`] used in the wire format. The
+    /// high byte identifies the subprotocol and the low byte the message
+    /// within it.
+    ///
+    /// Codes must never be changed or reused; new message types get new
+    /// codes.
+    fn from(message_type: MessageType) -> Self {
+        use MessageType::*;
+        match message_type {
+            Auxinfo(ty) => match ty {
+                AuxinfoMessageType::Ready => 0x0100,
+                AuxinfoMessageType::R1CommitHash => 0x0101,
+                AuxinfoMessageType::R2Decommit => 0x0102,
+                AuxinfoMessageType::R3Proof => 0x0103,
+            },
+            Keygen(ty) => match ty {
+                KeygenMessageType::Ready => 0x0200,
+                KeygenMessageType::R1CommitHash => 0x0201,
+                KeygenMessageType::R2Decommit => 0x0202,
+                KeygenMessageType::R2PrivateShare => 0x0203,
+                KeygenMessageType::R3Proof => 0x0204,
+            },
+            Refresh(ty) => match ty {
+                RefreshMessageType::Ready => 0x0300,
+                RefreshMessageType::R1CommitHash => 0x0301,
+                RefreshMessageType::R2Decommit => 0x0302,
+                RefreshMessageType::R3EncryptedUpdate => 0x0303,
+            },
+            Reshare(ty) => match ty {
+                ReshareMessageType::Ready => 0x0400,
+                ReshareMessageType::R1Dealing => 0x0401,
+                ReshareMessageType::R1PrivateShare => 0x0402,
+            },
+            Presign(ty) => match ty {
+                PresignMessageType::Ready => 0x0500,
+                PresignMessageType::RoundOne => 0x0501,
+                PresignMessageType::RoundOneBroadcast => 0x0502,
+                PresignMessageType::RoundTwo => 0x0503,
+                PresignMessageType::RoundThree => 0x0504,
+                PresignMessageType::Identification => 0x0505,
+            },
+            Sign(ty) => match ty {
+                SignMessageType::Ready => 0x0600,
+                SignMessageType::RoundOneShare => 0x0601,
+            },
+            Broadcast(ty) => match ty {
+                BroadcastMessageType::Disperse => 0x0700,
+                BroadcastMessageType::Redisperse => 0x0701,
+            },
+            Abort => 0x0800,
+        }
+    }
+}
+
+impl TryFrom<u16> for MessageType {
+    type Error = CallerError;
+
+    fn try_from(code: u16) -> std::result::Result<Self, Self::Error> {
+        use MessageType::*;
+        let message_type = match code {
+            0x0100 => Auxinfo(AuxinfoMessageType::Ready),
+            0x0101 => Auxinfo(AuxinfoMessageType::R1CommitHash),
+            0x0102 => Auxinfo(AuxinfoMessageType::R2Decommit),
+            0x0103 => Auxinfo(AuxinfoMessageType::R3Proof),
+            0x0200 => Keygen(KeygenMessageType::Ready),
+            0x0201 => Keygen(KeygenMessageType::R1CommitHash),
+            0x0202 => Keygen(KeygenMessageType::R2Decommit),
+            0x0203 => Keygen(KeygenMessageType::R2PrivateShare),
+            0x0204 => Keygen(KeygenMessageType::R3Proof),
+            0x0300 => Refresh(RefreshMessageType::Ready),
+            0x0301 => Refresh(RefreshMessageType::R1CommitHash),
+            0x0302 => Refresh(RefreshMessageType::R2Decommit),
+            0x0303 => Refresh(RefreshMessageType::R3EncryptedUpdate),
+            0x0400 => Reshare(ReshareMessageType::Ready),
+            0x0401 => Reshare(ReshareMessageType::R1Dealing),
+            0x0402 => Reshare(ReshareMessageType::R1PrivateShare),
+            0x0500 => Presign(PresignMessageType::Ready),
+            0x0501 => Presign(PresignMessageType::RoundOne),
+            0x0502 => Presign(PresignMessageType::RoundOneBroadcast),
+            0x0503 => Presign(PresignMessageType::RoundTwo),
+            0x0504 => Presign(PresignMessageType::RoundThree),
+            0x0505 => Presign(PresignMessageType::Identification),
+            0x0600 => Sign(SignMessageType::Ready),
+            0x0601 => Sign(SignMessageType::RoundOneShare),
+            0x0700 => Broadcast(BroadcastMessageType::Disperse),
+            0x0701 => Broadcast(BroadcastMessageType::Redisperse),
+            0x0800 => Abort,
+            _ => {
+                error!("Unknown message type code {:#06x}", code);
+                Err(CallerError::DeserializationFailed)?
+            }
+        };
+        Ok(message_type)
+    }
+}
+
+/// The version of the wire format produced by [`Message::to_bytes()`].
+///
+/// The version covers the envelope: the layout of the header fields and the
+/// [message type](MessageType) codes. It must be incremented whenever either
+/// changes.
+///
+/// Round payloads are the serde encoding of the protocol's internal round
+/// types, which can change between releases of this library without a change
+/// to the wire version. They are versioned separately by
+/// [`PAYLOAD_VERSION`].
+pub const WIRE_VERSION: u16 = 2;
+
+/// The version of the round payloads in messages produced by
+/// [`Message::to_bytes()`].
+///
+/// This is the version of this library, so payloads are only accepted from
+/// participants running the same release.
+pub const PAYLOAD_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Tag at the start of every encoded [`Message`].
+const MESSAGE_TAG: &[u8] = b"tss-ecdsa msg";
+
 /// A message that can be posted to (and read from) the communication channel.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Message {
@@ -181,6 +343,104 @@ impl Message {
         self.to
     }
 
+    /// Encode the message in the versioned wire format.
+    ///
+    /// This is the encoding that should be used to send messages between
+    /// participants. The header records both the [`WIRE_VERSION`] and the
+    /// [`PAYLOAD_VERSION`].
+    pub fn to_bytes(&self) -> Vec<u8> {
+        // Format:
+        // MESSAGE TAG
+        // Wire version (2 bytes)
+        // Payload version length in bytes (8 bytes)
+        // Payload version
+        // Message type code (2 bytes)
+        // Session identifier (16 bytes)
+        // Sender (16 bytes)
+        // Recipient (16 bytes)
+        // Payload length in bytes (8 bytes)
+        // Payload
+        [
+            MESSAGE_TAG,
+            &WIRE_VERSION.to_le_bytes(),
+            &PAYLOAD_VERSION.len().to_le_bytes(),
+            PAYLOAD_VERSION.as_bytes(),
+            &u16::from(self.message_type).to_le_bytes(),
+            &self.identifier.to_bytes(),
+            &self.from.to_bytes(),
+            &self.to.to_bytes(),
+            &self.unverified_bytes.len().to_le_bytes(),
+            &self.unverified_bytes,
+        ]
+        .concat()
+    }
+
+    /// Decode a message from the output of [`Message::to_bytes()`].
+    ///
+    /// Fails with [`CallerError::UnsupportedWireVersion`] if the message was
+    /// encoded with a different version of the wire format, or if its payload
+    /// was produced by a different version of this library.
+    pub fn try_from_bytes(bytes: Vec<u8>) -> Result<Self> {
+        let mut parser = ParseBytes::new(bytes);
+
+        let actual_tag = parser.take_bytes(MESSAGE_TAG.len())?;
+        if actual_tag != MESSAGE_TAG {
+            error!("Failed to deserialize `Message`: the tag is incorrect");
+            Err(CallerError::DeserializationFailed)?
+        }
+
+        let version = u16::from_le_bytes(Self::take_array(&mut parser)?);
+        if version != WIRE_VERSION {
+            error!(
+                "Received a message with wire format version {}, but only version {} is supported",
+                version, WIRE_VERSION
+            );
+            Err(CallerError::UnsupportedWireVersion)?
+        }
+
+        // Check the length first, so that a bogus length can't make us read
+        // past the end of the message
+        let payload_version_len = parser.take_len()?;
+        if payload_version_len != PAYLOAD_VERSION.len()
+            || parser.take_bytes(payload_version_len)? != PAYLOAD_VERSION.as_bytes()
+        {
+            error!(
+                "Received a message with a payload from a different release; only version {} is supported",
+                PAYLOAD_VERSION
+            );
+            Err(CallerError::UnsupportedWireVersion)?
+        }
+
+        let message_type =
+            MessageType::try_from(u16::from_le_bytes(Self::take_array(&mut parser)?))?;
+        let identifier = Identifier::from_bytes(Self::take_array(&mut parser)?);
+        let from = ParticipantIdentifier::from_bytes(Self::take_array(&mut parser)?);
+        let to = ParticipantIdentifier::from_bytes(Self::take_array(&mut parser)?);
+
+        // The payload must make up the rest of the bytes
+        let payload_len = parser.take_len()?;
+        let payload = parser.take_rest()?;
+        if payload.len() != payload_len {
+            error!("Failed to deserialize `Message`: the payload length is incorrect");
+            Err(CallerError::DeserializationFailed)?
+        }
+
+        Ok(Self {
+            message_type,
+            identifier,
+            from,
+            to,
+            unverified_bytes: payload.to_vec(),
+        })
+    }
+
+    fn take_array<const N: usize>(parser: &mut ParseBytes) -> Result<[u8; N]> {
+        parser
+            .take_bytes(N)?
+            .try_into()
+            .map_err(|_| CallerError::DeserializationFailed.into())
+    }
+
     /// Check if the message type is correct.
     pub(crate) fn check_type(&self, expected_type: MessageType) -> Result<()> {
         if self.message_type() != expected_type {
@@ -194,3 +454,100 @@ impl Message {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::testing::init_testing;
+
+    #[test]
+    fn every_message_type_has_a_unique_code() {
+        let codes: Vec<u16> = (0..=u16::MAX)
+            .filter(|code| MessageType::try_from(*code).is_ok())
+            .collect();
+        assert_eq!(codes.len(), 27);
+        for code in codes {
+            let message_type = MessageType::try_from(code).unwrap();
+            assert_eq!(u16::from(message_type), code);
+        }
+    }
+
+    #[test]
+    fn wire_format_is_stable() -> Result<()> {
+        let message = Message::new_from_serialized_data(
+            MessageType::Presign(PresignMessageType::RoundTwo),
+            Identifier::from(1),
+            ParticipantIdentifier::from_bytes([2; 16]),
+            ParticipantIdentifier::from_bytes([3; 16]),
+            vec![4, 5],
+        )?;
+        let bytes = message.to_bytes();
+        let expected = [
+            MESSAGE_TAG,
+            &[2, 0],
+            &PAYLOAD_VERSION.len().to_le_bytes(),
+            PAYLOAD_VERSION.as_bytes(),
+            &[0x03, 0x05],
+            &1u128.to_le_bytes(),
+            &[2; 16],
+            &[3; 16],
+            &[2, 0, 0, 0, 0, 0, 0, 0],
+            &[4, 5],
+        ]
+        .concat();
+        assert_eq!(bytes, expected);
+
+        let decoded = Message::try_from_bytes(bytes)?;
+        assert_eq!(decoded.message_type(), message.message_type());
+        assert_eq!(decoded.id(), message.id());
+        assert_eq!(decoded.from(), message.from());
+        assert_eq!(decoded.to(), message.to());
+        assert_eq!(decoded.unverified_bytes, message.unverified_bytes);
+        Ok(())
+    }
+
+    #[test]
+    fn malformed_messages_are_rejected() -> Result<()> {
+        let rng = &mut init_testing();
+        let message = Message::new(
+            MessageType::Keygen(KeygenMessageType::R1CommitHash),
+            Identifier::random(rng),
+            ParticipantIdentifier::random(rng),
+            ParticipantIdentifier::random(rng),
+            &[7u8; 32],
+        )?;
+        let bytes = message.to_bytes();
+        let version_offset = MESSAGE_TAG.len();
+        let payload_version_offset = version_offset + 2 + 8;
+        let type_offset = payload_version_offset + PAYLOAD_VERSION.len();
+
+        // Unknown versions are rejected with a specific error
+        let mut bad_version = bytes.clone();
+        bad_version[version_offset] = 1;
+        assert_eq!(
+            Message::try_from_bytes(bad_version).unwrap_err(),
+            InternalError::CallingApplicationMistake(CallerError::UnsupportedWireVersion)
+        );
+
+        // As are payloads from other releases
+        let mut bad_payload_version = bytes.clone();
+        bad_payload_version[payload_version_offset] ^= 1;
+        assert_eq!(
+            Message::try_from_bytes(bad_payload_version).unwrap_err(),
+            InternalError::CallingApplicationMistake(CallerError::UnsupportedWireVersion)
+        );
+
+        // So are unknown message types
+        let mut bad_type = bytes.clone();
+        bad_type[type_offset..type_offset + 2].copy_from_slice(&[0xff, 0xff]);
+        assert!(Message::try_from_bytes(bad_type).is_err());
+
+        // And truncated or extended messages
+        assert!(Message::try_from_bytes(bytes[..bytes.len() - 1].to_vec()).is_err());
+        assert!(Message::try_from_bytes([bytes.as_slice(), &[0]].concat()).is_err());
+
+        // And anything without the tag
+        assert!(Message::try_from_bytes(bytes[1..].to_vec()).is_err());
+        Ok(())
+    }
+}
@@ -12,7 +12,10 @@ use crate::{
 use libpaillier::unknown_order::BigNumber;
 use rand::{CryptoRng, RngCore};
 use serde::{Deserialize, Serialize};
-use std::fmt::Debug;
+use std::{
+    fmt::Debug,
+    sync::{Arc, Mutex},
+};
 use thiserror::Error;
 use tracing::error;
 use zeroize::ZeroizeOnDrop;
@@ -38,6 +41,8 @@ pub enum PaillierError {
     EncryptionFailed { x: BigNumber, n: BigNumber },
     #[error("The provided RNG failed to produce suitable values after a maximum number of attempts. Please check the RNG.")]
     RetryFailed,
+    #[error("A pool of precomputed nonces was poisoned by a panic in another thread")]
+    PoisonedNoncePool,
 
     #[cfg(test)]
     #[error("No pre-generated primes with size {0}")]
@@ -91,8 +96,81 @@ impl Ciphertext {
     }
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
-pub(crate) struct EncryptionKey(libpaillier::EncryptionKey);
+/// A nonce `r` together with `r^N mod N²`, which is the expensive part of
+/// encrypting with it.
+#[derive(ZeroizeOnDrop)]
+pub(crate) struct PrecomputedNonce {
+    nonce: Nonce,
+    randomizer: BigNumber,
+}
+
+/// A queue of [`PrecomputedNonce`]s for one [`EncryptionKey`].
+///
+/// Clones of a pool share the same queue, so nonces can be added from one
+/// thread while the key is used to encrypt on another. Each nonce is taken
+/// from the queue at most once.
+#[derive(Clone, Default)]
+pub(crate) struct NoncePool(Arc<Mutex<Vec<PrecomputedNonce>>>);
+
+impl NoncePool {
+    /// Take a nonce from the pool, if there are any.
+    ///
+    /// A poisoned pool is treated as empty, so encryption falls back to
+    /// generating a fresh nonce.
+    fn take(&self) -> Option<PrecomputedNonce> {
+        self.0.lock().ok()?.pop()
+    }
+
+    /// Add `nonces` to the pool.
+    pub(crate) fn extend(&self, nonces: Vec<PrecomputedNonce>) -> Result<()> {
+        self.0
+            .lock()
+            .map_err(|_| {
+                error!("Nonce pool lock was poisoned");
+                PaillierError::PoisonedNoncePool
+            })?
+            .extend(nonces);
+        Ok(())
+    }
+
+    /// The number of nonces in the pool.
+    pub(crate) fn len(&self) -> Result<usize> {
+        self.0.lock().map(|nonces| nonces.len()).map_err(|_| {
+            error!("Nonce pool lock was poisoned");
+            PaillierError::PoisonedNoncePool
+        })
+    }
+}
+
+/// A Paillier encryption key.
+///
+/// The key can have a [`NoncePool`] attached, in which case
+/// [`EncryptionKey::encrypt()`] uses its precomputed nonces before generating
+/// new ones. The pool is not serialized.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(
+    from = "libpaillier::EncryptionKey",
+    into = "libpaillier::EncryptionKey"
+)]
+pub(crate) struct EncryptionKey(libpaillier::EncryptionKey, Option<NoncePool>);
+
+impl From<libpaillier::EncryptionKey> for EncryptionKey {
+    fn from(key: libpaillier::EncryptionKey) -> Self {
+        Self(key, None)
+    }
+}
+
+impl From<EncryptionKey> for libpaillier::EncryptionKey {
+    fn from(key: EncryptionKey) -> Self {
+        key.0
+    }
+}
+
+impl Debug for EncryptionKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("EncryptionKey").field(&self.0).finish()
+    }
+}
 
 // This is stupid and should have been derived by the underlying crate.
 impl PartialEq for EncryptionKey {
@@ -131,7 +209,12 @@ impl EncryptionKey {
         x: &BigNumber,
     ) -> Result<(Ciphertext, Nonce)> {
         // Note: the check that `x` is in the proper range happens in
-        // `encrypt_with_nonce`.
+        // `encrypt_with_randomizer`.
+        if let Some(precomputed) = self.1.as_ref().and_then(NoncePool::take) {
+            let c = self.encrypt_with_randomizer(x, &precomputed.randomizer)?;
+            return Ok((c, precomputed.nonce.clone()));
+        }
+
         let nonce =
             random_bn_in_z_star(rng, self.modulus()).map_err(|_| PaillierError::RetryFailed)?;
         let c = self.encrypt_with_nonce(x, &MaskedNonce(nonce.clone()))?;
@@ -150,6 +233,12 @@ impl EncryptionKey {
         x: &BigNumber,
         nonce: &MaskedNonce,
     ) -> Result<Ciphertext> {
+        let randomizer = nonce.0.modpow(self.modulus(), self.0.nn());
+        self.encrypt_with_randomizer(x, &randomizer)
+    }
+
+    /// Encrypt plaintext `x` given `randomizer = r^N mod N²` for a nonce `r`.
+    fn encrypt_with_randomizer(&self, x: &BigNumber, randomizer: &BigNumber) -> Result<Ciphertext> {
         if &self.half_n() < x || x < &-self.half_n() {
             Err(PaillierError::EncryptionFailed {
                 x: x.clone(),
@@ -162,14 +251,38 @@ impl EncryptionKey {
         // canonical range `[0, N)` instead of in our range around 0. It seemed
         // less confusing to implement encryption directly than to try to move
         // the plaintext to the canonical range.
-        let one = BigNumber::one();
-        let base = one + self.modulus();
-        let a = base.modpow(x, self.0.nn());
-        let b = nonce.0.modpow(self.modulus(), self.0.nn());
-        let c = a.modmul(&b, self.0.nn());
+        let a = encode_plaintext(x, self.modulus());
+        let c = a.modmul(randomizer, self.0.nn());
         Ok(Ciphertext(c))
     }
 
+    /// Generate `count` nonces for this key, along with the expensive part of
+    /// encrypting with each of them.
+    pub(crate) fn precompute_nonces<R: RngCore + CryptoRng>(
+        &self,
+        rng: &mut R,
+        count: usize,
+    ) -> Result<Vec<PrecomputedNonce>> {
+        std::iter::repeat_with(|| -> Result<PrecomputedNonce> {
+            let nonce =
+                random_bn_in_z_star(rng, self.modulus()).map_err(|_| PaillierError::RetryFailed)?;
+            let randomizer = nonce.modpow(self.modulus(), self.0.nn());
+            Ok(PrecomputedNonce {
+                nonce: Nonce(nonce),
+                randomizer,
+            })
+        })
+        .take(count)
+        .collect()
+    }
+
+    /// Use the nonces in `pool` when encrypting under this key.
+    ///
+    /// The pool must only ever hold nonces precomputed for this key.
+    pub(crate) fn set_nonce_pool(&mut self, pool: NoncePool) {
+        self.1 = Some(pool);
+    }
+
     #[cfg(test)]
     /// Generate a random ciphertext for testing purposes.
     pub(crate) fn random_ciphertext(&self, rng: &mut (impl RngCore + CryptoRng)) -> Ciphertext {
@@ -216,8 +329,41 @@ impl EncryptionKey {
     }
 }
 
+/// Compute `(1 + N)^x mod N²`.
+///
+/// By the binomial theorem, this is equal to `1 + xN mod N²`, which saves us a
+/// modular exponentiation per encryption.
+fn encode_plaintext(x: &BigNumber, modulus: &BigNumber) -> BigNumber {
+    BigNumber::one() + x.nmod(modulus) * modulus
+}
+
+/// A Paillier decryption key.
+///
+/// Alongside the key itself, we keep the [`CrtParams`] derived from it, so
+/// that the owner of the key can decrypt and encrypt by working modulo the
+/// factors of `N` instead of modulo `N²`. Only the key is serialized; the
+/// parameters are recomputed when it is deserialized.
 #[derive(Clone, Serialize, Deserialize, ZeroizeOnDrop)]
-pub(crate) struct DecryptionKey(libpaillier::DecryptionKey);
+#[serde(
+    try_from = "libpaillier::DecryptionKey",
+    into = "libpaillier::DecryptionKey"
+)]
+pub(crate) struct DecryptionKey(libpaillier::DecryptionKey, CrtParams);
+
+impl TryFrom<libpaillier::DecryptionKey> for DecryptionKey {
+    type Error = PaillierError;
+
+    fn try_from(key: libpaillier::DecryptionKey) -> Result<Self> {
+        let crt = CrtParams::new(key.n(), key.totient())?;
+        Ok(Self(key, crt))
+    }
+}
+
+impl From<DecryptionKey> for libpaillier::DecryptionKey {
+    fn from(key: DecryptionKey) -> Self {
+        key.0.clone()
+    }
+}
 
 impl Debug for DecryptionKey {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -246,17 +392,28 @@ impl DecryptionKey {
         (self.0.n() - 1) / 2
     }
 
+    /// Decrypt a [`Ciphertext`].
+    ///
+    /// The plaintext is recovered modulo `p` and `q` separately and then
+    /// combined, which is considerably faster than libpaillier's decryption
+    /// modulo `N²`. The result is in the range `[-N/2, N/2]`.
     pub(crate) fn decrypt(&self, c: &Ciphertext) -> Result<BigNumber> {
-        let mut x = self
-            .0
-            .decrypt(&c.0)
-            .ok_or(PaillierError::DecryptionFailed)
-            .map(BigNumber::from_slice)?;
-
-        // Switch representation into `[-N/2, N/2]`. libpaillier (and indeed,
-        // `BigNumber`s in general) returns values represented in the canonical
-        // range `[0, N)`. A single subtraction will land us in the expected
-        // range for this application.
+        let crt = &self.1;
+        if c.0 <= BigNumber::zero()
+            || c.0 >= crt.n_squared
+            || c.0.gcd(self.modulus()) != BigNumber::one()
+        {
+            Err(PaillierError::DecryptionFailed)?
+        }
+
+        let x_p = decrypt_mod_factor(&c.0, &crt.p, &crt.p_squared, &crt.h_p);
+        let x_q = decrypt_mod_factor(&c.0, &crt.q, &crt.q_squared, &crt.h_q);
+        let mut x = crt_combine(&x_p, &x_q, &crt.p, &crt.q, &crt.q_inverse_mod_p);
+
+        // Switch representation into `[-N/2, N/2]`. The CRT combination (and
+        // indeed, `BigNumber`s in general) returns values represented in the
+        // canonical range `[0, N)`. A single subtraction will land us in the
+        // expected range for this application.
         if x > self.half_n() {
             x -= self.0.n();
         }
@@ -264,6 +421,81 @@ impl DecryptionKey {
         Ok(x)
     }
 
+    /// Decrypt a [`Ciphertext`], returning both the plaintext and the [`Nonce`]
+    /// used to produce it.
+    ///
+    /// This is useful for ciphertexts that were computed homomorphically from
+    /// ciphertexts produced by other parties, where the nonce is otherwise
+    /// unknown. The nonce is recovered as `c^{N^{-1} mod φ(N)} mod N`, which
+    /// we compute modulo `p` and `q` separately.
+    pub(crate) fn decrypt_with_nonce(&self, c: &Ciphertext) -> Result<(BigNumber, Nonce)> {
+        let x = self.decrypt(c)?;
+        let crt = &self.1;
+        let nonce_p = (&c.0 % &crt.p).modpow(&crt.n_inverse_mod_p_minus_one, &crt.p);
+        let nonce_q = (&c.0 % &crt.q).modpow(&crt.n_inverse_mod_q_minus_one, &crt.q);
+        let nonce = crt_combine(&nonce_p, &nonce_q, &crt.p, &crt.q, &crt.q_inverse_mod_p);
+        Ok((x, Nonce(nonce)))
+    }
+
+    /// Encrypt plaintext `x` under the [`EncryptionKey`] corresponding to this
+    /// [`DecryptionKey`], returning the resulting [`Ciphertext`] and
+    /// [`Nonce`].
+    ///
+    /// This produces the same ciphertexts as [`EncryptionKey::encrypt()`], but
+    /// is faster, so it should be used whenever a party encrypts under its
+    /// own key.
+    ///
+    /// There is no precomputed-table ("fixed base") variant. `(1 + N)^x` is
+    /// already just `1 + xN`, and the remaining cost `r^N` has a fixed
+    /// exponent but a fresh base for every encryption, so a table of powers of
+    /// a fixed base doesn't apply. Using one would require nonces of the form
+    /// `h^a` for a fixed `h`, which is a different encryption scheme from the
+    /// one the proofs are written for. Nonces can instead be prepared ahead of
+    /// time with [`EncryptionKey::precompute_nonces()`].
+    pub(crate) fn encrypt<R: RngCore + CryptoRng>(
+        &self,
+        rng: &mut R,
+        x: &BigNumber,
+    ) -> Result<(Ciphertext, Nonce)> {
+        // Note: the check that `x` is in the proper range happens in
+        // `encrypt_with_nonce`.
+        let nonce =
+            random_bn_in_z_star(rng, self.modulus()).map_err(|_| PaillierError::RetryFailed)?;
+        let c = self.encrypt_with_nonce(x, &MaskedNonce(nonce.clone()))?;
+        Ok((c, Nonce(nonce)))
+    }
+
+    /// Encrypt plaintext `x` using the provided [`MaskedNonce`], producing the
+    /// same [`Ciphertext`] as [`EncryptionKey::encrypt_with_nonce()`].
+    ///
+    /// The nonce's contribution `nonce^N mod N²` is computed modulo `p²` and
+    /// `q²` separately. The plaintext must be in the range `[-N/2, N/2]`.
+    pub(crate) fn encrypt_with_nonce(
+        &self,
+        x: &BigNumber,
+        nonce: &MaskedNonce,
+    ) -> Result<Ciphertext> {
+        if &self.half_n() < x || x < &-self.half_n() {
+            Err(PaillierError::EncryptionFailed {
+                x: x.clone(),
+                n: self.modulus().clone(),
+            })?
+        }
+
+        let crt = &self.1;
+        let a = encode_plaintext(x, self.modulus());
+        let b_p = nonce.0.modpow(self.modulus(), &crt.p_squared);
+        let b_q = nonce.0.modpow(self.modulus(), &crt.q_squared);
+        let b = crt_combine(
+            &b_p,
+            &b_q,
+            &crt.p_squared,
+            &crt.q_squared,
+            &crt.q_squared_inverse_mod_p_squared,
+        );
+        Ok(Ciphertext(a.modmul(&b, &crt.n_squared)))
+    }
+
     /// Generate a new [`DecryptionKey`] and its factors.
     ///
     /// The factors `p` and `q` are `PRIME_BITS`-long safe primes, and the
@@ -271,48 +503,24 @@ impl DecryptionKey {
     pub(crate) fn new(
         rng: &mut (impl CryptoRng + RngCore),
     ) -> Result<(Self, BigNumber, BigNumber)> {
-        // Generate a pair of safe primes that are `PRIME_BITS` long and return them if
-        // their product is `2 * PRIME_BITS` long (otherwise return `None`).
-        let generate_prime_pair = || -> Result<(BigNumber, BigNumber)> {
-            // As generating safe primes can be computationally expensive (> one minute per
-            // prime in github CI), we read precomputed ones from a file (but
-            // only in tests!)
-            #[cfg(not(test))]
-            let (p, q) = (
-                prime_gen::get_random_safe_prime(rng),
-                prime_gen::get_random_safe_prime(rng),
-            );
-            #[cfg(test)]
-            let (p, q) = prime_gen::get_prime_pair_from_pool_insecure(rng)?;
-
-            if p.bit_length() == PRIME_BITS
-                && q.bit_length() == PRIME_BITS
-                && (&p * &q).bit_length() == 2 * PRIME_BITS
-            {
-                Ok((p, q))
-            } else {
-                Err(PaillierError::CouldNotCreateKey)?
-            }
-        };
-
-        // A Paillier decryption key is the product of the two primes, but sometimes two
-        // n/2-bit primes can produce an n-1 bit modulus. Allow some (lazily
-        // evaluated) retries to handle that error.
-        let (p, q) = std::iter::repeat_with(generate_prime_pair)
-            .take(CRYPTOGRAPHIC_RETRY_MAX)
-            .find(|result| result.is_ok())
-            // We hit the maximum number of retries without getting an acceptable pair.
-            // We should never hit the second `?` unless `find` breaks.
-            .ok_or(PaillierError::RetryFailed)??;
+        let (p, q) = prime_gen::get_random_safe_prime_pair(rng)?;
+        let decryption_key = Self::from_primes(&p, &q)?;
+        Ok((decryption_key, p, q))
+    }
 
-        let decryption_key = DecryptionKey(
-            libpaillier::DecryptionKey::with_primes(&p, &q)
+    /// Create the [`DecryptionKey`] with factors `p` and `q`.
+    ///
+    /// This only checks that the resulting modulus is `2 * PRIME_BITS` long;
+    /// the caller is responsible for making sure the factors are suitable.
+    pub(crate) fn from_primes(p: &BigNumber, q: &BigNumber) -> Result<Self> {
+        let decryption_key = DecryptionKey::try_from(
+            libpaillier::DecryptionKey::with_primes(p, q)
                 .ok_or(PaillierError::CouldNotCreateKey)?,
-        );
+        )?;
 
         // Double check that the modulus is the correct size.
         if decryption_key.0.n().bit_length() == 2 * PRIME_BITS {
-            Ok((decryption_key, p, q))
+            Ok(decryption_key)
         } else {
             Err(PaillierError::CouldNotCreateKey)?
         }
@@ -357,13 +565,16 @@ impl DecryptionKey {
             Err(PaillierError::CouldNotCreateKey)?
         }
 
-        Ok(Self(decryption_key))
+        Self::try_from(decryption_key).map_err(|err| {
+            error!("Failed to deserialize decryption key: could not factor the modulus");
+            err
+        })
     }
 
     /// Retrieve the public [`EncryptionKey`] corresponding to this secret
     /// [`DecryptionKey`].
     pub(crate) fn encryption_key(&self) -> EncryptionKey {
-        EncryptionKey(libpaillier::EncryptionKey::from(&self.0))
+        EncryptionKey::from(libpaillier::EncryptionKey::from(&self.0))
     }
 
     /// Return this [`DecryptionKey`]s modulus.
@@ -377,6 +588,128 @@ impl DecryptionKey {
     }
 }
 
+/// Precomputed values for operating on Paillier ciphertexts modulo the
+/// factors `p` and `q` of a [`DecryptionKey`]'s modulus `N`.
+///
+/// The expensive part of both decryption and encryption is a modular
+/// exponentiation modulo `N²`. Doing the equivalent exponentiations modulo
+/// `p²` and `q²` (or modulo `p` and `q`, with shorter exponents) and
+/// combining the results with the Chinese remainder theorem is several times
+/// faster.
+#[derive(Clone, ZeroizeOnDrop)]
+struct CrtParams {
+    p: BigNumber,
+    q: BigNumber,
+    p_squared: BigNumber,
+    q_squared: BigNumber,
+    n_squared: BigNumber,
+    /// `L_p((1 + N)^(p - 1) mod p²)^-1 mod p`, where `L_p(x) = (x - 1) / p`.
+    h_p: BigNumber,
+    /// `L_q((1 + N)^(q - 1) mod q²)^-1 mod q`, where `L_q(x) = (x - 1) / q`.
+    h_q: BigNumber,
+    /// `q^-1 mod p`.
+    q_inverse_mod_p: BigNumber,
+    /// `(q²)^-1 mod p²`.
+    q_squared_inverse_mod_p_squared: BigNumber,
+    /// `N^-1 mod (p - 1)`.
+    n_inverse_mod_p_minus_one: BigNumber,
+    /// `N^-1 mod (q - 1)`.
+    n_inverse_mod_q_minus_one: BigNumber,
+}
+
+impl CrtParams {
+    /// Compute the parameters for the key with modulus `n` and totient
+    /// `totient`.
+    ///
+    /// libpaillier doesn't keep the factors of the modulus, so we recover
+    /// them: `p + q = N - φ(N) + 1`, and `|p - q|` is the square root of
+    /// `(p + q)² - 4N`.
+    fn new(n: &BigNumber, totient: &BigNumber) -> Result<Self> {
+        let sum: BigNumber = n - totient + 1;
+        let difference = integer_sqrt(&(&sum * &sum - BigNumber::from(4) * n));
+        let p: BigNumber = (&sum + &difference) / 2;
+        let q: BigNumber = (&sum - &difference) / 2;
+        if &(&p * &q) != n || p <= BigNumber::one() || q <= BigNumber::one() {
+            Err(PaillierError::CouldNotCreateKey)?
+        }
+
+        let p_squared = &p * &p;
+        let q_squared = &q * &q;
+        let base: BigNumber = n + 1;
+        let h = |prime: &BigNumber, prime_squared: &BigNumber| {
+            let exponent: BigNumber = prime - 1;
+            let x = base.modpow(&exponent, prime_squared);
+            let l: BigNumber = (x - 1) / prime;
+            l.invert(prime).ok_or(PaillierError::CouldNotCreateKey)
+        };
+        let invert = |x: &BigNumber, modulus: &BigNumber| {
+            x.invert(modulus).ok_or(PaillierError::CouldNotCreateKey)
+        };
+        let p_minus_one: BigNumber = &p - 1;
+        let q_minus_one: BigNumber = &q - 1;
+
+        Ok(Self {
+            h_p: h(&p, &p_squared)?,
+            h_q: h(&q, &q_squared)?,
+            q_inverse_mod_p: invert(&q, &p)?,
+            q_squared_inverse_mod_p_squared: invert(&q_squared, &p_squared)?,
+            n_inverse_mod_p_minus_one: invert(n, &p_minus_one)?,
+            n_inverse_mod_q_minus_one: invert(n, &q_minus_one)?,
+            n_squared: n * n,
+            p,
+            q,
+            p_squared,
+            q_squared,
+        })
+    }
+}
+
+/// Compute the plaintext of `c` modulo `prime`, which is one of the factors of
+/// the modulus, as `L(c^(prime - 1) mod prime²) * h mod prime`.
+fn decrypt_mod_factor(
+    c: &BigNumber,
+    prime: &BigNumber,
+    prime_squared: &BigNumber,
+    h: &BigNumber,
+) -> BigNumber {
+    let exponent: BigNumber = prime - 1;
+    let x = c.modpow(&exponent, prime_squared);
+    let l: BigNumber = (x - 1) / prime;
+    l.modmul(h, prime)
+}
+
+/// Find the `x` modulo `m_p * m_q` such that `x = x_p mod m_p` and `x = x_q mod
+/// m_q`, given `m_q_inverse = m_q^-1 mod m_p`.
+fn crt_combine(
+    x_p: &BigNumber,
+    x_q: &BigNumber,
+    m_p: &BigNumber,
+    m_q: &BigNumber,
+    m_q_inverse: &BigNumber,
+) -> BigNumber {
+    let h = x_p.modsub(x_q, m_p).modmul(m_q_inverse, m_p);
+    x_q + m_q * h
+}
+
+/// Compute the floor of the square root of `x`, using Newton's method.
+fn integer_sqrt(x: &BigNumber) -> BigNumber {
+    if x == &BigNumber::zero() {
+        return BigNumber::zero();
+    }
+    // Start from a power of two that is at least the square root, so the
+    // iterates decrease monotonically until they reach the answer.
+    let mut bytes = vec![0u8; x.bit_length() / 16 + 2];
+    bytes[0] = 1;
+    let mut root = BigNumber::from_slice(bytes);
+    loop {
+        let next: BigNumber = (&root + x / &root) / 2;
+        if next >= root {
+            return root;
+        }
+        root = next;
+    }
+}
+
 // Safe prime generation functions for production and testing.
 pub(crate) mod prime_gen {
     use super::*;
@@ -389,6 +722,43 @@ pub(crate) mod prime_gen {
         BigNumber::safe_prime_from_rng(PRIME_BITS, rng)
     }
 
+    /// Sample a pair of safe primes `p` and `q` at random, such that both are
+    /// `PRIME_BITS` long and their product is `2 * PRIME_BITS` long.
+    pub(crate) fn get_random_safe_prime_pair<R: RngCore + CryptoRng>(
+        rng: &mut R,
+    ) -> Result<(BigNumber, BigNumber)> {
+        // Generate a pair of safe primes that are `PRIME_BITS` long and return them if
+        // their product is `2 * PRIME_BITS` long (otherwise return an error).
+        let generate_prime_pair = || -> Result<(BigNumber, BigNumber)> {
+            // As generating safe primes can be computationally expensive (> one minute per
+            // prime in github CI), we read precomputed ones from a file (but
+            // only in tests!)
+            #[cfg(not(test))]
+            let (p, q) = (get_random_safe_prime(rng), get_random_safe_prime(rng));
+            #[cfg(test)]
+            let (p, q) = get_prime_pair_from_pool_insecure(rng)?;
+
+            if p.bit_length() == PRIME_BITS
+                && q.bit_length() == PRIME_BITS
+                && (&p * &q).bit_length() == 2 * PRIME_BITS
+            {
+                Ok((p, q))
+            } else {
+                Err(PaillierError::CouldNotCreateKey)?
+            }
+        };
+
+        // A Paillier decryption key is the product of the two primes, but sometimes two
+        // n/2-bit primes can produce an n-1 bit modulus. Allow some (lazily
+        // evaluated) retries to handle that error.
+        std::iter::repeat_with(generate_prime_pair)
+            .take(CRYPTOGRAPHIC_RETRY_MAX)
+            .find(|result| result.is_ok())
+            // We hit the maximum number of retries without getting an acceptable pair.
+            // We should never hit the second `?` unless `find` breaks.
+            .ok_or(PaillierError::RetryFailed)?
+    }
+
     #[cfg(test)]
     lazy_static::lazy_static! {
         /// List of `PRIME_BITS`-length safe primes, generated _insecurely_.
@@ -458,10 +828,10 @@ mod test {
     use crate::{
         paillier::Ciphertext,
         parameters::PRIME_BITS,
-        utils::{random_plusminus, testing::init_testing},
+        utils::{random_plusminus, random_plusminus_by_size, testing::init_testing},
     };
 
-    use super::{prime_gen, DecryptionKey, EncryptionKey};
+    use super::{prime_gen, DecryptionKey, EncryptionKey, MaskedNonce};
 
     #[test]
     #[ignore = "sometimes slow in debug mode"]
@@ -552,6 +922,75 @@ mod test {
         assert_eq!(decryption_key.decrypt(&ciphertext).unwrap(), msg);
     }
 
+    #[test]
+    fn paillier_decryption_recovers_nonce() {
+        let mut rng = init_testing();
+        let (decryption_key, _, _) = DecryptionKey::new(&mut rng).unwrap();
+        let encryption_key = decryption_key.encryption_key();
+
+        // Combine two ciphertexts homomorphically, so the resulting nonce is not
+        // known directly
+        let a = random_plusminus_by_size(&mut rng, 256);
+        let b = random_plusminus_by_size(&mut rng, 256);
+        let (c_a, _) = encryption_key.encrypt(&mut rng, &a).unwrap();
+        let (c_b, _) = encryption_key.encrypt(&mut rng, &b).unwrap();
+        let c = encryption_key
+            .multiply_and_add(&BigNumber::from(3), &c_a, &c_b)
+            .unwrap();
+
+        let (msg, nonce) = decryption_key.decrypt_with_nonce(&c).unwrap();
+        assert_eq!(msg, BigNumber::from(3) * &a + &b);
+        let reencrypted = encryption_key
+            .encrypt_with_nonce(&msg, &MaskedNonce(nonce.0.clone()))
+            .unwrap();
+        assert_eq!(reencrypted, c);
+    }
+
+    #[test]
+    fn crt_decryption_matches_libpaillier() {
+        let mut rng = init_testing();
+        let (decryption_key, _, _) = DecryptionKey::new(&mut rng).unwrap();
+        let encryption_key = decryption_key.encryption_key();
+
+        for _ in 0..10 {
+            let ciphertext = encryption_key.random_ciphertext(&mut rng);
+            let expected = decryption_key
+                .0
+                .decrypt(&ciphertext.0)
+                .map(BigNumber::from_slice)
+                .map(|x| {
+                    if x > decryption_key.half_n() {
+                        x - decryption_key.modulus()
+                    } else {
+                        x
+                    }
+                });
+            assert_eq!(decryption_key.decrypt(&ciphertext).ok(), expected);
+        }
+    }
+
+    #[test]
+    fn owner_encryption_matches_public_encryption() {
+        let mut rng = init_testing();
+        let (decryption_key, _, _) = DecryptionKey::new(&mut rng).unwrap();
+        let encryption_key = decryption_key.encryption_key();
+
+        for _ in 0..10 {
+            let msg = random_message(&mut rng, &encryption_key);
+            let (ciphertext, nonce) = decryption_key.encrypt(&mut rng, &msg).unwrap();
+            let expected = encryption_key
+                .encrypt_with_nonce(&msg, &MaskedNonce(nonce.0.clone()))
+                .unwrap();
+            assert_eq!(ciphertext, expected);
+            assert_eq!(decryption_key.decrypt(&ciphertext).unwrap(), msg);
+        }
+
+        // The owner's encryption enforces the same range as the public one
+        let too_big = decryption_key.half_n() + 1;
+        assert!(decryption_key.encrypt(&mut rng, &too_big).is_err());
+        assert!(decryption_key.encrypt(&mut rng, &-too_big).is_err());
+    }
+
     #[test]
     fn pailler_encryption_requires_input_in_Zn() {
         let mut rng = init_testing();
@@ -662,6 +1101,13 @@ mod test {
 
         assert!(reconstructed.is_ok());
         assert_eq!(reconstructed.unwrap(), decryption_key);
+
+        let serialized = bincode::serialize(&decryption_key).unwrap();
+        let deserialized: DecryptionKey = bincode::deserialize(&serialized).unwrap();
+        assert_eq!(deserialized, decryption_key);
+        let msg = random_plusminus_by_size(rng, 256);
+        let (ciphertext, _) = decryption_key.encrypt(rng, &msg).unwrap();
+        assert_eq!(deserialized.decrypt(&ciphertext).unwrap(), msg);
     }
 
     #[test]
@@ -674,7 +1120,8 @@ mod test {
 
         // Manually create small DK
         let small_decryption_key =
-            DecryptionKey(libpaillier::DecryptionKey::with_primes(&p, &q).unwrap());
+            DecryptionKey::try_from(libpaillier::DecryptionKey::with_primes(&p, &q).unwrap())
+                .unwrap();
 
         let bytes = small_decryption_key.into_bytes();
         assert!(DecryptionKey::try_from_bytes(&bytes).is_err());
@@ -683,7 +1130,8 @@ mod test {
         let p = BigNumber::safe_prime_from_rng(PRIME_BITS + 1, rng);
         let q = BigNumber::safe_prime_from_rng(PRIME_BITS + 1, rng);
         let large_decryption_key =
-            DecryptionKey(libpaillier::DecryptionKey::with_primes(&p, &q).unwrap());
+            DecryptionKey::try_from(libpaillier::DecryptionKey::with_primes(&p, &q).unwrap())
+                .unwrap();
         assert!(large_decryption_key.modulus().bit_length() > 2 * PRIME_BITS);
         let bytes = large_decryption_key.into_bytes();
         assert!(DecryptionKey::try_from_bytes(&bytes).is_err());
@@ -0,0 +1,103 @@
+// Copyright (c) 2022-2023 Bolt Labs Holdings, Inc
+//
+// This source code is licensed under both the MIT license found in the
+// LICENSE-MIT file in the root directory of this source tree and the Apache
+// License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+// of this source tree.
+
+//! Helpers for running independent computations, such as generating or
+//! verifying the zero-knowledge proofs for different participants, in
+//! parallel.
+//!
+//! With the `rayon` feature, the computations are spread over rayon's global
+//! thread pool. Otherwise, they run sequentially on the calling thread.
+//! Either way, results are returned in the same order as the inputs.
+
+use rand::{rngs::StdRng, CryptoRng, Rng, RngCore, SeedableRng};
+
+/// Apply `f` to each of the `items`.
+pub(crate) fn map<T, U, F>(items: Vec<T>, f: F) -> Vec<U>
+where
+    T: Send,
+    U: Send,
+    F: Fn(T) -> U + Send + Sync,
+{
+    #[cfg(feature = "rayon")]
+    {
+        use rayon::prelude::*;
+        items.into_par_iter().map(f).collect()
+    }
+    #[cfg(not(feature = "rayon"))]
+    {
+        items.into_iter().map(f).collect()
+    }
+}
+
+/// Apply `f` to each of the `items`, giving each call its own RNG.
+///
+/// The RNGs are all seeded from `rng` before any of the calls are made, so
+/// the results don't depend on whether the calls ran in parallel.
+pub(crate) fn map_with_rng<T, U, F, R>(rng: &mut R, items: Vec<T>, f: F) -> Vec<U>
+where
+    T: Send,
+    U: Send,
+    F: Fn(&mut StdRng, T) -> U + Send + Sync,
+    R: RngCore + CryptoRng,
+{
+    let seeded = items
+        .into_iter()
+        .map(|item| (StdRng::from_seed(rng.gen()), item))
+        .collect();
+    map(seeded, |(mut rng, item)| f(&mut rng, item))
+}
+
+/// Run `a` and `b`, and return both of their results.
+pub(crate) fn join<A, B, RA, RB>(a: A, b: B) -> (RA, RB)
+where
+    A: FnOnce() -> RA + Send,
+    B: FnOnce() -> RB + Send,
+    RA: Send,
+    RB: Send,
+{
+    #[cfg(feature = "rayon")]
+    {
+        rayon::join(a, b)
+    }
+    #[cfg(not(feature = "rayon"))]
+    {
+        (a(), b())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::testing::init_testing;
+
+    #[test]
+    fn results_are_in_input_order() {
+        let items = (0..100).collect::<Vec<u64>>();
+        let squares = map(items.clone(), |x| x * x);
+        assert_eq!(squares, items.iter().map(|x| x * x).collect::<Vec<_>>());
+
+        assert_eq!(join(|| 1, || 2), (1, 2));
+    }
+
+    #[test]
+    fn rngs_are_derived_deterministically() {
+        let seed = init_testing().gen();
+        let sample = || {
+            let mut rng = StdRng::from_seed(seed);
+            map_with_rng(&mut rng, vec![(); 10], |rng, ()| rng.next_u64())
+        };
+        let first = sample();
+        let second = sample();
+        assert_eq!(first, second);
+
+        // Each call gets a different RNG
+        let mut distinct = first.clone();
+        distinct.sort();
+        distinct.dedup();
+        assert_eq!(distinct.len(), first.len());
+    }
+}
@@ -11,16 +11,17 @@
 
 use crate::{
     broadcast::participant::{BroadcastParticipant, BroadcastTag},
-    errors::{InternalError, Result},
+    errors::{CallerError, InternalError, Result},
     local_storage::{storage as local_storage, LocalStorage, TypeTag},
     messages::{Message, MessageType},
     protocol::{ParticipantIdentifier, ProtocolType},
     Identifier,
 };
 use rand::{CryptoRng, RngCore};
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use std::fmt::Debug;
 use tracing::error;
+use zeroize::ZeroizeOnDrop;
 
 /// Possible outcomes from processing one or more messages.
 ///
@@ -243,8 +244,60 @@ pub trait ProtocolParticipant {
     /// The status of the protocol execution.
     fn status(&self) -> &Status;
 
+    /// The other participants whose messages are needed to finish the current
+    /// round, for [`Participant::waiting_on()`](crate::Participant::waiting_on()).
+    ///
+    /// This is empty if the participant hasn't been initialized or has
+    /// terminated. The default implementation always returns an empty list,
+    /// for protocols that don't track who they are waiting on.
+    fn waiting_on(&self) -> Vec<ParticipantIdentifier> {
+        Vec::new()
+    }
+
+    /// Stop the protocol execution: discard all session state and set the
+    /// status to [`Status::Aborted`].
+    ///
+    /// Discarded values that hold secrets are zeroized when they are dropped.
+    ///
+    /// This has no default implementation, since only the protocol can update
+    /// its [`status()`](ProtocolParticipant::status()).
+    fn abort(&mut self);
+
     /// The session identifier for the current session
     fn sid(&self) -> Identifier;
+
+    /// Serialize the state of the protocol execution, for
+    /// [`Participant::export_state()`](crate::Participant::export_state()).
+    ///
+    /// This includes everything that changes as messages are processed, but
+    /// not the session parameters or the input, which are fixed when the
+    /// participant is created.
+    ///
+    /// The default implementation returns an error, for protocols that can't
+    /// be saved and resumed.
+    fn export_state(&self) -> Result<Vec<u8>> {
+        error!(
+            "{:?} does not support exporting its state",
+            Self::protocol_type()
+        );
+        Err(CallerError::BadInput)?
+    }
+
+    /// Restore the state of a protocol execution from the output of
+    /// [`export_state()`](ProtocolParticipant::export_state()).
+    ///
+    /// This must only be called on a newly-created participant with the same
+    /// session parameters and input as the one that exported the state.
+    ///
+    /// The default implementation returns an error, for protocols that can't
+    /// be saved and resumed.
+    fn import_state(&mut self, _state: &[u8]) -> Result<()> {
+        error!(
+            "{:?} does not support importing its state",
+            Self::protocol_type()
+        );
+        Err(CallerError::BadInput)?
+    }
 }
 
 pub(crate) trait InnerProtocolParticipant: ProtocolParticipant {
@@ -387,6 +440,61 @@ pub(crate) trait InnerProtocolParticipant: ProtocolParticipant {
     fn status_mut(&mut self) -> &mut Status;
 }
 
+/// The state of a protocol execution that changes as messages are processed.
+///
+/// Most participants hold all such state in their [`LocalStorage`], their
+/// [`Status`] and their [`BroadcastParticipant`], so they can implement
+/// [`ProtocolParticipant::export_state()`] and
+/// [`ProtocolParticipant::import_state()`] with this type.
+#[derive(Serialize, Deserialize, ZeroizeOnDrop)]
+pub(crate) struct ParticipantState {
+    #[zeroize(skip)]
+    status: Status,
+    local_storage: Vec<u8>,
+    /// The state of the participant's broadcast sub-protocol, if it has one.
+    broadcast: Option<Vec<u8>>,
+}
+
+impl ParticipantState {
+    /// Serialize the state of a participant.
+    pub(crate) fn export(
+        status: &Status,
+        local_storage: &LocalStorage,
+        broadcast_participant: Option<&BroadcastParticipant>,
+    ) -> Result<Vec<u8>> {
+        let state = Self {
+            status: status.clone(),
+            local_storage: local_storage.export()?,
+            broadcast: broadcast_participant
+                .map(|participant| participant.export_state())
+                .transpose()?,
+        };
+        serialize!(&state)
+    }
+
+    /// Restore the state of a participant from the output of
+    /// [`ParticipantState::export()`].
+    pub(crate) fn import(
+        state: &[u8],
+        status: &mut Status,
+        local_storage: &mut LocalStorage,
+        broadcast_participant: Option<&mut BroadcastParticipant>,
+    ) -> Result<()> {
+        let state: Self = deserialize!(state)?;
+        match (broadcast_participant, &state.broadcast) {
+            (Some(participant), Some(broadcast)) => participant.import_state(broadcast)?,
+            (None, None) => {}
+            _ => {
+                error!("Participant state doesn't match the type of participant");
+                Err(CallerError::DeserializationFailed)?
+            }
+        }
+        *local_storage = LocalStorage::import(&state.local_storage)?;
+        *status = state.status.clone();
+        Ok(())
+    }
+}
+
 pub(crate) trait Broadcast {
     fn broadcast_participant(&mut self) -> &mut BroadcastParticipant;
     ///`sid` corresponds to a unique session identifier.
@@ -434,7 +542,7 @@ pub(crate) trait Broadcast {
 }
 
 /// Protocol status for implementors of [`ProtocolParticipant`].
-#[derive(Debug, Eq, PartialEq, Clone)]
+#[derive(Debug, Eq, PartialEq, Clone, Serialize, Deserialize)]
 pub enum Status {
     /// Participant is created but has not received a ready message from self.
     NotReady,
@@ -467,6 +575,9 @@ pub enum Status {
     ///
     /// This variant is used by [`BroadcastParticipant`]
     ParticipantCompletedBroadcast(Vec<ParticipantIdentifier>),
+    /// The session was abandoned, either by this participant or by another
+    /// participant, and this participant's session state was discarded.
+    Aborted,
 }
 
 impl Status {
@@ -503,3 +614,63 @@ macro_rules! run_only_once_per_tag {
         }
     }};
 }
+
+#[cfg(test)]
+pub(crate) mod testing {
+    use super::*;
+    use rand::Rng;
+    use std::collections::HashMap;
+
+    /// Run the given participants to completion, processing messages in a
+    /// random order, and return their outputs, ordered to match the
+    /// participants.
+    pub(crate) fn run_participants<P: ProtocolParticipant, R: RngCore + CryptoRng>(
+        quorum: &mut [P],
+        rng: &mut R,
+    ) -> Result<Vec<P::Output>> {
+        let mut inboxes = HashMap::new();
+        for participant in quorum.iter() {
+            let empty: [u8; 0] = [];
+            let ready = Message::new(
+                P::ready_type(),
+                participant.sid(),
+                participant.id(),
+                participant.id(),
+                &empty,
+            )?;
+            let _ = inboxes.insert(participant.id(), vec![ready]);
+        }
+        let mut outputs = std::iter::repeat_with(|| None)
+            .take(quorum.len())
+            .collect::<Vec<_>>();
+
+        while !quorum
+            .iter()
+            .all(|participant| *participant.status() == Status::TerminatedSuccessfully)
+        {
+            // Pick a random participant to process
+            let index = rng.gen_range(0..quorum.len());
+            let participant = &mut quorum[index];
+            let inbox = inboxes.get_mut(&participant.id()).unwrap();
+            if inbox.is_empty() {
+                continue;
+            }
+            let message = inbox.remove(rng.gen_range(0..inbox.len()));
+
+            // Deliver messages and save outputs
+            let (output, messages) = participant.process_message(rng, &message)?.into_parts();
+            if output.is_some() {
+                assert!(outputs[index].is_none());
+                outputs[index] = output;
+            }
+            for message in messages {
+                inboxes.get_mut(&message.to()).unwrap().push(message);
+            }
+        }
+
+        // Make sure every player got an output
+        let outputs: Vec<_> = outputs.into_iter().flatten().collect();
+        assert_eq!(outputs.len(), quorum.len());
+        Ok(outputs)
+    }
+}
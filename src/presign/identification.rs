@@ -0,0 +1,239 @@
+// Copyright (c) 2022-2023 Bolt Labs Holdings, Inc
+//
+// This source code is licensed under both the MIT license found in the
+// LICENSE-MIT file in the root directory of this source tree and the Apache
+// License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+// of this source tree.
+
+use crate::{
+    auxinfo::AuxInfoPublic,
+    errors::{InternalError, Result},
+    keygen::KeySharePublic,
+    messages::{Message, MessageType, PresignMessageType},
+    paillier::{Ciphertext, EncryptionKey},
+    presign::{round_one::PublicBroadcast as RoundOnePublicBroadcast, round_two},
+    protocol::ParticipantIdentifier,
+    utils::CurvePoint,
+    zkp::{
+        piaffg::{PiAffgInput, PiAffgProof},
+        pidec::PiDecProof,
+        pilog::PiLogProof,
+        pimul::{PiMulInput, PiMulProof},
+        Proof, ProofContext,
+    },
+};
+use libpaillier::unknown_order::BigNumber;
+use merlin::Transcript;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use tracing::error;
+
+/// The round two values (`Dⱼ,ᵢ`, `Fⱼ,ᵢ`, `Γⱼ`, `D̂ⱼ,ᵢ`, `F̂ⱼ,ᵢ` and the
+/// associated [`PiAffgProof`]s) that participant `i` received from participant
+/// `j`.
+///
+/// These are forwarded to every participant during the identification phase,
+/// so that everyone agrees on the values used to compute `δᵢ`. Since the
+/// proof can only be produced by `j`, the forwarding participant cannot
+/// tamper with them.
+#[derive(Clone, Serialize, Deserialize)]
+pub(crate) struct ReceivedAffine {
+    pub sender: ParticipantIdentifier,
+    pub D: Ciphertext,
+    pub F: Ciphertext,
+    pub Gamma: CurvePoint,
+    pub psi: PiAffgProof,
+    pub D_hat: Ciphertext,
+    pub F_hat: Ciphertext,
+    pub psi_hat: PiAffgProof,
+}
+
+impl ReceivedAffine {
+    /// Collect the values received in a round two message from `sender`.
+    pub(crate) fn new(sender: ParticipantIdentifier, public: &round_two::Public) -> Self {
+        Self {
+            sender,
+            D: public.D.clone(),
+            F: public.F.clone(),
+            Gamma: public.Gamma,
+            psi: public.psi.clone(),
+            D_hat: public.D_hat.clone(),
+            F_hat: public.F_hat.clone(),
+            psi_hat: public.psi_hat.clone(),
+        }
+    }
+}
+
+/// Public information produced in the identification phase of the presign
+/// protocol.
+///
+/// [`Public::from_message`] validates that a [`Message`] is a valid
+/// serialization of a batch of `Public`s, but _not_ that each `Public` is
+/// necessarily valid (i.e., that all the components are valid with respect to
+/// each other); use [`Public::verify`] to check the parts that only depend on
+/// the sender's values.
+#[derive(Clone, Serialize, Deserialize)]
+pub(crate) struct Public {
+    /// Encryption of `kᵢ ɣᵢ` under the sender's key.
+    pub H: Ciphertext,
+    /// Proof that `H` encrypts the product of the plaintexts of `Kᵢ` and `Gᵢ`.
+    pub psi_mul: PiMulProof,
+    /// The round two values the sender received from every other participant.
+    pub received: Vec<ReceivedAffine>,
+    /// Proof that the plaintext of the combined ciphertext (see
+    /// [`combine_ciphertexts`]) equals the sender's `δᵢ` modulo `q`.
+    pub psi_dec: PiDecProof,
+    /// Encryption of `kᵢ xᵢ` under the sender's key.
+    pub H_hat: Ciphertext,
+    /// Encryption of zero under the sender's key, which is the additive term
+    /// of the [`PiAffgProof`] about `H_hat`.
+    pub Y_hat: Ciphertext,
+    /// Proof that `H_hat` is `Kᵢ^{xᵢ}` times an encryption of zero, where `xᵢ`
+    /// is the discrete log of the sender's public key share.
+    pub psi_affg_hat: PiAffgProof,
+    /// Fresh encryption of `χᵢ` under the sender's key.
+    pub C_hat: Ciphertext,
+    /// Proof that the sender's `Sᵢ` is `Γ` raised to the plaintext of `C_hat`.
+    pub psi_log_hat: PiLogProof,
+    /// Proof that the plaintext of the combined ciphertext for `χᵢ` (see
+    /// [`combine_ciphertexts`]), divided by `C_hat`, is zero modulo `q`.
+    pub psi_dec_hat: PiDecProof,
+}
+
+impl Public {
+    /// Find the round two values the sender received from `pid`.
+    pub(crate) fn received_from(&self, pid: ParticipantIdentifier) -> Option<&ReceivedAffine> {
+        self.received.iter().find(|received| received.sender == pid)
+    }
+
+    /// Verify the parts of [`Public`] that depend only on values already known
+    /// to the verifier:
+    /// - the forwarded round two values cover exactly the prover's peers, and
+    ///   each forwarded [`PiAffgProof`] is valid,
+    /// - the [`PiMulProof`] about `H` is valid, and
+    /// - the [`PiAffgProof`] about `H_hat` is valid.
+    ///
+    /// The [`PiDecProof`]s and the [`PiLogProof`] are checked separately, once
+    /// every participant's forwarded values are available.
+    pub(crate) fn verify(
+        &self,
+        context: &impl ProofContext,
+        verifier_auxinfo_public: &AuxInfoPublic,
+        prover_auxinfo_public: &AuxInfoPublic,
+        prover_keyshare_public: &KeySharePublic,
+        prover_r1_public_broadcast: &RoundOnePublicBroadcast,
+        peer_publics: &[(&AuxInfoPublic, &KeySharePublic)],
+    ) -> Result<()> {
+        let peers = peer_publics
+            .iter()
+            .map(|(auxinfo, _)| auxinfo.participant())
+            .collect::<HashSet<_>>();
+        let senders = self
+            .received
+            .iter()
+            .map(|received| received.sender)
+            .collect::<HashSet<_>>();
+        if senders != peers || self.received.len() != peers.len() {
+            error!("Forwarded round two values don't match the set of participants");
+            return Err(InternalError::ProtocolError(None));
+        }
+
+        for (peer_auxinfo_public, peer_keyshare_public) in peer_publics {
+            let received = self
+                .received_from(peer_auxinfo_public.participant())
+                .ok_or(InternalError::InternalInvariantFailed)?;
+            let psi_input = PiAffgInput::new(
+                prover_auxinfo_public.params(),
+                prover_auxinfo_public.pk(),
+                peer_auxinfo_public.pk(),
+                &prover_r1_public_broadcast.K,
+                &received.D,
+                &received.F,
+                &received.Gamma,
+            );
+            let mut transcript = Transcript::new(b"PiAffgProof");
+            received
+                .psi
+                .clone()
+                .verify(psi_input, context, &mut transcript)?;
+
+            let psi_hat_input = PiAffgInput::new(
+                prover_auxinfo_public.params(),
+                prover_auxinfo_public.pk(),
+                peer_auxinfo_public.pk(),
+                &prover_r1_public_broadcast.K,
+                &received.D_hat,
+                &received.F_hat,
+                peer_keyshare_public.as_ref(),
+            );
+            let mut transcript = Transcript::new(b"PiAffgProof");
+            received
+                .psi_hat
+                .clone()
+                .verify(psi_hat_input, context, &mut transcript)?;
+        }
+
+        let psi_mul_input = PiMulInput::new(
+            prover_auxinfo_public.pk(),
+            &prover_r1_public_broadcast.K,
+            &prover_r1_public_broadcast.G,
+            &self.H,
+        );
+        let mut transcript = Transcript::new(b"PiMulProof");
+        self.psi_mul
+            .clone()
+            .verify(psi_mul_input, context, &mut transcript)?;
+
+        let psi_affg_hat_input = PiAffgInput::new(
+            verifier_auxinfo_public.params(),
+            prover_auxinfo_public.pk(),
+            prover_auxinfo_public.pk(),
+            &prover_r1_public_broadcast.K,
+            &self.H_hat,
+            &self.Y_hat,
+            prover_keyshare_public.as_ref(),
+        );
+        let mut transcript = Transcript::new(b"PiAffgProof");
+        self.psi_affg_hat
+            .clone()
+            .verify(psi_affg_hat_input, context, &mut transcript)?;
+
+        Ok(())
+    }
+}
+
+impl Public {
+    /// Parse the [`Public`] values from an identification message, one for
+    /// each presign record in the batch.
+    pub(crate) fn from_message(message: &Message) -> Result<Vec<Self>> {
+        message.check_type(MessageType::Presign(PresignMessageType::Identification))?;
+        let publics: Vec<Self> = deserialize!(&message.unverified_bytes)?;
+        Ok(publics)
+    }
+}
+
+/// Compute the ciphertext `Hᵢ ∏ⱼ Dⱼ,ᵢ ∏ⱼ Fᵢ,ⱼ^{-1}` under participant `i`'s
+/// key, whose plaintext is congruent to `δᵢ` modulo `q` if `i` behaved
+/// honestly.
+///
+/// Here `received` are the `Dⱼ,ᵢ` ciphertexts `i` received from the other
+/// participants and `sent` are the `Fᵢ,ⱼ` ciphertexts `i` sent to them. The
+/// same computation with `Ĥᵢ`, `D̂ⱼ,ᵢ` and `F̂ᵢ,ⱼ` gives a ciphertext whose
+/// plaintext is congruent to `χᵢ`.
+pub(crate) fn combine_ciphertexts<'a>(
+    encryption_key: &EncryptionKey,
+    H: &Ciphertext,
+    received: impl IntoIterator<Item = &'a Ciphertext>,
+    sent: impl IntoIterator<Item = &'a Ciphertext>,
+) -> Result<Ciphertext> {
+    let one = BigNumber::one();
+    let minus_one = -BigNumber::one();
+    let with_received = received.into_iter().try_fold(H.clone(), |sum, D| {
+        encryption_key.multiply_and_add(&one, D, &sum)
+    });
+    let combined = sent.into_iter().try_fold(
+        with_received.map_err(|_| InternalError::InternalInvariantFailed)?,
+        |sum, F| encryption_key.multiply_and_add(&minus_one, F, &sum),
+    );
+    combined.map_err(|_| InternalError::InternalInvariantFailed)
+}
@@ -12,6 +12,7 @@ use crate::{
     auxinfo::{self, AuxInfoPrivate, AuxInfoPublic},
     errors::{CallerError, InternalError, Result},
     keygen::{self, KeySharePrivate, KeySharePublic},
+    presign::NoncePrecomputer,
     ParticipantIdentifier,
 };
 
@@ -23,6 +24,8 @@ pub struct Input {
     keygen_output: keygen::Output,
     /// The auxiliary info for the key that will be used in the presign run.
     auxinfo_output: auxinfo::Output,
+    /// The number of presign records to produce.
+    record_count: usize,
 }
 
 impl Input {
@@ -30,16 +33,6 @@ impl Input {
     /// [`auxinfo`](crate::auxinfo::AuxInfoParticipant) and
     /// [`keygen`](crate::keygen::KeygenParticipant) protocols.
     pub fn new(auxinfo_output: auxinfo::Output, keygen_output: keygen::Output) -> Result<Self> {
-        if auxinfo_output.public_auxinfo().len() != keygen_output.public_key_shares().len() {
-            error!(
-                "Number of auxinfo ({:?}) and keyshare ({:?}) public entries is not equal",
-                auxinfo_output.public_auxinfo().len(),
-                keygen_output.public_key_shares().len()
-            );
-            Err(CallerError::BadInput)?
-        }
-
-        // The same set of participants must have produced the key shares and aux infos.
         let aux_pids = auxinfo_output
             .public_auxinfo()
             .iter()
@@ -50,17 +43,42 @@ impl Input {
             .iter()
             .map(KeySharePublic::participant)
             .collect::<HashSet<_>>();
-        if aux_pids != key_pids {
-            error!("Public auxinfo and keyshare inputs to presign weren't from the same set of parties.");
-            Err(CallerError::BadInput)?
+
+        if keygen_output.is_threshold() {
+            // For threshold key shares, aux infos are only needed for the
+            // participants that might take part in presigning, which must be at
+            // least a threshold of the keygen participants.
+            if !aux_pids.is_subset(&key_pids) || aux_pids.len() < keygen_output.threshold() {
+                error!(
+                    "Public auxinfo inputs to presign must come from at least {} of the keyshare participants",
+                    keygen_output.threshold()
+                );
+                Err(CallerError::BadInput)?
+            }
+        } else {
+            if auxinfo_output.public_auxinfo().len() != keygen_output.public_key_shares().len() {
+                error!(
+                    "Number of auxinfo ({:?}) and keyshare ({:?}) public entries is not equal",
+                    auxinfo_output.public_auxinfo().len(),
+                    keygen_output.public_key_shares().len()
+                );
+                Err(CallerError::BadInput)?
+            }
+
+            // The same set of participants must have produced the key shares and aux
+            // infos.
+            if aux_pids != key_pids {
+                error!("Public auxinfo and keyshare inputs to presign weren't from the same set of parties.");
+                Err(CallerError::BadInput)?
+            }
         }
 
         // There shouldn't be duplicates.
         // This check is redundant, since it's also checked in the `auxinfo::Output` and
         // `keygen::Output` constructors, so we actually don't test it below.
-        // Also, since we checked equality of the sets and the lengths already, checking
-        // for keygen also validates it for auxinfo
-        if key_pids.len() != keygen_output.public_key_shares().len() {
+        if key_pids.len() != keygen_output.public_key_shares().len()
+            || aux_pids.len() != auxinfo_output.public_auxinfo().len()
+        {
             error!("Duplicate participant IDs appeared in AuxInfo and KeyShare public input.");
             Err(CallerError::BadInput)?
         }
@@ -78,6 +96,85 @@ impl Input {
         Ok(Self {
             auxinfo_output,
             keygen_output,
+            record_count: 1,
+        })
+    }
+
+    /// Request a batch of `count` presign records from a single presign run,
+    /// instead of the default of one.
+    ///
+    /// Every message in the run carries the values for the whole batch, so
+    /// this saves the round trips and broadcast overhead of running a
+    /// separate session for each record. All participants must request the
+    /// same number of records; the count isn't negotiated, and a participant
+    /// that receives a batch of the wrong size fails the run without blaming
+    /// anyone. Each record in the batch is independent and must still be used
+    /// only once.
+    pub fn with_record_count(mut self, count: usize) -> Result<Self> {
+        if count == 0 {
+            error!("Presign must produce at least one record");
+            Err(CallerError::BadInput)?
+        }
+        self.record_count = count;
+        Ok(self)
+    }
+
+    /// Use the nonces precomputed by `precomputer` when encrypting values for
+    /// the other participants.
+    pub fn with_nonce_precomputer(self, precomputer: &NoncePrecomputer) -> Result<Self> {
+        let (mut public_auxinfo, private_auxinfo) = self.auxinfo_output.into_parts();
+        for auxinfo in &mut public_auxinfo {
+            auxinfo.set_nonce_pool(precomputer.pool(auxinfo.pk())?);
+        }
+        Ok(Self {
+            auxinfo_output: auxinfo::Output::from_parts(public_auxinfo, private_auxinfo)?,
+            ..self
+        })
+    }
+
+    /// The number of presign records to produce.
+    pub(crate) fn record_count(&self) -> usize {
+        self.record_count
+    }
+
+    /// Prepare the input for a presign run with the given quorum.
+    ///
+    /// For threshold key shares, this converts the quorum's Shamir shares into
+    /// additive shares with Lagrange coefficients and discards auxinfo for
+    /// participants outside the quorum. The quorum must be a subset of the
+    /// auxinfo participants that is at least the size of the threshold.
+    /// Full-threshold inputs are returned unchanged; their participant set is
+    /// checked by the caller.
+    pub(crate) fn for_quorum(self, quorum: &[ParticipantIdentifier]) -> Result<Self> {
+        if !self.keygen_output.is_threshold() {
+            return Ok(self);
+        }
+
+        if quorum
+            .iter()
+            .any(|pid| self.auxinfo_output.find_public(*pid).is_none())
+        {
+            error!("Presign quorum includes a participant with no public auxinfo");
+            Err(CallerError::BadInput)?
+        }
+
+        let keygen_output = self.keygen_output.to_additive_shares(quorum)?;
+        let public_auxinfo = self
+            .auxinfo_output
+            .public_auxinfo()
+            .iter()
+            .filter(|auxinfo| quorum.contains(&auxinfo.participant()))
+            .cloned()
+            .collect();
+        let auxinfo_output = auxinfo::Output::from_parts(
+            public_auxinfo,
+            self.auxinfo_output.private_auxinfo().clone(),
+        )?;
+
+        Ok(Self {
+            keygen_output,
+            auxinfo_output,
+            record_count: self.record_count,
         })
     }
 
@@ -217,6 +314,59 @@ mod test {
         );
     }
 
+    #[test]
+    fn threshold_inputs_allow_auxinfo_from_a_subset() -> Result<()> {
+        let rng = &mut init_testing();
+        let configs = ParticipantConfig::random_quorum(5, rng)?;
+        let pids = configs
+            .iter()
+            .map(ParticipantConfig::id)
+            .collect::<Vec<_>>();
+        let keygen_output = keygen::Output::simulate_threshold_set(&configs, 3, rng)
+            .pop()
+            .unwrap();
+
+        // Auxinfo from a threshold of the participants (including this one) works
+        let auxinfo_output = auxinfo::Output::simulate(&pids[2..], rng);
+        let input = Input::new(auxinfo_output, keygen_output.clone())?;
+
+        // Converting for a quorum with auxinfo produces additive shares
+        let quorum_input = input.clone().for_quorum(&pids[2..])?;
+        assert_eq!(quorum_input.participants().len(), 3);
+        assert!(!quorum_input.keygen_output.is_threshold());
+
+        // Converting for a quorum without auxinfo fails
+        assert!(input.for_quorum(&pids[1..4]).is_err());
+
+        // Auxinfo from fewer than a threshold of participants fails
+        let auxinfo_output = auxinfo::Output::simulate(&pids[3..], rng);
+        let result = Input::new(auxinfo_output, keygen_output);
+        assert_eq!(
+            result.unwrap_err(),
+            InternalError::CallingApplicationMistake(CallerError::BadInput)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn record_count_must_be_positive() -> Result<()> {
+        let rng = &mut init_testing();
+        let pids = std::iter::repeat_with(|| ParticipantIdentifier::random(rng))
+            .take(3)
+            .collect::<Vec<_>>();
+        let input = Input::new(
+            auxinfo::Output::simulate(&pids, rng),
+            keygen::Output::simulate(&pids, rng),
+        )?;
+        assert_eq!(input.record_count(), 1);
+        assert_eq!(input.clone().with_record_count(5)?.record_count(), 5);
+        assert_eq!(
+            input.with_record_count(0).unwrap_err(),
+            InternalError::CallingApplicationMistake(CallerError::BadInput)
+        );
+        Ok(())
+    }
+
     #[test]
     fn protocol_participants_must_match_input_participants() -> Result<()> {
         let rng = &mut init_testing();
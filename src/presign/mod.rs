@@ -11,18 +11,30 @@
 //! [`PresignParticipant`] for
 //! more details.
 //!
+//! Each [`PresignRecord`] must be used for at most one signature;
+//! [`PresignPool`] can be used to keep track of them. A [`NoncePrecomputer`]
+//! can do some of the work of a presign run ahead of time.
+//!
 //! [^cite]: Ran Canetti, Rosario Gennaro, Steven Goldfeder, Nikolaos
 //! Makriyannis, and Udi Peled. UC Non-Interactive, Proactive, Threshold ECDSA
 //! with Identifiable Aborts. [EPrint archive,
 //! 2021](https://eprint.iacr.org/2021/060.pdf).
 
+mod identification;
 mod input;
+mod nonces;
 mod participant;
+mod pool;
 mod record;
 mod round_one;
 mod round_three;
 mod round_two;
 
 pub use input::Input;
+pub use nonces::NoncePrecomputer;
 pub use participant::PresignParticipant;
+pub use pool::PresignPool;
 pub use record::PresignRecord;
+pub(crate) use record::ShareCommitment;
+#[cfg(test)]
+pub(crate) use round_three::corrupt_delta;
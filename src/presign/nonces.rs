@@ -0,0 +1,162 @@
+// Copyright (c) 2022-2023 Bolt Labs Holdings, Inc
+//
+// This source code is licensed under both the MIT license found in the
+// LICENSE-MIT file in the root directory of this source tree and the Apache
+// License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+// of this source tree.
+
+use std::{
+    collections::HashMap,
+    fmt::{Debug, Formatter},
+    sync::{Mutex, MutexGuard},
+};
+
+use rand::{CryptoRng, RngCore};
+use tracing::error;
+
+use crate::{
+    auxinfo::AuxInfoPublic,
+    errors::{InternalError, Result},
+    paillier::{EncryptionKey, NoncePool},
+};
+
+/// Precomputes the Paillier nonces used to encrypt values for other
+/// participants during presign.
+///
+/// Most of the cost of encrypting a value for another participant is
+/// computing `r^N mod N²` for a fresh nonce `r`, where `N` is the
+/// participant's Paillier modulus. This doesn't depend on the value being
+/// encrypted, so it can be done ahead of time, e.g. on a background thread
+/// while the application is idle. Presign runs whose [`Input`] was given the
+/// precomputer with
+/// [`with_nonce_precomputer()`](Input::with_nonce_precomputer()) use the
+/// precomputed nonces while there are any, and generate fresh ones after
+/// that.
+///
+/// The precomputer is safe to share between threads; each nonce is used for
+/// at most one encryption.
+///
+/// [`Input`]: crate::presign::Input
+#[derive(Default)]
+pub struct NoncePrecomputer {
+    /// The nonces for each Paillier modulus, keyed by its byte encoding.
+    pools: Mutex<HashMap<Vec<u8>, NoncePool>>,
+}
+
+impl Debug for NoncePrecomputer {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        // Only show how many keys there are nonces for; the nonces are secret.
+        let mut debug = f.debug_struct("NoncePrecomputer");
+        match self.pools.lock() {
+            Ok(pools) => {
+                debug.field("keys", &pools.len());
+            }
+            Err(_) => {
+                debug.field("keys", &"[poisoned]");
+            }
+        }
+        debug.finish()
+    }
+}
+
+impl NoncePrecomputer {
+    /// Create a precomputer with no nonces.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn lock(&self) -> Result<MutexGuard<'_, HashMap<Vec<u8>, NoncePool>>> {
+        self.pools.lock().map_err(|_| {
+            error!("Nonce precomputer lock was poisoned");
+            InternalError::InternalInvariantFailed
+        })
+    }
+
+    /// Get the pool of nonces for `key`, creating an empty one if there isn't
+    /// one yet.
+    pub(crate) fn pool(&self, key: &EncryptionKey) -> Result<NoncePool> {
+        Ok(self
+            .lock()?
+            .entry(key.modulus().to_bytes())
+            .or_default()
+            .clone())
+    }
+
+    /// Precompute `count` nonces for encrypting values for the participant
+    /// with the public auxinfo `auxinfo`.
+    ///
+    /// The lock on the precomputer is only held while the finished nonces
+    /// are added, so presign runs can keep using nonces while this runs.
+    pub fn precompute<R: RngCore + CryptoRng>(
+        &self,
+        rng: &mut R,
+        auxinfo: &AuxInfoPublic,
+        count: usize,
+    ) -> Result<()> {
+        let nonces = auxinfo
+            .pk()
+            .precompute_nonces(rng, count)
+            .map_err(|_| InternalError::InternalInvariantFailed)?;
+        self.pool(auxinfo.pk())?
+            .extend(nonces)
+            .map_err(|_| InternalError::InternalInvariantFailed)
+    }
+
+    /// The number of unused nonces for the participant with the public
+    /// auxinfo `auxinfo`.
+    ///
+    /// This can be used to decide when to precompute more.
+    pub fn remaining(&self, auxinfo: &AuxInfoPublic) -> Result<usize> {
+        match self.lock()?.get(&auxinfo.pk().modulus().to_bytes()) {
+            Some(pool) => pool
+                .len()
+                .map_err(|_| InternalError::InternalInvariantFailed),
+            None => Ok(0),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{auxinfo, utils::testing::init_testing, ParticipantConfig};
+    use libpaillier::unknown_order::BigNumber;
+
+    #[test]
+    fn precomputed_nonces_are_used_once() {
+        let rng = &mut init_testing();
+        let configs = ParticipantConfig::random_quorum(2, rng).unwrap();
+        let output = auxinfo::Output::simulate_set(&configs, rng).remove(0);
+        let pid = output.private_pid().unwrap();
+        let (mut publics, private) = output.into_parts();
+        publics.sort_by_key(|auxinfo| auxinfo.participant() != pid);
+        let decryption_key = private.decryption_key();
+
+        let precomputer = NoncePrecomputer::new();
+        precomputer.precompute(rng, &publics[0], 3).unwrap();
+        assert_eq!(precomputer.remaining(&publics[0]).unwrap(), 3);
+        assert_eq!(precomputer.remaining(&publics[1]).unwrap(), 0);
+
+        let pool = precomputer.pool(publics[0].pk()).unwrap();
+        publics[0].set_nonce_pool(pool);
+        let pk = publics[0].pk();
+
+        let mut nonces = Vec::new();
+        for i in 0..5 {
+            let x = BigNumber::from(i);
+            let (ciphertext, nonce) = pk.encrypt(rng, &x).unwrap();
+            assert_eq!(decryption_key.decrypt(&ciphertext).unwrap(), x);
+            assert_eq!(
+                decryption_key.decrypt_with_nonce(&ciphertext).unwrap().1,
+                nonce
+            );
+            nonces.push(nonce);
+        }
+        assert_eq!(precomputer.remaining(&publics[0]).unwrap(), 0);
+
+        // Each encryption used a different nonce, whether precomputed or not
+        for nonce in &nonces {
+            assert_eq!(nonces.iter().filter(|n| n == &nonce).count(), 1);
+        }
+    }
+}
@@ -15,20 +15,30 @@ use crate::{
     keygen::{KeySharePrivate, KeySharePublic},
     local_storage::LocalStorage,
     messages::{Message, MessageType, PresignMessageType},
+    paillier::{Ciphertext, Nonce},
+    parallel,
     parameters::ELL_PRIME,
-    participant::{Broadcast, InnerProtocolParticipant, ProcessOutcome, ProtocolParticipant},
+    participant::{
+        Broadcast, InnerProtocolParticipant, ParticipantState, ProcessOutcome, ProtocolParticipant,
+    },
     presign::{
+        identification::{self, ReceivedAffine},
         input::Input,
         record::{PresignRecord, RecordPair},
         round_one, round_three, round_two,
     },
     protocol::{ParticipantIdentifier, ProtocolType, SharedContext},
     run_only_once,
-    utils::{bn_to_scalar, k256_order, random_plusminus_by_size, random_positive_bn, CurvePoint},
+    utils::{
+        bn_to_scalar, k256_order, random_plusminus_by_size, random_positive_bn, scalar_to_bn,
+        CurvePoint,
+    },
     zkp::{
         piaffg::{PiAffgInput, PiAffgProof, PiAffgSecret},
+        pidec::{PiDecInput, PiDecProof, PiDecSecret},
         pienc::{PiEncInput, PiEncProof, PiEncSecret},
         pilog::{CommonInput, PiLogProof, ProverSecret},
+        pimul::{PiMulInput, PiMulProof, PiMulSecret},
         Proof, ProofContext,
     },
     Identifier,
@@ -39,47 +49,61 @@ use rand::{CryptoRng, RngCore};
 use std::collections::{HashMap, HashSet};
 use tracing::{error, info, instrument};
 
-// Local storage data types.
+// Local storage data types. Each round value is stored as a batch, with one
+// entry for each presign record being produced.
 mod storage {
     use crate::local_storage::TypeTag;
 
     pub(super) struct RoundOnePrivate;
     impl TypeTag for RoundOnePrivate {
-        type Value = crate::presign::round_one::Private;
+        type Value = Vec<crate::presign::round_one::Private>;
+        const NAME: &'static str = "presign.RoundOnePrivate";
     }
     pub(super) struct RoundOneComplete;
     impl TypeTag for RoundOneComplete {
         type Value = ();
+        const NAME: &'static str = "presign.RoundOneComplete";
     }
     pub(super) struct RoundOnePublicBroadcast;
     impl TypeTag for RoundOnePublicBroadcast {
-        type Value = crate::presign::round_one::PublicBroadcast;
+        type Value = Vec<crate::presign::round_one::PublicBroadcast>;
+        const NAME: &'static str = "presign.RoundOnePublicBroadcast";
     }
     pub(super) struct RoundTwoPrivate;
     impl TypeTag for RoundTwoPrivate {
-        type Value = crate::presign::round_two::Private;
+        type Value = Vec<crate::presign::round_two::Private>;
+        const NAME: &'static str = "presign.RoundTwoPrivate";
     }
     pub(super) struct RoundTwoPublic;
     impl TypeTag for RoundTwoPublic {
-        type Value = crate::presign::round_two::Public;
+        type Value = Vec<crate::presign::round_two::Public>;
+        const NAME: &'static str = "presign.RoundTwoPublic";
     }
     pub(super) struct RoundThreePrivate;
     impl TypeTag for RoundThreePrivate {
-        type Value = crate::presign::round_three::Private;
+        type Value = Vec<crate::presign::round_three::Private>;
+        const NAME: &'static str = "presign.RoundThreePrivate";
     }
     pub(super) struct RoundThreePublic;
     impl TypeTag for RoundThreePublic {
-        type Value = crate::presign::round_three::Public;
+        type Value = Vec<crate::presign::round_three::Public>;
+        const NAME: &'static str = "presign.RoundThreePublic";
+    }
+    pub(super) struct IdentificationPublic;
+    impl TypeTag for IdentificationPublic {
+        type Value = Vec<crate::presign::identification::Public>;
+        const NAME: &'static str = "presign.IdentificationPublic";
     }
 }
 
 /// This type includes relevant context for transcripts produced in `presign`,
-/// and includes [`SharedContext`] and [`AuxInfoPublic`]s for all participants
-/// (including this participant).
+/// and includes [`SharedContext`], [`AuxInfoPublic`]s for all participants
+/// (including this participant) and the number of records being produced.
 #[derive(Debug)]
 pub(crate) struct PresignContext {
     shared_context: SharedContext,
     auxinfo_public: Vec<AuxInfoPublic>,
+    record_count: u64,
 }
 
 impl ProofContext for PresignContext {
@@ -88,6 +112,7 @@ impl ProofContext for PresignContext {
             self.shared_context.as_bytes()?,
             bincode::serialize(&self.auxinfo_public)
                 .map_err(|_| InternalError::InternalInvariantFailed)?,
+            self.record_count.to_le_bytes().to_vec(),
         ]
         .concat())
     }
@@ -101,6 +126,7 @@ impl PresignContext {
         Self {
             shared_context: SharedContext::collect(p),
             auxinfo_public,
+            record_count: p.input().record_count() as u64,
         }
     }
 }
@@ -122,20 +148,34 @@ impl PresignContext {
 ///   - A single set of [private auxiliary information](`AuxInfoPrivate`) for
 ///     this participant.
 ///
+/// If the key shares are threshold (Shamir) shares, the participants in a
+/// presign session can be any subset of the keygen participants, of size at
+/// least the threshold, for which auxinfo was provided. Before round one, each
+/// participant converts the quorum's key shares into additive shares using
+/// Lagrange coefficients. The resulting [`PresignRecord`] can only be used to
+/// sign with the same quorum.
+///
+/// The input also sets how many records to produce (see
+/// [`Input::with_record_count()`]). A batch of records is produced by running
+/// the rounds below once, with every message carrying the values for the
+/// whole batch.
+///
 /// # Protocol output
 /// Upon successful completion, the participant outputs the following:
-/// - A single [`PresignRecord`], which corresponds to the **private** presign
-///   record of this participant.
+/// - A list of [`PresignRecord`]s, one for each requested record, which
+///   correspond to the **private** presign records of this participant. The
+///   records are in the same order for every participant.
 ///
 /// # 🔒 Storage requirement
-/// The [`PresignRecord`] output requires secure persistent storage.
+/// The [`PresignRecord`] outputs require secure persistent storage.
 ///
 /// # 🔒 Lifetime requirement
-/// The [`PresignRecord`] output must only be used once and then discarded.
+/// Each [`PresignRecord`] output must only be used once and then discarded.
 ///
 /// # High-level protocol description
 /// The goal of the presign protocol is to generate [`PresignRecord`]s for all
-/// protocol participants. The protocol proceeds in four rounds, and utilizes
+/// protocol participants. The protocol proceeds in four rounds (plus an
+/// identification phase if something goes wrong), and utilizes
 /// the [`KeySharePrivate`] (`xᵢ` in the paper) constructed during the
 /// [`keygen`](crate::keygen::KeygenParticipant) protocol.
 ///
@@ -178,6 +218,8 @@ impl PresignContext {
 ///    - An "unmasked" [`KeySharePrivate`] summation multiplied by its own key
 ///      share: `χᵢ = (∑ xⱼ) kᵢ`.
 ///
+///    - A commitment to its masked key share: `Sᵢ = Γ^{χᵢ}`.
+///
 ///    It also attaches a zero-knowledge proof (per participant) that the value
 ///    `Δᵢ` was computed correctly.
 ///
@@ -187,11 +229,30 @@ impl PresignContext {
 ///    checks that `g^{∑ δᵢ} = ∏ᵢ Δᵢ`, which essentially checks that the value
 ///    `g^{ɣ k}` was computed correctly, where `ɣ = ∑ ɣᵢ` and `k = ∑ kᵢ`.
 ///    (Recall that `g^{ɣ k}` was the value we were aiming to compute in the
-///    first place.)
+///    first place.) It also checks that `∏ᵢ Sᵢ = X^{∑ δᵢ}`, where `X` is the
+///    public key, so that the `Sᵢ` are consistent with the key.
 ///
 ///    If this holds, each participant can output its [`PresignRecord`] as the
 ///    tuple `(Γ^{(ɣ k)^{-1}}, kᵢ, χᵢ)`.
 ///
+/// 5. If either check in round four fails, the participant starts an
+///    identification phase (Figure 7, Output step 2b in the paper). Each
+///    participant sends every other participant an encryption `Hᵢ` of `kᵢ
+///    ɣᵢ` with a proof that it was computed correctly, the round two values
+///    it received (so that everyone agrees on them), and a proof that its
+///    `δᵢ` is the decryption of the ciphertext `Hᵢ ∏ⱼ Dⱼ,ᵢ ∏ⱼ Fᵢ,ⱼ^{-1}`,
+///    reduced modulo `q`. In the same way, it sends an encryption `Ĥᵢ` of `kᵢ
+///    xᵢ` and proves that `Sᵢ` is `Γ` raised to the plaintext of `Ĥᵢ ∏ⱼ
+///    D̂ⱼ,ᵢ ∏ⱼ F̂ᵢ,ⱼ^{-1}`, reduced modulo `q`, without revealing `χᵢ`. Once
+///    all of these are checked, the participant aborts with a
+///    [`ProtocolError`](InternalError::ProtocolError) naming the first
+///    participant whose values don't check out.
+///
+///    Round three values are not broadcast, so a malicious participant may
+///    cause only some honest participants to fail the check. Participants that
+///    already produced a [`PresignRecord`] still respond to identification
+///    messages, so that the others can finish identification.
+///
 /// [^cite]: Ran Canetti, Rosario Gennaro, Steven Goldfeder, Nikolaos
 /// Makriyannis, and Udi Peled. UC Non-Interactive, Proactive, Threshold ECDSA
 /// with Identifiable Aborts. [EPrint archive,
@@ -217,7 +278,7 @@ pub struct PresignParticipant {
 
 impl ProtocolParticipant for PresignParticipant {
     type Input = Input;
-    type Output = PresignRecord;
+    type Output = Vec<PresignRecord>;
 
     fn new(
         sid: Identifier,
@@ -225,6 +286,12 @@ impl ProtocolParticipant for PresignParticipant {
         other_participant_ids: Vec<ParticipantIdentifier>,
         input: Self::Input,
     ) -> Result<Self> {
+        // Threshold key shares are converted into additive shares for this quorum.
+        let quorum = std::iter::once(id)
+            .chain(other_participant_ids.iter().copied())
+            .collect::<Vec<_>>();
+        let input = input.for_quorum(&quorum)?;
+
         let input_participants = input.participants();
 
         // Check that we have the expected number of inputs
@@ -280,9 +347,26 @@ impl ProtocolParticipant for PresignParticipant {
         self.sid
     }
 
+    fn export_state(&self) -> Result<Vec<u8>> {
+        ParticipantState::export(
+            &self.status,
+            &self.local_storage,
+            Some(&self.broadcast_participant),
+        )
+    }
+
+    fn import_state(&mut self, state: &[u8]) -> Result<()> {
+        ParticipantState::import(
+            state,
+            &mut self.status,
+            &mut self.local_storage,
+            Some(&mut self.broadcast_participant),
+        )
+    }
+
     /// Process the incoming message.
     ///
-    /// This method produces a [`PresignRecord`] once presigning is complete.
+    /// This method produces the [`PresignRecord`]s once presigning is complete.
     #[cfg_attr(feature = "flame_it", flame("presign"))]
     #[instrument(skip_all, err(Debug))]
     fn process_message<R: RngCore + CryptoRng>(
@@ -292,7 +376,12 @@ impl ProtocolParticipant for PresignParticipant {
     ) -> Result<ProcessOutcome<Self::Output>> {
         info!("Processing presign message.");
 
-        if *self.status() == Status::TerminatedSuccessfully {
+        // A participant that terminated successfully may still be asked to take
+        // part in the identification phase by participants whose final check
+        // failed.
+        if *self.status() == Status::TerminatedSuccessfully
+            && message.message_type() != MessageType::Presign(PresignMessageType::Identification)
+        {
             Err(CallerError::ProtocolAlreadyTerminated)?;
         }
 
@@ -316,7 +405,10 @@ impl ProtocolParticipant for PresignParticipant {
                 self.handle_round_two_msg(rng, message)
             }
             MessageType::Presign(PresignMessageType::RoundThree) => {
-                self.handle_round_three_msg(message)
+                self.handle_round_three_msg(rng, message)
+            }
+            MessageType::Presign(PresignMessageType::Identification) => {
+                self.handle_identification_msg(rng, message)
             }
             message_type => {
                 error!(
@@ -331,6 +423,68 @@ impl ProtocolParticipant for PresignParticipant {
     fn status(&self) -> &Status {
         &self.status
     }
+
+    fn waiting_on(&self) -> Vec<ParticipantIdentifier> {
+        if self.status != Status::Initialized {
+            return Vec::new();
+        }
+        let others = &self.other_participant_ids;
+
+        // Round one: the broadcast ciphertexts and the accompanying proofs
+        let missing_round_one = self
+            .local_storage
+            .missing_ids::<storage::RoundOneComplete>(others);
+        if !missing_round_one.is_empty() {
+            let missing_broadcasts = self
+                .local_storage
+                .missing_ids::<storage::RoundOnePublicBroadcast>(&missing_round_one);
+            let waiting = self
+                .broadcast_participant
+                .waiting_on_broadcasts(BroadcastTag::PresignR1Ciphertexts, &missing_broadcasts);
+            return others
+                .iter()
+                .copied()
+                .filter(|pid| {
+                    waiting.contains(pid)
+                        || (missing_round_one.contains(pid) && !missing_broadcasts.contains(pid))
+                })
+                .collect();
+        }
+
+        // Round two
+        let round_two = others
+            .iter()
+            .copied()
+            .filter(|pid| {
+                !self
+                    .local_storage
+                    .contains::<storage::RoundTwoPrivate>(*pid)
+                    || !self.local_storage.contains::<storage::RoundTwoPublic>(*pid)
+            })
+            .collect::<Vec<_>>();
+        if !round_two.is_empty() {
+            return round_two;
+        }
+
+        // Round three
+        let missing_round_three = self
+            .local_storage
+            .missing_ids::<storage::RoundThreePublic>(others);
+        if !missing_round_three.is_empty() {
+            return missing_round_three;
+        }
+
+        // If we're still running after round three, the final check failed and
+        // we're in the identification phase. This includes our own message.
+        self.local_storage
+            .missing_ids::<storage::IdentificationPublic>(&self.all_participants())
+    }
+
+    fn abort(&mut self) {
+        self.local_storage = LocalStorage::default();
+        self.broadcast_participant.abort();
+        self.status = Status::Aborted;
+    }
 }
 
 impl InnerProtocolParticipant for PresignParticipant {
@@ -364,6 +518,25 @@ impl PresignParticipant {
         &self.input
     }
 
+    /// Check that a batch of values received from `sender` has one value for
+    /// each presign record.
+    ///
+    /// The record count is chosen locally by each participant and never
+    /// agreed on, so a mismatch may just mean that the participants were
+    /// configured differently. It doesn't blame the sender.
+    fn check_batch_size<T>(&self, sender: ParticipantIdentifier, batch: &[T]) -> Result<()> {
+        if batch.len() != self.input().record_count() {
+            error!(
+                "Expected a batch of {} values from {}, but got {}",
+                self.input().record_count(),
+                sender,
+                batch.len()
+            );
+            Err(InternalError::ProtocolError(None))?
+        }
+        Ok(())
+    }
+
     /// Handle "Ready" messages from the protocol participants.
     ///
     /// Once "Ready" messages have been received from all participants, this
@@ -401,14 +574,25 @@ impl PresignParticipant {
 
         let info = PresignKeyShareAndInfo::new(self.id, self.input())?;
         let other_public_auxinfo = self.input().all_but_one_auxinfo_public(self.id);
+        let context = self.retrieve_context();
+
+        // Run round one for each record in the batch.
+        let mut privates = Vec::with_capacity(self.input().record_count());
+        let mut r1_publics: HashMap<_, Vec<_>> = HashMap::new();
+        let mut r1_public_broadcasts = Vec::with_capacity(self.input().record_count());
+        for _ in 0..self.input().record_count() {
+            let (private, publics, public_broadcast) =
+                info.round_one(rng, &context, &other_public_auxinfo)?;
+            privates.push(private);
+            for (other_id, public) in publics {
+                r1_publics.entry(other_id).or_default().push(public);
+            }
+            r1_public_broadcasts.push(public_broadcast);
+        }
 
-        // Run round one.
-        let (private, r1_publics, r1_public_broadcast) =
-            info.round_one(rng, &self.retrieve_context(), &other_public_auxinfo)?;
-
-        // Store private round one value locally.
+        // Store private round one values locally.
         self.local_storage
-            .store::<storage::RoundOnePrivate>(self.id, private);
+            .store::<storage::RoundOnePrivate>(self.id, privates);
 
         // Generate round one messages for all other participants.
         let mut messages = r1_publics
@@ -427,7 +611,7 @@ impl PresignParticipant {
         let broadcast_messages = self.broadcast(
             rng,
             MessageType::Presign(PresignMessageType::RoundOneBroadcast),
-            serialize!(&r1_public_broadcast)?,
+            serialize!(&r1_public_broadcasts)?,
             sid,
             BroadcastTag::PresignR1Ciphertexts,
         )?;
@@ -449,9 +633,11 @@ impl PresignParticipant {
         info!("Presign: Handling round one broadcast message.");
 
         let message = broadcast_message.into_message(BroadcastTag::PresignR1Ciphertexts)?;
-        let public_broadcast: round_one::PublicBroadcast = deserialize!(&message.unverified_bytes)?;
+        let public_broadcasts: Vec<round_one::PublicBroadcast> =
+            deserialize!(&message.unverified_bytes)?;
+        self.check_batch_size(message.from(), &public_broadcasts)?;
         self.local_storage
-            .store::<storage::RoundOnePublicBroadcast>(message.from(), public_broadcast);
+            .store::<storage::RoundOnePublicBroadcast>(message.from(), public_broadcasts);
 
         // Check to see if we have already stored the round one non-broadcast
         // message from the given participant. If so, retrieve and process it.
@@ -501,19 +687,25 @@ impl PresignParticipant {
         // Note that the reason we do _not_ use the output of `retrieve` to make
         // this decision is that `retrieve` can either error out because an
         // entry isn't there, _or_ because of an internal invariant failure.
-        let r1_public_broadcast = self
+        let r1_public_broadcasts = self
             .local_storage
             .retrieve::<storage::RoundOnePublicBroadcast>(message.from())?;
 
         let info = PresignKeyShareAndInfo::new(self.id, self.input())?;
         let auxinfo_public = self.input().find_auxinfo_public(message.from())?;
-        let round_one_public = round_one::Public::try_from(message)?;
-        round_one_public.verify(
-            &self.retrieve_context(),
-            info.aux_info_public.params(),
-            auxinfo_public.pk(),
-            r1_public_broadcast,
-        )?;
+        let round_one_publics = round_one::Public::from_message(message)?;
+        self.check_batch_size(message.from(), &round_one_publics)?;
+        let context = self.retrieve_context();
+        for (round_one_public, r1_public_broadcast) in
+            round_one_publics.into_iter().zip(r1_public_broadcasts)
+        {
+            round_one_public.verify(
+                &context,
+                info.aux_info_public.params(),
+                auxinfo_public.pk(),
+                r1_public_broadcast,
+            )?;
+        }
         // Mark that we have completed round one for this participant.
         //
         // Since we don't need the round one message in the rest of the
@@ -567,37 +759,59 @@ impl PresignParticipant {
         }
 
         let info = PresignKeyShareAndInfo::new(self.id, self.input())?;
-        // We need this clone as the map below uses a mutable `self`.
-        let pids = self.other_participant_ids.clone();
-        let more_messages: Vec<Message> = pids
-            .into_iter()
-            .map(|pid| {
-                let r1_priv = self
-                    .local_storage
-                    .retrieve::<storage::RoundOnePrivate>(self.id)?;
-                let r1_public_broadcast = self
+        let context = self.retrieve_context();
+        let r1_privs = self
+            .local_storage
+            .retrieve::<storage::RoundOnePrivate>(self.id)?;
+        let recipients = self
+            .other_participant_ids
+            .iter()
+            .map(|&pid| {
+                let r1_public_broadcasts = self
                     .local_storage
                     .retrieve::<storage::RoundOnePublicBroadcast>(pid)?;
-                let sender_auxinfo_public = self.input().find_auxinfo_public(pid)?;
-                let (r2_priv, r2_pub) = info.round_two(
-                    rng,
-                    &self.retrieve_context(),
-                    sender_auxinfo_public,
-                    r1_priv,
-                    r1_public_broadcast,
-                )?;
-                self.local_storage
-                    .store::<storage::RoundTwoPrivate>(pid, r2_priv);
-                Message::new(
-                    MessageType::Presign(PresignMessageType::RoundTwo),
-                    sid,
-                    self.id,
-                    pid,
-                    &r2_pub,
-                )
+                let receiver_auxinfo_public = self.input().find_auxinfo_public(pid)?;
+                Ok((pid, r1_public_broadcasts, receiver_auxinfo_public))
             })
             .collect::<Result<Vec<_>>>()?;
-        messages.extend(more_messages);
+        // Each recipient's values and proofs are independent, so compute
+        // them in parallel.
+        let r2_values = parallel::map_with_rng(
+            rng,
+            recipients,
+            |rng, (pid, r1_public_broadcasts, receiver_auxinfo_public)| -> Result<_> {
+                let (r2_privs, r2_pubs): (Vec<_>, Vec<_>) = r1_privs
+                    .iter()
+                    .zip(r1_public_broadcasts)
+                    .map(|(r1_priv, r1_public_broadcast)| {
+                        info.round_two(
+                            rng,
+                            &context,
+                            receiver_auxinfo_public,
+                            r1_priv,
+                            r1_public_broadcast,
+                        )
+                    })
+                    .collect::<Result<Vec<_>>>()?
+                    .into_iter()
+                    .unzip();
+                Ok((pid, r2_privs, r2_pubs))
+            },
+        )
+        .into_iter()
+        .collect::<Result<Vec<_>>>()?;
+
+        for (pid, r2_privs, r2_pubs) in r2_values {
+            self.local_storage
+                .store::<storage::RoundTwoPrivate>(pid, r2_privs);
+            messages.push(Message::new(
+                MessageType::Presign(PresignMessageType::RoundTwo),
+                sid,
+                self.id,
+                pid,
+                &r2_pubs,
+            )?);
+        }
         Ok(messages)
     }
 
@@ -645,7 +859,7 @@ impl PresignParticipant {
             let outcomes = self
                 .fetch_messages(MessageType::Presign(PresignMessageType::RoundThree))?
                 .iter()
-                .map(|msg| self.handle_round_three_msg(msg))
+                .map(|msg| self.handle_round_three_msg(rng, msg))
                 .collect::<Result<Vec<_>>>()?;
             ProcessOutcome::collect_with_messages(outcomes, messages)
         } else {
@@ -670,46 +884,59 @@ impl PresignParticipant {
         info!("Generating round three presign messages.");
 
         let info = PresignKeyShareAndInfo::new(self.id, self.input())?;
-        // Collect the other participant's values from storage needed for round
-        // three.
-        let mut hashmap = HashMap::new();
-        for pid in self.other_participant_ids.clone() {
-            let auxinfo_public = self.input().find_auxinfo_public(pid)?;
-            let r2_private = self
-                .local_storage
-                .retrieve::<storage::RoundTwoPrivate>(pid)?;
-            let r2_public = self
-                .local_storage
-                .retrieve::<storage::RoundTwoPublic>(pid)?;
-            let _ = hashmap.insert(
-                pid,
-                round_three::Input {
-                    auxinfo_public: auxinfo_public.clone(),
-                    r2_private: r2_private.clone(),
-                    r2_public: r2_public.clone(),
-                },
-            );
-        }
-
-        let r1_priv = self
+        let context = self.retrieve_context();
+        let r1_privs = self
             .local_storage
             .retrieve::<storage::RoundOnePrivate>(self.id)?;
 
-        let (r3_private, r3_publics_map) =
-            info.round_three(rng, &self.retrieve_context(), r1_priv, &hashmap)?;
+        let mut r3_privates = Vec::with_capacity(r1_privs.len());
+        let mut r3_publics: HashMap<_, Vec<_>> = HashMap::new();
+        for (index, r1_priv) in r1_privs.iter().enumerate() {
+            // Collect the other participant's values from storage needed for
+            // round three of this record.
+            let mut hashmap = HashMap::new();
+            for pid in &self.other_participant_ids {
+                let auxinfo_public = self.input().find_auxinfo_public(*pid)?;
+                let r2_private = self
+                    .local_storage
+                    .retrieve::<storage::RoundTwoPrivate>(*pid)?
+                    .get(index)
+                    .ok_or(InternalError::InternalInvariantFailed)?;
+                let r2_public = self
+                    .local_storage
+                    .retrieve::<storage::RoundTwoPublic>(*pid)?
+                    .get(index)
+                    .ok_or(InternalError::InternalInvariantFailed)?;
+                let _ = hashmap.insert(
+                    *pid,
+                    round_three::Input {
+                        auxinfo_public: auxinfo_public.clone(),
+                        r2_private: r2_private.clone(),
+                        r2_public: r2_public.clone(),
+                    },
+                );
+            }
+
+            let (r3_private, r3_publics_map) =
+                info.round_three(rng, &context, r1_priv, &hashmap)?;
+            r3_privates.push(r3_private);
+            for (pid, r3_public) in r3_publics_map {
+                r3_publics.entry(pid).or_default().push(r3_public);
+            }
+        }
 
         self.local_storage
-            .store::<storage::RoundThreePrivate>(self.id, r3_private);
+            .store::<storage::RoundThreePrivate>(self.id, r3_privates);
 
-        let messages = r3_publics_map
+        let messages = r3_publics
             .into_iter()
-            .map(|(id, r3_public)| {
+            .map(|(id, r3_publics)| {
                 Message::new(
                     MessageType::Presign(PresignMessageType::RoundThree),
                     sid,
                     self.id,
                     id,
-                    &r3_public,
+                    &r3_publics,
                 )
             })
             .collect::<Result<Vec<_>>>()?;
@@ -717,10 +944,16 @@ impl PresignParticipant {
     }
 
     /// Handle a round three message.
+    ///
+    /// Once round three messages have been received from all other
+    /// participants, this either outputs the [`PresignRecord`]s or, if the
+    /// final consistency check fails for any of them, starts the
+    /// identification phase.
     #[cfg_attr(feature = "flame_it", flame("presign"))]
     #[instrument(skip_all, err(Debug))]
-    fn handle_round_three_msg(
+    fn handle_round_three_msg<R: RngCore + CryptoRng>(
         &mut self,
+        rng: &mut R,
         message: &Message,
     ) -> Result<ProcessOutcome<<Self as ProtocolParticipant>::Output>> {
         info!("Handling round three presign message.");
@@ -745,55 +978,604 @@ impl PresignParticipant {
             .local_storage
             .contains_for_all_ids::<storage::RoundThreePublic>(&self.other_participant_ids)
         {
-            let record = self.do_presign_finish()?;
+            let record_pairs = self.round_three_record_pairs()?;
+            if !record_pairs
+                .iter()
+                .all(|pair| pair.deltas_are_consistent() && pair.chi_commitments_are_consistent())
+            {
+                // Somebody cheated: run the identification phase to find out who.
+                // Our own identification message is also sent to ourselves, so
+                // that we only reach a verdict after our messages went out.
+                error!("Presign: delta or chi check failed. Starting identification phase.");
+                let messages = run_only_once!(self.gen_identification_msgs(rng, message.id()))?;
+                return Ok(ProcessOutcome::Processed(messages));
+            }
+
+            // Note: This `try_into` call does the check and computation
+            // specified in Step 2 of Output in the paper's protocol
+            // specification (Figure 7).
+            let records = record_pairs
+                .into_iter()
+                .map(PresignRecord::try_from)
+                .collect::<Result<Vec<_>>>()?;
             self.status = Status::TerminatedSuccessfully;
-            Ok(ProcessOutcome::Terminated(record))
+
+            // Other participants may have already asked us to take part in
+            // identification.
+            let messages = if self
+                .fetch_messages(MessageType::Presign(PresignMessageType::Identification))?
+                .is_empty()
+            {
+                vec![]
+            } else {
+                run_only_once!(self.gen_identification_msgs(rng, message.id()))?
+            };
+            Ok(ProcessOutcome::from(Some(records), messages))
         } else {
             Ok(ProcessOutcome::Incomplete)
         }
     }
 
-    /// Finish the presign protocol.
+    /// Collect this participant's round three private values and the other
+    /// participants' round three public values for each record, checking that
+    /// everyone agrees on `Γ`.
     #[cfg_attr(feature = "flame_it", flame("presign"))]
     #[instrument(skip_all, err(Debug))]
-    fn do_presign_finish(&mut self) -> Result<PresignRecord> {
-        info!("Doing presign finish. Creating presign record.");
-        // Collect the other participants' round three public values from storage.
-        let r3_pubs = self
-            .other_participant_ids
+    fn round_three_record_pairs(&self) -> Result<Vec<RecordPair>> {
+        let public_key = self
+            .input()
+            .public_key_shares()
             .iter()
-            .map(|pid| {
-                Ok(self
-                    .local_storage
-                    .retrieve::<storage::RoundThreePublic>(*pid)?
-                    .clone())
+            .fold(CurvePoint::IDENTITY, |sum, share| sum + *share.as_ref());
+
+        let r3_privates = self
+            .local_storage
+            .retrieve::<storage::RoundThreePrivate>(self.id)?;
+
+        r3_privates
+            .iter()
+            .enumerate()
+            .map(|(index, r3_private)| {
+                // Collect the other participants' round three public values from storage.
+                let r3_pubs = self
+                    .other_participant_ids
+                    .iter()
+                    .map(|pid| {
+                        let r3_pub = self
+                            .local_storage
+                            .retrieve::<storage::RoundThreePublic>(*pid)?
+                            .get(index)
+                            .ok_or(InternalError::InternalInvariantFailed)?;
+                        Ok((*pid, r3_pub.clone()))
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+
+                // Check consistency across all Gamma values. Each `Γⱼ` is bound by a
+                // proof to the plaintext of the broadcast `Gⱼ`, so every participant
+                // that verified the round two proofs computes the same `Γ`. A sender
+                // reporting a different one is therefore the one at fault.
+                for (pid, r3_pub) in &r3_pubs {
+                    if r3_pub.Gamma != r3_private.Gamma {
+                        error!(
+                            "Mismatch in Gamma values for r3_private and the r3_pub of participant: {:?}",
+                            pid
+                        );
+                        return Err(InternalError::ProtocolError(Some(*pid)));
+                    }
+                }
+
+                Ok(RecordPair {
+                    id: self.id,
+                    public_key,
+                    private: r3_private.clone(),
+                    publics: r3_pubs,
+                })
             })
-            .collect::<Result<Vec<_>>>()?;
+            .collect()
+    }
 
-        let r3_private = self
+    /// Generate identification messages, one for each participant (including
+    /// this one).
+    ///
+    /// For each record in the batch, each message contains encryptions `H` of
+    /// `kᵢ ɣᵢ` and `Ĥ` of `kᵢ xᵢ`, the round two values this participant
+    /// received, and proofs that `H`, `Ĥ`, `δᵢ` and `Sᵢ` were computed
+    /// correctly.
+    #[cfg_attr(feature = "flame_it", flame("presign"))]
+    #[instrument(skip_all, err(Debug))]
+    fn gen_identification_msgs<R: RngCore + CryptoRng>(
+        &mut self,
+        rng: &mut R,
+        sid: Identifier,
+    ) -> Result<Vec<Message>> {
+        info!("Presign: Generating identification messages.");
+
+        let info = PresignKeyShareAndInfo::new(self.id, self.input())?;
+        let context = self.retrieve_context();
+        let pk = info.aux_info_public.pk();
+        let r1_privs = self
+            .local_storage
+            .retrieve::<storage::RoundOnePrivate>(self.id)?;
+        let r3_privs = self
             .local_storage
             .retrieve::<storage::RoundThreePrivate>(self.id)?;
 
-        // Check consistency across all Gamma values
-        for (i, r3_pub) in r3_pubs.iter().enumerate() {
-            if r3_pub.Gamma != r3_private.Gamma {
-                error!(
-                    "Mismatch in Gamma values for r3_private and the r3_pub of participant: {:?}",
-                    &self.other_participant_ids[i]
-                );
-                return Err(InternalError::ProtocolError(None));
+        // The values for each record that are the same for every recipient.
+        struct Identification {
+            H: Ciphertext,
+            psi_mul: PiMulProof,
+            received: Vec<ReceivedAffine>,
+            combined: Ciphertext,
+            plaintext: BigNumber,
+            nonce: Nonce,
+            delta: BigNumber,
+            K: Ciphertext,
+            H_hat: Ciphertext,
+            H_hat_nonce: Nonce,
+            Y_hat: Ciphertext,
+            Y_hat_nonce: Nonce,
+            C_hat: Ciphertext,
+            C_hat_nonce: Nonce,
+            chi: BigNumber,
+            Gamma: CurvePoint,
+            S: CurvePoint,
+            combined_hat: Ciphertext,
+            plaintext_hat: BigNumber,
+            nonce_hat: Nonce,
+        }
+
+        let mut identifications = Vec::with_capacity(r1_privs.len());
+        for (index, (r1_priv, r3_priv)) in r1_privs.iter().zip(r3_privs).enumerate() {
+            // Compute `H = G^k` (rerandomized), which encrypts `k ɣ`, and prove it.
+            let (zero, H_nonce) = info
+                .aux_info_private
+                .decryption_key()
+                .encrypt(rng, &BigNumber::zero())
+                .map_err(|_| InternalError::InternalInvariantFailed)?;
+            let H = pk
+                .multiply_and_add(&r1_priv.k, &r1_priv.G, &zero)
+                .map_err(|_| InternalError::InternalInvariantFailed)?;
+            let mut transcript = Transcript::new(b"PiMulProof");
+            let psi_mul = PiMulProof::prove(
+                PiMulInput::new(pk, &r1_priv.K, &r1_priv.G, &H),
+                PiMulSecret::new(&r1_priv.k, &r1_priv.rho, &H_nonce),
+                &context,
+                &mut transcript,
+                rng,
+            )?;
+
+            // Compute `Ĥ = K^x` (rerandomized), which encrypts `k x`. It's proven
+            // separately for each recipient, since the proof depends on their
+            // setup parameters.
+            let decryption_key = info.aux_info_private.decryption_key();
+            let (zero_hat, H_hat_nonce) = decryption_key
+                .encrypt(rng, &BigNumber::zero())
+                .map_err(|_| InternalError::InternalInvariantFailed)?;
+            let H_hat = pk
+                .multiply_and_add(info.keyshare_private.as_ref(), &r1_priv.K, &zero_hat)
+                .map_err(|_| InternalError::InternalInvariantFailed)?;
+            let (Y_hat, Y_hat_nonce) = decryption_key
+                .encrypt(rng, &BigNumber::zero())
+                .map_err(|_| InternalError::InternalInvariantFailed)?;
+
+            // Collect the round two values we received and sent.
+            let mut received = Vec::with_capacity(self.other_participant_ids.len());
+            let mut sent = Vec::with_capacity(self.other_participant_ids.len());
+            let mut sent_hat = Vec::with_capacity(self.other_participant_ids.len());
+            for pid in &self.other_participant_ids {
+                let r2_public = self
+                    .local_storage
+                    .retrieve::<storage::RoundTwoPublic>(*pid)?
+                    .get(index)
+                    .ok_or(InternalError::InternalInvariantFailed)?;
+                received.push(ReceivedAffine::new(*pid, r2_public));
+                let r2_private = self
+                    .local_storage
+                    .retrieve::<storage::RoundTwoPrivate>(*pid)?
+                    .get(index)
+                    .ok_or(InternalError::InternalInvariantFailed)?;
+                sent.push(r2_private.F.clone());
+                sent_hat.push(r2_private.F_hat.clone());
             }
+
+            // The plaintext of the combined ciphertext is `δ` (before reducing mod
+            // `q`); decrypting also recovers its nonce.
+            let combined = identification::combine_ciphertexts(
+                pk,
+                &H,
+                received.iter().map(|received| &received.D),
+                &sent,
+            )?;
+            let (plaintext, nonce) = info
+                .aux_info_private
+                .decryption_key()
+                .decrypt_with_nonce(&combined)
+                .map_err(|_| InternalError::InternalInvariantFailed)?;
+            let delta = scalar_to_bn(&r3_priv.delta);
+
+            // `χ` can't be revealed, so instead encrypt it afresh and show that
+            // the combined ciphertext for `χ` divided by the fresh one encrypts
+            // a multiple of `q`.
+            let chi = scalar_to_bn(&r3_priv.chi);
+            let (C_hat, C_hat_nonce) = decryption_key
+                .encrypt(rng, &chi)
+                .map_err(|_| InternalError::InternalInvariantFailed)?;
+            let combined_hat = identification::combine_ciphertexts(
+                pk,
+                &H_hat,
+                received.iter().map(|received| &received.D_hat),
+                sent_hat.iter().chain(std::iter::once(&C_hat)),
+            )?;
+            let (plaintext_hat, nonce_hat) = decryption_key
+                .decrypt_with_nonce(&combined_hat)
+                .map_err(|_| InternalError::InternalInvariantFailed)?;
+
+            identifications.push(Identification {
+                H,
+                psi_mul,
+                received,
+                combined,
+                plaintext,
+                nonce,
+                delta,
+                K: r1_priv.K.clone(),
+                H_hat,
+                H_hat_nonce,
+                Y_hat,
+                Y_hat_nonce,
+                C_hat,
+                C_hat_nonce,
+                chi,
+                Gamma: r3_priv.Gamma,
+                S: r3_priv.S,
+                combined_hat,
+                plaintext_hat,
+                nonce_hat,
+            });
+        }
+
+        self.all_participants()
+            .into_iter()
+            .map(|pid| {
+                let verifier_auxinfo_public = self.input().find_auxinfo_public(pid)?;
+                let publics = identifications
+                    .iter()
+                    .map(|identification| {
+                        let mut transcript = Transcript::new(b"PiDecProof");
+                        let psi_dec = PiDecProof::prove(
+                            PiDecInput::new(
+                                verifier_auxinfo_public.params().scheme(),
+                                pk,
+                                &identification.combined,
+                                &identification.delta,
+                            ),
+                            PiDecSecret::new(&identification.plaintext, &identification.nonce),
+                            &context,
+                            &mut transcript,
+                            rng,
+                        )?;
+                        let psi_affg_hat = PiAffgProof::prove(
+                            PiAffgInput::new(
+                                verifier_auxinfo_public.params(),
+                                pk,
+                                pk,
+                                &identification.K,
+                                &identification.H_hat,
+                                &identification.Y_hat,
+                                info.keyshare_public.as_ref(),
+                            ),
+                            PiAffgSecret::new(
+                                info.keyshare_private.as_ref(),
+                                &BigNumber::zero(),
+                                &identification.H_hat_nonce,
+                                &identification.Y_hat_nonce,
+                            ),
+                            &context,
+                            &mut Transcript::new(b"PiAffgProof"),
+                            rng,
+                        )?;
+                        let psi_log_hat = PiLogProof::prove(
+                            CommonInput::new(
+                                &identification.C_hat,
+                                &identification.S,
+                                verifier_auxinfo_public.params().scheme(),
+                                pk,
+                                &identification.Gamma,
+                            ),
+                            ProverSecret::new(&identification.chi, &identification.C_hat_nonce),
+                            &context,
+                            &mut Transcript::new(b"PiLogProof"),
+                            rng,
+                        )?;
+                        let psi_dec_hat = PiDecProof::prove(
+                            PiDecInput::new(
+                                verifier_auxinfo_public.params().scheme(),
+                                pk,
+                                &identification.combined_hat,
+                                &BigNumber::zero(),
+                            ),
+                            PiDecSecret::new(
+                                &identification.plaintext_hat,
+                                &identification.nonce_hat,
+                            ),
+                            &context,
+                            &mut Transcript::new(b"PiDecProof"),
+                            rng,
+                        )?;
+                        Ok(identification::Public {
+                            H: identification.H.clone(),
+                            psi_mul: identification.psi_mul.clone(),
+                            received: identification.received.clone(),
+                            psi_dec,
+                            H_hat: identification.H_hat.clone(),
+                            Y_hat: identification.Y_hat.clone(),
+                            psi_affg_hat,
+                            C_hat: identification.C_hat.clone(),
+                            psi_log_hat,
+                            psi_dec_hat,
+                        })
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+                Message::new(
+                    MessageType::Presign(PresignMessageType::Identification),
+                    sid,
+                    self.id,
+                    pid,
+                    &publics,
+                )
+            })
+            .collect()
+    }
+
+    /// Handle an identification message.
+    ///
+    /// A participant that already terminated successfully only responds with
+    /// its own identification messages. Otherwise, once identification
+    /// messages from all participants (including our own) are received, this
+    /// returns a [`ProtocolError`](InternalError::ProtocolError) naming the
+    /// cheating participant.
+    #[cfg_attr(feature = "flame_it", flame("presign"))]
+    #[instrument(skip_all, err(Debug))]
+    fn handle_identification_msg<R: RngCore + CryptoRng>(
+        &mut self,
+        rng: &mut R,
+        message: &Message,
+    ) -> Result<ProcessOutcome<<Self as ProtocolParticipant>::Output>> {
+        info!("Presign: Handling identification message.");
+
+        // We can't take part in identification until we've finished round three.
+        if !self
+            .local_storage
+            .contains_for_all_ids::<storage::RoundThreePublic>(&self.other_participant_ids)
+        {
+            info!("Presign: Not done with round three. Stashing message.");
+            self.stash_message(message)?;
+            return Ok(ProcessOutcome::Incomplete);
+        }
+
+        if *self.status() == Status::TerminatedSuccessfully {
+            let messages = run_only_once!(self.gen_identification_msgs(rng, message.id()))?;
+            return Ok(ProcessOutcome::from(None, messages));
         }
 
-        // Note: This `try_into` call does the check and computation specified
-        // in Step 2 of Output in the paper's protocol specification (Figure 7).
-        let presign_record: PresignRecord = RecordPair {
-            private: r3_private.clone(),
-            publics: r3_pubs,
+        // Wait for our own identification message, which guarantees that the
+        // messages to everyone else have been sent, before handling others.
+        if message.from() == self.id {
+            let publics = identification::Public::from_message(message)?;
+            self.local_storage
+                .store::<storage::IdentificationPublic>(self.id, publics);
+            for message in
+                self.fetch_messages(MessageType::Presign(PresignMessageType::Identification))?
+            {
+                self.validate_and_store_identification_public(&message)?;
+            }
+        } else if self
+            .local_storage
+            .contains::<storage::IdentificationPublic>(self.id)
+        {
+            self.validate_and_store_identification_public(message)?;
+        } else {
+            self.stash_message(message)?;
+            return Ok(ProcessOutcome::Incomplete);
+        }
+
+        if self
+            .local_storage
+            .contains_for_all_ids::<storage::IdentificationPublic>(&self.all_participants())
+        {
+            let culprit = self.identify_culprit()?;
+            match culprit {
+                Some(pid) => error!("Presign: participant {pid} caused the protocol to fail."),
+                None => error!("Presign: identification failed to find a culprit."),
+            }
+            Err(InternalError::ProtocolError(culprit))
+        } else {
+            Ok(ProcessOutcome::Incomplete)
+        }
+    }
+
+    /// Find the participant whose `δᵢ` or `Sᵢ` doesn't match its proven
+    /// values, for any record in the batch.
+    ///
+    /// This assumes that identification messages from all participants were
+    /// validated and stored.
+    #[cfg_attr(feature = "flame_it", flame("presign"))]
+    fn identify_culprit(&self) -> Result<Option<ParticipantIdentifier>> {
+        for index in 0..self.input().record_count() {
+            if let Some(culprit) = self.identify_culprit_for_record(index)? {
+                return Ok(Some(culprit));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Find the participant whose `δᵢ` or `Sᵢ` doesn't match its proven values
+    /// for the record at `index` in the batch.
+    fn identify_culprit_for_record(&self, index: usize) -> Result<Option<ParticipantIdentifier>> {
+        let context = self.retrieve_context();
+        let verifier_auxinfo_public = self.input().find_auxinfo_public(self.id)?;
+        // Everyone agreed on `Γ` before the round three checks.
+        let Gamma = self
+            .local_storage
+            .retrieve::<storage::RoundThreePrivate>(self.id)?
+            .get(index)
+            .ok_or(InternalError::InternalInvariantFailed)?
+            .Gamma;
+
+        for pid in &self.other_participant_ids {
+            let public = self
+                .local_storage
+                .retrieve::<storage::IdentificationPublic>(*pid)?
+                .get(index)
+                .ok_or(InternalError::InternalInvariantFailed)?;
+            let prover_auxinfo_public = self.input().find_auxinfo_public(*pid)?;
+
+            // The `F` and `F̂` ciphertexts sent by `pid`, as reported by their
+            // recipients. The accompanying proofs were already checked, so these are
+            // the values `pid` actually sent.
+            let (sent, sent_hat): (Vec<_>, Vec<_>) = self
+                .all_participants()
+                .into_iter()
+                .filter(|peer| peer != pid)
+                .map(|peer| {
+                    if peer == self.id {
+                        let r2_public = self
+                            .local_storage
+                            .retrieve::<storage::RoundTwoPublic>(*pid)?
+                            .get(index)
+                            .ok_or(InternalError::InternalInvariantFailed)?;
+                        Ok((r2_public.F.clone(), r2_public.F_hat.clone()))
+                    } else {
+                        let received = self
+                            .local_storage
+                            .retrieve::<storage::IdentificationPublic>(peer)?
+                            .get(index)
+                            .and_then(|public| public.received_from(*pid))
+                            .ok_or(InternalError::InternalInvariantFailed)?;
+                        Ok((received.F.clone(), received.F_hat.clone()))
+                    }
+                })
+                .collect::<Result<Vec<_>>>()?
+                .into_iter()
+                .unzip();
+            let combined = identification::combine_ciphertexts(
+                prover_auxinfo_public.pk(),
+                &public.H,
+                public.received.iter().map(|received| &received.D),
+                &sent,
+            )?;
+
+            let r3_public = self
+                .local_storage
+                .retrieve::<storage::RoundThreePublic>(*pid)?
+                .get(index)
+                .ok_or(InternalError::InternalInvariantFailed)?;
+            let delta = BigNumber::from_slice(r3_public.delta.to_bytes());
+            let mut transcript = Transcript::new(b"PiDecProof");
+            let result = public.psi_dec.clone().verify(
+                PiDecInput::new(
+                    verifier_auxinfo_public.params().scheme(),
+                    prover_auxinfo_public.pk(),
+                    &combined,
+                    &delta,
+                ),
+                &context,
+                &mut transcript,
+            );
+            if result.is_err() {
+                error!("Presign: participant {pid} sent a delta that doesn't match its proof.");
+                return Ok(Some(*pid));
+            }
+
+            // `S` must be `Γ` raised to the plaintext of `C_hat`, and that
+            // plaintext must be congruent to the one of the combined ciphertext for
+            // `χ`, i.e. `Ĥ ∏ⱼ D̂ⱼ,ᵢ ∏ⱼ F̂ᵢ,ⱼ^{-1}`.
+            let mut transcript = Transcript::new(b"PiLogProof");
+            let log_result = public.psi_log_hat.clone().verify(
+                CommonInput::new(
+                    &public.C_hat,
+                    &r3_public.S,
+                    verifier_auxinfo_public.params().scheme(),
+                    prover_auxinfo_public.pk(),
+                    &Gamma,
+                ),
+                &context,
+                &mut transcript,
+            );
+            let combined_hat = identification::combine_ciphertexts(
+                prover_auxinfo_public.pk(),
+                &public.H_hat,
+                public.received.iter().map(|received| &received.D_hat),
+                sent_hat.iter().chain(std::iter::once(&public.C_hat)),
+            )?;
+            let mut transcript = Transcript::new(b"PiDecProof");
+            let dec_result = public.psi_dec_hat.clone().verify(
+                PiDecInput::new(
+                    verifier_auxinfo_public.params().scheme(),
+                    prover_auxinfo_public.pk(),
+                    &combined_hat,
+                    &BigNumber::zero(),
+                ),
+                &context,
+                &mut transcript,
+            );
+            if log_result.is_err() || dec_result.is_err() {
+                error!("Presign: participant {pid} sent an S that doesn't match its proofs.");
+                return Ok(Some(*pid));
+            }
+        }
+
+        Ok(None)
+    }
+
+    #[cfg_attr(feature = "flame_it", flame("presign"))]
+    fn validate_and_store_identification_public(&mut self, message: &Message) -> Result<()> {
+        let sender = message.from();
+        let publics = identification::Public::from_message(message)?;
+        self.check_batch_size(sender, &publics)?;
+
+        let input = self.input();
+        let verifier_auxinfo_public = input.find_auxinfo_public(self.id)?;
+        let sender_auxinfo_public = input.find_auxinfo_public(sender)?;
+        let sender_keyshare_public = input.find_keyshare_public(sender)?;
+        let peer_publics = self
+            .all_participants()
+            .into_iter()
+            .filter(|pid| *pid != sender)
+            .map(|pid| {
+                Ok((
+                    input.find_auxinfo_public(pid)?,
+                    input.find_keyshare_public(pid)?,
+                ))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let sender_r1_public_broadcasts = self
+            .local_storage
+            .retrieve::<storage::RoundOnePublicBroadcast>(sender)?;
+        let context = self.retrieve_context();
+        for (public, sender_r1_public_broadcast) in publics.iter().zip(sender_r1_public_broadcasts)
+        {
+            public
+                .verify(
+                    &context,
+                    verifier_auxinfo_public,
+                    sender_auxinfo_public,
+                    sender_keyshare_public,
+                    sender_r1_public_broadcast,
+                    &peer_publics,
+                )
+                .map_err(|e| match e {
+                    InternalError::ProtocolError(_) => {
+                        error!("Presign: invalid identification message from {sender}.");
+                        InternalError::ProtocolError(Some(sender))
+                    }
+                    e => e,
+                })?;
         }
-        .try_into()?;
 
-        Ok(presign_record)
+        self.local_storage
+            .store::<storage::IdentificationPublic>(sender, publics);
+        Ok(())
     }
 
     #[cfg_attr(feature = "flame_it", flame("presign"))]
@@ -803,25 +1585,39 @@ impl PresignParticipant {
         let receiver_auxinfo_public = input.find_auxinfo_public(message.to())?;
         let sender_auxinfo_public = input.find_auxinfo_public(message.from())?;
         let sender_keyshare_public = input.find_keyshare_public(message.from())?;
-        let receiver_r1_private = self
+        let receiver_r1_privates = self
             .local_storage
             .retrieve::<storage::RoundOnePrivate>(message.to())?;
-        let sender_r1_public_broadcast = self
+        let sender_r1_public_broadcasts = self
             .local_storage
             .retrieve::<storage::RoundOnePublicBroadcast>(message.from())?;
 
-        let round_two_public = round_two::Public::try_from(message)?;
-        round_two_public.clone().verify(
-            &self.retrieve_context(),
-            receiver_auxinfo_public,
-            receiver_r1_private,
-            sender_auxinfo_public,
-            sender_keyshare_public,
-            sender_r1_public_broadcast,
-        )?;
+        let round_two_publics = round_two::Public::from_message(message)?;
+        self.check_batch_size(message.from(), &round_two_publics)?;
+        let context = self.retrieve_context();
+        let records = round_two_publics
+            .iter()
+            .zip(receiver_r1_privates)
+            .zip(sender_r1_public_broadcasts)
+            .collect::<Vec<_>>();
+        parallel::map(
+            records,
+            |((round_two_public, receiver_r1_private), sender_r1_public_broadcast)| {
+                round_two_public.clone().verify(
+                    &context,
+                    receiver_auxinfo_public,
+                    receiver_r1_private,
+                    sender_auxinfo_public,
+                    sender_keyshare_public,
+                    sender_r1_public_broadcast,
+                )
+            },
+        )
+        .into_iter()
+        .collect::<Result<()>>()?;
 
         self.local_storage
-            .store::<storage::RoundTwoPublic>(message.from(), round_two_public);
+            .store::<storage::RoundTwoPublic>(message.from(), round_two_publics);
 
         Ok(())
     }
@@ -831,19 +1627,24 @@ impl PresignParticipant {
         let input = self.input();
         let receiver_auxinfo_public = input.find_auxinfo_public(message.to())?;
         let sender_auxinfo_public = input.find_auxinfo_public(message.from())?;
-        let sender_r1_public_broadcast = self
+        let sender_r1_public_broadcasts = self
             .local_storage
             .retrieve::<storage::RoundOnePublicBroadcast>(message.from())?;
-        let public = round_three::Public::try_from(message)?;
-        public.clone().verify(
-            &self.retrieve_context(),
-            receiver_auxinfo_public,
-            sender_auxinfo_public,
-            sender_r1_public_broadcast,
-        )?;
+        let publics = round_three::Public::from_message(message)?;
+        self.check_batch_size(message.from(), &publics)?;
+        let context = self.retrieve_context();
+        for (public, sender_r1_public_broadcast) in publics.iter().zip(sender_r1_public_broadcasts)
+        {
+            public.clone().verify(
+                &context,
+                receiver_auxinfo_public,
+                sender_auxinfo_public,
+                sender_r1_public_broadcast,
+            )?;
+        }
 
         self.local_storage
-            .store::<storage::RoundThreePublic>(message.from(), public);
+            .store::<storage::RoundThreePublic>(message.from(), publics);
         Ok(())
     }
 }
@@ -894,31 +1695,38 @@ impl PresignKeyShareAndInfo {
         let gamma = random_positive_bn(rng, &order);
 
         let (K, rho) = self
-            .aux_info_public
-            .pk()
+            .aux_info_private
+            .decryption_key()
             .encrypt(rng, &k)
             .map_err(|_| InternalError::InternalInvariantFailed)?;
         let (G, nu) = self
-            .aux_info_public
-            .pk()
+            .aux_info_private
+            .decryption_key()
             .encrypt(rng, &gamma)
             .map_err(|_| InternalError::InternalInvariantFailed)?;
-        let mut r1_publics = HashMap::new();
         let secret = PiEncSecret::new(&k, &rho);
-        for aux_info_public in other_auxinfos {
-            // Construct a proof that `K` is the ciphertext of `k` using
-            // parameters from the other participant.
-            let mut transcript = Transcript::new(b"PiEncProof");
-            let proof = PiEncProof::prove(
-                PiEncInput::new(aux_info_public.params(), self.aux_info_public.pk(), &K),
-                secret,
-                context,
-                &mut transcript,
-                rng,
-            )?;
-            let r1_public = round_one::Public::from(proof);
-            let _ = r1_publics.insert(aux_info_public.participant(), r1_public);
-        }
+        let r1_publics = parallel::map_with_rng(
+            rng,
+            other_auxinfos.to_vec(),
+            |rng, aux_info_public| -> Result<_> {
+                // Construct a proof that `K` is the ciphertext of `k` using
+                // parameters from the other participant.
+                let mut transcript = Transcript::new(b"PiEncProof");
+                let proof = PiEncProof::prove(
+                    PiEncInput::new(aux_info_public.params(), self.aux_info_public.pk(), &K),
+                    secret,
+                    context,
+                    &mut transcript,
+                    rng,
+                )?;
+                Ok((
+                    aux_info_public.participant(),
+                    round_one::Public::from(proof),
+                ))
+            },
+        )
+        .into_iter()
+        .collect::<Result<HashMap<_, _>>>()?;
 
         let r1_public_broadcast = round_one::PublicBroadcast {
             K: K.clone(),
@@ -988,13 +1796,13 @@ impl PresignKeyShareAndInfo {
             )
             .map_err(|_| InternalError::InternalInvariantFailed)?;
         let (F, r) = self
-            .aux_info_public
-            .pk()
+            .aux_info_private
+            .decryption_key()
             .encrypt(rng, &beta)
             .map_err(|_| InternalError::InternalInvariantFailed)?;
         let (F_hat, r_hat) = self
-            .aux_info_public
-            .pk()
+            .aux_info_private
+            .decryption_key()
             .encrypt(rng, &beta_hat)
             .map_err(|_| InternalError::InternalInvariantFailed)?;
 
@@ -1053,7 +1861,12 @@ impl PresignKeyShareAndInfo {
         )?;
 
         Ok((
-            round_two::Private { beta, beta_hat },
+            round_two::Private {
+                beta,
+                beta_hat,
+                F: F.clone(),
+                F_hat: F_hat.clone(),
+            },
             round_two::Public {
                 D,
                 D_hat,
@@ -1089,7 +1902,7 @@ impl PresignKeyShareAndInfo {
             .modmul(&sender_r1_priv.k, &order);
         let mut Gamma = g.multiply_by_bignum(&sender_r1_priv.gamma)?;
 
-        for round_three_input in other_participant_inputs.values() {
+        for (pid, round_three_input) in other_participant_inputs {
             let r2_pub_j = round_three_input.r2_public.clone();
             let r2_priv_j = round_three_input.r2_private.clone();
 
@@ -1102,7 +1915,7 @@ impl PresignKeyShareAndInfo {
                         "Decryption failed, ciphertext out of range: {:?}",
                         r2_pub_j.D
                     );
-                    InternalError::ProtocolError(None)
+                    InternalError::ProtocolError(Some(*pid))
                 })?;
             let alpha_hat = self
                 .aux_info_private
@@ -1113,7 +1926,7 @@ impl PresignKeyShareAndInfo {
                         "Decryption failed, ciphertext out of range: {:?}",
                         r2_pub_j.D_hat
                     );
-                    InternalError::ProtocolError(None)
+                    InternalError::ProtocolError(Some(*pid))
                 })?;
 
             // Note: We do a subtraction of `beta` and `beta_hat` here because
@@ -1129,6 +1942,7 @@ impl PresignKeyShareAndInfo {
 
         let delta_scalar = bn_to_scalar(&delta)?;
         let chi_scalar = bn_to_scalar(&chi)?;
+        let S = Gamma.multiply_by_scalar(&chi_scalar);
 
         let mut ret_publics = HashMap::new();
         for (other_id, round_three_input) in other_participant_inputs {
@@ -1151,6 +1965,7 @@ impl PresignKeyShareAndInfo {
                 Delta,
                 psi_double_prime,
                 Gamma,
+                S,
             };
             let _ = ret_publics.insert(*other_id, val);
         }
@@ -1163,6 +1978,7 @@ impl PresignKeyShareAndInfo {
             // are stored in this party's private component
             delta: delta_scalar,
             Delta,
+            S,
         };
 
         Ok((private, ret_publics))
@@ -1179,17 +1995,22 @@ mod test {
 
     use k256::Scalar;
     use libpaillier::unknown_order::BigNumber;
+    use merlin::Transcript;
     use rand::{CryptoRng, Rng, RngCore};
     use tracing::debug;
 
     use crate::{
         auxinfo,
-        errors::Result,
+        errors::{InternalError, Result},
         keygen,
         messages::{Message, MessageType, PresignMessageType},
-        participant::{ProcessOutcome, Status},
-        presign::{Input, PresignRecord},
+        participant::{InnerProtocolParticipant, ProcessOutcome, Status},
+        presign::{round_three, Input, PresignRecord},
         utils::{self, testing::init_testing, CurvePoint},
+        zkp::{
+            pilog::{CommonInput, PiLogProof, ProverSecret},
+            Proof,
+        },
         Identifier, ParticipantConfig, ParticipantIdentifier, ProtocolParticipant,
     };
 
@@ -1212,7 +2033,7 @@ mod test {
         quorum: &mut Vec<PresignParticipant>,
         inboxes: &mut HashMap<ParticipantIdentifier, Vec<Message>>,
         rng: &mut R,
-    ) -> Option<(usize, ProcessOutcome<PresignRecord>)> {
+    ) -> Option<(usize, ProcessOutcome<Vec<PresignRecord>>)> {
         // Pick a random participant to process
         let index = rng.gen_range(0..quorum.len());
         let participant = quorum.get_mut(index).unwrap();
@@ -1268,38 +2089,41 @@ mod test {
             .fold(BigNumber::zero(), |sum, key_share| sum + key_share.as_ref());
         // Converting to scalars automatically gets us the mod q
         assert_eq!(masked_key, utils::bn_to_scalar(&secret_key).unwrap() * mask);
-    }
 
-    #[test]
-    fn presign_produces_valid_outputs() -> Result<()> {
-        let quorum_size = 4;
-        let rng = &mut init_testing();
-
-        // Prepare prereqs for making PresignParticipants. Assume all the simulations
-        // are stable (e.g. keep config order)
-        let configs = ParticipantConfig::random_quorum(quorum_size, rng)?;
-        let keygen_outputs = keygen::Output::simulate_set(&configs, rng);
-        let auxinfo_outputs = auxinfo::Output::simulate_set(&configs, rng);
-
-        let sid = Identifier::random(rng);
-
-        // Make the participants
-        let mut quorum = zip(configs, zip(keygen_outputs.clone(), auxinfo_outputs))
-            .map(|(config, (keygen_output, auxinfo_output))| {
-                let input = Input::new(auxinfo_output, keygen_output)?;
-                PresignParticipant::new(sid, config.id(), config.other_ids().to_vec(), input)
-            })
-            .collect::<Result<Vec<_>>>()?;
+        // Every record has the same share commitments, and they include
+        // `R^{kᵢ}` and `R^{χᵢ}` for each record
+        assert!(records
+            .windows(2)
+            .all(|records| records[0].share_commitments() == records[1].share_commitments()));
+        assert_eq!(records[0].share_commitments().len(), records.len());
+        for record in &records {
+            assert!(record.share_commitments().iter().any(|commitment| {
+                commitment.mask() == &mask_point.multiply_by_scalar(record.mask_share())
+                    && commitment.masked_key()
+                        == &mask_point.multiply_by_scalar(record.masked_key_share())
+            }));
+        }
+    }
 
+    /// Run presign to completion with the given quorum and return the records.
+    ///
+    /// The outer list has one entry for each record in the batch, holding
+    /// the corresponding record from each participant, ordered to match the
+    /// quorum.
+    fn run_presign<R: RngCore + CryptoRng>(
+        quorum: &mut Vec<PresignParticipant>,
+        sid: Identifier,
+        rng: &mut R,
+    ) -> Result<Vec<Vec<PresignRecord>>> {
         // Make inboxes for message passing
         let mut inboxes = HashMap::new();
-        for participant in &quorum {
+        for participant in quorum.iter() {
             let _ = inboxes.insert(participant.id, vec![]);
         }
 
         // Make a place to store outputs
         let mut outputs = std::iter::repeat_with(|| None)
-            .take(quorum_size)
+            .take(quorum.len())
             .collect::<Vec<_>>();
 
         // Pass everyone a ready message
@@ -1319,7 +2143,7 @@ mod test {
             .iter()
             .all(|participant| *participant.status() == Status::TerminatedSuccessfully)
         {
-            let (index, outcome) = match process_messages(&mut quorum, &mut inboxes, rng) {
+            let (index, outcome) = match process_messages(quorum, &mut inboxes, rng) {
                 None => continue,
                 Some(x) => x,
             };
@@ -1337,14 +2161,385 @@ mod test {
         }
 
         // Get rid of any `None` outputs
-        let records = outputs.into_iter().flatten().collect::<Vec<_>>();
+        let outputs = outputs.into_iter().flatten().collect::<Vec<_>>();
+
+        // Every party must produce an output, with the same number of records
+        assert_eq!(outputs.len(), quorum.len());
+        let record_count = outputs[0].len();
+        assert!(outputs.iter().all(|records| records.len() == record_count));
+
+        // Group the records by their position in the batch
+        let mut outputs = outputs.into_iter().map(Vec::into_iter).collect::<Vec<_>>();
+        let records = (0..record_count)
+            .map(|_| {
+                outputs
+                    .iter_mut()
+                    .map(|records| records.next().unwrap())
+                    .collect()
+            })
+            .collect();
+        Ok(records)
+    }
+
+    #[test]
+    fn presign_produces_valid_outputs() -> Result<()> {
+        let quorum_size = 4;
+        let rng = &mut init_testing();
+
+        // Prepare prereqs for making PresignParticipants. Assume all the simulations
+        // are stable (e.g. keep config order)
+        let configs = ParticipantConfig::random_quorum(quorum_size, rng)?;
+        let keygen_outputs = keygen::Output::simulate_set(&configs, rng);
+        let auxinfo_outputs = auxinfo::Output::simulate_set(&configs, rng);
 
-        // Every party must produce an output
-        assert_eq!(records.len(), quorum_size);
+        let sid = Identifier::random(rng);
+
+        // Make the participants
+        let mut quorum = zip(configs, zip(keygen_outputs.clone(), auxinfo_outputs))
+            .map(|(config, (keygen_output, auxinfo_output))| {
+                let input = Input::new(auxinfo_output, keygen_output)?;
+                PresignParticipant::new(sid, config.id(), config.other_ids().to_vec(), input)
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut records = run_presign(&mut quorum, sid, rng)?;
+        assert_eq!(records.len(), 1);
 
         // Check validity of set; this will panic if anything is wrong
-        presign_record_set_is_valid(records, keygen_outputs);
+        presign_record_set_is_valid(records.remove(0), keygen_outputs);
+
+        Ok(())
+    }
+
+    #[test]
+    fn batch_presign_produces_valid_outputs() -> Result<()> {
+        let quorum_size = 3;
+        let record_count = 3;
+        let rng = &mut init_testing();
+
+        let configs = ParticipantConfig::random_quorum(quorum_size, rng)?;
+        let keygen_outputs = keygen::Output::simulate_set(&configs, rng);
+        let auxinfo_outputs = auxinfo::Output::simulate_set(&configs, rng);
+
+        let sid = Identifier::random(rng);
+        let mut quorum = zip(configs, zip(keygen_outputs.clone(), auxinfo_outputs))
+            .map(|(config, (keygen_output, auxinfo_output))| {
+                let input =
+                    Input::new(auxinfo_output, keygen_output)?.with_record_count(record_count)?;
+                PresignParticipant::new(sid, config.id(), config.other_ids().to_vec(), input)
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let records = run_presign(&mut quorum, sid, rng)?;
+        assert_eq!(records.len(), record_count);
+
+        // Each record in the batch uses a different mask
+        let mask_points = records
+            .iter()
+            .map(|set| *set[0].mask_point())
+            .collect::<Vec<_>>();
+        for (i, mask_point) in mask_points.iter().enumerate() {
+            assert!(!mask_points[i + 1..].contains(mask_point));
+        }
+
+        for set in records {
+            presign_record_set_is_valid(set, keygen_outputs.clone());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn batch_sizes_must_match() -> Result<()> {
+        let quorum_size = 2;
+        let rng = &mut init_testing();
+
+        let configs = ParticipantConfig::random_quorum(quorum_size, rng)?;
+        let keygen_outputs = keygen::Output::simulate_set(&configs, rng);
+        let auxinfo_outputs = auxinfo::Output::simulate_set(&configs, rng);
+
+        // The participants disagree on how many records to produce
+        let sid = Identifier::random(rng);
+        let mut quorum = zip(configs, zip(keygen_outputs, auxinfo_outputs))
+            .enumerate()
+            .map(|(i, (config, (keygen_output, auxinfo_output)))| {
+                let input = Input::new(auxinfo_output, keygen_output)?.with_record_count(i + 1)?;
+                PresignParticipant::new(sid, config.id(), config.other_ids().to_vec(), input)
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut inboxes = HashMap::new();
+        for participant in &quorum {
+            let empty: [u8; 0] = [];
+            let ready = Message::new(
+                MessageType::Presign(PresignMessageType::Ready),
+                sid,
+                participant.id(),
+                participant.id(),
+                &empty,
+            )?;
+            let _ = inboxes.insert(participant.id(), vec![ready]);
+        }
+
+        // Run until someone notices the mismatch
+        let error = loop {
+            let participant = quorum.get_mut(rng.gen_range(0..quorum_size)).unwrap();
+            let inbox = inboxes.get_mut(&participant.id()).unwrap();
+            if inbox.is_empty() {
+                continue;
+            }
+            let message = inbox.remove(rng.gen_range(0..inbox.len()));
+            match participant.process_message(rng, &message) {
+                Ok(outcome) => deliver_all(&outcome.into_parts().1, &mut inboxes),
+                Err(err) => break err,
+            }
+        };
+        // Either side could be misconfigured, so nobody is blamed
+        assert!(matches!(error, InternalError::ProtocolError(None)));
+        Ok(())
+    }
+
+    #[test]
+    fn presign_with_threshold_quorum_produces_valid_outputs() -> Result<()> {
+        let num_parties = 5;
+        let threshold = 3;
+        let rng = &mut init_testing();
+
+        let configs = ParticipantConfig::random_quorum(num_parties, rng)?;
+        let keygen_outputs = keygen::Output::simulate_threshold_set(&configs, threshold, rng);
+        let auxinfo_outputs = auxinfo::Output::simulate_set(&configs, rng);
+
+        // Presign with a quorum of size `threshold`, skipping the first party
+        let quorum_pids = configs[1..=threshold]
+            .iter()
+            .map(ParticipantConfig::id)
+            .collect::<Vec<_>>();
+
+        let sid = Identifier::random(rng);
+        let mut quorum = zip(&keygen_outputs, auxinfo_outputs)
+            .skip(1)
+            .zip(&quorum_pids)
+            .map(|((keygen_output, auxinfo_output), &pid)| {
+                let input = Input::new(auxinfo_output, keygen_output.clone())?;
+                let other_ids = quorum_pids
+                    .iter()
+                    .copied()
+                    .filter(|other| *other != pid)
+                    .collect();
+                PresignParticipant::new(sid, pid, other_ids, input)
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut records = run_presign(&mut quorum, sid, rng)?;
+
+        // The records are valid with respect to the quorum's additive shares
+        let additive_outputs = keygen_outputs[1..=threshold]
+            .iter()
+            .map(|output| output.to_additive_shares(&quorum_pids))
+            .collect::<Result<Vec<_>>>()?;
+        presign_record_set_is_valid(records.remove(0), additive_outputs);
+
+        Ok(())
+    }
+
+    #[test]
+    fn presign_quorum_must_meet_threshold() -> Result<()> {
+        let rng = &mut init_testing();
+        let configs = ParticipantConfig::random_quorum(4, rng)?;
+        let keygen_outputs = keygen::Output::simulate_threshold_set(&configs, 3, rng);
+        let auxinfo_outputs = auxinfo::Output::simulate_set(&configs, rng);
+
+        let input = Input::new(auxinfo_outputs[0].clone(), keygen_outputs[0].clone())?;
+        let result = PresignParticipant::new(
+            Identifier::random(rng),
+            configs[0].id(),
+            vec![configs[1].id()],
+            input,
+        );
+        assert!(result.is_err());
+        Ok(())
+    }
+
+    /// Run presign until every honest participant aborts, letting `tamper`
+    /// modify the messages sent by the first participant, and return that
+    /// participant's id along with the errors.
+    #[allow(clippy::type_complexity)]
+    fn run_presign_with_cheater<R, F>(
+        rng: &mut R,
+        mut tamper: F,
+    ) -> Result<(
+        ParticipantIdentifier,
+        HashMap<ParticipantIdentifier, InternalError>,
+    )>
+    where
+        R: RngCore + CryptoRng,
+        F: FnMut(&PresignParticipant, Message, &mut R) -> Result<Message>,
+    {
+        let quorum_size = 3;
+        let configs = ParticipantConfig::random_quorum(quorum_size, rng)?;
+        let keygen_outputs = keygen::Output::simulate_set(&configs, rng);
+        let auxinfo_outputs = auxinfo::Output::simulate_set(&configs, rng);
+
+        let sid = Identifier::random(rng);
+        let mut quorum = zip(configs, zip(keygen_outputs, auxinfo_outputs))
+            .map(|(config, (keygen_output, auxinfo_output))| {
+                let input = Input::new(auxinfo_output, keygen_output)?;
+                PresignParticipant::new(sid, config.id(), config.other_ids().to_vec(), input)
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let cheater = quorum[0].id();
+
+        let mut inboxes = HashMap::new();
+        for participant in &quorum {
+            let empty: [u8; 0] = [];
+            let ready = Message::new(
+                MessageType::Presign(PresignMessageType::Ready),
+                sid,
+                participant.id(),
+                participant.id(),
+                &empty,
+            )?;
+            let _ = inboxes.insert(participant.id(), vec![ready]);
+        }
+
+        // Run the protocol until every honest participant aborts
+        let mut errors = HashMap::new();
+        while errors.len() < quorum_size - 1 {
+            let participant = quorum.get_mut(rng.gen_range(0..quorum_size)).unwrap();
+            let inbox = inboxes.get_mut(&participant.id()).unwrap();
+            if errors.contains_key(&participant.id()) || inbox.is_empty() {
+                continue;
+            }
+            let message = inbox.remove(rng.gen_range(0..inbox.len()));
+            let messages = match participant.process_message(rng, &message) {
+                Ok(outcome) => outcome.into_parts().1,
+                Err(err) => {
+                    let _ = errors.insert(participant.id(), err);
+                    continue;
+                }
+            };
+
+            let messages = messages
+                .into_iter()
+                .map(|message| {
+                    if message.from() == cheater {
+                        tamper(participant, message, rng)
+                    } else {
+                        Ok(message)
+                    }
+                })
+                .collect::<Result<Vec<_>>>()?;
+            deliver_all(&messages, &mut inboxes);
+        }
+        Ok((cheater, errors))
+    }
+
+    #[test]
+    fn presign_identifies_participant_with_bad_delta() -> Result<()> {
+        let rng = &mut init_testing();
+
+        // The cheater sends the wrong `delta` to everyone in round three
+        let (cheater, errors) = run_presign_with_cheater(rng, |_, message, _| {
+            let round_three = MessageType::Presign(PresignMessageType::RoundThree);
+            if message.message_type() != round_three {
+                return Ok(message);
+            }
+            let mut publics = round_three::Public::from_message(&message)?;
+            publics[0].delta += Scalar::ONE;
+            Message::new(
+                round_three,
+                message.id(),
+                message.from(),
+                message.to(),
+                &publics,
+            )
+        })?;
+
+        // Every honest participant blames the cheater
+        assert!(!errors.contains_key(&cheater));
+        assert!(errors
+            .values()
+            .all(|err| *err == InternalError::ProtocolError(Some(cheater))));
+        Ok(())
+    }
+
+    #[test]
+    fn presign_identifies_participant_with_bad_chi_commitment() -> Result<()> {
+        let rng = &mut init_testing();
+
+        // The cheater sends the wrong `S` to everyone in round three
+        let (cheater, errors) = run_presign_with_cheater(rng, |_, message, _| {
+            let round_three = MessageType::Presign(PresignMessageType::RoundThree);
+            if message.message_type() != round_three {
+                return Ok(message);
+            }
+            let mut publics = round_three::Public::from_message(&message)?;
+            publics[0].S = publics[0].S + CurvePoint::GENERATOR;
+            Message::new(
+                round_three,
+                message.id(),
+                message.from(),
+                message.to(),
+                &publics,
+            )
+        })?;
+
+        // Every honest participant blames the cheater
+        assert!(!errors.contains_key(&cheater));
+        assert!(errors
+            .values()
+            .all(|err| *err == InternalError::ProtocolError(Some(cheater))));
+        Ok(())
+    }
+
+    #[test]
+    fn presign_identifies_participant_with_bad_gamma() -> Result<()> {
+        let rng = &mut init_testing();
+
+        // The cheater sends the wrong `Gamma` to everyone in round three, along
+        // with a valid proof for it
+        let (cheater, errors) = run_presign_with_cheater(rng, |cheater, message, rng| {
+            let round_three = MessageType::Presign(PresignMessageType::RoundThree);
+            if message.message_type() != round_three {
+                return Ok(message);
+            }
+            let r1_private = &cheater
+                .local_storage
+                .retrieve::<super::storage::RoundOnePrivate>(cheater.id())?[0];
+            let verifier_auxinfo_public = cheater.input().find_auxinfo_public(message.to())?;
+            let prover_auxinfo_public = cheater.input().find_auxinfo_public(cheater.id())?;
+
+            let mut publics = round_three::Public::from_message(&message)?;
+            let Gamma = publics[0].Gamma + CurvePoint::GENERATOR;
+            let Delta = Gamma.multiply_by_bignum(&r1_private.k)?;
+            publics[0].psi_double_prime = PiLogProof::prove(
+                CommonInput::new(
+                    &r1_private.K,
+                    &Delta,
+                    verifier_auxinfo_public.params().scheme(),
+                    prover_auxinfo_public.pk(),
+                    &Gamma,
+                ),
+                ProverSecret::new(&r1_private.k, &r1_private.rho),
+                &cheater.retrieve_context(),
+                &mut Transcript::new(b"PiLogProof"),
+                rng,
+            )?;
+            publics[0].Gamma = Gamma;
+            publics[0].Delta = Delta;
+            Message::new(
+                round_three,
+                message.id(),
+                message.from(),
+                message.to(),
+                &publics,
+            )
+        })?;
 
+        // Every honest participant blames the cheater
+        assert!(!errors.contains_key(&cheater));
+        assert!(errors
+            .values()
+            .all(|err| *err == InternalError::ProtocolError(Some(cheater))));
         Ok(())
     }
 }
@@ -0,0 +1,358 @@
+// Copyright (c) 2022-2023 Bolt Labs Holdings, Inc
+//
+// This source code is licensed under both the MIT license found in the
+// LICENSE-MIT file in the root directory of this source tree and the Apache
+// License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+// of this source tree.
+
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    fmt::{Debug, Formatter},
+    sync::{Mutex, MutexGuard},
+};
+
+use tracing::{error, info};
+
+use crate::{
+    errors::{CallerError, InternalError, Result},
+    presign::PresignRecord,
+    Identifier,
+};
+
+/// A pool of [`PresignRecord`]s that makes sure each record is used at most
+/// once.
+///
+/// Records are keyed by the key ID they were generated for (chosen by the
+/// calling application), the session [`Identifier`] of the presign run that
+/// produced them, and their index in that run's batch (see
+/// [`Input::with_record_count()`](crate::presign::Input::with_record_count())).
+/// All signers must use the same record, so one party (e.g. a coordinator)
+/// typically picks a record with [`take_next()`](PresignPool::take_next())
+/// and tells the others which session and index it came from, and they call
+/// [`take()`](PresignPool::take()) with those.
+///
+/// Taking a record removes it from the pool and marks it as consumed,
+/// regardless of whether signing with it then succeeds. A consumed record
+/// can't be added to the pool again.
+///
+/// The pool is safe to share between threads; each record is handed out to
+/// exactly one caller.
+///
+/// # 🔒 Storage requirements
+/// The pool is held in memory. Records that are persisted elsewhere must be
+/// deleted from persistent storage when they are taken from the pool, or they
+/// could be reused after a restart.
+#[derive(Default)]
+pub struct PresignPool {
+    inner: Mutex<PoolState>,
+}
+
+/// The presign session that produced a record, and the record's index in the
+/// session's batch.
+type RecordId = (Identifier, usize);
+
+#[derive(Default)]
+struct PoolState {
+    /// Available records for each key ID, oldest first.
+    available: HashMap<String, VecDeque<(RecordId, PresignRecord)>>,
+    /// Records that have been taken from the pool.
+    consumed: HashSet<(String, RecordId)>,
+}
+
+impl Debug for PresignPool {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        // Only show the number of records; the records themselves are secret.
+        let mut debug = f.debug_struct("PresignPool");
+        match self.inner.lock() {
+            Ok(state) => {
+                debug.field(
+                    "available",
+                    &state
+                        .available
+                        .iter()
+                        .map(|(key_id, records)| (key_id, records.len()))
+                        .collect::<HashMap<_, _>>(),
+                );
+            }
+            Err(_) => {
+                debug.field("available", &"[poisoned]");
+            }
+        }
+        debug.finish()
+    }
+}
+
+impl PresignPool {
+    /// Create an empty pool.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn lock(&self) -> Result<MutexGuard<'_, PoolState>> {
+        self.inner.lock().map_err(|_| {
+            error!("Presign pool lock was poisoned");
+            InternalError::InternalInvariantFailed
+        })
+    }
+
+    /// Add the record produced by the presign session `presign_sid` for the
+    /// key `key_id`, for a session that produced a single record.
+    ///
+    /// This is the same as [`add_batch()`](PresignPool::add_batch()) with a
+    /// batch of one; the record gets index `0`.
+    pub fn add(&self, key_id: &str, presign_sid: Identifier, record: PresignRecord) -> Result<()> {
+        self.add_batch(key_id, presign_sid, vec![record])
+    }
+
+    /// Add the batch of records produced by the presign session `presign_sid`
+    /// for the key `key_id`. Each record is keyed by its index in `records`,
+    /// which must be in the order the session output them.
+    ///
+    /// Fails, without adding any records, if the pool already holds a record
+    /// from this session with one of the indices, or if one of them has
+    /// already been consumed.
+    pub fn add_batch(
+        &self,
+        key_id: &str,
+        presign_sid: Identifier,
+        records: Vec<PresignRecord>,
+    ) -> Result<()> {
+        let mut state = self.lock()?;
+        let count = records.len();
+        if (0..count).any(|index| {
+            state
+                .consumed
+                .contains(&(key_id.to_string(), (presign_sid, index)))
+        }) {
+            error!(
+                "A presign record from session {} has already been used",
+                presign_sid
+            );
+            Err(CallerError::BadInput)?
+        }
+
+        let available = state.available.entry(key_id.to_string()).or_default();
+        if available
+            .iter()
+            .any(|((sid, index), _)| *sid == presign_sid && *index < count)
+        {
+            error!(
+                "The pool already holds a presign record from session {}",
+                presign_sid
+            );
+            Err(CallerError::BadInput)?
+        }
+        available.extend(
+            records
+                .into_iter()
+                .enumerate()
+                .map(|(index, record)| ((presign_sid, index), record)),
+        );
+        Ok(())
+    }
+
+    /// Take the record with index `index` from the presign session
+    /// `presign_sid` for the key `key_id`, and mark it as consumed.
+    ///
+    /// Fails if there is no such record, including if it was already taken.
+    pub fn take(
+        &self,
+        key_id: &str,
+        presign_sid: Identifier,
+        index: usize,
+    ) -> Result<PresignRecord> {
+        let mut state = self.lock()?;
+        let record = state.available.get_mut(key_id).and_then(|records| {
+            let position = records
+                .iter()
+                .position(|(id, _)| *id == (presign_sid, index))?;
+            records.remove(position)
+        });
+        match record {
+            Some((id, record)) => {
+                let _ = state.consumed.insert((key_id.to_string(), id));
+                Ok(record)
+            }
+            None => {
+                error!(
+                    "The pool doesn't hold presign record {} from session {}",
+                    index, presign_sid
+                );
+                Err(CallerError::BadInput)?
+            }
+        }
+    }
+
+    /// Take the oldest record for the key `key_id`, and mark it as consumed.
+    /// Returns the record along with the session it came from and its index
+    /// in the session's batch, or `None` if there are no records for the key.
+    pub fn take_next(&self, key_id: &str) -> Result<Option<(Identifier, usize, PresignRecord)>> {
+        let mut state = self.lock()?;
+        let next = state
+            .available
+            .get_mut(key_id)
+            .and_then(VecDeque::pop_front);
+        Ok(next.map(|(id, record)| {
+            let _ = state.consumed.insert((key_id.to_string(), id));
+            (id.0, id.1, record)
+        }))
+    }
+
+    /// The number of records available for the key `key_id`.
+    ///
+    /// This can be used to decide when to run more presign sessions in the
+    /// background.
+    pub fn remaining(&self, key_id: &str) -> Result<usize> {
+        Ok(self.lock()?.available.get(key_id).map_or(0, VecDeque::len))
+    }
+
+    /// Check whether the record with index `index` from the presign session
+    /// `presign_sid` for the key `key_id` has been taken from the pool.
+    pub fn is_consumed(&self, key_id: &str, presign_sid: Identifier, index: usize) -> Result<bool> {
+        Ok(self
+            .lock()?
+            .consumed
+            .contains(&(key_id.to_string(), (presign_sid, index))))
+    }
+
+    /// Discard all available records for the key `key_id`, and mark them as
+    /// consumed.
+    ///
+    /// Records become invalid when the key shares they were generated from
+    /// change (e.g. after running [`refresh`](crate::refresh)), so they must
+    /// be discarded then.
+    pub fn discard(&self, key_id: &str) -> Result<()> {
+        let mut state = self.lock()?;
+        if let Some(records) = state.available.remove(key_id) {
+            info!(
+                "Discarding {} presign records for key {}",
+                records.len(),
+                key_id
+            );
+            state
+                .consumed
+                .extend(records.into_iter().map(|(id, _)| (key_id.to_string(), id)));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{keygen, utils::testing::init_testing, ParticipantConfig};
+    use std::{sync::Arc, thread};
+
+    fn records(count: usize) -> Vec<PresignRecord> {
+        let rng = &mut init_testing();
+        let configs = ParticipantConfig::random_quorum(2, rng).unwrap();
+        let keygen_outputs = keygen::Output::simulate_set(&configs, rng);
+        (0..count)
+            .map(|_| PresignRecord::simulate_set(&keygen_outputs, rng).remove(0))
+            .collect()
+    }
+
+    #[test]
+    fn records_are_used_once() {
+        let rng = &mut init_testing();
+        let pool = PresignPool::new();
+        let sids: Vec<_> = (0..3).map(|_| Identifier::random(rng)).collect();
+        for (sid, record) in sids.iter().zip(records(3)) {
+            pool.add("key", *sid, record).unwrap();
+        }
+        assert_eq!(pool.remaining("key").unwrap(), 3);
+        assert_eq!(pool.remaining("other key").unwrap(), 0);
+
+        // Records can be taken by session or in order
+        let _ = pool.take("key", sids[1], 0).unwrap();
+        assert!(pool.is_consumed("key", sids[1], 0).unwrap());
+        let (sid, index, _) = pool.take_next("key").unwrap().unwrap();
+        assert_eq!((sid, index), (sids[0], 0));
+        assert_eq!(pool.remaining("key").unwrap(), 1);
+
+        // Taken records can't be taken or added again
+        assert!(pool.take("key", sids[1], 0).is_err());
+        let record = records(1).remove(0);
+        assert!(pool.add("key", sids[0], record).is_err());
+
+        // Discarding consumes the remaining records
+        pool.discard("key").unwrap();
+        assert!(pool.is_consumed("key", sids[2], 0).unwrap());
+        assert!(pool.take_next("key").unwrap().is_none());
+        assert_eq!(pool.remaining("key").unwrap(), 0);
+    }
+
+    #[test]
+    fn duplicate_sessions_are_rejected() {
+        let rng = &mut init_testing();
+        let pool = PresignPool::new();
+        let sid = Identifier::random(rng);
+        let mut records = records(2);
+        pool.add("key", sid, records.remove(0)).unwrap();
+        assert!(pool.add("key", sid, records.remove(0)).is_err());
+        assert_eq!(pool.remaining("key").unwrap(), 1);
+    }
+
+    #[test]
+    fn whole_batches_can_be_pooled() {
+        let rng = &mut init_testing();
+        let pool = PresignPool::new();
+        let sid = Identifier::random(rng);
+        let other_sid = Identifier::random(rng);
+        pool.add_batch("key", sid, records(3)).unwrap();
+        pool.add("key", other_sid, records(1).remove(0)).unwrap();
+        assert_eq!(pool.remaining("key").unwrap(), 4);
+
+        // Each record in the batch can be taken separately
+        let _ = pool.take("key", sid, 1).unwrap();
+        assert!(pool.is_consumed("key", sid, 1).unwrap());
+        assert!(!pool.is_consumed("key", sid, 0).unwrap());
+        assert!(pool.take("key", sid, 3).is_err());
+
+        // The rest come out in order
+        let taken: Vec<_> = std::iter::from_fn(|| pool.take_next("key").unwrap())
+            .map(|(sid, index, _)| (sid, index))
+            .collect();
+        assert_eq!(taken, vec![(sid, 0), (sid, 2), (other_sid, 0)]);
+
+        // A batch can't be added again, even partially
+        assert!(pool.add_batch("key", sid, records(1)).is_err());
+        assert_eq!(pool.remaining("key").unwrap(), 0);
+    }
+
+    #[test]
+    fn batches_are_added_all_or_nothing() {
+        let rng = &mut init_testing();
+        let pool = PresignPool::new();
+        let sid = Identifier::random(rng);
+        pool.add("key", sid, records(1).remove(0)).unwrap();
+
+        // The first record of the batch clashes with the one already added
+        assert!(pool.add_batch("key", sid, records(2)).is_err());
+        assert_eq!(pool.remaining("key").unwrap(), 1);
+    }
+
+    #[test]
+    fn concurrent_takes_get_distinct_records() {
+        let rng = &mut init_testing();
+        let pool = Arc::new(PresignPool::new());
+        for record in records(8) {
+            pool.add("key", Identifier::random(rng), record).unwrap();
+        }
+
+        let handles: Vec<_> = (0..16)
+            .map(|_| {
+                let pool = pool.clone();
+                thread::spawn(move || pool.take_next("key").unwrap().map(|(sid, ..)| sid))
+            })
+            .collect();
+        let taken: Vec<_> = handles
+            .into_iter()
+            .filter_map(|handle| handle.join().unwrap())
+            .collect();
+
+        assert_eq!(taken.len(), 8);
+        assert_eq!(taken.iter().collect::<HashSet<_>>().len(), 8);
+        assert_eq!(pool.remaining("key").unwrap(), 0);
+    }
+}
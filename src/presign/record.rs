@@ -13,16 +13,129 @@ use crate::{
         Result,
     },
     presign::round_three::{Private as RoundThreePrivate, Public as RoundThreePublic},
+    protocol::ParticipantIdentifier,
+    secret_store::{RecordType, SecretRecord},
     utils::{bn_to_scalar, CurvePoint, ParseBytes},
 };
 use k256::{elliptic_curve::PrimeField, Scalar};
-use std::fmt::Debug;
+use serde::{Deserialize, Serialize};
+use std::{collections::HashSet, fmt::Debug};
 use tracing::error;
 use zeroize::{Zeroize, ZeroizeOnDrop};
 
 pub(crate) struct RecordPair {
+    /// The participant that owns the private values.
+    pub(crate) id: ParticipantIdentifier,
+    /// The public key `X`, as the sum of the quorum's (additive) public key
+    /// shares.
+    pub(crate) public_key: CurvePoint,
     pub(crate) private: RoundThreePrivate,
-    pub(crate) publics: Vec<RoundThreePublic>,
+    pub(crate) publics: Vec<(ParticipantIdentifier, RoundThreePublic)>,
+}
+
+impl RecordPair {
+    /// Sum the `δᵢ` and `Δᵢ` values of all participants.
+    fn sum_deltas(&self) -> (Scalar, CurvePoint) {
+        self.publics.iter().fold(
+            (self.private.delta, self.private.Delta),
+            |(delta, Delta), (_, public)| (delta + public.delta, Delta + public.Delta),
+        )
+    }
+
+    /// Sum the `Sᵢ = Γ^{χᵢ}` values of all participants.
+    fn sum_chi_commitments(&self) -> CurvePoint {
+        self.publics
+            .iter()
+            .fold(self.private.S, |S, (_, public)| S + public.S)
+    }
+
+    /// Check that `g^{∑ δᵢ} = ∏ Δᵢ`.
+    ///
+    /// If this fails, some participant misbehaved, and the identification
+    /// phase can be used to find out who.
+    pub(crate) fn deltas_are_consistent(&self) -> bool {
+        let (delta, Delta) = self.sum_deltas();
+        CurvePoint::GENERATOR.multiply_by_scalar(&delta) == Delta
+    }
+
+    /// Check that `X^δ = ∏ Sᵢ`, where `X` is the public key and `δ = ∑ δᵢ`.
+    ///
+    /// Since `∏ Δᵢ = Γ^k` and `δ = kγ`, this checks that `∏ Sᵢ = Γ^{kx}`, i.e.
+    /// that the commitments to the `χᵢ` are consistent with the key. It only
+    /// makes sense once [`deltas_are_consistent()`](Self::deltas_are_consistent)
+    /// holds. If this fails, some participant misbehaved, and the
+    /// identification phase can be used to find out who.
+    pub(crate) fn chi_commitments_are_consistent(&self) -> bool {
+        let (delta, _) = self.sum_deltas();
+        self.public_key.multiply_by_scalar(&delta) == self.sum_chi_commitments()
+    }
+}
+
+/// Public commitments to a single participant's presign shares.
+///
+/// For the participant with shares `kᵢ` and `χᵢ`, this holds `R^{kᵢ}` and
+/// `R^{χᵢ}`. A signature share `σᵢ = kᵢ m + r χᵢ` from that participant is
+/// correct if and only if `R^{σᵢ} = (R^{kᵢ})^m (R^{χᵢ})^r`, which lets signers
+/// blame whoever sent a bad share.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct ShareCommitment {
+    pid: ParticipantIdentifier,
+    mask: CurvePoint,
+    masked_key: CurvePoint,
+}
+
+impl ShareCommitment {
+    /// Length of an encoded [`ShareCommitment`] in bytes.
+    const ENCODED_LEN: usize = 16 + 2 * 33;
+
+    /// The participant whose shares this commits to.
+    pub(crate) fn participant(&self) -> ParticipantIdentifier {
+        self.pid
+    }
+
+    /// The commitment `R^{kᵢ}` to the mask share.
+    pub(crate) fn mask(&self) -> &CurvePoint {
+        &self.mask
+    }
+
+    /// The commitment `R^{χᵢ}` to the masked key share.
+    pub(crate) fn masked_key(&self) -> &CurvePoint {
+        &self.masked_key
+    }
+
+    /// Adjust the commitment for signing under the key `x + t` instead of `x`,
+    /// i.e. for the masked key share `χᵢ + kᵢ t`.
+    pub(crate) fn with_key_tweak(&self, tweak: &Scalar) -> Self {
+        Self {
+            masked_key: self.masked_key + self.mask.multiply_by_scalar(tweak),
+            ..*self
+        }
+    }
+
+    fn to_bytes(self) -> Vec<u8> {
+        [
+            self.pid.to_bytes().as_slice(),
+            &self.mask.to_bytes(),
+            &self.masked_key.to_bytes(),
+        ]
+        .concat()
+    }
+
+    fn try_from_bytes(bytes: &[u8]) -> Result<Self> {
+        if bytes.len() != Self::ENCODED_LEN {
+            Err(CallerError::DeserializationFailed)?
+        }
+        let (pid, points) = bytes.split_at(16);
+        let (mask, masked_key) = points.split_at(33);
+        Ok(Self {
+            pid: ParticipantIdentifier::from_bytes(
+                pid.try_into()
+                    .map_err(|_| CallerError::DeserializationFailed)?,
+            ),
+            mask: CurvePoint::try_from_bytes(mask)?,
+            masked_key: CurvePoint::try_from_bytes(masked_key)?,
+        })
+    }
 }
 
 /// The precomputation used to create a partial signature.
@@ -50,15 +163,25 @@ pub(crate) struct RecordPair {
 /// combining all of these shares, we get `(∑ kᵢ) m + r (∑ χᵢ) = k^{-1} (m + r
 /// d_A)`, which is exactly a valid (normal) ECDSA signature.
 ///
+/// The record also holds public commitments to every participant's `kᵢ` and
+/// `χᵢ`, so that a bad signature share can be traced back to its sender.
+///
 /// [^cite]: [Wikipedia](https://en.wikipedia.org/wiki/Elliptic_Curve_Digital_Signature_Algorithm#Signature_generation_algorithm)
 #[derive(Zeroize, ZeroizeOnDrop, PartialEq, Eq)]
 pub struct PresignRecord {
     R: CurvePoint,
     k: Scalar,
     chi: Scalar,
+    #[zeroize(skip)]
+    share_commitments: Vec<ShareCommitment>,
 }
 
-const RECORD_TAG: &[u8] = b"Presign Record";
+/// Tag that starts an encoded [`PresignRecord`].
+///
+/// The version suffix was added when the share commitments became part of the
+/// encoding, so that records in the earlier format are rejected up front
+/// rather than failing partway through parsing.
+const RECORD_TAG: &[u8] = b"Presign Record v2";
 
 impl Debug for PresignRecord {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -69,25 +192,24 @@ impl Debug for PresignRecord {
             .field("R", &"[redacted]")
             .field("k", &"[redacted]")
             .field("chi", &"[redacted]")
+            .field("share_commitments", &self.share_commitments)
             .finish()
     }
 }
 
 impl TryFrom<RecordPair> for PresignRecord {
     type Error = crate::errors::InternalError;
-    fn try_from(RecordPair { private, publics }: RecordPair) -> Result<Self> {
-        let mut delta = private.delta;
-        let mut Delta = private.Delta;
-        for p in publics {
-            delta += &p.delta;
-            Delta = Delta + p.Delta;
-        }
-
-        let g = CurvePoint::GENERATOR;
-        if g.multiply_by_scalar(&delta) != Delta {
+    fn try_from(pair: RecordPair) -> Result<Self> {
+        let (delta, Delta) = pair.sum_deltas();
+        if CurvePoint::GENERATOR.multiply_by_scalar(&delta) != Delta {
             error!("Could not create PresignRecord: mismatch between calculated private and public deltas");
             return Err(ProtocolError(None));
         }
+        if !pair.chi_commitments_are_consistent() {
+            error!("Could not create PresignRecord: mismatch between the public key and the chi commitments");
+            return Err(ProtocolError(None));
+        }
+        let private = &pair.private;
 
         let delta_inv = Option::<Scalar>::from(delta.invert()).ok_or_else(|| {
             error!("Could not invert delta as it is 0. Either you got profoundly unlucky or more likely there's a bug");
@@ -95,10 +217,25 @@ impl TryFrom<RecordPair> for PresignRecord {
         })?;
         let R = private.Gamma.multiply_by_scalar(&delta_inv);
 
+        // `R = Γ^{δ^{-1}}`, so `R^{kᵢ} = Δᵢ^{δ^{-1}}` and `R^{χᵢ} = Sᵢ^{δ^{-1}}`.
+        let share_commitments = std::iter::once((pair.id, &private.Delta, &private.S))
+            .chain(
+                pair.publics
+                    .iter()
+                    .map(|(pid, public)| (*pid, &public.Delta, &public.S)),
+            )
+            .map(|(pid, Delta, S)| ShareCommitment {
+                pid,
+                mask: Delta.multiply_by_scalar(&delta_inv),
+                masked_key: S.multiply_by_scalar(&delta_inv),
+            })
+            .collect();
+
         Ok(PresignRecord {
             R,
             k: bn_to_scalar(&private.k)?,
             chi: private.chi,
+            share_commitments,
         })
     }
 }
@@ -113,18 +250,33 @@ impl PresignRecord {
     pub(crate) fn masked_key_share(&self) -> &Scalar {
         &self.chi
     }
+
+    /// Get the mask point (`R` in the paper) from the record.
+    pub(crate) fn mask_point(&self) -> &CurvePoint {
+        &self.R
+    }
+
+    /// Get the commitments to every participant's presign shares.
+    pub(crate) fn share_commitments(&self) -> &[ShareCommitment] {
+        &self.share_commitments
+    }
+
+    /// Get the quorum that generated the record. Only this exact set of
+    /// participants can sign with it.
+    pub(crate) fn quorum(&self) -> HashSet<ParticipantIdentifier> {
+        self.share_commitments
+            .iter()
+            .map(ShareCommitment::participant)
+            .collect()
+    }
+
     /// Compute the x-projection of the randomly-selected point `R` from the
     /// [`PresignRecord`].
     pub(crate) fn x_projection(&self) -> Result<Scalar> {
-        let x_projection = self.R.x_affine();
-
-        // Note: I don't think this is a foolproof transformation. The `from_repr`
-        // method expects a scalar in the range `[0, q)`, but there's no
-        // guarantee that the x-coordinate of `R` will be in that range.
-        Option::from(Scalar::from_repr(x_projection)).ok_or_else(|| {
-            error!("Unable to compute x-projection of curve point: failed to convert x coord to `Scalar`");
-            InternalInvariantFailed
-        })
+        // Note: I don't think this is a foolproof transformation. The conversion
+        // expects a scalar in the range `[0, q)`, but there's no guarantee that
+        // the x-coordinate of `R` will be in that range.
+        self.R.x_projection()
     }
 
     /// Convert private material into bytes.
@@ -132,6 +284,11 @@ impl PresignRecord {
     /// 🔒 This is intended for use by the calling application for secure
     /// storage. The output of this function should be handled with care.
     pub fn into_bytes(self) -> Vec<u8> {
+        self.to_bytes()
+    }
+
+    /// Convert private material into bytes, without consuming the record.
+    pub(crate) fn to_bytes(&self) -> Vec<u8> {
         // Format:
         // RECORD TAG
         // Curve point length in bytes (8 bytes)
@@ -140,6 +297,8 @@ impl PresignRecord {
         // k randomness share
         // chi share length in bytes (8 bytes)
         // chi share
+        // Number of share commitments (8 bytes)
+        // Share commitments (pid | R^k | R^chi, each of fixed length)
 
         let mut point = self.R.to_bytes();
         let point_len = point.len().to_le_bytes();
@@ -150,6 +309,9 @@ impl PresignRecord {
         let mut chi_share = self.chi.to_bytes();
         let chi_share_len = chi_share.len().to_le_bytes();
 
+        let commitments_count = self.share_commitments.len().to_le_bytes();
+        let commitments = Self::share_commitments_to_bytes(&self.share_commitments);
+
         let bytes = [
             RECORD_TAG,
             &point_len,
@@ -158,6 +320,8 @@ impl PresignRecord {
             random_share.as_ref(),
             &chi_share_len,
             chi_share.as_ref(),
+            &commitments_count,
+            &commitments,
         ]
         .concat();
 
@@ -208,21 +372,37 @@ impl PresignRecord {
 
             // Parse the chi share
             let chi_share_len = parser.take_len()?;
-            let chi_share_slice = parser.take_rest()?;
-            if chi_share_slice.len() != chi_share_len {
-                Err(CallerError::DeserializationFailed)?
-            }
+            let chi_share_slice = parser.take_bytes(chi_share_len)?;
             let mut chi_share_bytes: [u8; 32] = chi_share_slice
                 .try_into()
                 .map_err(|_| CallerError::DeserializationFailed)?;
             let chi_share: Option<_> = Scalar::from_repr(chi_share_bytes.into()).into();
             chi_share_bytes.zeroize();
 
+            // Parse the share commitments, which must make up the rest of the
+            // bytes
+            let commitments_count = parser.take_len()?;
+            let commitments_slice = parser.take_rest()?;
+            if commitments_count.checked_mul(ShareCommitment::ENCODED_LEN)
+                != Some(commitments_slice.len())
+            {
+                Err(CallerError::DeserializationFailed)?
+            }
+            let share_commitments = commitments_slice
+                .chunks_exact(ShareCommitment::ENCODED_LEN)
+                .map(ShareCommitment::try_from_bytes)
+                .collect::<Result<Vec<_>>>()?;
+
             // The random and chi shares both need to be elements of `F_q`;
             // the k256::Scalar's parsing methods check this for us.
 
             match (random_share, chi_share) {
-                (Some(k), Some(chi)) => Ok(Self { R: point, k, chi }),
+                (Some(k), Some(chi)) => Ok(Self {
+                    R: point,
+                    k,
+                    chi,
+                    share_commitments,
+                }),
                 _ => Err(CallerError::DeserializationFailed)?,
             }
         };
@@ -237,14 +417,35 @@ impl PresignRecord {
         if result.is_err() {
             error!(
                 "Failed to deserialize `PresignRecord`. Expected format:
-                    {:?} | curve_point | k | chi
-                where the last three elements are each prepended by an 8 byte
-                little-endian encoded usize describing the length of the remainder of the field",
+                    {:?} | curve_point | k | chi | share_commitments
+                where the curve point and scalars are each prepended by an 8 byte
+                little-endian encoded usize describing the length of the remainder of the field,
+                and the share commitments are prepended by their count",
                 RECORD_TAG
             );
         }
         result
     }
+
+    fn share_commitments_to_bytes(share_commitments: &[ShareCommitment]) -> Vec<u8> {
+        share_commitments
+            .iter()
+            .flat_map(|commitment| commitment.to_bytes())
+            .collect()
+    }
+}
+
+impl SecretRecord for PresignRecord {
+    const RECORD_TYPE: RecordType = RecordType::PresignRecord;
+    const SINGLE_USE: bool = true;
+
+    fn to_record_bytes(&self) -> Result<Vec<u8>> {
+        Ok(self.to_bytes())
+    }
+
+    fn from_record_bytes(bytes: Vec<u8>) -> Result<Self> {
+        Self::try_from_bytes(bytes)
+    }
 }
 
 #[cfg(test)]
@@ -254,27 +455,34 @@ mod tests {
 
     use crate::{
         keygen,
-        presign::{participant::presign_record_set_is_valid, record::RECORD_TAG},
+        presign::{
+            participant::presign_record_set_is_valid,
+            record::{ShareCommitment, RECORD_TAG},
+        },
         utils::{bn_to_scalar, testing::init_testing, CurvePoint},
-        ParticipantConfig, PresignRecord,
+        ParticipantConfig, ParticipantIdentifier, PresignRecord,
     };
 
     impl PresignRecord {
-        pub(crate) fn mask_point(&self) -> &CurvePoint {
-            &self.R
-        }
-
         /// Simulate creation of a random presign record. Do not use outside of
         /// testing.
         fn simulate(rng: &mut StdRng) -> PresignRecord {
             let mask_point = CurvePoint::random(StdRng::from_seed(rng.gen()));
             let mask_share = Scalar::random(StdRng::from_seed(rng.gen()));
-            let masked_key_share = Scalar::random(rng);
+            let masked_key_share = Scalar::random(StdRng::from_seed(rng.gen()));
+            let share_commitments = std::iter::repeat_with(|| ShareCommitment {
+                pid: ParticipantIdentifier::random(rng),
+                mask: CurvePoint::random(StdRng::from_seed(rng.gen())),
+                masked_key: CurvePoint::random(StdRng::from_seed(rng.gen())),
+            })
+            .take(3)
+            .collect();
 
             PresignRecord {
                 R: mask_point,
                 k: mask_share,
                 chi: masked_key_share,
+                share_commitments,
             }
         }
 
@@ -302,16 +510,28 @@ mod tests {
             let masked_key_shares = keygen_outputs
                 .iter()
                 .map(|output| bn_to_scalar(output.private_key_share().as_ref()).unwrap())
-                .map(|secret_key_share| secret_key_share * mask);
+                .map(|secret_key_share| secret_key_share * mask)
+                .collect::<Vec<_>>();
 
             assert_eq!(masked_key_shares.len(), keygen_outputs.len());
             assert_eq!(mask_shares.len(), keygen_outputs.len());
 
+            let share_commitments = keygen_outputs
+                .iter()
+                .zip(std::iter::zip(&mask_shares, &masked_key_shares))
+                .map(|(output, (mask_share, masked_key_share))| ShareCommitment {
+                    pid: output.private_pid().unwrap(),
+                    mask: mask_point.multiply_by_scalar(mask_share),
+                    masked_key: mask_point.multiply_by_scalar(masked_key_share),
+                })
+                .collect::<Vec<_>>();
+
             std::iter::zip(masked_key_shares, mask_shares)
                 .map(|(masked_key_share, mask_share)| Self {
                     R: mask_point,
                     k: mask_share,
                     chi: masked_key_share,
+                    share_commitments: share_commitments.clone(),
                 })
                 .collect()
         }
@@ -332,7 +552,10 @@ mod tests {
     fn record_bytes_conversion_works() {
         let rng = &mut init_testing();
         let record = PresignRecord::simulate(rng);
-        let clone = PresignRecord { ..record };
+        let clone = PresignRecord {
+            share_commitments: record.share_commitments.clone(),
+            ..record
+        };
 
         let bytes = record.into_bytes();
         let reconstructed = PresignRecord::try_from_bytes(bytes);
@@ -350,7 +573,7 @@ mod tests {
         let share_bytes = &record.into_bytes()[RECORD_TAG.len()..];
 
         // Tag must have correct content
-        let wrong_tag = b"NotTheRightTag";
+        let wrong_tag = b"NotTheRightTag v2";
         assert_eq!(wrong_tag.len(), RECORD_TAG.len());
         let bad_bytes = [wrong_tag.as_slice(), share_bytes].concat();
         assert!(PresignRecord::try_from_bytes(bad_bytes).is_err());
@@ -363,6 +586,10 @@ mod tests {
         let bad_bytes = [RECORD_TAG, b"TAG EXTENSION!", share_bytes].concat();
         assert!(PresignRecord::try_from_bytes(bad_bytes).is_err());
 
+        // The tag from before the share commitments were encoded is rejected
+        let bad_bytes = [b"Presign Record".as_slice(), share_bytes].concat();
+        assert!(PresignRecord::try_from_bytes(bad_bytes).is_err());
+
         // Normal serialization works
         let bytes = [RECORD_TAG, share_bytes].concat();
         assert!(PresignRecord::try_from_bytes(bytes).is_ok());
@@ -394,14 +621,17 @@ mod tests {
     #[test]
     fn point_field_must_have_length_prepended() {
         let rng = &mut init_testing();
-        let PresignRecord { R, k, chi } = PresignRecord::simulate(rng);
+        let record = PresignRecord::simulate(rng);
 
-        let point = R.to_bytes();
+        let commitments_count = record.share_commitments.len().to_le_bytes();
+        let commitments = PresignRecord::share_commitments_to_bytes(&record.share_commitments);
 
-        let random_share = k.to_bytes();
+        let point = record.R.to_bytes();
+
+        let random_share = record.k.to_bytes();
         let random_share_len = random_share.len().to_le_bytes();
 
-        let chi_share = chi.to_bytes();
+        let chi_share = record.chi.to_bytes();
         let chi_share_len = chi_share.len().to_le_bytes();
 
         let back = [
@@ -410,6 +640,8 @@ mod tests {
             random_share.as_ref(),
             &chi_share_len,
             chi_share.as_ref(),
+            &commitments_count,
+            &commitments,
         ]
         .concat();
 
@@ -419,18 +651,28 @@ mod tests {
     #[test]
     fn k_field_must_have_length_prepended() {
         let rng = &mut init_testing();
-        let PresignRecord { R, k, chi } = PresignRecord::simulate(rng);
+        let record = PresignRecord::simulate(rng);
 
-        let point = R.to_bytes();
+        let commitments_count = record.share_commitments.len().to_le_bytes();
+        let commitments = PresignRecord::share_commitments_to_bytes(&record.share_commitments);
+
+        let point = record.R.to_bytes();
         let point_len = point.len().to_le_bytes();
 
-        let random_share = k.to_bytes();
+        let random_share = record.k.to_bytes();
         let front = [RECORD_TAG, &point_len, &point].concat();
 
-        let chi_share = chi.to_bytes();
+        let chi_share = record.chi.to_bytes();
         let chi_share_len = chi_share.len().to_le_bytes();
 
-        let back = [random_share.as_slice(), &chi_share_len, &chi_share].concat();
+        let back = [
+            random_share.as_slice(),
+            &chi_share_len,
+            &chi_share,
+            &commitments_count,
+            &commitments,
+        ]
+        .concat();
 
         test_length_field(&front, random_share.len(), &back)
     }
@@ -438,15 +680,18 @@ mod tests {
     #[test]
     fn chi_field_must_have_length_prepended() {
         let rng = &mut init_testing();
-        let PresignRecord { R, k, chi } = PresignRecord::simulate(rng);
+        let record = PresignRecord::simulate(rng);
 
-        let point = R.to_bytes();
+        let commitments_count = record.share_commitments.len().to_le_bytes();
+        let commitments = PresignRecord::share_commitments_to_bytes(&record.share_commitments);
+
+        let point = record.R.to_bytes();
         let point_len = point.len().to_le_bytes();
 
-        let random_share = k.to_bytes();
+        let random_share = record.k.to_bytes();
         let random_share_len = random_share.len().to_le_bytes();
 
-        let chi_share = chi.to_bytes();
+        let chi_share = record.chi.to_bytes();
 
         let front = [
             RECORD_TAG,
@@ -457,7 +702,9 @@ mod tests {
         ]
         .concat();
 
-        test_length_field(&front, chi_share.len(), chi_share.as_ref())
+        let back = [chi_share.as_slice(), &commitments_count, &commitments].concat();
+
+        test_length_field(&front, chi_share.len(), &back)
     }
 
     #[test]
@@ -469,15 +716,18 @@ mod tests {
         assert!(PresignRecord::try_from_bytes(bytes.to_vec()).is_err());
         assert!(PresignRecord::try_from_bytes(RECORD_TAG.to_vec()).is_err());
 
-        let PresignRecord { R, k, chi } = PresignRecord::simulate(rng);
+        let record = PresignRecord::simulate(rng);
+
+        let commitments_count = record.share_commitments.len().to_le_bytes();
+        let commitments = PresignRecord::share_commitments_to_bytes(&record.share_commitments);
 
-        let point = R.to_bytes();
+        let point = record.R.to_bytes();
         let point_len = point.len().to_le_bytes();
 
-        let random_share = k.to_bytes();
+        let random_share = record.k.to_bytes();
         let random_share_len = random_share.len().to_le_bytes();
 
-        let chi_share = chi.to_bytes();
+        let chi_share = record.chi.to_bytes();
         let chi_share_len = chi_share.len().to_le_bytes();
 
         let zero_len = 0usize.to_le_bytes();
@@ -520,6 +770,32 @@ mod tests {
         .concat();
         assert!(PresignRecord::try_from_bytes(bytes).is_err());
 
+        // Missing share commitments don't pass
+        let bytes = [
+            RECORD_TAG,
+            &point_len,
+            &point,
+            &random_share_len,
+            random_share.as_ref(),
+            &chi_share_len,
+            chi_share.as_ref(),
+        ]
+        .concat();
+        assert!(PresignRecord::try_from_bytes(bytes).is_err());
+
+        let bytes = [
+            RECORD_TAG,
+            &point_len,
+            &point,
+            &random_share_len,
+            random_share.as_ref(),
+            &chi_share_len,
+            chi_share.as_ref(),
+            &commitments_count,
+        ]
+        .concat();
+        assert!(PresignRecord::try_from_bytes(bytes).is_err());
+
         // Full thing works (e.g. the encoding scheme used above is correct)
         let bytes = [
             RECORD_TAG,
@@ -529,8 +805,38 @@ mod tests {
             random_share.as_ref(),
             &chi_share_len,
             chi_share.as_ref(),
+            &commitments_count,
+            &commitments,
         ]
         .concat();
         assert!(PresignRecord::try_from_bytes(bytes).is_ok());
     }
+
+    #[test]
+    fn share_commitment_count_must_be_correct() {
+        let rng = &mut init_testing();
+        let record = PresignRecord::simulate(rng);
+        let count = record.share_commitments.len();
+        let bytes = record.into_bytes();
+
+        // The count sits right before the fixed-length commitments
+        let count_offset = bytes.len() - count * ShareCommitment::ENCODED_LEN - 8;
+        let with_count = |count: usize| {
+            [
+                &bytes[..count_offset],
+                &count.to_le_bytes(),
+                &bytes[count_offset + 8..],
+            ]
+            .concat()
+        };
+
+        assert!(PresignRecord::try_from_bytes(with_count(count - 1)).is_err());
+        assert!(PresignRecord::try_from_bytes(with_count(count + 1)).is_err());
+        assert!(PresignRecord::try_from_bytes(with_count(usize::MAX)).is_err());
+        assert!(PresignRecord::try_from_bytes(with_count(count)).is_ok());
+
+        // Trailing bytes that don't make up a full commitment don't pass
+        let bytes = [with_count(count).as_slice(), &[0u8; 5]].concat();
+        assert!(PresignRecord::try_from_bytes(bytes).is_err());
+    }
 }
@@ -7,7 +7,7 @@
 // of this source tree.
 
 use crate::{
-    errors::{InternalError, Result},
+    errors::Result,
     messages::{Message, MessageType, PresignMessageType},
     paillier::{Ciphertext, EncryptionKey, Nonce},
     ring_pedersen::VerifiedRingPedersen,
@@ -23,7 +23,7 @@ use std::fmt::Debug;
 use zeroize::ZeroizeOnDrop;
 
 /// Private data used in round one of the presign protocol.
-#[derive(ZeroizeOnDrop)]
+#[derive(Serialize, Deserialize, ZeroizeOnDrop)]
 pub(crate) struct Private {
     pub k: BigNumber,
     pub rho: Nonce,
@@ -50,8 +50,8 @@ impl Debug for Private {
 
 /// Public information produced in round one of the presign protocol.
 ///
-/// This type implements [`TryFrom`] on [`Message`], which validates that
-/// [`Message`] is a valid serialization of `Public`, but _not_ that `Public` is
+/// [`Public::from_message`] validates that a [`Message`] is a valid
+/// serialization of a batch of `Public`s, but _not_ that each `Public` is
 /// necessarily valid (i.e., that all the components are valid with respect to
 /// each other); use [`Public::verify`] to check this latter condition.
 #[derive(Debug, Serialize, Deserialize)]
@@ -91,12 +91,12 @@ impl Public {
     }
 }
 
-impl TryFrom<&Message> for Public {
-    type Error = InternalError;
-
-    fn try_from(message: &Message) -> std::result::Result<Self, Self::Error> {
+impl Public {
+    /// Parse the [`Public`] values from a round one message, one for each
+    /// presign record in the batch.
+    pub(crate) fn from_message(message: &Message) -> Result<Vec<Self>> {
         message.check_type(MessageType::Presign(PresignMessageType::RoundOne))?;
-        let public: Self = deserialize!(&message.unverified_bytes)?;
-        Ok(public)
+        let publics: Vec<Self> = deserialize!(&message.unverified_bytes)?;
+        Ok(publics)
     }
 }
@@ -28,7 +28,7 @@ use std::fmt::Debug;
 use tracing::error;
 use zeroize::ZeroizeOnDrop;
 
-#[derive(Clone, ZeroizeOnDrop)]
+#[derive(Clone, Serialize, Deserialize, ZeroizeOnDrop)]
 pub(crate) struct Private {
     pub k: BigNumber,
     pub chi: Scalar,
@@ -38,6 +38,8 @@ pub(crate) struct Private {
     pub delta: Scalar,
     #[zeroize(skip)]
     pub Delta: CurvePoint,
+    #[zeroize(skip)]
+    pub S: CurvePoint,
 }
 
 impl Debug for Private {
@@ -50,14 +52,15 @@ impl Debug for Private {
             .field("delta", &self.delta)
             .field("Gamma", &self.Gamma)
             .field("Delta", &self.Delta)
+            .field("S", &self.S)
             .finish()
     }
 }
 
 /// Public information produced in round three of the presign protocol.
 ///
-/// This type implements [`TryFrom`] on [`Message`], which validates that
-/// [`Message`] is a valid serialization of `Public`, but _not_ that `Public` is
+/// [`Public::from_message`] validates that a [`Message`] is a valid
+/// serialization of a batch of `Public`s, but _not_ that each `Public` is
 /// necessarily valid (i.e., that all the components are valid with respect to
 /// each other); use [`Public::verify`] to check this latter condition.
 #[derive(Clone, Serialize, Deserialize)]
@@ -67,6 +70,8 @@ pub(crate) struct Public {
     pub psi_double_prime: PiLogProof,
     /// Gamma value included for convenience
     pub Gamma: CurvePoint,
+    /// `Γ^{χᵢ}`, used to check the sender's signature share when signing.
+    pub S: CurvePoint,
 }
 
 impl Public {
@@ -95,22 +100,46 @@ impl Public {
     }
 }
 
-impl TryFrom<&Message> for Public {
-    type Error = InternalError;
-
-    fn try_from(message: &Message) -> std::result::Result<Self, Self::Error> {
+impl Public {
+    /// Parse the [`Public`] values from a round three message, one for each
+    /// presign record in the batch.
+    pub(crate) fn from_message(message: &Message) -> Result<Vec<Self>> {
         message.check_type(MessageType::Presign(PresignMessageType::RoundThree))?;
-        let public: Self = deserialize!(&message.unverified_bytes)?;
+        let publics: Vec<Self> = deserialize!(&message.unverified_bytes)?;
 
         // Normal `Scalar` deserialization doesn't check that the value is in range.
         // Here we convert to bytes and back, using the checked `from_repr` method to
         // make sure the value is a valid, canonical Scalar.
-        if Scalar::from_repr(public.delta.to_bytes()).is_none().into() {
-            error!("Deserialized round 3 message `delta` field is out of range");
-            Err(InternalError::ProtocolError(Some(message.from())))?
+        for public in &publics {
+            if Scalar::from_repr(public.delta.to_bytes()).is_none().into() {
+                error!("Deserialized round 3 message `delta` field is out of range");
+                Err(InternalError::ProtocolError(Some(message.from())))?
+            }
         }
-        Ok(public)
+        Ok(publics)
+    }
+}
+
+/// Change the `delta` values in a round three message, so that the
+/// recipient's delta check fails and it starts the identification phase.
+/// Other messages are returned unchanged.
+#[cfg(test)]
+pub(crate) fn corrupt_delta(message: Message) -> Result<Message> {
+    let round_three = MessageType::Presign(PresignMessageType::RoundThree);
+    if message.message_type() != round_three {
+        return Ok(message);
+    }
+    let mut publics = Public::from_message(&message)?;
+    for public in &mut publics {
+        public.delta += Scalar::ONE;
     }
+    Message::new(
+        round_three,
+        message.id(),
+        message.from(),
+        message.to(),
+        &publics,
+    )
 }
 
 /// Used to bundle the inputs passed to round_three() together
@@ -8,10 +8,11 @@
 
 use crate::{
     auxinfo::AuxInfoPublic,
-    errors::{InternalError, Result},
+    errors::Result,
     keygen::KeySharePublic,
     messages::{Message, MessageType, PresignMessageType},
     paillier::Ciphertext,
+    parallel,
     presign::round_one::{Private as RoundOnePrivate, PublicBroadcast as RoundOnePublicBroadcast},
     utils::CurvePoint,
     zkp::{
@@ -26,10 +27,18 @@ use serde::{Deserialize, Serialize};
 use std::fmt::Debug;
 use zeroize::ZeroizeOnDrop;
 
-#[derive(Clone, ZeroizeOnDrop)]
+#[derive(Clone, Serialize, Deserialize, ZeroizeOnDrop)]
 pub(crate) struct Private {
     pub beta: BigNumber,
     pub beta_hat: BigNumber,
+    /// The encryption of `beta` sent to the receiver, kept in case the
+    /// identification phase is needed.
+    #[zeroize(skip)]
+    pub F: Ciphertext,
+    /// The encryption of `beta_hat` sent to the receiver, kept for the same
+    /// reason.
+    #[zeroize(skip)]
+    pub F_hat: Ciphertext,
 }
 
 impl Debug for Private {
@@ -37,14 +46,16 @@ impl Debug for Private {
         f.debug_struct("presign::round_two::Private")
             .field("beta", &"[redacted]")
             .field("beta_hat", &"[redacted]")
+            .field("F", &self.F)
+            .field("F_hat", &self.F_hat)
             .finish()
     }
 }
 
 /// Public information produced in round two of the presign protocol.
 ///
-/// This type implements [`TryFrom`] on [`Message`], which validates that
-/// [`Message`] is a valid serialization of `Public`, but _not_ that `Public` is
+/// [`Public::from_message`] validates that a [`Message`] is a valid
+/// serialization of a batch of `Public`s, but _not_ that each `Public` is
 /// necessarily valid (i.e., that all the components are valid with respect to
 /// each other); use [`Public::verify`] to check this latter condition.
 #[derive(Clone, Serialize, Deserialize)]
@@ -73,57 +84,73 @@ impl Public {
         prover_r1_public_broadcast: &RoundOnePublicBroadcast,
     ) -> Result<()> {
         let g = CurvePoint::GENERATOR;
+        let Public {
+            D,
+            D_hat,
+            F,
+            F_hat,
+            Gamma,
+            psi,
+            psi_hat,
+            psi_prime,
+        } = self;
 
-        // Verify the psi proof
         let psi_input = PiAffgInput::new(
             verifier_auxinfo_public.params(),
             verifier_auxinfo_public.pk(),
             prover_auxinfo_public.pk(),
             &verifier_r1_private.K,
-            &self.D,
-            &self.F,
-            &self.Gamma,
+            &D,
+            &F,
+            &Gamma,
         );
-        let mut transcript = Transcript::new(b"PiAffgProof");
-
-        self.psi.verify(psi_input, context, &mut transcript)?;
-
-        // Verify the psi_hat proof
         let psi_hat_input = PiAffgInput::new(
             verifier_auxinfo_public.params(),
             verifier_auxinfo_public.pk(),
             prover_auxinfo_public.pk(),
             &verifier_r1_private.K,
-            &self.D_hat,
-            &self.F_hat,
+            &D_hat,
+            &F_hat,
             prover_keyshare_public.as_ref(),
         );
-        let mut transcript = Transcript::new(b"PiAffgProof");
-        self.psi_hat
-            .verify(psi_hat_input, context, &mut transcript)?;
-
-        // Verify the psi_prime proof
         let psi_prime_input = CommonInput::new(
             &prover_r1_public_broadcast.G,
-            &self.Gamma,
+            &Gamma,
             verifier_auxinfo_public.params().scheme(),
             prover_auxinfo_public.pk(),
             &g,
         );
-        let mut transcript = Transcript::new(b"PiLogProof");
-        self.psi_prime
-            .verify(psi_prime_input, context, &mut transcript)?;
+
+        // The three proofs are independent, so verify them in parallel.
+        let (psi_result, (psi_hat_result, psi_prime_result)) = parallel::join(
+            || psi.verify(psi_input, context, &mut Transcript::new(b"PiAffgProof")),
+            || {
+                parallel::join(
+                    || psi_hat.verify(psi_hat_input, context, &mut Transcript::new(b"PiAffgProof")),
+                    || {
+                        psi_prime.verify(
+                            psi_prime_input,
+                            context,
+                            &mut Transcript::new(b"PiLogProof"),
+                        )
+                    },
+                )
+            },
+        );
+        psi_result?;
+        psi_hat_result?;
+        psi_prime_result?;
 
         Ok(())
     }
 }
 
-impl TryFrom<&Message> for Public {
-    type Error = InternalError;
-
-    fn try_from(message: &Message) -> std::result::Result<Self, Self::Error> {
+impl Public {
+    /// Parse the [`Public`] values from a round two message, one for each
+    /// presign record in the batch.
+    pub(crate) fn from_message(message: &Message) -> Result<Vec<Self>> {
         message.check_type(MessageType::Presign(PresignMessageType::RoundTwo))?;
-        let public: Self = deserialize!(&message.unverified_bytes)?;
-        Ok(public)
+        let publics: Vec<Self> = deserialize!(&message.unverified_bytes)?;
+        Ok(publics)
     }
 }
@@ -15,7 +15,8 @@ use crate::{
     messages::{Message, MessageType},
     participant::{InnerProtocolParticipant, ProtocolParticipant, Status},
     protocol::participant_config::ParticipantConfig,
-    utils::{k256_order, CurvePoint},
+    secret_store::{SecretRecord, SecretStore},
+    utils::{k256_order, CurvePoint, ParseBytes},
     zkp::ProofContext,
 };
 use libpaillier::unknown_order::BigNumber;
@@ -27,14 +28,17 @@ use std::{
     fmt::{Debug, Formatter},
 };
 use tracing::{error, info, instrument, trace};
+use zeroize::{Zeroize, ZeroizeOnDrop};
 
 /// The set of subprotocols that a [`Participant`] can execute.
 ///
 /// Note: An external user will never explicitly instantiate a `Broadcast`
 /// participant; this type is created internally to the library.
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum ProtocolType {
     Keygen,
+    Refresh,
+    Reshare,
     AuxInfo,
     Presign,
     Broadcast,
@@ -42,6 +46,53 @@ pub enum ProtocolType {
     InteractiveSign,
 }
 
+/// Tag at the start of a [`Participant`]'s exported state.
+const STATE_TAG: &[u8] = b"Participant State";
+
+/// Version of the format of a [`Participant`]'s exported state.
+const STATE_VERSION: u32 = 1;
+
+/// The exported state of a [`Participant`], following the tag and version.
+#[derive(Serialize, Deserialize, ZeroizeOnDrop)]
+struct ExportedState {
+    /// The version of this library that exported the state. The participant
+    /// state is only readable by the same version.
+    library_version: String,
+    #[zeroize(skip)]
+    protocol_type: ProtocolType,
+    #[zeroize(skip)]
+    sid: Identifier,
+    #[zeroize(skip)]
+    id: ParticipantIdentifier,
+    #[zeroize(skip)]
+    other_ids: Vec<ParticipantIdentifier>,
+    state: Vec<u8>,
+}
+
+/// The payload of a [`MessageType::Abort`] message.
+#[derive(Serialize, Deserialize)]
+struct AbortMessage {
+    reason: String,
+    blamed: Option<ParticipantIdentifier>,
+}
+
+/// The number of characters of another participant's abort reason that are
+/// kept; the rest are dropped.
+const MAX_ABORT_REASON_LEN: usize = 256;
+
+impl AbortMessage {
+    /// The reason given by the sender, truncated and with control and other
+    /// non-printable characters escaped, so that it's safe to log.
+    fn sanitized_reason(&self) -> String {
+        self.reason
+            .chars()
+            .take(MAX_ABORT_REASON_LEN)
+            .collect::<String>()
+            .escape_debug()
+            .to_string()
+    }
+}
+
 /// The driver for a party executing a sub-protocol of the threshold signing
 /// protocol.
 ///
@@ -96,6 +147,12 @@ pub enum ProtocolType {
 /// - Freshness: The storage contains the most recent state of the execution and
 ///   avoids replay attacks.
 /// - Secure deletion: Data can be securely deleted from storage.
+///
+/// # Crash recovery
+/// A session in progress can be saved with [`Participant::export_state()`]
+/// and resumed, e.g. after a restart, with [`Participant::import_state()`].
+/// The exported state contains secrets and must be stored according to the
+/// requirements above.
 #[derive(Debug)]
 pub struct Participant<P>
 where
@@ -107,6 +164,11 @@ where
     /// The [`ProtocolParticipant`] driver defining the actual protocol
     /// execution.
     participant: P,
+
+    /// The output and final messages of a protocol execution whose output
+    /// couldn't be written to a [`SecretStore`], kept so that they aren't
+    /// lost.
+    unstored_output: Option<(P::Output, Vec<Message>)>,
 }
 
 impl<P: ProtocolParticipant> Participant<P> {
@@ -123,6 +185,7 @@ impl<P: ProtocolParticipant> Participant<P> {
         Ok(Participant {
             id,
             participant: P::new(sid, id, other_ids, input)?,
+            unstored_output: None,
         })
     }
 
@@ -167,11 +230,15 @@ impl<P: ProtocolParticipant> Participant<P> {
         match (message.message_type(), P::protocol_type()) {
             (MessageType::Auxinfo(_), ProtocolType::AuxInfo)
             | (MessageType::Keygen(_), ProtocolType::Keygen)
+            | (MessageType::Refresh(_), ProtocolType::Refresh)
+            | (MessageType::Reshare(_), ProtocolType::Reshare)
             | (MessageType::Presign(_), ProtocolType::Presign)
             | (MessageType::Sign(_), ProtocolType::Sign)
             // Interactive sign runs presign and sign in sequence, so we allow both message types
             | (MessageType::Presign(_), ProtocolType::InteractiveSign)
-            | (MessageType::Sign(_), ProtocolType::InteractiveSign) => {}
+            | (MessageType::Sign(_), ProtocolType::InteractiveSign)
+            // Any session can be aborted
+            | (MessageType::Abort, _) => {}
             _ => {
                 error!(
                     "Message type did not match type of this participant: got {:?}, expected {:?}",
@@ -196,12 +263,82 @@ impl<P: ProtocolParticipant> Participant<P> {
             Err(CallerError::InvalidMessageSender)?
         }
 
+        if *self.status() == Status::Aborted {
+            error!("Received a message for a session that was aborted");
+            Err(CallerError::ProtocolAlreadyTerminated)?
+        }
+
+        if message.message_type() == MessageType::Abort {
+            return self.handle_abort(message);
+        }
+
         // Handle it!
         let outcome = self.participant.process_message(rng, message)?;
         let (output, messages) = outcome.into_parts();
         Ok((output, messages))
     }
 
+    /// Abandon the session, e.g. because another participant misbehaved or
+    /// didn't respond in time.
+    ///
+    /// This discards the participant's session state and sets its status to
+    /// [`Status::Aborted`], after which it rejects any further messages. The
+    /// returned messages tell each of the other participants to do the same
+    /// and must be sent to them. `reason` is a human-readable explanation, and
+    /// `blamed` is the participant responsible for the abort, if known.
+    ///
+    /// A participant that receives one of these messages returns
+    /// [`InternalError::SessionAborted`] from
+    /// [`process_single_message()`](Participant::process_single_message()).
+    #[instrument(skip_all)]
+    pub fn abort(
+        &mut self,
+        reason: &str,
+        blamed: Option<ParticipantIdentifier>,
+    ) -> Result<Vec<Message>> {
+        info!("Aborting the session: {}", reason);
+        let payload = AbortMessage {
+            reason: reason.to_string(),
+            blamed,
+        };
+        let messages = self
+            .participant
+            .other_ids()
+            .iter()
+            .map(|&other_id| {
+                Message::new(MessageType::Abort, self.sid(), self.id, other_id, &payload)
+            })
+            .collect::<Result<Vec<_>>>()?;
+        self.participant.abort();
+        Ok(messages)
+    }
+
+    /// Handle an abort message from another participant.
+    fn handle_abort(&mut self, message: &Message) -> Result<(Option<P::Output>, Vec<Message>)> {
+        // If we've already finished, our output is still valid
+        if *self.status() == Status::TerminatedSuccessfully {
+            info!(
+                "Ignoring abort from {} for a session that already terminated",
+                message.from()
+            );
+            return Ok((None, vec![]));
+        }
+
+        let abort: AbortMessage = deserialize!(&message.unverified_bytes)?;
+        let reason = abort.sanitized_reason();
+        error!(
+            "Participant {} aborted the session: {}",
+            message.from(),
+            reason
+        );
+        self.participant.abort();
+        Err(InternalError::SessionAborted {
+            from: message.from(),
+            blamed: abort.blamed,
+            reason,
+        })
+    }
+
     /// Produce a message to signal to this participant that the protocol can
     /// begin.
     #[instrument(skip_all)]
@@ -215,6 +352,160 @@ impl<P: ProtocolParticipant> Participant<P> {
     pub fn status(&self) -> &Status {
         self.participant.status()
     }
+
+    /// Return the other participants whose messages this participant is
+    /// waiting for in order to finish the current round of the protocol.
+    ///
+    /// This lets the calling application time out a session that has stalled
+    /// and report who was responsible. The list is empty if the participant
+    /// hasn't been started with
+    /// [`initialize_message()`](Participant::initialize_message()) or has
+    /// terminated.
+    ///
+    /// Rounds that use echo broadcast only complete once every participant
+    /// has forwarded every other participant's message. In those rounds, a
+    /// participant is listed if we haven't received its own message, or if we
+    /// have received another participant's message but not its forwarded copy.
+    pub fn waiting_on(&self) -> Vec<ParticipantIdentifier> {
+        self.participant.waiting_on()
+    }
+
+    /// Export the state of the protocol execution, so that it can be resumed
+    /// with [`Participant::import_state()`].
+    ///
+    /// The state includes everything the participant has computed and received
+    /// so far, including messages that arrived too early to be processed. It
+    /// doesn't include the participant's input, which must be provided again
+    /// when importing.
+    ///
+    /// To make sure a resumed participant is consistent with what the other
+    /// participants have seen, export the state after each call to
+    /// [`process_single_message()`](Participant::process_single_message()),
+    /// and persist it before sending the resulting messages.
+    ///
+    /// # 🔒 Storage requirements
+    /// The state contains secret values and must be stored securely by the
+    /// calling application. It should be deleted once the protocol
+    /// terminates.
+    pub fn export_state(&self) -> Result<Vec<u8>> {
+        let exported = ExportedState {
+            library_version: env!("CARGO_PKG_VERSION").to_string(),
+            protocol_type: P::protocol_type(),
+            sid: self.sid(),
+            id: self.id,
+            other_ids: self.participant.other_ids().to_vec(),
+            state: self.participant.export_state()?,
+        };
+        let mut payload = serialize!(&exported)?;
+        let bytes = [STATE_TAG, &STATE_VERSION.to_le_bytes(), &payload].concat();
+        payload.zeroize();
+        Ok(bytes)
+    }
+
+    /// Resume a protocol execution from a state produced by
+    /// [`Participant::export_state()`].
+    ///
+    /// The `input` must be the same as the input of the participant that
+    /// exported the state, and the state must have been exported by the same
+    /// version of this library.
+    pub fn import_state(input: P::Input, state: Vec<u8>) -> Result<Self> {
+        let mut parser = ParseBytes::new(state);
+
+        // Parse within a closure so that the input bytes are zeroized and the
+        // error is logged regardless of where parsing fails
+        let mut parse = || -> Result<ExportedState> {
+            let actual_tag = parser.take_bytes(STATE_TAG.len())?;
+            if actual_tag != STATE_TAG {
+                Err(CallerError::DeserializationFailed)?
+            }
+
+            let version_bytes: [u8; 4] = parser
+                .take_bytes(4)?
+                .try_into()
+                .map_err(|_| CallerError::DeserializationFailed)?;
+            if u32::from_le_bytes(version_bytes) != STATE_VERSION {
+                Err(CallerError::DeserializationFailed)?
+            }
+
+            let exported: ExportedState = deserialize!(parser.take_rest()?)?;
+            if exported.library_version != env!("CARGO_PKG_VERSION") {
+                Err(CallerError::DeserializationFailed)?
+            }
+            Ok(exported)
+        };
+        let result = parse();
+        parser.zeroize();
+        let exported = result.map_err(|e| {
+            error!("Failed to parse exported participant state");
+            e
+        })?;
+
+        if exported.protocol_type != P::protocol_type() {
+            error!(
+                "Tried to import the state of a {:?} participant into a {:?} participant",
+                exported.protocol_type,
+                P::protocol_type()
+            );
+            Err(CallerError::BadInput)?
+        }
+
+        let mut participant = P::new(exported.sid, exported.id, exported.other_ids.clone(), input)?;
+        participant.import_state(&exported.state)?;
+
+        Ok(Participant {
+            id: exported.id,
+            participant,
+            unstored_output: None,
+        })
+    }
+}
+
+impl<P: ProtocolParticipant> Participant<P>
+where
+    P::Output: SecretRecord,
+{
+    /// Process the given message, and persist the output to `store` under
+    /// `key_id` if the protocol terminates.
+    ///
+    /// This behaves like
+    /// [`process_single_message()`](Participant::process_single_message()),
+    /// except that the output is written to the store before it is returned.
+    /// If the write fails, the error is returned, and the output and any
+    /// messages to send are kept until they're retrieved with
+    /// [`take_unstored_output()`](Participant::take_unstored_output()). As
+    /// with [`SecretStore::put_record()`], a
+    /// [single-use](SecretRecord::SINGLE_USE) output is never written over an
+    /// existing record, so each one needs its own `key_id`.
+    pub fn process_single_message_and_store<R: RngCore + CryptoRng, S: SecretStore>(
+        &mut self,
+        message: &Message,
+        rng: &mut R,
+        store: &mut S,
+        key_id: &str,
+    ) -> Result<(Option<P::Output>, Vec<Message>)> {
+        let (output, messages) = self.process_single_message(message, rng)?;
+        if let Some(output) = output {
+            info!("Persisting protocol output to the secret store.");
+            if let Err(err) = store.put_record(key_id, &output) {
+                error!("Failed to persist the protocol output; keeping it until it's taken");
+                self.unstored_output = Some((output, messages));
+                return Err(err);
+            }
+            return Ok((Some(output), messages));
+        }
+        Ok((None, messages))
+    }
+
+    /// Take the output and messages of an execution whose output couldn't be
+    /// persisted by
+    /// [`process_single_message_and_store()`](Participant::process_single_message_and_store()),
+    /// if there is one.
+    ///
+    /// The calling application should persist the output itself, e.g. with
+    /// [`SecretStore::put_record()`], before sending the messages.
+    pub fn take_unstored_output(&mut self) -> Option<(P::Output, Vec<Message>)> {
+        self.unstored_output.take()
+    }
 }
 
 pub(crate) mod participant_config {
@@ -410,6 +701,27 @@ impl ParticipantIdentifier {
         trace!("Created new Participant Identifier({random_bytes})");
         Self(random_bytes)
     }
+
+    /// The point at which a Shamir-shared polynomial is evaluated to produce
+    /// this participant's share.
+    ///
+    /// This is the identifier, interpreted as an integer, plus one. The result
+    /// is always non-zero and is unique among participants, since identifiers
+    /// are much smaller than the order of the curve.
+    pub(crate) fn evaluation_point(&self) -> BigNumber {
+        BigNumber::from_slice(self.0.to_be_bytes()) + BigNumber::one()
+    }
+
+    /// Encode the identifier as little-endian bytes.
+    pub(crate) fn to_bytes(self) -> [u8; 16] {
+        self.0.to_le_bytes()
+    }
+
+    /// Decode an identifier from the output of
+    /// [`ParticipantIdentifier::to_bytes()`].
+    pub(crate) fn from_bytes(bytes: [u8; 16]) -> Self {
+        Self(u128::from_le_bytes(bytes))
+    }
 }
 
 /// The `SharedContext` contains fixed known parameters across the entire
@@ -576,6 +888,16 @@ impl Identifier {
         trace!("Created new Session Identifier({random_bytes})");
         Self(random_bytes)
     }
+
+    /// Encode the identifier as little-endian bytes.
+    pub(crate) fn to_bytes(self) -> [u8; 16] {
+        self.0.to_le_bytes()
+    }
+
+    /// Decode an identifier from the output of [`Identifier::to_bytes()`].
+    pub(crate) fn from_bytes(bytes: [u8; 16]) -> Self {
+        Self(u128::from_le_bytes(bytes))
+    }
 }
 
 impl From<u128> for Identifier {
@@ -594,10 +916,12 @@ impl std::fmt::Display for Identifier {
 mod tests {
     use super::*;
     use crate::{
-        auxinfo::AuxInfoParticipant,
-        keygen::KeygenParticipant,
+        auxinfo::{self, AuxInfoParticipant},
+        keygen::{self, KeygenParticipant},
+        messages::KeygenMessageType,
         participant::Status,
         presign,
+        secret_store::{MemorySecretStore, RecordType},
         sign::{self, InteractiveSignParticipant, SignParticipant},
         utils::testing::init_testing,
         PresignParticipant,
@@ -616,8 +940,12 @@ mod tests {
         // Set up a single valid participant
         let config = ParticipantConfig::random(QUORUM_SIZE, &mut rng);
         let auxinfo_sid = Identifier::random(&mut rng);
-        let mut participant =
-            Participant::<AuxInfoParticipant>::from_config(config, auxinfo_sid, ()).unwrap();
+        let mut participant = Participant::<AuxInfoParticipant>::from_config(
+            config,
+            auxinfo_sid,
+            auxinfo::Input::default(),
+        )
+        .unwrap();
 
         // Make a message with the wrong session ID
         let message = participant.initialize_message()?;
@@ -651,8 +979,12 @@ mod tests {
         // Set up a single valid participant
         let config = ParticipantConfig::random(QUORUM_SIZE, &mut rng);
         let auxinfo_sid = Identifier::random(&mut rng);
-        let mut participant =
-            Participant::<AuxInfoParticipant>::from_config(config, auxinfo_sid, ()).unwrap();
+        let mut participant = Participant::<AuxInfoParticipant>::from_config(
+            config,
+            auxinfo_sid,
+            auxinfo::Input::default(),
+        )
+        .unwrap();
 
         // Make a message with the wrong participant to field
         let message = participant.initialize_message()?;
@@ -685,8 +1017,12 @@ mod tests {
         // Set up a single valid participant
         let config = ParticipantConfig::random(QUORUM_SIZE, &mut rng);
         let auxinfo_sid = Identifier::random(&mut rng);
-        let mut participant =
-            Participant::<AuxInfoParticipant>::from_config(config, auxinfo_sid, ()).unwrap();
+        let mut participant = Participant::<AuxInfoParticipant>::from_config(
+            config,
+            auxinfo_sid,
+            auxinfo::Input::default(),
+        )
+        .unwrap();
 
         // Make a message with the wrong protocol type
         let message = participant.initialize_message()?;
@@ -720,8 +1056,12 @@ mod tests {
         // Set up a single valid participant
         let config = ParticipantConfig::random(QUORUM_SIZE, &mut rng);
         let auxinfo_sid = Identifier::random(&mut rng);
-        let mut participant =
-            Participant::<AuxInfoParticipant>::from_config(config, auxinfo_sid, ()).unwrap();
+        let mut participant = Participant::<AuxInfoParticipant>::from_config(
+            config,
+            auxinfo_sid,
+            auxinfo::Input::default(),
+        )
+        .unwrap();
 
         //message with the wrong sender participant
         let message = participant.initialize_message()?;
@@ -811,7 +1151,12 @@ mod tests {
             .clone()
             .into_iter()
             .map(|config| {
-                Participant::<AuxInfoParticipant>::from_config(config, auxinfo_sid, ()).unwrap()
+                Participant::<AuxInfoParticipant>::from_config(
+                    config,
+                    auxinfo_sid,
+                    auxinfo::Input::default(),
+                )
+                .unwrap()
             })
             .collect::<Vec<_>>();
 
@@ -857,7 +1202,12 @@ mod tests {
             .clone()
             .into_iter()
             .map(|config| {
-                Participant::<KeygenParticipant>::from_config(config, keygen_sid, ()).unwrap()
+                Participant::<KeygenParticipant>::from_config(
+                    config,
+                    keygen_sid,
+                    keygen::Input::default(),
+                )
+                .unwrap()
             })
             .collect::<Vec<_>>();
         let mut keygen_outputs: HashMap<
@@ -960,7 +1310,7 @@ mod tests {
         let mut sign_quorum = configs
             .into_iter()
             .map(|config| {
-                let record = presign_outputs.remove(&config.id()).unwrap();
+                let record = presign_outputs.remove(&config.id()).unwrap().remove(0);
                 let input = sign::Input::new(message, record, public_key_shares.clone());
                 Participant::<SignParticipant>::from_config(config, sign_sid, input)
             })
@@ -1016,7 +1366,12 @@ mod tests {
             .clone()
             .into_iter()
             .map(|config| {
-                Participant::<KeygenParticipant>::from_config(config, keygen_sid, ()).unwrap()
+                Participant::<KeygenParticipant>::from_config(
+                    config,
+                    keygen_sid,
+                    keygen::Input::default(),
+                )
+                .unwrap()
             })
             .collect::<Vec<_>>();
         let mut keygen_outputs: HashMap<
@@ -1069,7 +1424,12 @@ mod tests {
             .clone()
             .into_iter()
             .map(|config| {
-                Participant::<AuxInfoParticipant>::from_config(config, auxinfo_sid, ()).unwrap()
+                Participant::<AuxInfoParticipant>::from_config(
+                    config,
+                    auxinfo_sid,
+                    auxinfo::Input::default(),
+                )
+                .unwrap()
             })
             .collect::<Vec<_>>();
 
@@ -1184,4 +1544,398 @@ mod tests {
 
         Ok(())
     }
+
+    /// Simulate a crash and restart of a participant, by exporting its state
+    /// and resuming from the exported state.
+    fn restart<P: ProtocolParticipant>(
+        participant: &mut Participant<P>,
+        input: P::Input,
+    ) -> Result<()> {
+        let state = participant.export_state()?;
+        *participant = Participant::import_state(input, state)?;
+        Ok(())
+    }
+
+    #[test]
+    fn keygen_resumes_after_restarts() -> Result<()> {
+        let rng = &mut init_testing();
+        let QUORUM_SIZE = 3;
+        let configs = ParticipantConfig::random_quorum(QUORUM_SIZE, rng)?;
+        let input = keygen::Input::with_threshold(2)?;
+
+        let sid = Identifier::random(rng);
+        let mut quorum = configs
+            .into_iter()
+            .map(|config| Participant::<KeygenParticipant>::from_config(config, sid, input))
+            .collect::<Result<Vec<_>>>()?;
+        let mut inboxes: HashMap<ParticipantIdentifier, Vec<Message>> =
+            quorum.iter().map(|p| (p.id, vec![])).collect();
+        for participant in &quorum {
+            let inbox = inboxes.get_mut(&participant.id).unwrap();
+            inbox.push(participant.initialize_message()?);
+        }
+
+        // Restart a random participant after each message
+        let mut outputs = HashMap::new();
+        while outputs.len() < QUORUM_SIZE {
+            if let Some((pid, output)) = process_random_message(&mut quorum, &mut inboxes, rng)? {
+                assert!(outputs.insert(pid, output).is_none());
+            }
+            let participant = quorum.iter_mut().choose(rng).unwrap();
+            restart(participant, input)?;
+        }
+
+        assert!(inboxes_are_empty(&inboxes));
+        assert!(quorum
+            .iter()
+            .all(|p| *p.status() == Status::TerminatedSuccessfully));
+
+        // Everyone should agree on the key
+        let public_keys = outputs
+            .values()
+            .map(keygen::Output::public_key)
+            .collect::<Result<Vec<_>>>()?;
+        assert!(public_keys.windows(2).all(|keys| keys[0] == keys[1]));
+        Ok(())
+    }
+
+    #[test]
+    fn participants_report_who_stalled_the_protocol() -> Result<()> {
+        let rng = &mut init_testing();
+        let QUORUM_SIZE = 3;
+        let configs = ParticipantConfig::random_quorum(QUORUM_SIZE, rng)?;
+        let input = keygen::Input::with_threshold(2)?;
+
+        let sid = Identifier::random(rng);
+        let mut quorum = configs
+            .into_iter()
+            .map(|config| Participant::<KeygenParticipant>::from_config(config, sid, input))
+            .collect::<Result<Vec<_>>>()?;
+        assert!(quorum.iter().all(|p| p.waiting_on().is_empty()));
+
+        let mut inboxes: HashMap<ParticipantIdentifier, Vec<Message>> =
+            quorum.iter().map(|p| (p.id, vec![])).collect();
+        for participant in &quorum {
+            let inbox = inboxes.get_mut(&participant.id).unwrap();
+            inbox.push(participant.initialize_message()?);
+        }
+
+        // Run the protocol, but drop the staller's round three messages
+        let staller = quorum[0].id;
+        while !inboxes_are_empty(&inboxes) {
+            let participant = quorum.iter_mut().choose(rng).unwrap();
+            let inbox = inboxes.get_mut(&participant.id).unwrap();
+            if inbox.is_empty() {
+                continue;
+            }
+            let message = inbox.remove(rng.gen_range(0..inbox.len()));
+            let (_, messages) = participant.process_single_message(&message, rng)?;
+            let messages = messages
+                .into_iter()
+                .filter(|message| {
+                    message.from() != staller
+                        || message.message_type() != MessageType::Keygen(KeygenMessageType::R3Proof)
+                })
+                .collect::<Vec<_>>();
+            deliver_all(&messages, &mut inboxes);
+        }
+
+        // The staller finished; everyone else is waiting on it
+        for participant in &quorum {
+            if participant.id == staller {
+                assert_eq!(*participant.status(), Status::TerminatedSuccessfully);
+                assert!(participant.waiting_on().is_empty());
+            } else {
+                assert_eq!(participant.waiting_on(), vec![staller]);
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn abort_reasons_are_sanitized() {
+        let abort = AbortMessage {
+            reason: "bad\n\u{1b}[31mline".to_string(),
+            blamed: None,
+        };
+        assert_eq!(abort.sanitized_reason(), "bad\\n\\u{1b}[31mline");
+
+        let abort = AbortMessage {
+            reason: "x".repeat(10 * MAX_ABORT_REASON_LEN),
+            blamed: None,
+        };
+        assert_eq!(abort.sanitized_reason().len(), MAX_ABORT_REASON_LEN);
+    }
+
+    #[test]
+    fn aborting_stops_the_session_for_everyone() -> Result<()> {
+        let rng = &mut init_testing();
+        let QUORUM_SIZE = 3;
+        let configs = ParticipantConfig::random_quorum(QUORUM_SIZE, rng)?;
+        let input = keygen::Input::with_threshold(2)?;
+
+        let sid = Identifier::random(rng);
+        let mut quorum = configs
+            .into_iter()
+            .map(|config| Participant::<KeygenParticipant>::from_config(config, sid, input))
+            .collect::<Result<Vec<_>>>()?;
+
+        // Everyone starts the protocol, and sends out their first round messages
+        let mut first_round = Vec::new();
+        for participant in quorum.iter_mut() {
+            let ready = participant.initialize_message()?;
+            let (_, messages) = participant.process_single_message(&ready, rng)?;
+            first_round.extend(messages);
+        }
+
+        // One participant gives up, blaming another
+        let blamed = quorum[2].id;
+        let aborts = quorum[0].abort("timed out", Some(blamed))?;
+        assert_eq!(aborts.len(), QUORUM_SIZE - 1);
+        assert_eq!(*quorum[0].status(), Status::Aborted);
+        assert!(quorum[0].waiting_on().is_empty());
+
+        for abort in &aborts {
+            let participant = quorum.iter_mut().find(|p| p.id == abort.to()).unwrap();
+            assert_eq!(
+                participant.process_single_message(abort, rng).unwrap_err(),
+                InternalError::SessionAborted {
+                    from: abort.from(),
+                    blamed: Some(blamed),
+                    reason: "timed out".to_string(),
+                }
+            );
+            assert_eq!(*participant.status(), Status::Aborted);
+        }
+
+        // Nobody processes messages for the session anymore
+        for message in &first_round {
+            let participant = quorum.iter_mut().find(|p| p.id == message.to()).unwrap();
+            assert_eq!(
+                participant
+                    .process_single_message(message, rng)
+                    .unwrap_err(),
+                InternalError::CallingApplicationMistake(CallerError::ProtocolAlreadyTerminated)
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn keygen_outputs_are_persisted_on_completion() -> Result<()> {
+        let rng = &mut init_testing();
+        let QUORUM_SIZE = 3;
+        let configs = ParticipantConfig::random_quorum(QUORUM_SIZE, rng)?;
+        let input = keygen::Input::with_threshold(2)?;
+
+        let sid = Identifier::random(rng);
+        let mut quorum = configs
+            .into_iter()
+            .map(|config| Participant::<KeygenParticipant>::from_config(config, sid, input))
+            .collect::<Result<Vec<_>>>()?;
+        let mut stores: HashMap<ParticipantIdentifier, MemorySecretStore> = quorum
+            .iter()
+            .map(|p| (p.id, MemorySecretStore::new()))
+            .collect();
+        let mut inboxes: HashMap<ParticipantIdentifier, Vec<Message>> =
+            quorum.iter().map(|p| (p.id, vec![])).collect();
+        for participant in &quorum {
+            let inbox = inboxes.get_mut(&participant.id).unwrap();
+            inbox.push(participant.initialize_message()?);
+        }
+
+        let mut outputs = HashMap::new();
+        while !inboxes_are_empty(&inboxes) {
+            let participant = quorum.iter_mut().choose(rng).unwrap();
+            let inbox = inboxes.get_mut(&participant.id).unwrap();
+            if inbox.is_empty() {
+                continue;
+            }
+            let message = inbox.remove(rng.gen_range(0..inbox.len()));
+            let store = stores.get_mut(&participant.id).unwrap();
+            let (output, messages) =
+                participant.process_single_message_and_store(&message, rng, store, "wallet")?;
+            if let Some(output) = output {
+                assert!(outputs.insert(participant.id, output).is_none());
+            }
+            deliver_all(&messages, &mut inboxes);
+        }
+        assert_eq!(outputs.len(), QUORUM_SIZE);
+
+        // Each participant's output was persisted to its store
+        for (pid, output) in outputs {
+            let stored: keygen::Output = stores[&pid].get_record("wallet")?.unwrap();
+            assert_eq!(stored.private_key_share(), output.private_key_share());
+            assert_eq!(stored.public_key()?, output.public_key()?);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn outputs_are_kept_when_they_cannot_be_persisted() -> Result<()> {
+        /// A store whose writes always fail.
+        struct BrokenStore;
+        impl SecretStore for BrokenStore {
+            fn put(&mut self, _: &str, _: RecordType, _: &[u8]) -> Result<()> {
+                Err(CallerError::SecretStoreFailed)?
+            }
+            fn get(&self, _: &str, _: RecordType) -> Result<Option<Vec<u8>>> {
+                Ok(None)
+            }
+            fn delete(&mut self, _: &str, _: RecordType) -> Result<()> {
+                Ok(())
+            }
+        }
+
+        let rng = &mut init_testing();
+        let QUORUM_SIZE = 3;
+        let configs = ParticipantConfig::random_quorum(QUORUM_SIZE, rng)?;
+        let input = keygen::Input::with_threshold(2)?;
+
+        let sid = Identifier::random(rng);
+        let mut quorum = configs
+            .into_iter()
+            .map(|config| Participant::<KeygenParticipant>::from_config(config, sid, input))
+            .collect::<Result<Vec<_>>>()?;
+        let mut inboxes: HashMap<ParticipantIdentifier, Vec<Message>> =
+            quorum.iter().map(|p| (p.id, vec![])).collect();
+        for participant in &quorum {
+            let inbox = inboxes.get_mut(&participant.id).unwrap();
+            inbox.push(participant.initialize_message()?);
+        }
+
+        let mut outputs = HashMap::new();
+        while !inboxes_are_empty(&inboxes) {
+            let participant = quorum.iter_mut().choose(rng).unwrap();
+            let inbox = inboxes.get_mut(&participant.id).unwrap();
+            if inbox.is_empty() {
+                continue;
+            }
+            let message = inbox.remove(rng.gen_range(0..inbox.len()));
+            let messages = match participant.process_single_message_and_store(
+                &message,
+                rng,
+                &mut BrokenStore,
+                "wallet",
+            ) {
+                Ok((output, messages)) => {
+                    // Every output fails to persist, so none is returned
+                    assert!(output.is_none());
+                    messages
+                }
+                Err(err) => {
+                    assert_eq!(
+                        err,
+                        InternalError::CallingApplicationMistake(CallerError::SecretStoreFailed)
+                    );
+                    // The output and messages are still available
+                    let (output, messages) = participant.take_unstored_output().unwrap();
+                    assert!(participant.take_unstored_output().is_none());
+                    assert!(outputs.insert(participant.id, output).is_none());
+                    messages
+                }
+            };
+            deliver_all(&messages, &mut inboxes);
+        }
+        assert_eq!(outputs.len(), QUORUM_SIZE);
+        Ok(())
+    }
+
+    #[test]
+    fn interactive_signing_resumes_after_restarts() -> Result<()> {
+        let rng = &mut init_testing();
+        let QUORUM_SIZE = 3;
+        let configs = ParticipantConfig::random_quorum(QUORUM_SIZE, rng)?;
+        let keygen_outputs = keygen::Output::simulate_set(&configs, rng);
+        let auxinfo_outputs = auxinfo::Output::simulate_set(&configs, rng);
+        let public_key = keygen_outputs[0].public_key()?;
+        let message = b"Testing signing with restarts";
+
+        // Inputs aren't `Clone`, so rebuild them from the outputs as needed
+        let input_for = |pid: ParticipantIdentifier| {
+            let index = configs.iter().position(|c| c.id() == pid).unwrap();
+            sign::InteractiveInput::new(
+                message,
+                keygen_outputs[index].clone(),
+                auxinfo_outputs[index].clone(),
+            )
+        };
+
+        let sid = Identifier::random(rng);
+        let mut quorum = configs
+            .iter()
+            .map(|config| {
+                Participant::<InteractiveSignParticipant>::from_config(
+                    config.clone(),
+                    sid,
+                    input_for(config.id())?,
+                )
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let mut inboxes: HashMap<ParticipantIdentifier, Vec<Message>> =
+            quorum.iter().map(|p| (p.id, vec![])).collect();
+        for participant in &quorum {
+            let inbox = inboxes.get_mut(&participant.id).unwrap();
+            inbox.push(participant.initialize_message()?);
+        }
+
+        // Restart a random participant after each message, so that restarts
+        // happen during both presigning and signing
+        let mut signatures = Vec::new();
+        while signatures.len() < QUORUM_SIZE {
+            if let Some((_, signature)) = process_random_message(&mut quorum, &mut inboxes, rng)? {
+                signatures.push(signature);
+            }
+            let participant = quorum.iter_mut().choose(rng).unwrap();
+            let input = input_for(participant.id())?;
+            restart(participant, input)?;
+        }
+
+        assert!(inboxes_are_empty(&inboxes));
+        assert!(signatures.windows(2).all(|sig| sig[0] == sig[1]));
+        assert!(public_key.verify(message, signatures[0].as_ref()).is_ok());
+        Ok(())
+    }
+
+    #[test]
+    fn imported_state_must_be_valid() -> Result<()> {
+        let rng = &mut init_testing();
+        let config = ParticipantConfig::random(3, rng);
+        let sid = Identifier::random(rng);
+        let participant =
+            Participant::<KeygenParticipant>::from_config(config, sid, keygen::Input::default())?;
+        let state = participant.export_state()?;
+
+        // The state can be imported...
+        let imported = Participant::<KeygenParticipant>::import_state(
+            keygen::Input::default(),
+            state.clone(),
+        )?;
+        assert_eq!(imported.id(), participant.id());
+        assert_eq!(imported.sid(), participant.sid());
+        assert_eq!(imported.status(), participant.status());
+
+        // ...but not by a participant for a different protocol...
+        assert!(Participant::<AuxInfoParticipant>::import_state(
+            auxinfo::Input::default(),
+            state.clone()
+        )
+        .is_err());
+
+        // ...and not if it's been modified or truncated
+        let mut wrong_version = state.clone();
+        wrong_version[STATE_TAG.len()] += 1;
+        assert!(Participant::<KeygenParticipant>::import_state(
+            keygen::Input::default(),
+            wrong_version
+        )
+        .is_err());
+        let truncated = state[..state.len() - 1].to_vec();
+        assert!(Participant::<KeygenParticipant>::import_state(
+            keygen::Input::default(),
+            truncated
+        )
+        .is_err());
+        Ok(())
+    }
 }
@@ -0,0 +1,172 @@
+// Copyright (c) 2022-2023 Bolt Labs Holdings, Inc
+//
+// This source code is licensed under both the MIT license found in the
+// LICENSE-MIT file in the root directory of this source tree and the Apache
+// License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+// of this source tree.
+
+use std::collections::HashSet;
+
+use tracing::error;
+
+use crate::{
+    auxinfo::{self, AuxInfoPrivate, AuxInfoPublic},
+    errors::{CallerError, InternalError, Result},
+    keygen::{self, KeySharePrivate, KeySharePublic},
+    ParticipantIdentifier,
+};
+
+/// Input needed for a
+/// [`RefreshParticipant`](crate::refresh::RefreshParticipant) to run.
+#[derive(Debug, Clone)]
+pub struct Input {
+    /// The key share material that will be refreshed.
+    keygen_output: keygen::Output,
+    /// The auxiliary info used to encrypt the private updates.
+    auxinfo_output: auxinfo::Output,
+}
+
+impl Input {
+    /// Creates a new [`Input`] from the outputs of the
+    /// [`auxinfo`](crate::auxinfo::AuxInfoParticipant) and
+    /// [`keygen`](crate::keygen::KeygenParticipant) protocols.
+    ///
+    /// The key shares may be full-threshold or threshold key shares; in
+    /// either case, every key share holder must have auxinfo.
+    pub fn new(auxinfo_output: auxinfo::Output, keygen_output: keygen::Output) -> Result<Self> {
+        let aux_pids = auxinfo_output
+            .public_auxinfo()
+            .iter()
+            .map(AuxInfoPublic::participant)
+            .collect::<HashSet<_>>();
+        let key_pids = keygen_output
+            .public_key_shares()
+            .iter()
+            .map(KeySharePublic::participant)
+            .collect::<HashSet<_>>();
+
+        // The same set of participants must have produced the key shares and aux
+        // infos, and there shouldn't be duplicates.
+        if aux_pids != key_pids
+            || key_pids.len() != keygen_output.public_key_shares().len()
+            || aux_pids.len() != auxinfo_output.public_auxinfo().len()
+        {
+            error!("Public auxinfo and keyshare inputs to refresh weren't from the same set of parties.");
+            Err(CallerError::BadInput)?
+        }
+
+        // The participant IDs for the private components of each output should match
+        if keygen_output.private_pid() != auxinfo_output.private_pid() {
+            error!("Expected private keygen and auxinfo outputs to correspond to the same participant, but they didn't");
+            Err(CallerError::BadInput)?
+        }
+
+        Ok(Self {
+            auxinfo_output,
+            keygen_output,
+        })
+    }
+
+    /// Get the set of participants that contributed to the input.
+    ///
+    /// By construction, this must be the same for the auxinfo and key share
+    /// lists.
+    pub(crate) fn participants(&self) -> Vec<ParticipantIdentifier> {
+        self.keygen_output
+            .public_key_shares()
+            .iter()
+            .map(KeySharePublic::participant)
+            .collect()
+    }
+
+    /// The number of participants required to sign, if the key shares are
+    /// threshold key shares.
+    pub(crate) fn threshold(&self) -> Option<usize> {
+        self.keygen_output
+            .is_threshold()
+            .then(|| self.keygen_output.threshold())
+    }
+
+    pub(crate) fn keygen_output(&self) -> &keygen::Output {
+        &self.keygen_output
+    }
+
+    pub(crate) fn private_key_share(&self) -> &KeySharePrivate {
+        self.keygen_output.private_key_share()
+    }
+
+    pub(crate) fn private_auxinfo(&self) -> &AuxInfoPrivate {
+        self.auxinfo_output.private_auxinfo()
+    }
+
+    /// Returns the [`AuxInfoPublic`] associated with the given
+    /// [`ParticipantIdentifier`].
+    pub(crate) fn find_auxinfo_public(&self, pid: ParticipantIdentifier) -> Result<&AuxInfoPublic> {
+        self.auxinfo_output.find_public(pid)
+            .ok_or_else(|| {
+                error!("Refresh input doesn't contain a public auxinfo for {}, even though we checked for it at construction.", pid);
+                InternalError::InternalInvariantFailed
+            })
+    }
+
+    /// Returns the [`KeySharePublic`] associated with the given
+    /// [`ParticipantIdentifier`].
+    pub(crate) fn find_keyshare_public(
+        &self,
+        pid: ParticipantIdentifier,
+    ) -> Result<&KeySharePublic> {
+        self.keygen_output
+            .public_key_shares()
+            .iter()
+            .find(|item| item.participant() == pid)
+            .ok_or_else(|| {
+                error!("Refresh input doesn't contain a public keyshare for {}, even though we checked for it at construction.", pid);
+                InternalError::InternalInvariantFailed
+            })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Input;
+    use crate::{
+        auxinfo,
+        errors::{CallerError, InternalError},
+        keygen,
+        utils::testing::init_testing,
+        ParticipantIdentifier,
+    };
+
+    #[test]
+    fn inputs_must_have_same_participant_sets() {
+        let rng = &mut init_testing();
+        let pids = std::iter::repeat_with(|| ParticipantIdentifier::random(rng))
+            .take(5)
+            .collect::<Vec<_>>();
+        let keygen_output = keygen::Output::simulate(&pids, rng);
+        let auxinfo_output = auxinfo::Output::simulate(&pids, rng);
+
+        // Same participants works
+        assert!(Input::new(auxinfo_output.clone(), keygen_output).is_ok());
+
+        // Key shares held by only a subset of the auxinfo participants fails
+        let short_keygen = keygen::Output::simulate(&pids[1..], rng);
+        let result = Input::new(auxinfo_output, short_keygen);
+        assert_eq!(
+            result.unwrap_err(),
+            InternalError::CallingApplicationMistake(CallerError::BadInput)
+        );
+
+        // Entirely different participants fails
+        let other_pids = std::iter::repeat_with(|| ParticipantIdentifier::random(rng))
+            .take(5)
+            .collect::<Vec<_>>();
+        let keygen_output = keygen::Output::simulate(&other_pids, rng);
+        let auxinfo_output = auxinfo::Output::simulate(&pids, rng);
+        let result = Input::new(auxinfo_output, keygen_output);
+        assert_eq!(
+            result.unwrap_err(),
+            InternalError::CallingApplicationMistake(CallerError::BadInput)
+        );
+    }
+}
@@ -0,0 +1,998 @@
+//! Types and functions related to the key refresh sub-protocol Participant.
+
+// Copyright (c) 2022-2023 Bolt Labs Holdings, Inc
+//
+// This source code is licensed under both the MIT license found in the
+// LICENSE-MIT file in the root directory of this source tree and the Apache
+// License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+// of this source tree.
+
+use std::collections::HashSet;
+
+use crate::{
+    broadcast::participant::{BroadcastOutput, BroadcastParticipant, BroadcastTag},
+    errors::{CallerError, InternalError, Result},
+    keygen::{self, shamir::Polynomial, KeySharePrivate, KeySharePublic},
+    local_storage::LocalStorage,
+    messages::{Message, MessageType, RefreshMessageType},
+    paillier::Ciphertext,
+    participant::{
+        Broadcast, InnerProtocolParticipant, ParticipantState, ProcessOutcome, ProtocolParticipant,
+        Status,
+    },
+    protocol::{ParticipantIdentifier, ProtocolType, SharedContext},
+    refresh::{
+        input::Input,
+        refresh_commit::{RefreshCommit, RefreshDecommit},
+    },
+    run_only_once,
+    utils::k256_order,
+    zkp::pisch::{CommonInput, PiSchPrecommit, PiSchProof, ProverSecret},
+    Identifier,
+};
+
+use libpaillier::unknown_order::BigNumber;
+use merlin::Transcript;
+use rand::{CryptoRng, RngCore};
+use serde::{Deserialize, Serialize};
+use tracing::{error, info, instrument};
+
+mod storage {
+    use super::*;
+    use crate::local_storage::TypeTag;
+
+    pub(super) struct Commit;
+    impl TypeTag for Commit {
+        type Value = RefreshCommit;
+        const NAME: &'static str = "refresh.Commit";
+    }
+    pub(super) struct Decommit;
+    impl TypeTag for Decommit {
+        type Value = RefreshDecommit;
+        const NAME: &'static str = "refresh.Decommit";
+    }
+    pub(super) struct SchnorrPrecoms;
+    impl TypeTag for SchnorrPrecoms {
+        type Value = Vec<PiSchPrecommit>;
+        const NAME: &'static str = "refresh.SchnorrPrecoms";
+    }
+    pub(super) struct GlobalRid;
+    impl TypeTag for GlobalRid {
+        const NAME: &'static str = "refresh.GlobalRid";
+        type Value = [u8; 32];
+    }
+    pub(super) struct PrivateUpdate;
+    impl TypeTag for PrivateUpdate {
+        type Value = KeySharePrivate;
+        const NAME: &'static str = "refresh.PrivateUpdate";
+    }
+    pub(super) struct ReceivedUpdate;
+    impl TypeTag for ReceivedUpdate {
+        type Value = KeySharePrivate;
+        const NAME: &'static str = "refresh.ReceivedUpdate";
+    }
+}
+
+/// The private update sent from one participant to another in round three,
+/// encrypted under the recipient's Paillier key, along with proofs of
+/// knowledge of the discrete log of each of the sender's public updates.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct EncryptedUpdate {
+    /// Encryption of the recipient's private update (`C_i^j` in the paper).
+    ciphertext: Ciphertext,
+    /// Schnorr proofs for each public update, in the same order as the
+    /// sender's decommitment (`ψ_i^j` in the paper).
+    proofs: Vec<PiSchProof>,
+}
+
+impl EncryptedUpdate {
+    fn from_message(message: &Message) -> Result<Self> {
+        message.check_type(MessageType::Refresh(RefreshMessageType::R3EncryptedUpdate))?;
+        let encrypted_update: EncryptedUpdate = deserialize!(&message.unverified_bytes)?;
+        for proof in &encrypted_update.proofs {
+            proof
+                .check_ranges()
+                .map_err(|_| InternalError::ProtocolError(Some(message.from())))?;
+        }
+        Ok(encrypted_update)
+    }
+}
+
+/// A [`ProtocolParticipant`] that runs the key refresh protocol[^cite].
+///
+/// # Protocol input
+/// The protocol takes an [`Input`] which contains:
+/// - The output of the [`keygen`](crate::keygen::KeygenParticipant)
+///   protocol, which holds the key shares to refresh; and
+/// - The output of the [`auxinfo`](crate::auxinfo::AuxInfoParticipant)
+///   protocol, whose Paillier keys are used to encrypt the updates.
+///
+/// Every participant holding a key share must take part in the refresh, even
+/// for threshold key shares.
+///
+/// # Protocol output
+/// Upon successful completion, the participant produces a new
+/// [`keygen::Output`] with re-randomized key shares for the same public key.
+/// The old key shares are still valid shares of the same private key, so
+/// they must be deleted once every participant has the new output.
+///
+/// # 🔒 Storage requirements
+/// The [private key share](KeySharePrivate) in the output requires secure
+/// persistent storage, and the private key share in the input must be
+/// securely deleted once the refresh has succeeded everywhere.
+///
+/// # High-level protocol description
+/// The key refresh protocol runs in four rounds:
+/// - In the first round, each participant samples a random update for every
+///   participant's key share, such that the updates sum to zero. It broadcasts
+///   a commitment to (1) the public form of each update and (2) a
+///   "precommitment" to a Schnorr proof for each of them.
+/// - Once all commitment broadcasts have been received, the second round
+///   proceeds by each participant opening its commitment to all other
+///   participants.
+/// - In the third round, each participant (1) checks the validity of all the
+///   commitments, including that the public updates sum to the identity, and
+///   (2) sends each other participant its private update, encrypted under the
+///   recipient's Paillier key, along with Schnorr proofs that it knows the
+///   private value corresponding to every public update.
+/// - Finally, in the last round each participant decrypts its updates and
+///   checks them against the public updates, and checks the validity of all
+///   the Schnorr proofs. If that succeeds, each participant adds the updates
+///   to its own private key share and to every participant's public key share.
+///
+/// For threshold key shares, the updates are instead evaluations of a random
+/// polynomial with a zero constant term. Each participant also commits to
+/// Feldman commitments to the polynomial's coefficients, and the updated
+/// public key shares are computed from the sum of the old and new
+/// commitments.
+///
+/// [^cite]: Ran Canetti, Rosario Gennaro, Steven Goldfeder, Nikolaos
+/// Makriyannis, and Udi Peled. UC Non-Interactive, Proactive, Threshold ECDSA
+/// with Identifiable Aborts. [EPrint archive,
+/// 2021](https://eprint.iacr.org/2021/060.pdf). Figure 6.
+#[derive(Debug)]
+pub struct RefreshParticipant {
+    /// The current session identifier
+    sid: Identifier,
+    /// A unique identifier for this participant.
+    id: ParticipantIdentifier,
+    /// A list of all other participant identifiers participating in the
+    /// protocol
+    other_participant_ids: Vec<ParticipantIdentifier>,
+    /// Input to the protocol
+    input: Input,
+    /// Local storage for this participant to store secrets
+    local_storage: LocalStorage,
+    /// Broadcast subprotocol handler
+    broadcast_participant: BroadcastParticipant,
+    /// Status of the protocol execution.
+    status: Status,
+}
+
+impl ProtocolParticipant for RefreshParticipant {
+    type Input = Input;
+    type Output = keygen::Output;
+
+    fn new(
+        sid: Identifier,
+        id: ParticipantIdentifier,
+        other_participant_ids: Vec<ParticipantIdentifier>,
+        input: Self::Input,
+    ) -> Result<Self> {
+        // Every key share holder must participate, and nobody else.
+        let input_set = input.participants().into_iter().collect::<HashSet<_>>();
+        let participants = std::iter::once(id)
+            .chain(other_participant_ids.iter().copied())
+            .collect::<HashSet<_>>();
+        if participants.len() != other_participant_ids.len() + 1 || participants != input_set {
+            error!("The provided inputs are not from the specified set of participants");
+            Err(CallerError::BadInput)?
+        }
+
+        if input.keygen_output().private_pid()? != id {
+            error!("The provided private key share doesn't belong to this participant");
+            Err(CallerError::BadInput)?
+        }
+
+        Ok(Self {
+            sid,
+            id,
+            other_participant_ids: other_participant_ids.clone(),
+            input,
+            local_storage: Default::default(),
+            broadcast_participant: BroadcastParticipant::new(sid, id, other_participant_ids, ())?,
+            status: Status::NotReady,
+        })
+    }
+
+    fn ready_type() -> MessageType {
+        MessageType::Refresh(RefreshMessageType::Ready)
+    }
+
+    fn protocol_type() -> ProtocolType {
+        ProtocolType::Refresh
+    }
+
+    fn id(&self) -> ParticipantIdentifier {
+        self.id
+    }
+
+    fn other_ids(&self) -> &[ParticipantIdentifier] {
+        &self.other_participant_ids
+    }
+
+    fn sid(&self) -> Identifier {
+        self.sid
+    }
+
+    fn export_state(&self) -> Result<Vec<u8>> {
+        ParticipantState::export(
+            &self.status,
+            &self.local_storage,
+            Some(&self.broadcast_participant),
+        )
+    }
+
+    fn import_state(&mut self, state: &[u8]) -> Result<()> {
+        ParticipantState::import(
+            state,
+            &mut self.status,
+            &mut self.local_storage,
+            Some(&mut self.broadcast_participant),
+        )
+    }
+
+    #[cfg_attr(feature = "flame_it", flame("refresh"))]
+    #[instrument(skip_all)]
+    fn process_message<R: RngCore + CryptoRng>(
+        &mut self,
+        rng: &mut R,
+        message: &Message,
+    ) -> Result<ProcessOutcome<Self::Output>> {
+        info!(
+            "REFRESH: Player {}: received {:?} from {}",
+            self.id(),
+            message.message_type(),
+            message.from()
+        );
+
+        if *self.status() == Status::TerminatedSuccessfully {
+            Err(CallerError::ProtocolAlreadyTerminated)?;
+        }
+
+        if !self.status().is_ready() && message.message_type() != Self::ready_type() {
+            self.stash_message(message)?;
+            return Ok(ProcessOutcome::Incomplete);
+        }
+
+        match message.message_type() {
+            MessageType::Refresh(RefreshMessageType::Ready) => self.handle_ready_msg(rng, message),
+            MessageType::Refresh(RefreshMessageType::R1CommitHash) => {
+                let broadcast_outcome = self.handle_broadcast(rng, message)?;
+
+                // Handle the broadcasted message if all parties have agreed on it
+                broadcast_outcome.convert(self, Self::handle_round_one_msg, rng)
+            }
+            MessageType::Refresh(RefreshMessageType::R2Decommit) => {
+                self.handle_round_two_msg(rng, message)
+            }
+            MessageType::Refresh(RefreshMessageType::R3EncryptedUpdate) => {
+                self.handle_round_three_msg(rng, message)
+            }
+            message_type => {
+                error!(
+                    "Incorrect MessageType given to RefreshParticipant. Got: {:?}",
+                    message_type
+                );
+                Err(InternalError::InternalInvariantFailed)
+            }
+        }
+    }
+
+    fn status(&self) -> &Status {
+        &self.status
+    }
+
+    fn waiting_on(&self) -> Vec<ParticipantIdentifier> {
+        if self.status != Status::Initialized {
+            return Vec::new();
+        }
+        let others = &self.other_participant_ids;
+
+        // Round one: the broadcast commitments
+        let missing_commits = self.local_storage.missing_ids::<storage::Commit>(others);
+        if !missing_commits.is_empty() {
+            return self
+                .broadcast_participant
+                .waiting_on_broadcasts(BroadcastTag::RefreshR1CommitHash, &missing_commits);
+        }
+
+        // Round two: the decommitments
+        let missing_decommits = self.local_storage.missing_ids::<storage::Decommit>(others);
+        if !missing_decommits.is_empty() {
+            return missing_decommits;
+        }
+
+        // Round three: the encrypted updates
+        self.local_storage
+            .missing_ids::<storage::ReceivedUpdate>(others)
+    }
+
+    fn abort(&mut self) {
+        self.local_storage = LocalStorage::default();
+        self.broadcast_participant.abort();
+        self.status = Status::Aborted;
+    }
+}
+
+impl InnerProtocolParticipant for RefreshParticipant {
+    type Context = SharedContext;
+
+    fn retrieve_context(&self) -> <Self as InnerProtocolParticipant>::Context {
+        SharedContext::collect(self)
+    }
+
+    fn local_storage(&self) -> &LocalStorage {
+        &self.local_storage
+    }
+
+    fn local_storage_mut(&mut self) -> &mut LocalStorage {
+        &mut self.local_storage
+    }
+
+    fn status_mut(&mut self) -> &mut Status {
+        &mut self.status
+    }
+}
+
+impl Broadcast for RefreshParticipant {
+    fn broadcast_participant(&mut self) -> &mut BroadcastParticipant {
+        &mut self.broadcast_participant
+    }
+}
+
+impl RefreshParticipant {
+    /// Handle "Ready" messages from the protocol participants.
+    ///
+    /// Once "Ready" messages have been received from all participants, this
+    /// method will trigger this participant to generate its round one message.
+    #[cfg_attr(feature = "flame_it", flame("refresh"))]
+    #[instrument(skip_all, err(Debug))]
+    fn handle_ready_msg<R: RngCore + CryptoRng>(
+        &mut self,
+        rng: &mut R,
+        message: &Message,
+    ) -> Result<ProcessOutcome<<Self as ProtocolParticipant>::Output>> {
+        info!("Handling ready refresh message.");
+
+        let ready_outcome = self.process_ready_message(rng, message)?;
+        let round_one_messages = run_only_once!(self.gen_round_one_msgs(rng, message.id()))?;
+        // extend the output with r1 messages (if they hadn't already been generated)
+        Ok(ready_outcome.with_messages(round_one_messages))
+    }
+
+    /// Generate the protocol's round one message.
+    ///
+    /// The outcome is a broadcast message containing a commitment to: (1) the
+    /// public update for every participant's key share and (2) a
+    /// "pre-commitment" to a Schnorr proof for each update.
+    #[cfg_attr(feature = "flame_it", flame("refresh"))]
+    #[instrument(skip_all, err(Debug))]
+    fn gen_round_one_msgs<R: RngCore + CryptoRng>(
+        &mut self,
+        rng: &mut R,
+        sid: Identifier,
+    ) -> Result<Vec<Message>> {
+        info!("Generating round one refresh messages.");
+
+        let participants = self.input.participants();
+        let q = k256_order();
+
+        // Sample updates that don't change the shared key. These correspond to
+        // `x_i^j` in the paper.
+        let (private_updates, coefficient_commitments) = match self.input.threshold() {
+            Some(threshold) => {
+                let polynomial = Polynomial::random(&BigNumber::zero(), threshold, rng)?;
+                let updates = participants
+                    .iter()
+                    .map(|pid| {
+                        KeySharePrivate::from_bignumber(
+                            &polynomial.evaluate(&pid.evaluation_point()),
+                        )
+                    })
+                    .collect::<Vec<_>>();
+                (updates, Some(polynomial.commit()?))
+            }
+            None => {
+                let mut updates = std::iter::repeat_with(|| KeySharePrivate::random(rng))
+                    .take(participants.len() - 1)
+                    .collect::<Vec<_>>();
+                let sum = updates.iter().fold(BigNumber::zero(), |sum, update| {
+                    sum.modadd(update.as_ref(), &q)
+                });
+                updates.push(KeySharePrivate::from_bignumber(&sum.modneg(&q)));
+                (updates, None)
+            }
+        };
+
+        // These correspond to `X_i^j` in the paper.
+        let public_updates = participants
+            .iter()
+            .zip(&private_updates)
+            .map(|(&pid, update)| Ok(KeySharePublic::new(pid, update.public_share()?)))
+            .collect::<Result<Vec<_>>>()?;
+
+        // These correspond to `A_i^j` in the paper.
+        let sch_precoms = std::iter::repeat_with(|| PiSchProof::precommit(rng))
+            .take(participants.len())
+            .collect::<Result<Vec<_>>>()?;
+        let decom = RefreshDecommit::new(
+            rng,
+            &sid,
+            &self.id,
+            public_updates,
+            &sch_precoms,
+            coefficient_commitments,
+        );
+        // This corresponds to `V_i` in the paper.
+        let com = decom.commit()?;
+        let com_bytes = serialize!(&com)?;
+
+        self.local_storage.store::<storage::Commit>(self.id, com);
+        self.local_storage
+            .store::<storage::Decommit>(self.id, decom);
+        self.local_storage
+            .store::<storage::SchnorrPrecoms>(self.id, sch_precoms);
+        for (pid, update) in participants.into_iter().zip(private_updates) {
+            self.local_storage
+                .store::<storage::PrivateUpdate>(pid, update);
+        }
+
+        let messages = self.broadcast(
+            rng,
+            MessageType::Refresh(RefreshMessageType::R1CommitHash),
+            com_bytes,
+            sid,
+            BroadcastTag::RefreshR1CommitHash,
+        )?;
+        Ok(messages)
+    }
+
+    /// Handle round one messages from the protocol participants.
+    ///
+    /// In round one, each participant broadcasts its commitment to its public
+    /// updates and "precommitments" to Schnorr proofs. Once all such
+    /// commitments have been received, this participant will send an opening of
+    /// its own commitment to all other parties.
+    #[cfg_attr(feature = "flame_it", flame("refresh"))]
+    #[instrument(skip_all, err(Debug))]
+    fn handle_round_one_msg<R: RngCore + CryptoRng>(
+        &mut self,
+        rng: &mut R,
+        broadcast_message: BroadcastOutput,
+    ) -> Result<ProcessOutcome<<Self as ProtocolParticipant>::Output>> {
+        info!("Handling round one refresh message.");
+
+        let message = broadcast_message.into_message(BroadcastTag::RefreshR1CommitHash)?;
+        let refresh_commit = RefreshCommit::from_message(&message)?;
+        self.local_storage
+            .store::<storage::Commit>(message.from(), refresh_commit);
+
+        // Check if we've received all the commits, which signals an end to
+        // round one.
+        //
+        // Note: As in keygen, this does _not_ check `self.all_participants` on
+        // purpose, since we may not have generated our own round one message
+        // yet.
+        let r1_done = self
+            .local_storage
+            .contains_for_all_ids::<storage::Commit>(&self.other_participant_ids);
+
+        if r1_done {
+            // Finish round 1 by generating messages for round 2
+            let round_one_messages = run_only_once!(self.gen_round_two_msgs(rng, message.id()))?;
+
+            // Process any round 2 messages we may have received early
+            let round_two_outcomes = self
+                .fetch_messages(MessageType::Refresh(RefreshMessageType::R2Decommit))?
+                .iter()
+                .map(|msg| self.handle_round_two_msg(rng, msg))
+                .collect::<Result<Vec<_>>>()?;
+
+            ProcessOutcome::collect_with_messages(round_two_outcomes, round_one_messages)
+        } else {
+            // Otherwise, wait for more round 1 messages
+            Ok(ProcessOutcome::Incomplete)
+        }
+    }
+
+    /// Generate the protocol's round two messages.
+    ///
+    /// The outcome is an opening to the commitment generated in round one.
+    #[cfg_attr(feature = "flame_it", flame("refresh"))]
+    #[instrument(skip_all, err(Debug))]
+    fn gen_round_two_msgs<R: RngCore + CryptoRng>(
+        &mut self,
+        rng: &mut R,
+        sid: Identifier,
+    ) -> Result<Vec<Message>> {
+        info!("Generating round two refresh messages.");
+
+        let mut messages = vec![];
+        // Check that we've generated our round one data before trying to
+        // retrieve it; see the equivalent check in keygen.
+        if !self.local_storage.contains::<storage::Decommit>(self.id) {
+            let more_messages = run_only_once!(self.gen_round_one_msgs(rng, sid))?;
+            messages.extend_from_slice(&more_messages);
+        }
+
+        let decom = self.local_storage.retrieve::<storage::Decommit>(self.id)?;
+        let more_messages = self.message_for_other_participants(
+            MessageType::Refresh(RefreshMessageType::R2Decommit),
+            decom,
+        )?;
+        messages.extend_from_slice(&more_messages);
+        Ok(messages)
+    }
+
+    /// Handle the protocol's round two messages.
+    ///
+    /// Here we check that the decommitments from each participant are valid,
+    /// and that their updates don't change the shared key.
+    #[cfg_attr(feature = "flame_it", flame("refresh"))]
+    #[instrument(skip_all, err(Debug))]
+    fn handle_round_two_msg<R: RngCore + CryptoRng>(
+        &mut self,
+        rng: &mut R,
+        message: &Message,
+    ) -> Result<ProcessOutcome<<Self as ProtocolParticipant>::Output>> {
+        info!("Handling round two refresh message.");
+        // We must receive all commitments in round 1 before we start processing
+        // decommits in round 2.
+        let r1_done = self
+            .local_storage
+            .contains_for_all_ids::<storage::Commit>(&self.all_participants());
+        if !r1_done {
+            // Store any early round 2 messages
+            self.stash_message(message)?;
+            return Ok(ProcessOutcome::Incomplete);
+        }
+        // Check that the decommitment contained in the message is valid for the
+        // previously received commitment.
+        let decom = RefreshDecommit::from_message(message)?;
+        let com = self
+            .local_storage
+            .retrieve::<storage::Commit>(message.from())?;
+        decom.verify(&message.id(), &message.from(), com)?;
+        decom.validate(&self.input.participants(), self.input.threshold())?;
+        self.local_storage
+            .store::<storage::Decommit>(message.from(), decom);
+
+        // Check if we've received all the decommits
+        let r2_done = self
+            .local_storage
+            .contains_for_all_ids::<storage::Decommit>(&self.all_participants());
+
+        if r2_done {
+            // Generate messages for round 3...
+            let round_three_messages =
+                run_only_once!(self.gen_round_three_msgs(rng, message.id()))?;
+
+            // ...and handle any messages that other participants have sent for round 3.
+            let round_three_outcomes = self
+                .fetch_messages(MessageType::Refresh(RefreshMessageType::R3EncryptedUpdate))?
+                .iter()
+                .map(|msg| self.handle_round_three_msg(rng, msg))
+                .collect::<Result<Vec<_>>>()?;
+            ProcessOutcome::collect_with_messages(round_three_outcomes, round_three_messages)
+        } else {
+            // Otherwise, wait for more round 2 messages.
+            Ok(ProcessOutcome::Incomplete)
+        }
+    }
+
+    /// Generate the protocol's round three messages.
+    ///
+    /// At this point, we have validated each participant's commitment, and can
+    /// now send each participant its private update, encrypted under its
+    /// Paillier key, along with Schnorr proofs that this participant knows the
+    /// private value corresponding to each of its public updates.
+    #[cfg_attr(feature = "flame_it", flame("refresh"))]
+    #[instrument(skip_all, err(Debug))]
+    fn gen_round_three_msgs<R: RngCore + CryptoRng>(
+        &mut self,
+        rng: &mut R,
+        sid: Identifier,
+    ) -> Result<Vec<Message>> {
+        info!("Generating round three refresh messages.");
+
+        // Construct `global rid` out of each participant's `rid`s.
+        let mut global_rid = [0u8; 32];
+        for pid in self.all_participants() {
+            let decom = self.local_storage.retrieve::<storage::Decommit>(pid)?;
+            for i in 0..32 {
+                global_rid[i] ^= decom.rid[i];
+            }
+        }
+        self.local_storage
+            .store::<storage::GlobalRid>(self.id, global_rid);
+
+        let decom = self.local_storage.retrieve::<storage::Decommit>(self.id)?;
+        let precoms = self
+            .local_storage
+            .retrieve::<storage::SchnorrPrecoms>(self.id)?;
+        let context = self.retrieve_context();
+        let proofs = decom
+            .updates
+            .iter()
+            .zip(precoms)
+            .map(|(update, precom)| {
+                let private_update = self
+                    .local_storage
+                    .retrieve::<storage::PrivateUpdate>(update.participant())?;
+                let transcript =
+                    schnorr_proof_transcript(&global_rid, self.id, update.participant())?;
+                PiSchProof::prove_from_precommit(
+                    &context,
+                    precom,
+                    &CommonInput::new(update),
+                    &ProverSecret::new(private_update.as_ref()),
+                    &transcript,
+                )
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let _ = self
+            .local_storage
+            .remove::<storage::SchnorrPrecoms>(self.id)?;
+
+        let mut messages = Vec::with_capacity(self.other_participant_ids.len());
+        for pid in self.other_participant_ids.clone() {
+            let private_update = self.local_storage.remove::<storage::PrivateUpdate>(pid)?;
+            let (ciphertext, _) = self
+                .input
+                .find_auxinfo_public(pid)?
+                .pk()
+                .encrypt(rng, private_update.as_ref())
+                .map_err(|_| InternalError::InternalInvariantFailed)?;
+            messages.push(Message::new(
+                MessageType::Refresh(RefreshMessageType::R3EncryptedUpdate),
+                sid,
+                self.id,
+                pid,
+                &EncryptedUpdate {
+                    ciphertext,
+                    proofs: proofs.clone(),
+                },
+            )?);
+        }
+
+        // Our own update doesn't need to be sent anywhere.
+        let my_update = self
+            .local_storage
+            .remove::<storage::PrivateUpdate>(self.id)?;
+        self.local_storage
+            .store::<storage::ReceivedUpdate>(self.id, my_update);
+
+        Ok(messages)
+    }
+
+    /// Handle the protocol's round three messages.
+    ///
+    /// Here we decrypt the private update from each participant, check it
+    /// against the sender's public update, and validate the sender's Schnorr
+    /// proofs. Once every participant's update has been received, we apply
+    /// them to the key shares and terminate the protocol.
+    #[cfg_attr(feature = "flame_it", flame("refresh"))]
+    #[instrument(skip_all, err(Debug))]
+    fn handle_round_three_msg<R: RngCore + CryptoRng>(
+        &mut self,
+        rng: &mut R,
+        message: &Message,
+    ) -> Result<ProcessOutcome<<Self as ProtocolParticipant>::Output>> {
+        info!("Handling round three refresh message.");
+
+        if !self.local_storage.contains::<storage::GlobalRid>(self.id) {
+            self.stash_message(message)?;
+            return Ok(ProcessOutcome::Incomplete);
+        }
+        let encrypted_update = EncryptedUpdate::from_message(message)?;
+        let global_rid = *self.local_storage.retrieve::<storage::GlobalRid>(self.id)?;
+        let decom = self
+            .local_storage
+            .retrieve::<storage::Decommit>(message.from())?;
+
+        // Check the proofs for each of the sender's public updates.
+        if encrypted_update.proofs.len() != decom.updates.len() {
+            error!("Received the wrong number of Schnorr proofs");
+            return Err(InternalError::ProtocolError(Some(message.from())));
+        }
+        let batch = encrypted_update
+            .proofs
+            .into_iter()
+            .zip(&decom.updates)
+            .zip(&decom.As)
+            .map(|((proof, update), precommitment)| {
+                if proof.commitment() != precommitment {
+                    error!("Schnorr proof doesn't match the sender's precommitment");
+                    return Err(InternalError::ProtocolError(Some(message.from())));
+                }
+                let transcript =
+                    schnorr_proof_transcript(&global_rid, message.from(), update.participant())?;
+                Ok((message.from(), proof, CommonInput::new(update), transcript))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        PiSchProof::verify_batch(batch, &self.retrieve_context(), rng)?;
+
+        // Decrypt our private update and check it against the public one.
+        let x = self
+            .input
+            .private_auxinfo()
+            .decryption_key()
+            .decrypt(&encrypted_update.ciphertext)
+            .map_err(|_| {
+                error!("Decryption failed, ciphertext out of range");
+                InternalError::ProtocolError(Some(message.from()))
+            })?;
+        if x < BigNumber::zero() || x >= k256_order() {
+            error!("Received a private update that was out of range");
+            return Err(InternalError::ProtocolError(Some(message.from())));
+        }
+        let private_update = KeySharePrivate::from_bignumber(&x);
+        if &private_update.public_share()? != decom.update_for(self.id)?.as_ref() {
+            error!("Private update doesn't match the sender's public update");
+            return Err(InternalError::ProtocolError(Some(message.from())));
+        }
+        self.local_storage
+            .store::<storage::ReceivedUpdate>(message.from(), private_update);
+
+        // Check if we've received all the updates
+        let r3_done = self
+            .local_storage
+            .contains_for_all_ids::<storage::ReceivedUpdate>(&self.all_participants());
+
+        // If so, we completed the protocol! Return the outputs.
+        if r3_done {
+            let output = self.apply_updates()?;
+            self.status = Status::TerminatedSuccessfully;
+            Ok(ProcessOutcome::Terminated(output))
+        } else {
+            // Otherwise, we'll have to wait for more round three messages.
+            Ok(ProcessOutcome::Incomplete)
+        }
+    }
+
+    /// Add the received private updates to this participant's private key
+    /// share, and every participant's public updates to their public key
+    /// shares.
+    ///
+    /// The refreshed output keeps the original chain code, so child keys
+    /// derived from it don't change.
+    fn apply_updates(&mut self) -> Result<keygen::Output> {
+        let q = k256_order();
+        let participants = self.input.participants();
+
+        let private_share = participants
+            .iter()
+            .map(|pid| self.local_storage.remove::<storage::ReceivedUpdate>(*pid))
+            .collect::<Result<Vec<_>>>()?
+            .iter()
+            .fold(
+                self.input.private_key_share().as_ref().clone(),
+                |sum, update| sum.modadd(update.as_ref(), &q),
+            );
+        let private_key_share = KeySharePrivate::from_bignumber(&private_share);
+
+        let decoms = participants
+            .iter()
+            .map(|pid| self.local_storage.retrieve::<storage::Decommit>(*pid))
+            .collect::<Result<Vec<_>>>()?;
+
+        let public_key_shares = match self.input.threshold() {
+            None => participants
+                .iter()
+                .map(|&pid| {
+                    let share = decoms.iter().try_fold(
+                        *self.input.find_keyshare_public(pid)?.as_ref(),
+                        |sum, decom| Ok::<_, InternalError>(sum + *decom.update_for(pid)?.as_ref()),
+                    )?;
+                    Ok(KeySharePublic::new(pid, share))
+                })
+                .collect::<Result<Vec<_>>>()?,
+            Some(_) => {
+                // The updated polynomial is the sum of the old polynomial and
+                // every participant's update polynomial, so its commitments
+                // are the sums of the individual commitments.
+                let old_commitments = self
+                    .input
+                    .find_keyshare_public(self.id)?
+                    .shamir()
+                    .ok_or(InternalError::InternalInvariantFailed)?
+                    .commitments();
+                let commitments = old_commitments
+                    .iter()
+                    .enumerate()
+                    .map(|(k, commitment)| {
+                        decoms.iter().try_fold(*commitment, |sum, decom| {
+                            let update_commitments =
+                                decom.coefficient_commitments.as_ref().ok_or_else(|| {
+                                    error!("Stored decommitment is missing Feldman commitments");
+                                    InternalError::InternalInvariantFailed
+                                })?;
+                            Ok::<_, InternalError>(sum + update_commitments[k])
+                        })
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+                participants
+                    .iter()
+                    .map(|&pid| KeySharePublic::new_shamir(pid, commitments.clone()))
+                    .collect::<Result<Vec<_>>>()?
+            }
+        };
+
+        let output = keygen::Output::from_parts(
+            public_key_shares,
+            private_key_share,
+            self.input.keygen_output().chain_code(),
+        )?;
+
+        // The updates should never change the shared key.
+        if output.public_key()? != self.input.keygen_output().public_key()? {
+            error!("Refreshed key shares don't match the original public key");
+            Err(InternalError::InternalInvariantFailed)?
+        }
+        Ok(output)
+    }
+}
+
+/// Generate a [`Transcript`] for the [`PiSchProof`] that `prover` knows the
+/// update to `recipient`'s key share.
+fn schnorr_proof_transcript(
+    global_rid: &[u8; 32],
+    prover: ParticipantIdentifier,
+    recipient: ParticipantIdentifier,
+) -> Result<Transcript> {
+    let mut transcript = Transcript::new(b"refresh schnorr");
+    transcript.append_message(b"rid", &serialize!(global_rid)?);
+    transcript.append_message(b"prover", &serialize!(&prover)?);
+    transcript.append_message(b"recipient", &serialize!(&recipient)?);
+    Ok(transcript)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        auxinfo,
+        keygen::shamir::lagrange_coefficient_at_zero,
+        participant::testing::run_participants,
+        utils::{testing::init_testing, CurvePoint},
+        ParticipantConfig,
+    };
+
+    /// Run refresh to completion with the given key shares and return the
+    /// outputs, ordered to match the configs.
+    fn run_refresh<R: RngCore + CryptoRng>(
+        configs: &[ParticipantConfig],
+        keygen_outputs: Vec<keygen::Output>,
+        rng: &mut R,
+    ) -> Result<Vec<keygen::Output>> {
+        let sid = Identifier::random(rng);
+        let auxinfo_outputs = auxinfo::Output::simulate_set(configs, rng);
+        let mut quorum = configs
+            .iter()
+            .zip(auxinfo_outputs)
+            .zip(keygen_outputs)
+            .map(|((config, auxinfo_output), keygen_output)| {
+                let input = Input::new(auxinfo_output, keygen_output)?;
+                RefreshParticipant::new(sid, config.id(), config.other_ids().to_vec(), input)
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        run_participants(&mut quorum, rng)
+    }
+
+    #[test]
+    fn refresh_produces_new_shares_of_the_same_key() -> Result<()> {
+        let rng = &mut init_testing();
+        let configs = ParticipantConfig::random_quorum(3, rng)?;
+        let keygen_outputs = keygen::Output::simulate_set(&configs, rng);
+        let public_key = keygen_outputs[0].public_key()?;
+
+        let outputs = run_refresh(&configs, keygen_outputs.clone(), rng)?;
+
+        for (old, new) in keygen_outputs.iter().zip(&outputs) {
+            // The public key and participant set are unchanged...
+            assert_eq!(new.public_key()?, public_key);
+            assert_eq!(new.public_key_shares(), outputs[0].public_key_shares());
+            assert!(!new.is_threshold());
+
+            // ...but the key shares are new
+            assert_ne!(new.private_key_share(), old.private_key_share());
+            assert_ne!(new.public_key_shares(), old.public_key_shares());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn refresh_keeps_derived_child_keys() -> Result<()> {
+        let rng = &mut init_testing();
+        let configs = ParticipantConfig::random_quorum(3, rng)?;
+        let keygen_outputs = keygen::Output::simulate_set(&configs, rng);
+        let child_key = keygen_outputs[0]
+            .derive_child(&"m/1/2".parse()?)?
+            .public_key()?;
+
+        let outputs = run_refresh(&configs, keygen_outputs.clone(), rng)?;
+
+        for (old, new) in keygen_outputs.iter().zip(&outputs) {
+            assert_eq!(new.chain_code(), old.chain_code());
+            assert_eq!(
+                new.derive_child(&"m/1/2".parse()?)?.public_key()?,
+                child_key
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn refresh_preserves_threshold_key_shares() -> Result<()> {
+        let rng = &mut init_testing();
+        let THRESHOLD = 2;
+        let configs = ParticipantConfig::random_quorum(4, rng)?;
+        let keygen_outputs = keygen::Output::simulate_threshold_set(&configs, THRESHOLD, rng);
+        let public_key = keygen_outputs[0].public_key()?;
+
+        let outputs = run_refresh(&configs, keygen_outputs.clone(), rng)?;
+
+        for (old, new) in keygen_outputs.iter().zip(&outputs) {
+            assert_eq!(new.public_key()?, public_key);
+            assert_eq!(new.threshold(), THRESHOLD);
+            assert_ne!(new.private_key_share(), old.private_key_share());
+        }
+
+        // Any `THRESHOLD` refreshed shares still reconstruct the private key
+        let q = k256_order();
+        for quorum_outputs in outputs.windows(THRESHOLD) {
+            let points = quorum_outputs
+                .iter()
+                .map(|output| Ok(output.private_pid()?.evaluation_point()))
+                .collect::<Result<Vec<_>>>()?;
+            let secret = quorum_outputs.iter().zip(&points).try_fold(
+                BigNumber::zero(),
+                |sum, (output, point)| {
+                    let lambda = lagrange_coefficient_at_zero(point, &points)?;
+                    Ok::<_, InternalError>(
+                        sum.modadd(&lambda.modmul(output.private_key_share().as_ref(), &q), &q),
+                    )
+                },
+            )?;
+            let secret_public = CurvePoint::GENERATOR.multiply_by_bignum(&secret)?;
+            assert_eq!(
+                k256::ecdsa::VerifyingKey::from_encoded_point(&secret_public.into()).unwrap(),
+                public_key
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn refresh_requires_every_key_share_holder() -> Result<()> {
+        let rng = &mut init_testing();
+        let configs = ParticipantConfig::random_quorum(3, rng)?;
+        let keygen_outputs = keygen::Output::simulate_threshold_set(&configs, 2, rng);
+        let auxinfo_outputs = auxinfo::Output::simulate_set(&configs, rng);
+
+        let input = Input::new(auxinfo_outputs[0].clone(), keygen_outputs[0].clone())?;
+        let result = RefreshParticipant::new(
+            Identifier::random(rng),
+            configs[0].id(),
+            configs[0].other_ids()[..1].to_vec(),
+            input,
+        );
+        assert_eq!(
+            result.unwrap_err(),
+            InternalError::CallingApplicationMistake(CallerError::BadInput)
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,181 @@
+// Copyright (c) 2022-2023 Bolt Labs Holdings, Inc
+//
+// This source code is licensed under both the MIT license found in the
+// LICENSE-MIT file in the root directory of this source tree and the Apache
+// License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+// of this source tree.
+
+use std::collections::HashSet;
+
+use crate::{
+    errors::{InternalError, Result},
+    keygen::{shamir::evaluate_commitments, KeySharePublic},
+    messages::{Message, MessageType, RefreshMessageType},
+    protocol::{Identifier, ParticipantIdentifier},
+    utils::CurvePoint,
+    zkp::pisch::PiSchPrecommit,
+};
+use merlin::Transcript;
+use rand::{CryptoRng, RngCore};
+use serde::{Deserialize, Serialize};
+use tracing::{error, instrument};
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub(crate) struct RefreshCommit {
+    hash: [u8; 32],
+}
+impl RefreshCommit {
+    pub(crate) fn from_message(message: &Message) -> Result<Self> {
+        message.check_type(MessageType::Refresh(RefreshMessageType::R1CommitHash))?;
+        let refresh_commit: RefreshCommit = deserialize!(&message.unverified_bytes)?;
+        Ok(refresh_commit)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub(crate) struct RefreshDecommit {
+    ///`sid` corresponds to a unique session identifier.
+    pub sid: Identifier,
+    pub sender: ParticipantIdentifier,
+    pub rid: [u8; 32],
+    pub u_i: [u8; 32],
+    /// The public update for each participant's key share (`X_i^j` in the
+    /// paper).
+    pub updates: Vec<KeySharePublic>,
+    /// Schnorr precommitments for each public update, in the same order as
+    /// `updates` (`A_i^j` in the paper).
+    pub As: Vec<CurvePoint>,
+    /// Feldman commitments to the sender's update polynomial, present only
+    /// when refreshing threshold key shares.
+    pub coefficient_commitments: Option<Vec<CurvePoint>>,
+}
+
+impl RefreshDecommit {
+    ///`sid` corresponds to a unique session identifier.
+    pub(crate) fn new<R: RngCore + CryptoRng>(
+        rng: &mut R,
+        sid: &Identifier,
+        sender: &ParticipantIdentifier,
+        updates: Vec<KeySharePublic>,
+        sch_precoms: &[PiSchPrecommit],
+        coefficient_commitments: Option<Vec<CurvePoint>>,
+    ) -> Self {
+        let mut rid = [0u8; 32];
+        let mut u_i = [0u8; 32];
+        rng.fill_bytes(rid.as_mut_slice());
+        rng.fill_bytes(u_i.as_mut_slice());
+        Self {
+            sid: *sid,
+            sender: *sender,
+            rid,
+            u_i,
+            updates,
+            As: sch_precoms
+                .iter()
+                .map(|precom| *precom.precommitment())
+                .collect(),
+            coefficient_commitments,
+        }
+    }
+
+    pub(crate) fn from_message(message: &Message) -> Result<Self> {
+        message.check_type(MessageType::Refresh(RefreshMessageType::R2Decommit))?;
+        let refresh_decommit: RefreshDecommit = deserialize!(&message.unverified_bytes)?;
+        Ok(refresh_decommit)
+    }
+
+    /// Get the public update for the given participant's key share.
+    pub(crate) fn update_for(&self, pid: ParticipantIdentifier) -> Result<&KeySharePublic> {
+        self.updates
+            .iter()
+            .find(|update| update.participant() == pid)
+            .ok_or_else(|| {
+                error!("Decommitment doesn't contain a public update for {}", pid);
+                InternalError::InternalInvariantFailed
+            })
+    }
+
+    /// Check that the public updates re-randomize the key shares of exactly
+    /// the given participants without changing the shared key.
+    ///
+    /// For full-threshold key shares, this means the updates sum to the
+    /// identity. For threshold key shares, the updates must be evaluations
+    /// of a polynomial of the right degree with a zero constant term.
+    pub(crate) fn validate(
+        &self,
+        participants: &[ParticipantIdentifier],
+        threshold: Option<usize>,
+    ) -> Result<()> {
+        let update_pids = self
+            .updates
+            .iter()
+            .map(KeySharePublic::participant)
+            .collect::<HashSet<_>>();
+        let well_formed = update_pids.len() == self.updates.len()
+            && update_pids == participants.iter().copied().collect()
+            && self.As.len() == self.updates.len();
+        if !well_formed {
+            error!("decommitment doesn't contain exactly one update for each participant");
+            return Err(InternalError::ProtocolError(Some(self.sender)));
+        }
+
+        let valid = match (threshold, &self.coefficient_commitments) {
+            (None, None) => {
+                self.updates
+                    .iter()
+                    .fold(CurvePoint::IDENTITY, |sum, update| sum + *update.as_ref())
+                    == CurvePoint::IDENTITY
+            }
+            (Some(threshold), Some(commitments)) => {
+                commitments.len() == threshold
+                    && commitments.first() == Some(&CurvePoint::IDENTITY)
+                    && self.updates.iter().try_fold(true, |valid, update| {
+                        let expected = evaluate_commitments(
+                            commitments,
+                            &update.participant().evaluation_point(),
+                        )?;
+                        Ok::<_, InternalError>(valid && update.as_ref() == &expected)
+                    })?
+            }
+            _ => false,
+        };
+        if !valid {
+            error!("decommitment contains updates that would change the shared key");
+            return Err(InternalError::ProtocolError(Some(self.sender)));
+        }
+        Ok(())
+    }
+
+    pub(crate) fn commit(&self) -> Result<RefreshCommit> {
+        let mut transcript = Transcript::new(b"RefreshR1");
+        transcript.append_message(b"decom", &serialize!(&self)?);
+        let mut hash = [0u8; 32];
+        transcript.challenge_bytes(b"hashing r1", &mut hash);
+        Ok(RefreshCommit { hash })
+    }
+
+    #[instrument(skip_all, err(Debug))]
+    /// `sid` is a unique session identifier.
+    pub(crate) fn verify(
+        &self,
+        sid: &Identifier,
+        sender: &ParticipantIdentifier,
+        com: &RefreshCommit,
+    ) -> Result<()> {
+        let mut transcript = Transcript::new(b"RefreshR1");
+        let decom = &mut self.clone();
+        decom.sid = *sid;
+        decom.sender = *sender;
+        transcript.append_message(b"decom", &serialize!(&decom)?);
+        let mut hash = [0u8; 32];
+        transcript.challenge_bytes(b"hashing r1", &mut hash);
+        let rebuilt_com = RefreshCommit { hash };
+
+        if rebuilt_com == *com {
+            Ok(())
+        } else {
+            error!("decommitment does not match original commitment");
+            Err(InternalError::ProtocolError(Some(*sender)))
+        }
+    }
+}
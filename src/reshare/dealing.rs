@@ -0,0 +1,114 @@
+// Copyright (c) 2022-2023 Bolt Labs Holdings, Inc
+//
+// This source code is licensed under both the MIT license found in the
+// LICENSE-MIT file in the root directory of this source tree and the Apache
+// License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+// of this source tree.
+
+use std::collections::HashSet;
+
+use serde::{Deserialize, Serialize};
+use tracing::error;
+
+use crate::{
+    errors::{InternalError, Result},
+    keygen::{shamir::evaluate_commitments, KeySharePublic},
+    messages::{Message, MessageType, ReshareMessageType},
+    utils::CurvePoint,
+    ParticipantIdentifier,
+};
+
+/// The public commitments to the shares a dealer gives the new participants.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) enum DealingCommitments {
+    /// Feldman commitments to the dealer's sharing polynomial, for threshold
+    /// key shares.
+    Shamir(Vec<CurvePoint>),
+    /// The public form of each new participant's additive share, for
+    /// full-threshold key shares.
+    Additive(Vec<KeySharePublic>),
+}
+
+/// The public part of a dealer's resharing of its key share, broadcast to all
+/// participants.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct Dealing {
+    /// The chain code of the key being reshared, which the new output keeps.
+    pub chain_code: [u8; 32],
+    pub commitments: DealingCommitments,
+}
+
+impl Dealing {
+    pub(crate) fn from_message(message: &Message) -> Result<Self> {
+        message.check_type(MessageType::Reshare(ReshareMessageType::R1Dealing))?;
+        let dealing: Dealing = deserialize!(&message.unverified_bytes)?;
+        Ok(dealing)
+    }
+
+    /// The public form of the secret being dealt.
+    pub(crate) fn secret_commitment(&self) -> CurvePoint {
+        match &self.commitments {
+            DealingCommitments::Shamir(commitments) => {
+                commitments.first().copied().unwrap_or(CurvePoint::IDENTITY)
+            }
+            DealingCommitments::Additive(shares) => shares
+                .iter()
+                .fold(CurvePoint::IDENTITY, |sum, share| sum + *share.as_ref()),
+        }
+    }
+
+    /// The public form of the share dealt to the given new participant.
+    pub(crate) fn share_for(&self, pid: ParticipantIdentifier) -> Result<CurvePoint> {
+        match &self.commitments {
+            DealingCommitments::Shamir(commitments) => {
+                evaluate_commitments(commitments, &pid.evaluation_point())
+            }
+            DealingCommitments::Additive(shares) => shares
+                .iter()
+                .find(|share| share.participant() == pid)
+                .map(|share| *share.as_ref())
+                .ok_or_else(|| {
+                    error!("Dealing doesn't contain a share for {}, even though we checked for it when we received it.", pid);
+                    InternalError::InternalInvariantFailed
+                }),
+        }
+    }
+
+    /// Check that the dealing has the expected form for the new participants
+    /// and threshold, and that it deals the `expected_secret`.
+    pub(crate) fn validate(
+        &self,
+        dealer: ParticipantIdentifier,
+        new_participants: &[ParticipantIdentifier],
+        new_threshold: Option<usize>,
+        expected_secret: &CurvePoint,
+    ) -> Result<()> {
+        let well_formed = match (&self.commitments, new_threshold) {
+            (DealingCommitments::Shamir(commitments), Some(threshold)) => {
+                commitments.len() == threshold
+            }
+            (DealingCommitments::Additive(shares), None) => {
+                let pids = shares
+                    .iter()
+                    .map(KeySharePublic::participant)
+                    .collect::<HashSet<_>>();
+                shares.len() == new_participants.len()
+                    && pids == new_participants.iter().copied().collect()
+            }
+            _ => false,
+        };
+        if !well_formed {
+            error!(
+                "Dealing from {} has the wrong form for the new sharing",
+                dealer
+            );
+            return Err(InternalError::ProtocolError(Some(dealer)));
+        }
+
+        if &self.secret_commitment() != expected_secret {
+            error!("Dealing from {} doesn't deal its share of the key", dealer);
+            return Err(InternalError::ProtocolError(Some(dealer)));
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,265 @@
+// Copyright (c) 2022-2023 Bolt Labs Holdings, Inc
+//
+// This source code is licensed under both the MIT license found in the
+// LICENSE-MIT file in the root directory of this source tree and the Apache
+// License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+// of this source tree.
+
+use std::collections::HashSet;
+
+use libpaillier::unknown_order::BigNumber;
+use tracing::error;
+
+use crate::{
+    errors::{CallerError, InternalError, Result},
+    keygen::{
+        self, shamir::lagrange_coefficient_at_zero, validate_public_key_shares, KeySharePrivate,
+        KeySharePublic,
+    },
+    utils::{k256_order, CurvePoint},
+    ParticipantIdentifier,
+};
+
+/// Input needed for a
+/// [`ReshareParticipant`](crate::reshare::ReshareParticipant) to run.
+///
+/// Participants that hold a share of the existing key use [`Input::new()`];
+/// participants that are joining and don't hold a share use
+/// [`Input::new_participant()`]. In both cases, every participant must agree
+/// on the new participant set and on the type of sharing to produce.
+#[derive(Debug, Clone)]
+pub struct Input {
+    /// The public key shares of the existing key.
+    old_public_key_shares: Vec<KeySharePublic>,
+    /// This participant's share of the existing key, if it holds one.
+    old_private_key_share: Option<KeySharePrivate>,
+    /// The participants that will hold shares of the key after resharing.
+    new_participants: Vec<ParticipantIdentifier>,
+    /// The type of sharing to produce for the new participants.
+    new_sharing: keygen::Input,
+    /// The chain code of the existing key, if this participant holds a share.
+    chain_code: Option<[u8; 32]>,
+}
+
+impl Input {
+    /// Creates a new [`Input`] for a participant holding a share of the key,
+    /// from the output of the [`keygen`](crate::keygen::KeygenParticipant)
+    /// protocol (or of a previous resharing or refresh).
+    ///
+    /// The `new_participants` will hold shares of the same key after
+    /// resharing, using the sharing described by `new_sharing`. They may or may
+    /// not include this participant.
+    pub fn new(
+        keygen_output: keygen::Output,
+        new_participants: Vec<ParticipantIdentifier>,
+        new_sharing: keygen::Input,
+    ) -> Result<Self> {
+        let (old_public_key_shares, old_private_key_share, chain_code) = keygen_output.into_parts();
+        Self::validate_new_participants(&new_participants, new_sharing)?;
+        Ok(Self {
+            old_public_key_shares,
+            old_private_key_share: Some(old_private_key_share),
+            new_participants,
+            new_sharing,
+            chain_code: Some(chain_code),
+        })
+    }
+
+    /// Creates a new [`Input`] for a participant that is joining and doesn't
+    /// hold a share of the key.
+    ///
+    /// The `old_public_key_shares` must be the public key shares of the
+    /// existing key, as held by the current share holders; they are used to
+    /// check the new shares. The key's chain code is learned from the share
+    /// holders during resharing.
+    pub fn new_participant(
+        old_public_key_shares: Vec<KeySharePublic>,
+        new_participants: Vec<ParticipantIdentifier>,
+        new_sharing: keygen::Input,
+    ) -> Result<Self> {
+        validate_public_key_shares(&old_public_key_shares)?;
+        Self::validate_new_participants(&new_participants, new_sharing)?;
+        Ok(Self {
+            old_public_key_shares,
+            old_private_key_share: None,
+            new_participants,
+            new_sharing,
+            chain_code: None,
+        })
+    }
+
+    fn validate_new_participants(
+        new_participants: &[ParticipantIdentifier],
+        new_sharing: keygen::Input,
+    ) -> Result<()> {
+        let unique_participants = new_participants.iter().collect::<HashSet<_>>();
+        if new_participants.is_empty() || unique_participants.len() != new_participants.len() {
+            error!("The new participants for resharing must be a non-empty set with no duplicates");
+            Err(CallerError::BadInput)?
+        }
+        if new_sharing
+            .threshold()
+            .is_some_and(|threshold| threshold > new_participants.len())
+        {
+            error!("The new threshold is larger than the number of new participants");
+            Err(CallerError::BadInput)?
+        }
+        Ok(())
+    }
+
+    /// Get the participants that hold a share of the existing key.
+    pub(crate) fn old_participants(&self) -> Vec<ParticipantIdentifier> {
+        self.old_public_key_shares
+            .iter()
+            .map(KeySharePublic::participant)
+            .collect()
+    }
+
+    /// Get the participants that will hold a share of the key after
+    /// resharing.
+    pub(crate) fn new_participants(&self) -> &[ParticipantIdentifier] {
+        &self.new_participants
+    }
+
+    /// The number of existing shares required to reconstruct the key, if they
+    /// are threshold key shares.
+    pub(crate) fn old_threshold(&self) -> Option<usize> {
+        self.old_public_key_shares
+            .first()
+            .and_then(KeySharePublic::shamir)
+            .map(keygen::ShamirData::threshold)
+    }
+
+    /// The number of new shares required to reconstruct the key, if the new
+    /// shares are threshold key shares.
+    pub(crate) fn new_threshold(&self) -> Option<usize> {
+        self.new_sharing.threshold()
+    }
+
+    pub(crate) fn old_private_key_share(&self) -> Option<&KeySharePrivate> {
+        self.old_private_key_share.as_ref()
+    }
+
+    /// The chain code of the existing key, if this participant holds a share.
+    pub(crate) fn chain_code(&self) -> Option<&[u8; 32]> {
+        self.chain_code.as_ref()
+    }
+
+    /// Compute the public key.
+    pub(crate) fn public_key(&self) -> Result<CurvePoint> {
+        match self
+            .old_public_key_shares
+            .first()
+            .and_then(KeySharePublic::shamir)
+        {
+            Some(shamir) => shamir.commitments().first().copied().ok_or_else(|| {
+                error!("Threshold key shares are missing their Feldman commitments");
+                CallerError::BadInput.into()
+            }),
+            None => Ok(self
+                .old_public_key_shares
+                .iter()
+                .fold(CurvePoint::IDENTITY, |sum, share| sum + *share.as_ref())),
+        }
+    }
+
+    /// Compute the Lagrange coefficient that converts the given dealer's
+    /// existing key share into an additive share for the set of `dealers`.
+    ///
+    /// For additive key shares, this is always one.
+    pub(crate) fn dealer_coefficient(
+        &self,
+        dealer: ParticipantIdentifier,
+        dealers: &[ParticipantIdentifier],
+    ) -> Result<BigNumber> {
+        match self.old_threshold() {
+            Some(_) => {
+                let points = dealers
+                    .iter()
+                    .map(ParticipantIdentifier::evaluation_point)
+                    .collect::<Vec<_>>();
+                lagrange_coefficient_at_zero(&dealer.evaluation_point(), &points)
+            }
+            None => Ok(BigNumber::one()),
+        }
+    }
+
+    /// Compute the public form of the given dealer's additive share of the
+    /// key, for the set of `dealers`.
+    pub(crate) fn dealer_public_share(
+        &self,
+        dealer: ParticipantIdentifier,
+        dealers: &[ParticipantIdentifier],
+    ) -> Result<CurvePoint> {
+        let public_key_share = self
+            .old_public_key_shares
+            .iter()
+            .find(|share| share.participant() == dealer)
+            .ok_or_else(|| {
+                error!("Reshare input doesn't contain a public keyshare for dealer {}, even though we checked for it at construction.", dealer);
+                InternalError::InternalInvariantFailed
+            })?;
+        public_key_share
+            .as_ref()
+            .multiply_by_bignum(&self.dealer_coefficient(dealer, dealers)?)
+    }
+
+    /// Compute this participant's additive share of the key, for the set of
+    /// `dealers`.
+    pub(crate) fn dealer_private_share(
+        &self,
+        id: ParticipantIdentifier,
+        dealers: &[ParticipantIdentifier],
+    ) -> Result<BigNumber> {
+        let private_key_share = self.old_private_key_share().ok_or_else(|| {
+            error!("Tried to deal a share without holding a share of the key");
+            InternalError::InternalInvariantFailed
+        })?;
+        Ok(self
+            .dealer_coefficient(id, dealers)?
+            .modmul(private_key_share.as_ref(), &k256_order()))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Input;
+    use crate::{keygen, utils::testing::init_testing, ParticipantConfig, ParticipantIdentifier};
+
+    #[test]
+    fn new_participants_must_be_valid() {
+        let rng = &mut init_testing();
+        let configs = ParticipantConfig::random_quorum(3, rng).unwrap();
+        let keygen_output = keygen::Output::simulate_set(&configs, rng).remove(0);
+        let new_pids = std::iter::repeat_with(|| ParticipantIdentifier::random(rng))
+            .take(4)
+            .collect::<Vec<_>>();
+        let two_of_n = keygen::Input::with_threshold(2).unwrap();
+
+        // Any non-empty set of new participants works
+        assert!(Input::new(keygen_output.clone(), new_pids.clone(), two_of_n).is_ok());
+        assert!(Input::new(
+            keygen_output.clone(),
+            new_pids.clone(),
+            keygen::Input::full_threshold()
+        )
+        .is_ok());
+
+        // Empty or duplicated sets don't
+        assert!(Input::new(keygen_output.clone(), vec![], two_of_n).is_err());
+        let duplicated = [new_pids.as_slice(), &new_pids[..1]].concat();
+        assert!(Input::new(keygen_output.clone(), duplicated, two_of_n).is_err());
+
+        // The threshold can't be larger than the new set
+        let five_of_n = keygen::Input::with_threshold(5).unwrap();
+        assert!(Input::new(keygen_output.clone(), new_pids.clone(), five_of_n).is_err());
+
+        // New participants must provide valid public key shares
+        let public_key_shares = keygen_output.public_key_shares().to_vec();
+        assert!(
+            Input::new_participant(public_key_shares.clone(), new_pids.clone(), two_of_n).is_ok()
+        );
+        let duplicated = [public_key_shares.as_slice(), &public_key_shares[..1]].concat();
+        assert!(Input::new_participant(duplicated, new_pids, two_of_n).is_err());
+    }
+}
@@ -0,0 +1,17 @@
+// Copyright (c) 2022-2023 Bolt Labs Holdings, Inc
+//
+// This source code is licensed under both the MIT license found in the
+// LICENSE-MIT file in the root directory of this source tree and the Apache
+// License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+// of this source tree.
+
+//! This module implements resharing of an existing key to a new set of
+//! participants, possibly with a new threshold. The public key is unchanged.
+//! See [`ReshareParticipant`] for more details.
+
+mod dealing;
+mod input;
+mod participant;
+
+pub use input::Input;
+pub use participant::ReshareParticipant;
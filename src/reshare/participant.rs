@@ -0,0 +1,1016 @@
+//! Types and functions related to the key resharing sub-protocol Participant.
+
+// Copyright (c) 2022-2023 Bolt Labs Holdings, Inc
+//
+// This source code is licensed under both the MIT license found in the
+// LICENSE-MIT file in the root directory of this source tree and the Apache
+// License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+// of this source tree.
+
+use std::collections::HashSet;
+
+use crate::{
+    broadcast::participant::{BroadcastOutput, BroadcastParticipant, BroadcastTag},
+    errors::{CallerError, InternalError, Result},
+    keygen::{
+        self,
+        shamir::{Polynomial, PrivateShare},
+        KeySharePrivate, KeySharePublic,
+    },
+    local_storage::LocalStorage,
+    messages::{Message, MessageType, ReshareMessageType},
+    participant::{
+        Broadcast, InnerProtocolParticipant, ParticipantState, ProcessOutcome, ProtocolParticipant,
+        Status,
+    },
+    protocol::{ParticipantIdentifier, ProtocolType, SharedContext},
+    reshare::{
+        dealing::{Dealing, DealingCommitments},
+        input::Input,
+    },
+    run_only_once,
+    utils::{k256_order, random_positive_bn, CurvePoint},
+    Identifier,
+};
+
+use libpaillier::unknown_order::BigNumber;
+use rand::{CryptoRng, RngCore};
+use tracing::{error, info, instrument};
+
+mod storage {
+    use super::*;
+    use crate::local_storage::TypeTag;
+
+    pub(super) struct Dealing;
+    impl TypeTag for Dealing {
+        type Value = super::Dealing;
+        const NAME: &'static str = "reshare.Dealing";
+    }
+    pub(super) struct ReceivedShare;
+    impl TypeTag for ReceivedShare {
+        type Value = PrivateShare;
+        const NAME: &'static str = "reshare.ReceivedShare";
+    }
+}
+
+/// A [`ProtocolParticipant`] that runs the key resharing protocol.
+///
+/// Resharing moves a key from its current share holders (the "old"
+/// participants) to a new set of share holders, possibly with a different
+/// threshold, without changing the public key. The two sets may overlap.
+///
+/// # Protocol input
+/// The protocol takes an [`Input`] which contains:
+/// - The public key shares of the existing key, along with this participant's
+///   private key share if it holds one; and
+/// - The new participants and the type of sharing to produce for them (see
+///   [`keygen::Input`]).
+///
+/// The session must include every new participant, and enough old
+/// participants to reconstruct the key: all of them for full-threshold key
+/// shares, or at least the threshold for threshold key shares. Every old
+/// participant in the session deals a share; old participants that are not
+/// in the session take no part.
+///
+/// # Protocol output
+/// Upon successful completion, each new participant produces a new
+/// [`keygen::Output`] holding its share of the same key. Old participants that
+/// are not new participants produce `None`.
+///
+/// # 🔒 Storage requirements
+/// The [private key share](KeySharePrivate) in the output requires secure
+/// persistent storage. Once every new participant has its output, the old
+/// private key shares must be securely deleted: any threshold of them can
+/// still reconstruct the key.
+///
+/// # 🔒 Networking requirements
+/// Each dealer sends a private share to every new participant. These messages
+/// must be sent over a confidential channel, in addition to the usual
+/// authentication and integrity requirements.
+///
+/// # High-level protocol description
+/// The resharing protocol runs in a single round. Each dealer converts its key
+/// share into an additive share of the key for the set of dealers (using
+/// Lagrange interpolation, for threshold key shares), and deals it out to the
+/// new participants:
+/// - For a threshold sharing, the dealer samples a polynomial of degree `t-1`
+///   whose constant term is its additive share. It broadcasts Feldman
+///   commitments to the polynomial's coefficients, and privately sends each new
+///   participant the polynomial evaluated at its evaluation point.
+/// - For a full-threshold sharing, the dealer splits its additive share into
+///   random additive pieces, one for each new participant. It broadcasts the
+///   public form of every piece, and privately sends each new participant its
+///   piece.
+///
+/// Every participant checks that each dealing is for the dealer's share of the
+/// key, by comparing it against the dealer's existing public key share. Each
+/// new participant also checks its private shares against the dealings, and
+/// adds them up to get its new key share.
+#[derive(Debug)]
+pub struct ReshareParticipant {
+    /// The current session identifier
+    sid: Identifier,
+    /// A unique identifier for this participant.
+    id: ParticipantIdentifier,
+    /// A list of all other participant identifiers participating in the
+    /// protocol
+    other_participant_ids: Vec<ParticipantIdentifier>,
+    /// The participants in the session that hold a share of the existing key
+    dealers: Vec<ParticipantIdentifier>,
+    /// Input to the protocol
+    input: Input,
+    /// Local storage for this participant to store secrets
+    local_storage: LocalStorage,
+    /// Broadcast subprotocol handler
+    broadcast_participant: BroadcastParticipant,
+    /// Status of the protocol execution.
+    status: Status,
+}
+
+impl ProtocolParticipant for ReshareParticipant {
+    type Input = Input;
+    type Output = Option<keygen::Output>;
+
+    fn new(
+        sid: Identifier,
+        id: ParticipantIdentifier,
+        other_participant_ids: Vec<ParticipantIdentifier>,
+        input: Self::Input,
+    ) -> Result<Self> {
+        let participants = std::iter::once(id)
+            .chain(other_participant_ids.iter().copied())
+            .collect::<HashSet<_>>();
+        if participants.len() != other_participant_ids.len() + 1 {
+            error!("The participant set contains duplicates");
+            Err(CallerError::BadInput)?
+        }
+
+        // Everyone in the session must be an old or new share holder, and every
+        // new share holder must be in the session.
+        let old_participants = input.old_participants();
+        let new_participants = input
+            .new_participants()
+            .iter()
+            .copied()
+            .collect::<HashSet<_>>();
+        if participants
+            .iter()
+            .any(|pid| !old_participants.contains(pid) && !new_participants.contains(pid))
+            || !new_participants.is_subset(&participants)
+        {
+            error!("The session participants don't match the old and new participant sets");
+            Err(CallerError::BadInput)?
+        }
+
+        // The old share holders in the session must be able to reconstruct the key.
+        let dealers = old_participants
+            .iter()
+            .copied()
+            .filter(|pid| participants.contains(pid))
+            .collect::<Vec<_>>();
+        let enough_dealers = match input.old_threshold() {
+            Some(threshold) => dealers.len() >= threshold,
+            None => dealers.len() == old_participants.len(),
+        };
+        if !enough_dealers {
+            error!("Not enough holders of the existing key are taking part in resharing");
+            Err(CallerError::BadInput)?
+        }
+
+        // Old share holders must provide their private key share.
+        match input.old_private_key_share() {
+            Some(private_key_share) => {
+                // With a single "dealer", this is just our existing public key share.
+                if !old_participants.contains(&id)
+                    || input.dealer_public_share(id, &[id])? != private_key_share.public_share()?
+                {
+                    error!("The provided private key share doesn't belong to this participant");
+                    Err(CallerError::BadInput)?
+                }
+            }
+            None => {
+                if old_participants.contains(&id) {
+                    error!("A holder of the existing key must provide its private key share");
+                    Err(CallerError::BadInput)?
+                }
+            }
+        }
+
+        Ok(Self {
+            sid,
+            id,
+            other_participant_ids: other_participant_ids.clone(),
+            dealers,
+            input,
+            local_storage: Default::default(),
+            broadcast_participant: BroadcastParticipant::new(sid, id, other_participant_ids, ())?,
+            status: Status::NotReady,
+        })
+    }
+
+    fn ready_type() -> MessageType {
+        MessageType::Reshare(ReshareMessageType::Ready)
+    }
+
+    fn protocol_type() -> ProtocolType {
+        ProtocolType::Reshare
+    }
+
+    fn id(&self) -> ParticipantIdentifier {
+        self.id
+    }
+
+    fn other_ids(&self) -> &[ParticipantIdentifier] {
+        &self.other_participant_ids
+    }
+
+    fn sid(&self) -> Identifier {
+        self.sid
+    }
+
+    fn export_state(&self) -> Result<Vec<u8>> {
+        ParticipantState::export(
+            &self.status,
+            &self.local_storage,
+            Some(&self.broadcast_participant),
+        )
+    }
+
+    fn import_state(&mut self, state: &[u8]) -> Result<()> {
+        ParticipantState::import(
+            state,
+            &mut self.status,
+            &mut self.local_storage,
+            Some(&mut self.broadcast_participant),
+        )
+    }
+
+    #[cfg_attr(feature = "flame_it", flame("reshare"))]
+    #[instrument(skip_all)]
+    fn process_message<R: RngCore + CryptoRng>(
+        &mut self,
+        rng: &mut R,
+        message: &Message,
+    ) -> Result<ProcessOutcome<Self::Output>> {
+        info!(
+            "RESHARE: Player {}: received {:?} from {}",
+            self.id(),
+            message.message_type(),
+            message.from()
+        );
+
+        if *self.status() == Status::TerminatedSuccessfully {
+            Err(CallerError::ProtocolAlreadyTerminated)?;
+        }
+
+        if !self.status().is_ready() && message.message_type() != Self::ready_type() {
+            self.stash_message(message)?;
+            return Ok(ProcessOutcome::Incomplete);
+        }
+
+        match message.message_type() {
+            MessageType::Reshare(ReshareMessageType::Ready) => self.handle_ready_msg(rng, message),
+            MessageType::Reshare(ReshareMessageType::R1Dealing) => {
+                let broadcast_outcome = self.handle_broadcast(rng, message)?;
+
+                // Handle the broadcasted message if all parties have agreed on it
+                broadcast_outcome.convert(self, Self::handle_dealing_msg, rng)
+            }
+            MessageType::Reshare(ReshareMessageType::R1PrivateShare) => {
+                self.handle_private_share_msg(message)
+            }
+            message_type => {
+                error!(
+                    "Incorrect MessageType given to ReshareParticipant. Got: {:?}",
+                    message_type
+                );
+                Err(InternalError::InternalInvariantFailed)
+            }
+        }
+    }
+
+    fn status(&self) -> &Status {
+        &self.status
+    }
+
+    fn waiting_on(&self) -> Vec<ParticipantIdentifier> {
+        if self.status != Status::Initialized {
+            return Vec::new();
+        }
+        let other_dealers = self
+            .dealers
+            .iter()
+            .copied()
+            .filter(|pid| *pid != self.id)
+            .collect::<Vec<_>>();
+
+        // The broadcast dealings and, for new participants, the private shares
+        let missing_dealings = self
+            .local_storage
+            .missing_ids::<storage::Dealing>(&other_dealers);
+        let mut waiting = self
+            .broadcast_participant
+            .waiting_on_broadcasts(BroadcastTag::ReshareR1Dealing, &missing_dealings);
+        if self.is_new_participant() {
+            waiting.extend(
+                self.local_storage
+                    .missing_ids::<storage::ReceivedShare>(&other_dealers),
+            );
+        }
+        self.other_participant_ids
+            .iter()
+            .copied()
+            .filter(|pid| waiting.contains(pid))
+            .collect()
+    }
+
+    fn abort(&mut self) {
+        self.local_storage = LocalStorage::default();
+        self.broadcast_participant.abort();
+        self.status = Status::Aborted;
+    }
+}
+
+impl InnerProtocolParticipant for ReshareParticipant {
+    type Context = SharedContext;
+
+    fn retrieve_context(&self) -> <Self as InnerProtocolParticipant>::Context {
+        SharedContext::collect(self)
+    }
+
+    fn local_storage(&self) -> &LocalStorage {
+        &self.local_storage
+    }
+
+    fn local_storage_mut(&mut self) -> &mut LocalStorage {
+        &mut self.local_storage
+    }
+
+    fn status_mut(&mut self) -> &mut Status {
+        &mut self.status
+    }
+}
+
+impl Broadcast for ReshareParticipant {
+    fn broadcast_participant(&mut self) -> &mut BroadcastParticipant {
+        &mut self.broadcast_participant
+    }
+}
+
+impl ReshareParticipant {
+    /// Whether this participant holds a share of the existing key.
+    fn is_dealer(&self) -> bool {
+        self.dealers.contains(&self.id)
+    }
+
+    /// Whether this participant will hold a share of the key after resharing.
+    fn is_new_participant(&self) -> bool {
+        self.input.new_participants().contains(&self.id)
+    }
+
+    /// Handle "Ready" messages from the protocol participants.
+    ///
+    /// Once "Ready" messages have been received from all participants, dealers
+    /// generate their dealing messages.
+    #[cfg_attr(feature = "flame_it", flame("reshare"))]
+    #[instrument(skip_all, err(Debug))]
+    fn handle_ready_msg<R: RngCore + CryptoRng>(
+        &mut self,
+        rng: &mut R,
+        message: &Message,
+    ) -> Result<ProcessOutcome<<Self as ProtocolParticipant>::Output>> {
+        info!("Handling ready reshare message.");
+
+        let ready_outcome = self.process_ready_message(rng, message)?;
+        if !self.is_dealer() {
+            return Ok(ready_outcome);
+        }
+
+        let dealing_messages = run_only_once!(self.gen_dealing_msgs(rng, message.id()))?;
+        // Our own dealing may have been the last thing we were waiting for.
+        let finish_outcome = self.maybe_finish()?;
+        ready_outcome
+            .with_messages(dealing_messages)
+            .consolidate(vec![finish_outcome])
+    }
+
+    /// Generate this dealer's messages.
+    ///
+    /// The outcome is a broadcast message containing the public commitments to
+    /// the dealt shares, and a private share for each new participant.
+    #[cfg_attr(feature = "flame_it", flame("reshare"))]
+    #[instrument(skip_all, err(Debug))]
+    fn gen_dealing_msgs<R: RngCore + CryptoRng>(
+        &mut self,
+        rng: &mut R,
+        sid: Identifier,
+    ) -> Result<Vec<Message>> {
+        info!("Generating reshare dealing messages.");
+
+        let q = k256_order();
+        let secret = self.input.dealer_private_share(self.id, &self.dealers)?;
+        let new_participants = self.input.new_participants().to_vec();
+
+        let (shares, commitments) = match self.input.new_threshold() {
+            Some(threshold) => {
+                let polynomial = Polynomial::random(&secret, threshold, rng)?;
+                let shares = new_participants
+                    .iter()
+                    .map(|pid| PrivateShare::new(polynomial.evaluate(&pid.evaluation_point())))
+                    .collect::<Vec<_>>();
+                (shares, DealingCommitments::Shamir(polynomial.commit()?))
+            }
+            None => {
+                let mut pieces = std::iter::repeat_with(|| random_positive_bn(rng, &q))
+                    .take(new_participants.len() - 1)
+                    .collect::<Vec<_>>();
+                let sum = pieces
+                    .iter()
+                    .fold(BigNumber::zero(), |sum, piece| sum.modadd(piece, &q));
+                pieces.push(secret.modsub(&sum, &q));
+                let public_pieces = new_participants
+                    .iter()
+                    .zip(&pieces)
+                    .map(|(&pid, piece)| {
+                        Ok(KeySharePublic::new(
+                            pid,
+                            CurvePoint::GENERATOR.multiply_by_bignum(piece)?,
+                        ))
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+                let shares = pieces.into_iter().map(PrivateShare::new).collect();
+                (shares, DealingCommitments::Additive(public_pieces))
+            }
+        };
+
+        let chain_code = *self.input.chain_code().ok_or_else(|| {
+            error!("Tried to deal a share without knowing the key's chain code");
+            InternalError::InternalInvariantFailed
+        })?;
+        let dealing = Dealing {
+            chain_code,
+            commitments,
+        };
+
+        let mut messages = self.broadcast(
+            rng,
+            MessageType::Reshare(ReshareMessageType::R1Dealing),
+            serialize!(&dealing)?,
+            sid,
+            BroadcastTag::ReshareR1Dealing,
+        )?;
+        self.local_storage
+            .store::<storage::Dealing>(self.id, dealing);
+
+        for (pid, share) in new_participants.into_iter().zip(shares) {
+            if pid == self.id {
+                // Our own share doesn't need to be sent anywhere.
+                self.local_storage
+                    .store::<storage::ReceivedShare>(self.id, share);
+            } else {
+                messages.push(Message::new(
+                    MessageType::Reshare(ReshareMessageType::R1PrivateShare),
+                    sid,
+                    self.id,
+                    pid,
+                    &share,
+                )?);
+            }
+        }
+        Ok(messages)
+    }
+
+    /// Handle a dealing broadcast by one of the dealers.
+    ///
+    /// Here we check that the dealing is for the dealer's share of the key,
+    /// and check any private share the dealer already sent us against it.
+    #[cfg_attr(feature = "flame_it", flame("reshare"))]
+    #[instrument(skip_all, err(Debug))]
+    fn handle_dealing_msg<R: RngCore + CryptoRng>(
+        &mut self,
+        _rng: &mut R,
+        broadcast_message: BroadcastOutput,
+    ) -> Result<ProcessOutcome<<Self as ProtocolParticipant>::Output>> {
+        info!("Handling reshare dealing message.");
+
+        let message = broadcast_message.into_message(BroadcastTag::ReshareR1Dealing)?;
+        if !self.dealers.contains(&message.from()) {
+            error!("Received a dealing from a participant that doesn't hold a key share");
+            return Err(InternalError::ProtocolError(Some(message.from())));
+        }
+        let dealing = Dealing::from_message(&message)?;
+        let expected_secret = self
+            .input
+            .dealer_public_share(message.from(), &self.dealers)?;
+        dealing.validate(
+            message.from(),
+            self.input.new_participants(),
+            self.input.new_threshold(),
+            &expected_secret,
+        )?;
+        // Participants holding a share of the key know its chain code, so they
+        // can tell if a dealer sent the wrong one.
+        if self
+            .input
+            .chain_code()
+            .is_some_and(|chain_code| chain_code != &dealing.chain_code)
+        {
+            error!("Dealing from {} has the wrong chain code", message.from());
+            return Err(InternalError::ProtocolError(Some(message.from())));
+        }
+        self.local_storage
+            .store::<storage::Dealing>(message.from(), dealing);
+
+        // Now that we have the dealing, check any private share the dealer sent
+        // us early.
+        for share_message in self.fetch_messages_by_sender(
+            MessageType::Reshare(ReshareMessageType::R1PrivateShare),
+            message.from(),
+        )? {
+            self.verify_private_share(&share_message)?;
+        }
+
+        self.maybe_finish()
+    }
+
+    /// Handle a private share sent by one of the dealers.
+    #[cfg_attr(feature = "flame_it", flame("reshare"))]
+    #[instrument(skip_all, err(Debug))]
+    fn handle_private_share_msg(
+        &mut self,
+        message: &Message,
+    ) -> Result<ProcessOutcome<<Self as ProtocolParticipant>::Output>> {
+        info!("Handling reshare private share message.");
+
+        if !self.is_new_participant() || !self.dealers.contains(&message.from()) {
+            error!("Received a private share, but either we aren't a new participant or the sender isn't a dealer");
+            return Err(InternalError::ProtocolError(Some(message.from())));
+        }
+
+        // We need the sender's dealing to check the share against.
+        if !self
+            .local_storage
+            .contains::<storage::Dealing>(message.from())
+        {
+            self.stash_message(message)?;
+            return Ok(ProcessOutcome::Incomplete);
+        }
+
+        self.verify_private_share(message)?;
+        self.maybe_finish()
+    }
+
+    /// Check that a private share is consistent with the sender's dealing, and
+    /// store it.
+    fn verify_private_share(&mut self, message: &Message) -> Result<()> {
+        let share = PrivateShare::from_message_of_type(
+            message,
+            MessageType::Reshare(ReshareMessageType::R1PrivateShare),
+        )?;
+        let expected = self
+            .local_storage
+            .retrieve::<storage::Dealing>(message.from())?
+            .share_for(self.id)?;
+        if !share.verify_public(&expected)? {
+            error!("Private share doesn't match the sender's dealing");
+            return Err(InternalError::ProtocolError(Some(message.from())));
+        }
+        self.local_storage
+            .store::<storage::ReceivedShare>(message.from(), share);
+        Ok(())
+    }
+
+    /// Terminate the protocol if we have every dealing (and, for new
+    /// participants, every private share).
+    fn maybe_finish(&mut self) -> Result<ProcessOutcome<<Self as ProtocolParticipant>::Output>> {
+        if *self.status() == Status::TerminatedSuccessfully {
+            return Ok(ProcessOutcome::Incomplete);
+        }
+        let dealings_done = self
+            .local_storage
+            .contains_for_all_ids::<storage::Dealing>(&self.dealers);
+        let shares_done = !self.is_new_participant()
+            || self
+                .local_storage
+                .contains_for_all_ids::<storage::ReceivedShare>(&self.dealers);
+        if !dealings_done || !shares_done {
+            return Ok(ProcessOutcome::Incomplete);
+        }
+
+        let output = if self.is_new_participant() {
+            Some(self.compute_output()?)
+        } else {
+            None
+        };
+        self.status = Status::TerminatedSuccessfully;
+        Ok(ProcessOutcome::Terminated(output))
+    }
+
+    /// Add up the received private shares to get this participant's new key
+    /// share, and combine the dealings to get every new participant's public
+    /// key share.
+    fn compute_output(&mut self) -> Result<keygen::Output> {
+        let q = k256_order();
+        let dealers = self.dealers.clone();
+
+        let private_share = dealers
+            .iter()
+            .map(|pid| self.local_storage.remove::<storage::ReceivedShare>(*pid))
+            .collect::<Result<Vec<_>>>()?
+            .iter()
+            .fold(BigNumber::zero(), |sum, share| {
+                sum.modadd(share.as_ref(), &q)
+            });
+        let private_key_share = KeySharePrivate::from_bignumber(&private_share);
+
+        let dealings = dealers
+            .iter()
+            .map(|pid| self.local_storage.retrieve::<storage::Dealing>(*pid))
+            .collect::<Result<Vec<_>>>()?;
+
+        // Each dealing was checked against the dealer's share of the key, so
+        // together they should always deal the key itself.
+        let dealt_key = dealings.iter().fold(CurvePoint::IDENTITY, |sum, dealing| {
+            sum + dealing.secret_commitment()
+        });
+        if dealt_key != self.input.public_key()? {
+            error!("Dealings don't add up to the original public key");
+            Err(InternalError::InternalInvariantFailed)?
+        }
+
+        // The new output keeps the key's chain code, so child keys derived from
+        // it don't change. Every dealer holds the key, so they must agree on it.
+        let chain_code = dealings
+            .first()
+            .map(|dealing| dealing.chain_code)
+            .ok_or(InternalError::InternalInvariantFailed)?;
+        if dealings
+            .iter()
+            .any(|dealing| dealing.chain_code != chain_code)
+        {
+            error!("Dealers disagree on the chain code of the key");
+            Err(InternalError::ProtocolError(None))?
+        }
+
+        let new_participants = self.input.new_participants();
+        let public_key_shares = match self.input.new_threshold() {
+            Some(threshold) => {
+                // The new polynomial is the sum of the dealers' polynomials, so
+                // its commitments are the sums of the individual commitments.
+                let commitments = (0..threshold)
+                    .map(|k| {
+                        dealings.iter().try_fold(
+                            CurvePoint::IDENTITY,
+                            |sum, dealing| match &dealing.commitments {
+                                DealingCommitments::Shamir(commitments) => Ok(sum + commitments[k]),
+                                DealingCommitments::Additive(_) => {
+                                    error!("Stored dealing has the wrong form");
+                                    Err(InternalError::InternalInvariantFailed)
+                                }
+                            },
+                        )
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+                new_participants
+                    .iter()
+                    .map(|&pid| KeySharePublic::new_shamir(pid, commitments.clone()))
+                    .collect::<Result<Vec<_>>>()?
+            }
+            None => new_participants
+                .iter()
+                .map(|&pid| {
+                    let share = dealings
+                        .iter()
+                        .try_fold(CurvePoint::IDENTITY, |sum, dealing| {
+                            Ok::<_, InternalError>(sum + dealing.share_for(pid)?)
+                        })?;
+                    Ok(KeySharePublic::new(pid, share))
+                })
+                .collect::<Result<Vec<_>>>()?,
+        };
+
+        keygen::Output::from_parts(public_key_shares, private_key_share, chain_code)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        keygen::shamir::lagrange_coefficient_at_zero, utils::testing::init_testing,
+        ParticipantConfig,
+    };
+    use k256::ecdsa::VerifyingKey;
+    use rand::Rng;
+    use std::collections::HashMap;
+
+    impl ReshareParticipant {
+        pub fn initialize_reshare_message(
+            &self,
+            reshare_identifier: Identifier,
+        ) -> Result<Message> {
+            let empty: [u8; 0] = [];
+            Message::new(
+                MessageType::Reshare(ReshareMessageType::Ready),
+                reshare_identifier,
+                self.id,
+                self.id,
+                &empty,
+            )
+        }
+    }
+
+    /// Run resharing to completion with the given participants and inputs, and
+    /// return each participant's output.
+    fn run_reshare<R: RngCore + CryptoRng>(
+        inputs: Vec<(ParticipantIdentifier, Input)>,
+        rng: &mut R,
+    ) -> Result<HashMap<ParticipantIdentifier, Option<keygen::Output>>> {
+        let sid = Identifier::random(rng);
+        let pids = inputs.iter().map(|(pid, _)| *pid).collect::<Vec<_>>();
+        let mut quorum = inputs
+            .into_iter()
+            .map(|(pid, input)| {
+                let others = pids.iter().copied().filter(|&other| other != pid).collect();
+                ReshareParticipant::new(sid, pid, others, input)
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut inboxes = HashMap::new();
+        for participant in &quorum {
+            let _ = inboxes.insert(
+                participant.id,
+                vec![participant.initialize_reshare_message(sid)?],
+            );
+        }
+        let mut outputs = HashMap::new();
+
+        while !quorum
+            .iter()
+            .all(|participant| *participant.status() == Status::TerminatedSuccessfully)
+        {
+            // Pick a random participant to process
+            let index = rng.gen_range(0..quorum.len());
+            let participant = quorum.get_mut(index).unwrap();
+            let inbox = inboxes.get_mut(&participant.id).unwrap();
+            if inbox.is_empty() {
+                continue;
+            }
+            let message = inbox.remove(rng.gen_range(0..inbox.len()));
+
+            // Deliver messages and save outputs
+            let (output, messages) = participant.process_message(rng, &message)?.into_parts();
+            if let Some(output) = output {
+                assert!(outputs.insert(participant.id, output).is_none());
+            }
+            for message in messages {
+                inboxes.get_mut(&message.to()).unwrap().push(message);
+            }
+        }
+
+        // Make sure every player got an output and every message was delivered
+        assert_eq!(outputs.len(), quorum.len());
+        assert!(inboxes.values().all(Vec::is_empty));
+        Ok(outputs)
+    }
+
+    /// Reconstruct the public key from a threshold of private key shares.
+    fn reconstruct(outputs: &[&keygen::Output]) -> Result<VerifyingKey> {
+        let q = k256_order();
+        let points = outputs
+            .iter()
+            .map(|output| Ok(output.private_pid()?.evaluation_point()))
+            .collect::<Result<Vec<_>>>()?;
+        let secret =
+            outputs
+                .iter()
+                .zip(&points)
+                .try_fold(BigNumber::zero(), |sum, (output, point)| {
+                    let lambda = lagrange_coefficient_at_zero(point, &points)?;
+                    Ok::<_, InternalError>(
+                        sum.modadd(&lambda.modmul(output.private_key_share().as_ref(), &q), &q),
+                    )
+                })?;
+        let secret_public = CurvePoint::GENERATOR.multiply_by_bignum(&secret)?;
+        Ok(VerifyingKey::from_encoded_point(&secret_public.into()).unwrap())
+    }
+
+    fn random_pids<R: RngCore + CryptoRng>(n: usize, rng: &mut R) -> Vec<ParticipantIdentifier> {
+        std::iter::repeat_with(|| ParticipantIdentifier::random(rng))
+            .take(n)
+            .collect()
+    }
+
+    #[test]
+    fn reshare_moves_threshold_key_to_new_participants() -> Result<()> {
+        let rng = &mut init_testing();
+        let configs = ParticipantConfig::random_quorum(3, rng)?;
+        let old_pids = configs
+            .iter()
+            .map(ParticipantConfig::id)
+            .collect::<Vec<_>>();
+        let keygen_outputs = keygen::Output::simulate_threshold_set(&configs, 2, rng);
+        let public_key = keygen_outputs[0].public_key()?;
+        let old_public_key_shares = keygen_outputs[0].public_key_shares().to_vec();
+
+        // The first old participant is absent, the second leaves, the third stays
+        // and three more join, with a new threshold of three.
+        let joiners = random_pids(3, rng);
+        let new_pids = [&old_pids[2..], joiners.as_slice()].concat();
+        let new_sharing = keygen::Input::with_threshold(3)?;
+        let mut inputs = keygen_outputs[1..]
+            .iter()
+            .zip(&old_pids[1..])
+            .map(|(output, &pid)| {
+                Ok((
+                    pid,
+                    Input::new(output.clone(), new_pids.clone(), new_sharing)?,
+                ))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        for &pid in &joiners {
+            let input = Input::new_participant(
+                old_public_key_shares.clone(),
+                new_pids.clone(),
+                new_sharing,
+            )?;
+            inputs.push((pid, input));
+        }
+
+        let outputs = run_reshare(inputs, rng)?;
+
+        // The leaving participant doesn't get a key share
+        assert!(outputs[&old_pids[1]].is_none());
+
+        let new_outputs = new_pids
+            .iter()
+            .map(|pid| outputs[pid].as_ref().unwrap())
+            .collect::<Vec<_>>();
+        for output in &new_outputs {
+            assert_eq!(output.public_key()?, public_key);
+            assert_eq!(output.threshold(), 3);
+            assert_eq!(
+                output.public_key_shares(),
+                new_outputs[0].public_key_shares()
+            );
+        }
+
+        // Any three new shares reconstruct the key, but two don't
+        for quorum in new_outputs.windows(3) {
+            assert_eq!(reconstruct(quorum)?, public_key);
+        }
+        assert_ne!(reconstruct(&new_outputs[..2])?, public_key);
+        Ok(())
+    }
+
+    #[test]
+    fn reshare_moves_full_threshold_key_to_new_participants() -> Result<()> {
+        let rng = &mut init_testing();
+        let configs = ParticipantConfig::random_quorum(3, rng)?;
+        let old_pids = configs
+            .iter()
+            .map(ParticipantConfig::id)
+            .collect::<Vec<_>>();
+        let keygen_outputs = keygen::Output::simulate_set(&configs, rng);
+        let public_key = keygen_outputs[0].public_key()?;
+        let old_public_key_shares = keygen_outputs[0].public_key_shares().to_vec();
+
+        // Every old participant must take part; two of them stay and one joins.
+        let joiner = ParticipantIdentifier::random(rng);
+        let new_pids = vec![old_pids[0], old_pids[2], joiner];
+        let new_sharing = keygen::Input::full_threshold();
+        let mut inputs = keygen_outputs
+            .iter()
+            .zip(&old_pids)
+            .map(|(output, &pid)| {
+                Ok((
+                    pid,
+                    Input::new(output.clone(), new_pids.clone(), new_sharing)?,
+                ))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        inputs.push((
+            joiner,
+            Input::new_participant(old_public_key_shares, new_pids.clone(), new_sharing)?,
+        ));
+
+        let outputs = run_reshare(inputs, rng)?;
+        assert!(outputs[&old_pids[1]].is_none());
+
+        let new_outputs = new_pids
+            .iter()
+            .map(|pid| outputs[pid].as_ref().unwrap())
+            .collect::<Vec<_>>();
+        let q = k256_order();
+        let secret = new_outputs.iter().fold(BigNumber::zero(), |sum, output| {
+            sum.modadd(output.private_key_share().as_ref(), &q)
+        });
+        let secret_public = CurvePoint::GENERATOR.multiply_by_bignum(&secret)?;
+        for output in &new_outputs {
+            assert!(!output.is_threshold());
+            assert_eq!(output.public_key()?, public_key);
+            assert_eq!(
+                output.public_key_shares(),
+                new_outputs[0].public_key_shares()
+            );
+        }
+        assert_eq!(
+            VerifyingKey::from_encoded_point(&secret_public.into()).unwrap(),
+            public_key
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn reshare_keeps_derived_child_keys() -> Result<()> {
+        let rng = &mut init_testing();
+        let configs = ParticipantConfig::random_quorum(3, rng)?;
+        let old_pids = configs
+            .iter()
+            .map(ParticipantConfig::id)
+            .collect::<Vec<_>>();
+        let keygen_outputs = keygen::Output::simulate_threshold_set(&configs, 2, rng);
+        let child_key = keygen_outputs[0]
+            .derive_child(&"m/1/2".parse()?)?
+            .public_key()?;
+
+        // Two old participants stay and one joins, so the joiner can only learn
+        // the chain code from the dealers.
+        let joiner = ParticipantIdentifier::random(rng);
+        let new_pids = vec![old_pids[0], old_pids[1], joiner];
+        let new_sharing = keygen::Input::with_threshold(2)?;
+        let mut inputs = keygen_outputs[..2]
+            .iter()
+            .zip(&old_pids)
+            .map(|(output, &pid)| {
+                Ok((
+                    pid,
+                    Input::new(output.clone(), new_pids.clone(), new_sharing)?,
+                ))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        inputs.push((
+            joiner,
+            Input::new_participant(
+                keygen_outputs[0].public_key_shares().to_vec(),
+                new_pids.clone(),
+                new_sharing,
+            )?,
+        ));
+
+        let outputs = run_reshare(inputs, rng)?;
+
+        for pid in &new_pids {
+            let output = outputs[pid].as_ref().unwrap();
+            assert_eq!(output.chain_code(), keygen_outputs[0].chain_code());
+            assert_eq!(
+                output.derive_child(&"m/1/2".parse()?)?.public_key()?,
+                child_key
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn reshare_requires_valid_participant_sets() -> Result<()> {
+        let rng = &mut init_testing();
+        let sid = Identifier::random(rng);
+        let configs = ParticipantConfig::random_quorum(3, rng)?;
+        let old_pids = configs
+            .iter()
+            .map(ParticipantConfig::id)
+            .collect::<Vec<_>>();
+        let keygen_outputs = keygen::Output::simulate_threshold_set(&configs, 2, rng);
+        let old_public_key_shares = keygen_outputs[0].public_key_shares().to_vec();
+        let joiner = ParticipantIdentifier::random(rng);
+        let new_pids = vec![old_pids[0], joiner];
+        let new_sharing = keygen::Input::with_threshold(2)?;
+        let input = Input::new(keygen_outputs[0].clone(), new_pids.clone(), new_sharing)?;
+
+        // A threshold of old participants plus the new participants works
+        assert!(ReshareParticipant::new(
+            sid,
+            old_pids[0],
+            vec![old_pids[1], joiner],
+            input.clone()
+        )
+        .is_ok());
+
+        // Too few old participants don't
+        assert!(ReshareParticipant::new(sid, old_pids[0], vec![joiner], input.clone()).is_err());
+
+        // Every new participant must take part
+        assert!(
+            ReshareParticipant::new(sid, old_pids[0], vec![old_pids[1], old_pids[2]], input)
+                .is_err()
+        );
+
+        // Old participants must provide their key share
+        let joiner_input = Input::new_participant(old_public_key_shares, new_pids, new_sharing)?;
+        assert!(
+            ReshareParticipant::new(sid, old_pids[1], vec![old_pids[0], joiner], joiner_input)
+                .is_err()
+        );
+        Ok(())
+    }
+}
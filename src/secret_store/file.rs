@@ -0,0 +1,278 @@
+// Copyright (c) 2022-2023 Bolt Labs Holdings, Inc
+//
+// This source code is licensed under both the MIT license found in the
+// LICENSE-MIT file in the root directory of this source tree and the Apache
+// License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+// of this source tree.
+
+use std::{
+    fmt::{Debug, Formatter},
+    fs::{self, File},
+    io::{ErrorKind, Write},
+    path::{Path, PathBuf},
+};
+
+use chacha20poly1305::{
+    aead::{Aead, AeadCore, KeyInit, OsRng, Payload},
+    Key, XChaCha20Poly1305, XNonce,
+};
+use sha2::{Digest, Sha256};
+use tracing::error;
+
+use crate::{
+    errors::{CallerError, Result},
+    secret_store::{RecordType, SecretStore},
+};
+
+/// Length of the nonce at the start of each record file.
+const NONCE_LEN: usize = 24;
+
+/// A [`SecretStore`] that keeps each record in its own file in a directory.
+///
+/// Records are encrypted with XChaCha20-Poly1305 under a 256-bit key supplied
+/// by the calling application. Each record is bound to its key ID and type, so
+/// record files can't be swapped or renamed without detection.
+///
+/// Records are written to a temporary file and then renamed into place, so a
+/// crash during a write leaves either the old record or the new one. The
+/// directory is synced after each change, so that a completed write or delete
+/// survives a crash too.
+///
+/// Record files are named after a hash of the key ID, so key IDs of any length
+/// and content can be used.
+///
+/// # 🔒 Storage requirements
+/// The encryption key must be stored securely by the calling application, and
+/// must not be stored in the same directory as the records. Deleting a record
+/// removes its file, but whether the contents are actually erased depends on
+/// the file system.
+pub struct FileSecretStore {
+    directory: PathBuf,
+    cipher: XChaCha20Poly1305,
+}
+
+impl Debug for FileSecretStore {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FileSecretStore")
+            .field("directory", &self.directory)
+            .field("key", &"[redacted]")
+            .finish()
+    }
+}
+
+impl FileSecretStore {
+    /// Create a store that keeps records in `directory`, encrypted under
+    /// `key`. The directory is created if it doesn't exist.
+    pub fn new(directory: impl Into<PathBuf>, key: &[u8; 32]) -> Result<Self> {
+        let directory = directory.into();
+        fs::create_dir_all(&directory).map_err(|e| {
+            error!("Failed to create secret store directory: {}", e);
+            CallerError::SecretStoreFailed
+        })?;
+        Ok(Self {
+            directory,
+            cipher: XChaCha20Poly1305::new(Key::from_slice(key)),
+        })
+    }
+
+    /// The directory the records are kept in.
+    pub fn directory(&self) -> &Path {
+        &self.directory
+    }
+
+    fn file_name(key_id: &str, record_type: RecordType) -> String {
+        // Hash the key ID so that any string makes a valid file name, however
+        // long it is. The key ID is bound to the record by the associated data,
+        // so this doesn't need to be reversible.
+        format!(
+            "{}.{}",
+            hex::encode(Sha256::digest(key_id.as_bytes())),
+            record_type.name()
+        )
+    }
+
+    /// Flush the directory itself, so that renames and deletions of record
+    /// files are durable.
+    fn sync_directory(&self) -> std::io::Result<()> {
+        File::open(&self.directory)?.sync_all()
+    }
+
+    /// The associated data for a record, which binds its ciphertext to its
+    /// key ID and type.
+    fn associated_data(key_id: &str, record_type: RecordType) -> Vec<u8> {
+        [
+            &key_id.len().to_le_bytes(),
+            key_id.as_bytes(),
+            record_type.name().as_bytes(),
+        ]
+        .concat()
+    }
+}
+
+impl SecretStore for FileSecretStore {
+    fn put(&mut self, key_id: &str, record_type: RecordType, value: &[u8]) -> Result<()> {
+        let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let ciphertext = self
+            .cipher
+            .encrypt(
+                &nonce,
+                Payload {
+                    msg: value,
+                    aad: &Self::associated_data(key_id, record_type),
+                },
+            )
+            .map_err(|_| {
+                error!("Failed to encrypt secret store record");
+                CallerError::SecretStoreFailed
+            })?;
+
+        let file_name = Self::file_name(key_id, record_type);
+        let temp_path = self.directory.join(format!(".{file_name}.tmp"));
+        let write = || -> std::io::Result<()> {
+            let mut file = File::create(&temp_path)?;
+            file.write_all(&nonce)?;
+            file.write_all(&ciphertext)?;
+            file.sync_all()?;
+            fs::rename(&temp_path, self.directory.join(&file_name))?;
+            self.sync_directory()
+        };
+        write().map_err(|e| {
+            error!("Failed to write secret store record: {}", e);
+            CallerError::SecretStoreFailed.into()
+        })
+    }
+
+    fn get(&self, key_id: &str, record_type: RecordType) -> Result<Option<Vec<u8>>> {
+        let path = self.directory.join(Self::file_name(key_id, record_type));
+        let bytes = match fs::read(path) {
+            Ok(bytes) => bytes,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+            Err(e) => {
+                error!("Failed to read secret store record: {}", e);
+                Err(CallerError::SecretStoreFailed)?
+            }
+        };
+
+        if bytes.len() < NONCE_LEN {
+            error!("Secret store record is too short");
+            Err(CallerError::SecretStoreFailed)?
+        }
+        let (nonce, ciphertext) = bytes.split_at(NONCE_LEN);
+        let plaintext = self
+            .cipher
+            .decrypt(
+                XNonce::from_slice(nonce),
+                Payload {
+                    msg: ciphertext,
+                    aad: &Self::associated_data(key_id, record_type),
+                },
+            )
+            .map_err(|_| {
+                error!("Failed to decrypt secret store record. Either the key is wrong or the record was modified");
+                CallerError::SecretStoreFailed
+            })?;
+        Ok(Some(plaintext))
+    }
+
+    fn delete(&mut self, key_id: &str, record_type: RecordType) -> Result<()> {
+        let path = self.directory.join(Self::file_name(key_id, record_type));
+        match fs::remove_file(path).and_then(|()| self.sync_directory()) {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(()),
+            Err(e) => {
+                error!("Failed to delete secret store record: {}", e);
+                Err(CallerError::SecretStoreFailed)?
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        secret_store::tests::{
+            outputs_round_trip, presign_records_are_not_replaced,
+            records_can_be_replaced_and_deleted,
+        },
+        utils::testing::init_testing,
+    };
+    use rand::Rng;
+
+    /// Create a store in a fresh temporary directory.
+    fn temp_store(key: &[u8; 32]) -> FileSecretStore {
+        let rng = &mut init_testing();
+        let name = format!(
+            "tss-ecdsa-secret-store-{}",
+            hex::encode(rng.gen::<[u8; 16]>())
+        );
+        FileSecretStore::new(std::env::temp_dir().join(name), key).unwrap()
+    }
+
+    #[test]
+    fn file_store_works() {
+        let mut store = temp_store(&[1; 32]);
+        outputs_round_trip(&mut store);
+        records_can_be_replaced_and_deleted(&mut store);
+        presign_records_are_not_replaced(&mut store);
+
+        // Records persist across instances with the same key
+        let reopened = FileSecretStore::new(store.directory(), &[1; 32]).unwrap();
+        assert!(reopened
+            .get("key", RecordType::AuxInfoOutput)
+            .unwrap()
+            .is_some());
+
+        fs::remove_dir_all(store.directory()).unwrap();
+    }
+
+    #[test]
+    fn file_store_accepts_long_key_ids() {
+        let mut store = temp_store(&[1; 32]);
+        let key_id = "k".repeat(1000);
+        store
+            .put(&key_id, RecordType::KeygenOutput, b"secret")
+            .unwrap();
+        assert_eq!(
+            store.get(&key_id, RecordType::KeygenOutput).unwrap(),
+            Some(b"secret".to_vec())
+        );
+        store.delete(&key_id, RecordType::KeygenOutput).unwrap();
+        assert!(store
+            .get(&key_id, RecordType::KeygenOutput)
+            .unwrap()
+            .is_none());
+
+        fs::remove_dir_all(store.directory()).unwrap();
+    }
+
+    #[test]
+    fn file_store_detects_wrong_keys_and_tampering() {
+        let mut store = temp_store(&[1; 32]);
+        store
+            .put("key", RecordType::KeygenOutput, b"secret")
+            .unwrap();
+
+        // Records can't be read with the wrong key...
+        let wrong_key = FileSecretStore::new(store.directory(), &[2; 32]).unwrap();
+        assert!(wrong_key.get("key", RecordType::KeygenOutput).is_err());
+
+        // ...or after being moved to a different key ID or type...
+        let path = store
+            .directory()
+            .join(FileSecretStore::file_name("key", RecordType::KeygenOutput));
+        let moved = store
+            .directory()
+            .join(FileSecretStore::file_name("key", RecordType::AuxInfoOutput));
+        fs::copy(&path, moved).unwrap();
+        assert!(store.get("key", RecordType::AuxInfoOutput).is_err());
+
+        // ...or after being modified
+        let mut bytes = fs::read(&path).unwrap();
+        *bytes.last_mut().unwrap() ^= 1;
+        fs::write(&path, bytes).unwrap();
+        assert!(store.get("key", RecordType::KeygenOutput).is_err());
+
+        fs::remove_dir_all(store.directory()).unwrap();
+    }
+}
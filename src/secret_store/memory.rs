@@ -0,0 +1,79 @@
+// Copyright (c) 2022-2023 Bolt Labs Holdings, Inc
+//
+// This source code is licensed under both the MIT license found in the
+// LICENSE-MIT file in the root directory of this source tree and the Apache
+// License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+// of this source tree.
+
+use std::{
+    collections::HashMap,
+    fmt::{Debug, Formatter},
+};
+
+use zeroize::Zeroizing;
+
+use crate::{
+    errors::Result,
+    secret_store::{RecordType, SecretStore},
+};
+
+/// A [`SecretStore`] that keeps records in memory.
+///
+/// Records are zeroized when they are replaced, deleted or dropped. Nothing is
+/// persisted, so records are lost when the store is dropped.
+#[derive(Default)]
+pub struct MemorySecretStore {
+    records: HashMap<(String, RecordType), Zeroizing<Vec<u8>>>,
+}
+
+impl Debug for MemorySecretStore {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MemorySecretStore")
+            .field("records", &self.records.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+impl MemorySecretStore {
+    /// Create an empty store.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl SecretStore for MemorySecretStore {
+    fn put(&mut self, key_id: &str, record_type: RecordType, value: &[u8]) -> Result<()> {
+        let _ = self.records.insert(
+            (key_id.to_string(), record_type),
+            Zeroizing::new(value.to_vec()),
+        );
+        Ok(())
+    }
+
+    fn get(&self, key_id: &str, record_type: RecordType) -> Result<Option<Vec<u8>>> {
+        Ok(self
+            .records
+            .get(&(key_id.to_string(), record_type))
+            .map(|value| value.to_vec()))
+    }
+
+    fn delete(&mut self, key_id: &str, record_type: RecordType) -> Result<()> {
+        let _ = self.records.remove(&(key_id.to_string(), record_type));
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::secret_store::tests::{
+        outputs_round_trip, presign_records_are_not_replaced, records_can_be_replaced_and_deleted,
+    };
+
+    #[test]
+    fn memory_store_works() {
+        outputs_round_trip(&mut MemorySecretStore::new());
+        records_can_be_replaced_and_deleted(&mut MemorySecretStore::new());
+        presign_records_are_not_replaced(&mut MemorySecretStore::new());
+    }
+}
@@ -0,0 +1,247 @@
+// Copyright (c) 2022-2023 Bolt Labs Holdings, Inc
+//
+// This source code is licensed under both the MIT license found in the
+// LICENSE-MIT file in the root directory of this source tree and the Apache
+// License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+// of this source tree.
+
+//! Storage backends for the secret outputs of the subprotocols.
+//!
+//! The outputs of [`keygen`](crate::keygen::KeygenParticipant),
+//! [`auxinfo`](crate::auxinfo::AuxInfoParticipant) and
+//! [`presign`](crate::PresignParticipant) contain private values that the
+//! calling application must store securely. A [`SecretStore`] is a place to
+//! put them: it holds opaque records, keyed by a key ID chosen by the
+//! application and a [`RecordType`].
+//!
+//! This module provides two implementations:
+//! - [`MemorySecretStore`] keeps records in memory, which is useful for testing
+//!   and for applications that handle persistence some other way; and
+//! - [`FileSecretStore`] keeps each record in its own file, encrypted with a key
+//!   supplied by the application.
+//!
+//! Applications can implement [`SecretStore`] for other backends, such as a
+//! database or a hardware security module.
+//!
+//! Outputs can be stored and loaded with [`SecretStore::put_record()`] and
+//! [`SecretStore::get_record()`], or stored automatically when a protocol
+//! completes with
+//! [`Participant::process_single_message_and_store()`](crate::Participant::process_single_message_and_store()).
+
+mod file;
+mod memory;
+
+pub use file::FileSecretStore;
+pub use memory::MemorySecretStore;
+
+use serde::{Deserialize, Serialize};
+use tracing::error;
+use zeroize::Zeroize;
+
+use crate::errors::{CallerError, Result};
+
+/// The type of a record held in a [`SecretStore`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum RecordType {
+    /// The [`Output`](crate::keygen::Output) of keygen.
+    KeygenOutput,
+    /// The [`Output`](crate::auxinfo::Output) of auxinfo.
+    AuxInfoOutput,
+    /// A [`PresignRecord`](crate::PresignRecord).
+    PresignRecord,
+    /// A [`SafePrimePool`](crate::auxinfo::SafePrimePool).
+    SafePrimes,
+}
+
+impl RecordType {
+    /// A short name for the record type, suitable for use in file names.
+    pub fn name(&self) -> &'static str {
+        match self {
+            RecordType::KeygenOutput => "keygen-output",
+            RecordType::AuxInfoOutput => "auxinfo-output",
+            RecordType::PresignRecord => "presign-record",
+            RecordType::SafePrimes => "safe-primes",
+        }
+    }
+}
+
+/// A type that can be persisted in a [`SecretStore`].
+pub trait SecretRecord: Sized {
+    /// The type of record this is stored as.
+    const RECORD_TYPE: RecordType;
+
+    /// Whether the record must only be used once. A single-use record is
+    /// never replaced by [`SecretStore::put_record()`], since replacing it
+    /// would silently lose it; each one needs its own key ID.
+    const SINGLE_USE: bool = false;
+
+    /// Convert the value into bytes.
+    ///
+    /// 🔒 The output may contain secret values and must be handled with care.
+    fn to_record_bytes(&self) -> Result<Vec<u8>>;
+
+    /// Convert bytes produced by
+    /// [`to_record_bytes()`](SecretRecord::to_record_bytes()) back into a
+    /// value.
+    fn from_record_bytes(bytes: Vec<u8>) -> Result<Self>;
+}
+
+/// A storage backend for secret records.
+///
+/// Records are opaque byte strings, keyed by a key ID and a [`RecordType`].
+///
+/// # 🔒 Storage requirements
+/// Implementations must satisfy the requirements on external storage
+/// described for [`Participant`](crate::Participant): records must be
+/// encrypted at rest, reads must return the most recent write, and deleted
+/// records must be securely deleted.
+pub trait SecretStore {
+    /// Store a record, replacing any existing record with the same key ID and
+    /// type.
+    fn put(&mut self, key_id: &str, record_type: RecordType, value: &[u8]) -> Result<()>;
+
+    /// Retrieve a record, or `None` if there is no record with the given key
+    /// ID and type.
+    fn get(&self, key_id: &str, record_type: RecordType) -> Result<Option<Vec<u8>>>;
+
+    /// Delete a record. Deleting a record that doesn't exist is not an error.
+    fn delete(&mut self, key_id: &str, record_type: RecordType) -> Result<()>;
+
+    /// Store a value under the given key ID.
+    ///
+    /// Fails if the value is [single-use](SecretRecord::SINGLE_USE) and there
+    /// is already a record of the same type under `key_id`.
+    fn put_record<T: SecretRecord>(&mut self, key_id: &str, record: &T) -> Result<()>
+    where
+        Self: Sized,
+    {
+        if T::SINGLE_USE {
+            if let Some(mut existing) = self.get(key_id, T::RECORD_TYPE)? {
+                existing.zeroize();
+                error!(
+                    "Tried to replace the single-use {} record under key ID {}",
+                    T::RECORD_TYPE.name(),
+                    key_id
+                );
+                Err(CallerError::BadInput)?
+            }
+        }
+        let mut bytes = record.to_record_bytes()?;
+        let result = self.put(key_id, T::RECORD_TYPE, &bytes);
+        bytes.zeroize();
+        result
+    }
+
+    /// Retrieve the value stored under the given key ID, or `None` if there
+    /// isn't one.
+    fn get_record<T: SecretRecord>(&self, key_id: &str) -> Result<Option<T>>
+    where
+        Self: Sized,
+    {
+        self.get(key_id, T::RECORD_TYPE)?
+            .map(T::from_record_bytes)
+            .transpose()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{auxinfo, keygen, utils::testing::init_testing, ParticipantConfig, PresignRecord};
+
+    /// Check that each kind of output survives a round trip through the
+    /// store.
+    pub(super) fn outputs_round_trip(store: &mut impl SecretStore) {
+        let rng = &mut init_testing();
+        let configs = ParticipantConfig::random_quorum(3, rng).unwrap();
+        let keygen_outputs = keygen::Output::simulate_set(&configs, rng);
+        let auxinfo_output = auxinfo::Output::simulate_set(&configs, rng).remove(0);
+        let record = PresignRecord::simulate_set(&keygen_outputs, rng).remove(0);
+        let keygen_output = keygen_outputs[0].clone();
+
+        store.put_record("key", &keygen_output).unwrap();
+        store.put_record("key", &auxinfo_output).unwrap();
+        store.put_record("key", &record).unwrap();
+
+        let loaded: keygen::Output = store.get_record("key").unwrap().unwrap();
+        assert_eq!(
+            loaded.public_key_shares(),
+            keygen_output.public_key_shares()
+        );
+        assert_eq!(
+            loaded.private_key_share(),
+            keygen_output.private_key_share()
+        );
+        assert_eq!(loaded.rid(), keygen_output.rid());
+
+        let loaded: auxinfo::Output = store.get_record("key").unwrap().unwrap();
+        assert_eq!(loaded.into_parts(), auxinfo_output.into_parts());
+
+        let loaded: PresignRecord = store.get_record("key").unwrap().unwrap();
+        assert!(loaded == record);
+
+        // Records with a different key ID are separate
+        assert!(store
+            .get_record::<keygen::Output>("other key")
+            .unwrap()
+            .is_none());
+    }
+
+    /// Check that a presign record can't be replaced by another one, but can be
+    /// stored again once it has been deleted.
+    pub(super) fn presign_records_are_not_replaced(store: &mut impl SecretStore) {
+        let rng = &mut init_testing();
+        let configs = ParticipantConfig::random_quorum(3, rng).unwrap();
+        let keygen_outputs = keygen::Output::simulate_set(&configs, rng);
+        let mut records = PresignRecord::simulate_set(&keygen_outputs, rng);
+
+        store.put_record("presign key", &records[0]).unwrap();
+        assert!(store.put_record("presign key", &records[1]).is_err());
+        let loaded: PresignRecord = store.get_record("presign key").unwrap().unwrap();
+        assert!(loaded == records[0]);
+
+        // Other key IDs are unaffected
+        store.put_record("other presign key", &records[1]).unwrap();
+
+        store
+            .delete("presign key", RecordType::PresignRecord)
+            .unwrap();
+        let record = records.remove(2);
+        store.put_record("presign key", &record).unwrap();
+        let loaded: PresignRecord = store.get_record("presign key").unwrap().unwrap();
+        assert!(loaded == record);
+    }
+
+    /// Check that records can be overwritten and deleted.
+    pub(super) fn records_can_be_replaced_and_deleted(store: &mut impl SecretStore) {
+        store
+            .put("key", RecordType::KeygenOutput, b"first")
+            .unwrap();
+        store
+            .put("key", RecordType::AuxInfoOutput, b"other")
+            .unwrap();
+        store
+            .put("key", RecordType::KeygenOutput, b"second")
+            .unwrap();
+        assert_eq!(
+            store.get("key", RecordType::KeygenOutput).unwrap().unwrap(),
+            b"second"
+        );
+
+        store.delete("key", RecordType::KeygenOutput).unwrap();
+        assert!(store
+            .get("key", RecordType::KeygenOutput)
+            .unwrap()
+            .is_none());
+        assert_eq!(
+            store
+                .get("key", RecordType::AuxInfoOutput)
+                .unwrap()
+                .unwrap(),
+            b"other"
+        );
+
+        // Deleting a missing record is fine
+        store.delete("key", RecordType::KeygenOutput).unwrap();
+    }
+}
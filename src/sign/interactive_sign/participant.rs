@@ -5,9 +5,12 @@
 // License, Version 2.0 found in the LICENSE-APACHE file in the root directory
 // of this source tree.
 
+use generic_array::typenum::U32;
 use rand::{CryptoRng, RngCore};
+use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
 use tracing::{error, info};
+use zeroize::ZeroizeOnDrop;
 
 use crate::{
     auxinfo,
@@ -32,7 +35,9 @@ use crate::{
 /// # Protocol input
 /// The protocol takes several fields as input:
 /// - a message digest, which is the hash of the message to be signed. This
-///   library expects a 256-bit digest (e.g. produced by SHA3-256 (Keccak)).
+///   library expects a 256-bit digest. By default the message is hashed with
+///   SHA2-256, but the caller can instead provide a digest computed some other
+///   way (e.g. with Keccak-256); see [`Input::new_from_prehash()`].
 /// - The [`Output`](crate::keygen::Output) of a [`keygen`] protocol execution
 ///   - A list of [public key shares](crate::keygen::KeySharePublic), one for
 ///     each participant (including this participant);
@@ -65,6 +70,18 @@ pub struct InteractiveSignParticipant {
     signing_message_storage: MessageQueue,
 }
 
+/// The serialized state of an [`InteractiveSignParticipant`].
+#[derive(Serialize, Deserialize, ZeroizeOnDrop)]
+struct InteractiveSignState {
+    presigner: Vec<u8>,
+    #[zeroize(skip)]
+    signing_message_storage: MessageQueue,
+    /// The output of presigning, once it's done.
+    presign_record: Option<Vec<u8>>,
+    /// The state of the signer, once presigning is done.
+    signer: Option<Vec<u8>>,
+}
+
 /// Set of possible states for the signing material.
 ///
 /// Either we have not yet started the signing protocol, so we are only saving
@@ -74,7 +91,7 @@ enum SigningMaterial {
     /// When we create the `signer`, we'll need to pass this input, plus the
     /// output of `presign`.
     PartialInput {
-        digest: Sha256,
+        digest: [u8; 32],
         public_keys: Vec<KeySharePublic>,
     },
     Signer {
@@ -83,7 +100,7 @@ enum SigningMaterial {
 }
 
 impl SigningMaterial {
-    fn new_partial_input(digest: Sha256, public_keys: Vec<KeySharePublic>) -> Self {
+    fn new_partial_input(digest: [u8; 32], public_keys: Vec<KeySharePublic>) -> Self {
         Self::PartialInput {
             digest,
             public_keys,
@@ -109,7 +126,7 @@ impl SigningMaterial {
                 digest,
                 public_keys,
             } => {
-                let signing_input = sign::Input::new_from_digest(digest, record, public_keys);
+                let signing_input = sign::Input::new_from_prehash(digest, record, public_keys);
                 // Note: this shouldn't throw an error because the only failure case should have
                 // also been checked by the presign constructor, and computation
                 // halted far before we reach this point.
@@ -141,7 +158,7 @@ impl SigningMaterial {
 /// Input for the interactive signing protocol.
 #[derive(Debug)]
 pub struct Input {
-    message_digest: Sha256,
+    message_digest: [u8; 32],
     presign_input: presign::Input,
 }
 
@@ -161,12 +178,45 @@ impl Input {
         message: &[u8],
         keygen_output: keygen::Output,
         auxinfo_output: auxinfo::Output,
+    ) -> Result<Self> {
+        Self::new_from_digest(
+            Sha256::new().chain_update(message),
+            keygen_output,
+            auxinfo_output,
+        )
+    }
+
+    /// Construct a new input for interactive signing of a message that has
+    /// been hashed by the caller into the given `digest`, e.g. a [`Sha256`]
+    /// hasher that has been fed the message.
+    ///
+    /// See [`Input::new()`] for the other arguments.
+    pub fn new_from_digest<D: Digest<OutputSize = U32>>(
+        digest: D,
+        keygen_output: keygen::Output,
+        auxinfo_output: auxinfo::Output,
+    ) -> Result<Self> {
+        Self::new_from_prehash(digest.finalize().into(), keygen_output, auxinfo_output)
+    }
+
+    /// Construct a new input for interactive signing of a 32-byte digest
+    /// computed by the caller, such as a Bitcoin sighash or a Keccak-256 hash.
+    ///
+    /// The digest is signed as-is, as in the ECDSA `sign_prehash` operation.
+    /// See [`Input::new()`] for the other arguments.
+    ///
+    /// # 🔒 Input requirements
+    /// The `prehash` must be the output of a cryptographic hash function on
+    /// the message. Signing values that aren't hashes can allow forgeries.
+    pub fn new_from_prehash(
+        prehash: [u8; 32],
+        keygen_output: keygen::Output,
+        auxinfo_output: auxinfo::Output,
     ) -> Result<Self> {
         let presign_input = presign::Input::new(auxinfo_output, keygen_output)?;
-        let message_digest = Sha256::new().chain_update(message);
 
         Ok(Self {
-            message_digest,
+            message_digest: prehash,
             presign_input,
         })
     }
@@ -259,6 +309,9 @@ impl ProtocolParticipant for InteractiveSignParticipant {
         if !self.presigner.status().is_ready() {
             return &Status::NotReady;
         }
+        if *self.presigner.status() == Status::Aborted {
+            return &Status::Aborted;
+        }
         match &self.signing_material {
             SigningMaterial::PartialInput { .. } => &Status::RunningPresign,
             SigningMaterial::Signer { signer } => match signer.status() {
@@ -268,10 +321,70 @@ impl ProtocolParticipant for InteractiveSignParticipant {
         }
     }
 
+    fn waiting_on(&self) -> Vec<ParticipantIdentifier> {
+        match &self.signing_material {
+            SigningMaterial::PartialInput { .. } => self.presigner.waiting_on(),
+            SigningMaterial::Signer { signer } => signer.waiting_on(),
+        }
+    }
+
+    fn abort(&mut self) {
+        self.presigner.abort();
+        if let SigningMaterial::Signer { signer } = &mut self.signing_material {
+            signer.abort();
+        }
+        self.signing_message_storage = MessageQueue::default();
+    }
+
     fn sid(&self) -> Identifier {
         // Note: signer should have the same sid
         self.presigner.sid()
     }
+
+    fn export_state(&self) -> Result<Vec<u8>> {
+        let (presign_record, signer) = match &self.signing_material {
+            SigningMaterial::PartialInput { .. } => (None, None),
+            SigningMaterial::Signer { signer } => (
+                Some(signer.presign_record().to_bytes()),
+                Some(signer.export_state()?),
+            ),
+        };
+        let state = InteractiveSignState {
+            presigner: self.presigner.export_state()?,
+            signing_message_storage: self.signing_message_storage.clone(),
+            presign_record,
+            signer,
+        };
+        serialize!(&state)
+    }
+
+    fn import_state(&mut self, state: &[u8]) -> Result<()> {
+        let state: InteractiveSignState = deserialize!(state)?;
+        self.presigner.import_state(&state.presigner)?;
+        self.signing_message_storage = state.signing_message_storage.clone();
+
+        // If presigning was done, recreate the signer from its output
+        match (&state.presign_record, &state.signer) {
+            (Some(record), Some(signer_state)) => {
+                let record = PresignRecord::try_from_bytes(record.clone())?;
+                self.signing_material.update(
+                    record,
+                    self.id(),
+                    self.other_ids().to_vec(),
+                    self.sid(),
+                )?;
+                self.signing_material
+                    .as_mut_signer()?
+                    .import_state(signer_state)?;
+            }
+            (None, None) => {}
+            _ => {
+                error!("Interactive signing state must have both a presign record and a signer state, or neither");
+                Err(CallerError::DeserializationFailed)?
+            }
+        }
+        Ok(())
+    }
 }
 
 impl InteractiveSignParticipant {
@@ -299,12 +412,20 @@ impl InteractiveSignParticipant {
     ) -> Result<ProcessOutcome<<Self as ProtocolParticipant>::Output>> {
         // Process message and get the components of the outcome
         let outcome = self.presigner.process_message(rng, message)?;
-        let (maybe_record, presign_messages) = outcome.into_parts();
+        let (maybe_records, presign_messages) = outcome.into_parts();
 
         // If presigning didn't finish, stop here.
-        let record = match maybe_record {
+        let mut records = match maybe_records {
             None => return Ok(ProcessOutcome::from(None, presign_messages)),
-            Some(record) => record,
+            Some(records) => records,
+        };
+        // We only ask for one record, so that's what we should get.
+        let record = match (records.pop(), records.is_empty()) {
+            (Some(record), true) => record,
+            _ => {
+                error!("Presigning for interactive signing should produce exactly one record");
+                Err(InternalError::InternalInvariantFailed)?
+            }
         };
 
         // Otherwise, presigning is done, so retrieve the input for sign and create the
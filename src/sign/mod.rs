@@ -19,7 +19,7 @@
 mod interactive_sign;
 mod non_interactive_sign;
 
-use k256::Scalar;
+use k256::{ecdsa::RecoveryId, Scalar};
 use serde::{Deserialize, Serialize};
 use tracing::error;
 
@@ -28,26 +28,72 @@ use crate::errors::{InternalError, Result};
 pub use interactive_sign::participant::{Input as InteractiveInput, InteractiveSignParticipant};
 pub use non_interactive_sign::participant::{Input, SignParticipant};
 
-/// ECDSA signature on a message.
+/// ECDSA signature on a message, along with its recovery ID.
 ///
 /// When generated by this library, the signature will be produced by the
 /// threshold ECDSA algorithm by Canetti et al.
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
-pub struct Signature(k256::ecdsa::Signature);
+pub struct Signature {
+    signature: k256::ecdsa::Signature,
+    #[serde(with = "recovery_id_serde")]
+    recovery_id: RecoveryId,
+}
 
 impl Signature {
-    pub(super) fn try_from_scalars(r: Scalar, s: Scalar) -> Result<Self> {
-        Ok(Self(k256::ecdsa::Signature::from_scalars(r, s)
-            .map_err(|e| {
+    pub(super) fn try_from_scalars(r: Scalar, s: Scalar, recovery_id: RecoveryId) -> Result<Self> {
+        Ok(Self {
+            signature: k256::ecdsa::Signature::from_scalars(r, s).map_err(|e| {
                 error!("Failed to generate `Signature` from `Scalar`s but they should be correctly formatted {e:?}");
                 InternalError::InternalInvariantFailed
-            })?
-        ))
+            })?,
+            recovery_id,
+        })
+    }
+
+    /// Get the recovery ID, which identifies the public key that the signature
+    /// verifies under among the (at most four) candidates that can be
+    /// computed from the signature and message digest.
+    pub fn recovery_id(&self) -> RecoveryId {
+        self.recovery_id
+    }
+
+    /// Encode the signature in the 65-byte recoverable form `r || s || v`,
+    /// where `r` and `s` are 32-byte big-endian integers and `v` is the
+    /// [recovery ID](Signature::recovery_id()) as a single byte (`0` or `1`).
+    ///
+    /// Some applications expect an offset to be added to `v`, e.g. `27` for
+    /// legacy Ethereum signatures; that is left to the caller.
+    pub fn to_recoverable_bytes(&self) -> [u8; 65] {
+        let mut bytes = [0u8; 65];
+        bytes[..64].copy_from_slice(&self.signature.to_bytes());
+        bytes[64] = self.recovery_id.to_byte();
+        bytes
     }
 }
 
 impl AsRef<k256::ecdsa::Signature> for Signature {
     fn as_ref(&self) -> &k256::ecdsa::Signature {
-        &self.0
+        &self.signature
+    }
+}
+
+/// [`RecoveryId`] doesn't implement serde traits, so we (de)serialize it as a
+/// single byte.
+mod recovery_id_serde {
+    use k256::ecdsa::RecoveryId;
+    use serde::{de::Error, Deserialize, Deserializer, Serialize, Serializer};
+
+    pub(super) fn serialize<S: Serializer>(
+        recovery_id: &RecoveryId,
+        serializer: S,
+    ) -> std::result::Result<S::Ok, S::Error> {
+        recovery_id.to_byte().serialize(serializer)
+    }
+
+    pub(super) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> std::result::Result<RecoveryId, D::Error> {
+        let byte = u8::deserialize(deserializer)?;
+        RecoveryId::from_byte(byte).ok_or_else(|| D::Error::custom("invalid recovery ID"))
     }
 }
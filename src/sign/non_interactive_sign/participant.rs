@@ -9,7 +9,7 @@ use std::collections::HashSet;
 
 use generic_array::{typenum::U32, GenericArray};
 use k256::{
-    ecdsa::{signature::DigestVerifier, VerifyingKey},
+    ecdsa::{signature::hazmat::PrehashVerifier, RecoveryId},
     elliptic_curve::{ops::Reduce, scalar::IsHigh, subtle::ConditionallySelectable},
     Scalar, U256,
 };
@@ -20,10 +20,10 @@ use zeroize::Zeroize;
 
 use crate::{
     errors::{CallerError, InternalError, Result},
-    keygen::KeySharePublic,
+    keygen::{derive_child_key, DerivationPath, KeySharePublic, ShamirData},
     local_storage::LocalStorage,
     messages::{Message, MessageType, SignMessageType},
-    participant::{InnerProtocolParticipant, ProcessOutcome, Status},
+    participant::{InnerProtocolParticipant, ParticipantState, ProcessOutcome, Status},
     protocol::{ProtocolType, SharedContext},
     run_only_once,
     sign::{non_interactive_sign::share::SignatureShare, Signature},
@@ -42,7 +42,9 @@ use crate::{
 /// # Protocol input
 /// The protocol takes two fields as input:
 /// - a message digest, which is the hash of the message to be signed. This
-///   library expects a 256-bit digest (e.g. produced by SHA3-256 (Keccak)).
+///   library expects a 256-bit digest. By default the message is hashed with
+///   SHA2-256, but the caller can instead provide a digest computed some other
+///   way (e.g. with Keccak-256); see [`Input::new_from_prehash()`].
 /// - a [`PresignRecord`]. This must be fresh (never used for any other
 ///   execution of the threshold ECDSA protocol, even a failed run) and must
 ///   have been generated using the private share of the key under which the
@@ -50,12 +52,22 @@ use crate::{
 ///
 ///
 /// # Protocol output
-/// Upon successful completion, the participant outputs a [`Signature`].
+/// Upon successful completion, the participant outputs a [`Signature`], along
+/// with its recovery ID.
 /// The signature is on the message which was used to produce the provided
 ///   input message digest. It verifies under the public verification key
 /// corresponding to the private signing key used to produce the input
 ///   [`PresignRecord`].
 ///
+/// The signature can instead be produced under a child of that key, using
+/// BIP32 non-hardened derivation; see [`Input::with_derivation_path()`].
+///
+/// If the combined signature does not verify, each signature share is checked
+/// against the commitments to the sender's presign shares stored in the
+/// [`PresignRecord`], and the protocol fails with
+/// [`ProtocolError`](InternalError::ProtocolError) naming the sender of the
+/// first bad share.
+///
 /// # 🔒 Storage requirement
 /// The [`PresignRecord`] provided as input must be discarded; no copies should
 /// remain after use.
@@ -76,75 +88,143 @@ pub struct SignParticipant {
 /// Input for the non-interactive signing protocol.
 #[derive(Debug)]
 pub struct Input {
-    message_digest: Sha256,
+    /// The 256-bit digest of the message to sign.
+    message_digest: [u8; 32],
     presign_record: PresignRecord,
     public_key_shares: Vec<KeySharePublic>,
+    /// The difference between the signing key and the key used to generate
+    /// the presign record.
+    key_tweak: Scalar,
 }
 
 impl Input {
     /// Construct a new input for signing.
     ///
     /// The `public_key_shares` should be the same ones used to generate the
-    /// [`PresignRecord`].
+    /// [`PresignRecord`]. For threshold key shares, these are the public key
+    /// shares of all the keygen participants, not just the signing quorum.
     pub fn new(
         message: &[u8],
         record: PresignRecord,
         public_key_shares: Vec<KeySharePublic>,
     ) -> Self {
-        Self {
-            message_digest: Sha256::new().chain_update(message),
-            presign_record: record,
+        Self::new_from_digest(
+            Sha256::new().chain_update(message),
+            record,
             public_key_shares,
-        }
+        )
+    }
+
+    /// Construct a new input for signing a message that has been hashed by
+    /// the caller into the given `digest`, e.g. a [`Sha256`] hasher that has
+    /// been fed the message.
+    ///
+    /// See [`Input::new()`] for the other arguments.
+    pub fn new_from_digest<D: Digest<OutputSize = U32>>(
+        digest: D,
+        record: PresignRecord,
+        public_key_shares: Vec<KeySharePublic>,
+    ) -> Self {
+        Self::new_from_prehash(digest.finalize().into(), record, public_key_shares)
     }
 
-    /// Internal-only method to create an input from a pre-existing digest.
+    /// Construct a new input for signing a 32-byte digest computed by the
+    /// caller, such as a Bitcoin sighash or a Keccak-256 hash.
+    ///
+    /// The digest is signed as-is, as in the ECDSA `sign_prehash` operation.
+    /// See [`Input::new()`] for the other arguments.
     ///
-    /// This exists so that interactive signing can hash the message as soon as
-    /// they receive it, rather that waiting until presigning completes.
-    pub(crate) fn new_from_digest(
-        message_digest: Sha256,
+    /// # 🔒 Input requirements
+    /// The `prehash` must be the output of a cryptographic hash function on
+    /// the message. Signing values that aren't hashes can allow forgeries.
+    pub fn new_from_prehash(
+        prehash: [u8; 32],
         record: PresignRecord,
         public_key_shares: Vec<KeySharePublic>,
     ) -> Self {
         Self {
-            message_digest,
+            message_digest: prehash,
             presign_record: record,
             public_key_shares,
+            key_tweak: Scalar::ZERO,
         }
     }
 
+    /// Sign under the child key at the given BIP32 derivation `path`, using
+    /// non-hardened derivation, rather than under the key used to generate the
+    /// [`PresignRecord`].
+    ///
+    /// The `chain_code` must be the [chain
+    /// code](crate::keygen::Output::chain_code()) of the key used to generate
+    /// the presign record, and every signer must use the same path. The
+    /// signature verifies under the public key of
+    /// [`Output::derive_child()`](crate::keygen::Output::derive_child()) for
+    /// the same path. This replaces any path set previously.
+    pub fn with_derivation_path(
+        mut self,
+        chain_code: &[u8; 32],
+        path: &DerivationPath,
+    ) -> Result<Self> {
+        self.key_tweak = derive_child_key(&self.parent_public_key()?, chain_code, path)?.tweak;
+        Ok(self)
+    }
+
     /// Retrieve the presign record.
     pub(crate) fn presign_record(&self) -> &PresignRecord {
         &self.presign_record
     }
 
-    /// Compute the digest. Note that this forces a clone of the `Sha256`
-    /// object.
+    /// Get the message digest.
     pub(crate) fn digest(&self) -> GenericArray<u8, U32> {
-        self.message_digest.clone().finalize()
+        self.message_digest.into()
     }
 
-    pub(crate) fn public_key(&self) -> Result<k256::ecdsa::VerifyingKey> {
-        // Add up all the key shares
-        let public_key_point = self
+    /// Get the signing threshold, if the public key shares are threshold
+    /// (Shamir) shares.
+    fn threshold(&self) -> Option<usize> {
+        self.public_key_shares
+            .first()
+            .and_then(KeySharePublic::shamir)
+            .map(ShamirData::threshold)
+    }
+
+    /// Get the key that the presign record was generated for.
+    fn parent_public_key(&self) -> Result<CurvePoint> {
+        match self
             .public_key_shares
-            .iter()
-            .fold(CurvePoint::IDENTITY, |sum, share| sum + *share.as_ref());
+            .first()
+            .and_then(KeySharePublic::shamir)
+        {
+            // The public key is the commitment to the constant term of the shared
+            // polynomial
+            Some(shamir) => shamir.commitments().first().copied().ok_or_else(|| {
+                error!("Threshold key shares are missing their Feldman commitments");
+                CallerError::BadInput.into()
+            }),
+            // Otherwise, add up all the key shares
+            None => Ok(self
+                .public_key_shares
+                .iter()
+                .fold(CurvePoint::IDENTITY, |sum, share| sum + *share.as_ref())),
+        }
+    }
 
-        VerifyingKey::from_encoded_point(&public_key_point.into()).map_err(|_| {
-            error!("Keygen output does not produce a valid public key");
-            CallerError::BadInput.into()
-        })
+    /// Get the key that the signature will verify under, taking any
+    /// derivation path into account.
+    pub(crate) fn public_key(&self) -> Result<k256::ecdsa::VerifyingKey> {
+        let public_key_point =
+            self.parent_public_key()? + CurvePoint::GENERATOR.multiply_by_scalar(&self.key_tweak);
+
+        public_key_point.to_verifying_key()
     }
 }
 
 /// Context for fiat-Shamir proofs generated in the non-interactive signing
 /// protocol.
 ///
-/// Note that this is only used in the case of identifiable abort, which is not
-/// yet implemented. A correct execution of signing does not involve any ZK
-/// proofs.
+/// Note that signing does not currently involve any ZK proofs: a bad signature
+/// share is identified using the share commitments in the [`PresignRecord`]
+/// instead.
 pub(crate) struct SignContext {
     shared_context: SharedContext,
     message_digest: [u8; 32],
@@ -165,7 +245,7 @@ impl SignContext {
     pub(crate) fn collect(p: &SignParticipant) -> Self {
         Self {
             shared_context: SharedContext::collect(p),
-            message_digest: p.input.digest().into(),
+            message_digest: p.input.message_digest,
         }
     }
 }
@@ -173,16 +253,33 @@ impl SignContext {
 mod storage {
     use k256::Scalar;
 
-    use crate::{local_storage::TypeTag, sign::non_interactive_sign::share::SignatureShare};
+    use crate::{
+        local_storage::TypeTag, presign::ShareCommitment,
+        sign::non_interactive_sign::share::SignatureShare, utils::CurvePoint,
+    };
 
     pub(super) struct Share;
     impl TypeTag for Share {
         type Value = SignatureShare;
+        const NAME: &'static str = "sign.Share";
     }
 
     pub(super) struct XProj;
     impl TypeTag for XProj {
         type Value = Scalar;
+        const NAME: &'static str = "sign.XProj";
+    }
+
+    pub(super) struct MaskPoint;
+    impl TypeTag for MaskPoint {
+        type Value = CurvePoint;
+        const NAME: &'static str = "sign.MaskPoint";
+    }
+
+    pub(super) struct ShareCommitments;
+    impl TypeTag for ShareCommitments {
+        type Value = Vec<ShareCommitment>;
+        const NAME: &'static str = "sign.ShareCommitments";
     }
 }
 
@@ -209,7 +306,9 @@ impl ProtocolParticipant for SignParticipant {
     {
         let config = ParticipantConfig::new(id, &other_participant_ids)?;
 
-        // The input must contain exactly one public key per participant ID.
+        // The input must contain exactly one public key per participant ID. For
+        // threshold key shares, the signers can be any subset of the key share
+        // holders of at least the threshold size.
         let public_key_pids = input
             .public_key_shares
             .iter()
@@ -218,7 +317,21 @@ impl ProtocolParticipant for SignParticipant {
         let pids = std::iter::once(id)
             .chain(other_participant_ids)
             .collect::<HashSet<_>>();
-        if public_key_pids != pids || config.count() != input.public_key_shares.len() {
+        let unique_public_keys = public_key_pids.len() == input.public_key_shares.len();
+        let valid_signers = match input.threshold() {
+            Some(threshold) => pids.is_subset(&public_key_pids) && config.count() >= threshold,
+            None => public_key_pids == pids && config.count() == input.public_key_shares.len(),
+        };
+        if !unique_public_keys || !valid_signers {
+            Err(CallerError::BadInput)?
+        }
+
+        // The presign record must have been generated by exactly the signers. For
+        // threshold key shares, this means a record can't be used by a different
+        // quorum, even one that could otherwise sign.
+        let quorum = input.presign_record.quorum();
+        if quorum != pids || input.presign_record.share_commitments().len() != quorum.len() {
+            error!("Presign record was not generated by the set of signing participants");
             Err(CallerError::BadInput)?
         }
 
@@ -275,9 +388,29 @@ impl ProtocolParticipant for SignParticipant {
         &self.status
     }
 
+    fn waiting_on(&self) -> Vec<ParticipantIdentifier> {
+        if self.status != Status::Initialized {
+            return Vec::new();
+        }
+        self.storage.missing_ids::<storage::Share>(self.other_ids())
+    }
+
+    fn abort(&mut self) {
+        self.storage = LocalStorage::default();
+        self.status = Status::Aborted;
+    }
+
     fn sid(&self) -> Identifier {
         self.sid
     }
+
+    fn export_state(&self) -> Result<Vec<u8>> {
+        ParticipantState::export(&self.status, &self.storage, None)
+    }
+
+    fn import_state(&mut self, state: &[u8]) -> Result<()> {
+        ParticipantState::import(state, &mut self.status, &mut self.storage, None)
+    }
 }
 
 impl InnerProtocolParticipant for SignParticipant {
@@ -301,6 +434,11 @@ impl InnerProtocolParticipant for SignParticipant {
 }
 
 impl SignParticipant {
+    /// Get the presign record this participant signs with.
+    pub(crate) fn presign_record(&self) -> &PresignRecord {
+        self.input.presign_record()
+    }
+
     /// Handle a "Ready" message from ourselves.
     ///
     /// Once a "Ready" message has been received, continue to generate the round
@@ -344,10 +482,23 @@ impl SignParticipant {
         // Compute the x-projection of `R` from the `PresignRecord`
         let x_projection = record.x_projection()?;
 
+        // Under a derived key `x + t`, the masked key share `χᵢ` (a share of
+        // `k x`) becomes `χᵢ + kᵢ t`
+        let key_tweak = self.input.key_tweak;
+        let masked_key_share = record.masked_key_share() + &(record.mask_share() * &key_tweak);
+
         // Compute the share
-        let share = SignatureShare::new(
-            record.mask_share() * &digest + (x_projection * record.masked_key_share()),
-        );
+        let share =
+            SignatureShare::new(record.mask_share() * &digest + (x_projection * masked_key_share));
+
+        // Save the public parts of the record, in case we need to check the
+        // signature shares
+        let mask_point = *record.mask_point();
+        let share_commitments = record
+            .share_commitments()
+            .iter()
+            .map(|commitment| commitment.with_key_tweak(&key_tweak))
+            .collect::<Vec<_>>();
 
         // Erase the presign record
         self.input.presign_record.zeroize();
@@ -357,6 +508,10 @@ impl SignParticipant {
             .store::<storage::Share>(self.id(), share.clone());
         self.storage
             .store::<storage::XProj>(self.id(), x_projection);
+        self.storage
+            .store::<storage::MaskPoint>(self.id(), mask_point);
+        self.storage
+            .store::<storage::ShareCommitments>(self.id(), share_commitments);
 
         // Form output messages
         self.message_for_other_participants(
@@ -400,30 +555,77 @@ impl SignParticipant {
         let shares = self
             .all_participants()
             .into_iter()
-            .map(|pid| self.storage.remove::<storage::Share>(pid))
+            .map(|pid| Ok((pid, self.storage.remove::<storage::Share>(pid)?)))
             .collect::<Result<Vec<_>>>()?;
         let x_projection = self.storage.remove::<storage::XProj>(self.id())?;
 
         // Sum up the signature shares and convert to BIP-0062 format (negating if the
         // sum is > group order /2)
-        let mut sum = shares.into_iter().fold(Scalar::ZERO, |a, b| a + b);
-        sum.conditional_assign(&sum.negate(), sum.is_high());
+        let mut sum = shares.iter().fold(Scalar::ZERO, |a, (_, b)| a + b.clone());
+        let is_high = sum.is_high();
+        sum.conditional_assign(&sum.negate(), is_high);
+
+        // The recovery ID is the parity of the y-coordinate of `R`, flipped if we
+        // negated `s` (which corresponds to negating `R`). The x-coordinate of `R`
+        // is never reduced, since computing the x-projection fails if it's not a
+        // valid scalar.
+        let mask_point = self.storage.retrieve::<storage::MaskPoint>(self.id())?;
+        let recovery_id = RecoveryId::new(mask_point.y_is_odd() ^ bool::from(is_high), false);
 
-        let signature = Signature::try_from_scalars(x_projection, sum)?;
+        let signature = Signature::try_from_scalars(x_projection, sum, recovery_id)?;
 
         // Verify signature
-        self.input
+        if let Err(e) = self
+            .input
             .public_key()?
-            .verify_digest(self.input.message_digest.clone(), signature.as_ref())
-            .map_err(|e| {
-                error!("Failed to verify signature {:?}", e);
-                InternalError::ProtocolError(None)
-            })?;
+            .verify_prehash(&self.input.message_digest, signature.as_ref())
+        {
+            error!("Failed to verify signature {:?}", e);
+            return Err(InternalError::ProtocolError(
+                self.find_bad_share(&shares, &x_projection)?,
+            ));
+        }
 
         // Output full signature
         self.status = Status::TerminatedSuccessfully;
         Ok(ProcessOutcome::Terminated(signature))
     }
+
+    /// Check each signature share against the presign commitments of its
+    /// sender, returning the sender of the first bad share (if any).
+    ///
+    /// If every share passes, the combined signature is guaranteed to be
+    /// valid, so a signature that fails to verify always results in a
+    /// participant being named here.
+    fn find_bad_share(
+        &mut self,
+        shares: &[(ParticipantIdentifier, SignatureShare)],
+        x_projection: &Scalar,
+    ) -> Result<Option<ParticipantIdentifier>> {
+        let mask_point = self.storage.remove::<storage::MaskPoint>(self.id())?;
+        let share_commitments = self
+            .storage
+            .remove::<storage::ShareCommitments>(self.id())?;
+        let digest = <Scalar as Reduce<U256>>::reduce_bytes(&self.input.digest());
+
+        for (pid, share) in shares {
+            let commitment = share_commitments
+                .iter()
+                .find(|commitment| commitment.participant() == *pid)
+                .ok_or_else(|| {
+                    error!("Presign record is missing the share commitment for {}, even though we checked for it at construction.", pid);
+                    InternalError::InternalInvariantFailed
+                })?;
+            if !share.is_consistent_with(commitment, &mask_point, &digest, x_projection) {
+                error!(
+                    "Signature share from {} is inconsistent with its presign commitments",
+                    pid
+                );
+                return Ok(Some(*pid));
+            }
+        }
+        Ok(None)
+    }
 }
 
 #[cfg(test)]
@@ -431,7 +633,10 @@ mod test {
     use std::collections::HashMap;
 
     use k256::{
-        ecdsa::signature::{DigestVerifier, Verifier},
+        ecdsa::{
+            signature::{hazmat::PrehashVerifier, DigestVerifier, Verifier},
+            VerifyingKey,
+        },
         elliptic_curve::{ops::Reduce, scalar::IsHigh, subtle::ConditionallySelectable},
         Scalar, U256,
     };
@@ -440,14 +645,14 @@ mod test {
     use tracing::debug;
 
     use crate::{
-        errors::Result,
-        keygen,
-        messages::{Message, MessageType},
+        errors::{InternalError, Result},
+        keygen::{self, DerivationPath},
+        messages::{Message, MessageType, SignMessageType},
         participant::{ProcessOutcome, Status},
         presign::PresignRecord,
-        sign::{self, Signature},
+        sign::{self, non_interactive_sign::share::SignatureShare, Signature},
         utils::{bn_to_scalar, testing::init_testing},
-        Identifier, ParticipantConfig, ProtocolParticipant,
+        Identifier, ParticipantConfig, ParticipantIdentifier, ProtocolParticipant,
     };
 
     use super::SignParticipant;
@@ -477,6 +682,35 @@ mod test {
         Some((participant, outcome))
     }
 
+    /// Run signing to completion, delivering messages in order, and return
+    /// everyone's signature.
+    fn run_signing<R: RngCore + CryptoRng>(
+        quorum: &mut [SignParticipant],
+        sid: Identifier,
+        rng: &mut R,
+    ) -> Result<Vec<Signature>> {
+        let mut inbox = quorum
+            .iter()
+            .map(|participant| {
+                Message::new(
+                    MessageType::Sign(SignMessageType::Ready),
+                    sid,
+                    participant.id(),
+                    participant.id(),
+                    &[0u8; 0],
+                )
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let mut signatures = Vec::new();
+        while let Some(message) = inbox.pop() {
+            let participant = quorum.iter_mut().find(|p| p.id() == message.to()).unwrap();
+            let (output, messages) = participant.process_message(rng, &message)?.into_parts();
+            signatures.extend(output);
+            inbox.extend(messages);
+        }
+        Ok(signatures)
+    }
+
     #[test]
     fn signing_always_works() {
         for _ in 0..1000 {
@@ -618,6 +852,20 @@ mod test {
         let distributed_sig = &signatures[0];
         assert_eq!(distributed_sig.as_ref(), &non_distributed_sig);
 
+        // The recovery ID recovers the public key
+        assert_eq!(
+            &VerifyingKey::recover_from_prehash(
+                &Sha256::digest(message),
+                distributed_sig.as_ref(),
+                distributed_sig.recovery_id()
+            )
+            .unwrap(),
+            public_key
+        );
+        let bytes = distributed_sig.to_recoverable_bytes();
+        assert_eq!(&bytes[..64], distributed_sig.as_ref().to_bytes().as_slice());
+        assert_eq!(bytes[64], distributed_sig.recovery_id().to_byte());
+
         // Verify that we have a valid signature under the public key for the `message`
         assert!(public_key.verify(message, distributed_sig.as_ref()).is_ok());
         assert!(public_key
@@ -628,4 +876,277 @@ mod test {
             .is_ok());
         Ok(())
     }
+
+    #[test]
+    fn signing_identifies_participant_with_bad_share() -> Result<()> {
+        let quorum_size = 4;
+        let rng = &mut init_testing();
+        let sid = Identifier::random(rng);
+        let message = b"the quick brown fox jumped over the lazy dog";
+
+        let configs = ParticipantConfig::random_quorum(quorum_size, rng)?;
+        let keygen_outputs = keygen::Output::simulate_set(&configs, rng);
+        let presign_records = PresignRecord::simulate_set(&keygen_outputs, rng);
+
+        let inputs = std::iter::zip(keygen_outputs, presign_records).map(|(keygen, record)| {
+            sign::Input::new(message, record, keygen.public_key_shares().to_vec())
+        });
+        let mut quorum = std::iter::zip(configs, inputs)
+            .map(|(config, input)| {
+                SignParticipant::new(sid, config.id(), config.other_ids().to_vec(), input)
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let cheater = quorum[0].id();
+
+        let mut inbox = quorum
+            .iter()
+            .map(|participant| {
+                Message::new(
+                    MessageType::Sign(SignMessageType::Ready),
+                    sid,
+                    participant.id(),
+                    participant.id(),
+                    &[0u8; 0],
+                )
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        // The cheater sends a bad share to everyone else
+        let mut results = HashMap::new();
+        while let Some(message) = inbox.pop() {
+            let participant = quorum.iter_mut().find(|p| p.id() == message.to()).unwrap();
+            let (output, messages) = match participant.process_message(rng, &message) {
+                Ok(outcome) => outcome.into_parts(),
+                Err(err) => {
+                    assert!(results.insert(participant.id(), Err(err)).is_none());
+                    continue;
+                }
+            };
+            if output.is_some() {
+                assert!(results.insert(participant.id(), Ok(())).is_none());
+            }
+            for message in messages {
+                if message.from() != cheater {
+                    inbox.push(message);
+                    continue;
+                }
+                let share = SignatureShare::try_from(&message)?;
+                let bad_share = SignatureShare::new(share + SignatureShare::new(Scalar::ONE));
+                inbox.push(Message::new(
+                    message.message_type(),
+                    sid,
+                    cheater,
+                    message.to(),
+                    &bad_share,
+                )?);
+            }
+        }
+
+        // The cheater's own signature is fine, but every honest participant
+        // blames the cheater
+        assert_eq!(results.len(), quorum_size);
+        for (pid, result) in results {
+            if pid == cheater {
+                assert!(result.is_ok());
+            } else {
+                assert_eq!(result, Err(InternalError::ProtocolError(Some(cheater))));
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn signing_from_prehash_works() -> Result<()> {
+        let quorum_size = 3;
+        let rng = &mut init_testing();
+        let sid = Identifier::random(rng);
+        // A digest computed outside the library, e.g. a Keccak-256 hash
+        let prehash: [u8; 32] = rng.gen();
+
+        let configs = ParticipantConfig::random_quorum(quorum_size, rng)?;
+        let keygen_outputs = keygen::Output::simulate_set(&configs, rng);
+        let presign_records = PresignRecord::simulate_set(&keygen_outputs, rng);
+        let public_key = keygen_outputs[0].public_key()?;
+
+        let inputs = std::iter::zip(&keygen_outputs, presign_records).map(|(keygen, record)| {
+            sign::Input::new_from_prehash(prehash, record, keygen.public_key_shares().to_vec())
+        });
+        let mut quorum = std::iter::zip(configs, inputs)
+            .map(|(config, input)| {
+                SignParticipant::new(sid, config.id(), config.other_ids().to_vec(), input)
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let signatures = run_signing(&mut quorum, sid, rng)?;
+        assert_eq!(signatures.len(), quorum_size);
+        for signature in &signatures {
+            assert!(public_key
+                .verify_prehash(&prehash, signature.as_ref())
+                .is_ok());
+            assert_eq!(
+                VerifyingKey::recover_from_prehash(
+                    &prehash,
+                    signature.as_ref(),
+                    signature.recovery_id()
+                )
+                .unwrap(),
+                public_key
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn digest_constructors_agree() {
+        let rng = &mut init_testing();
+        let message = b"the quick brown fox jumped over the lazy dog";
+        let keygen_outputs =
+            keygen::Output::simulate_set(&ParticipantConfig::random_quorum(2, rng).unwrap(), rng);
+        let mut records = PresignRecord::simulate_set(&keygen_outputs, rng);
+        let public_key_shares = keygen_outputs[0].public_key_shares().to_vec();
+
+        let from_message = sign::Input::new(message, records.remove(0), public_key_shares.clone());
+        let from_digest = sign::Input::new_from_digest(
+            Sha256::new().chain_update(message),
+            records.remove(0),
+            public_key_shares.clone(),
+        );
+        let from_prehash = sign::Input::new_from_prehash(
+            Sha256::digest(message).into(),
+            PresignRecord::simulate_set(&keygen_outputs, rng).remove(0),
+            public_key_shares,
+        );
+        assert_eq!(from_message.digest(), from_digest.digest());
+        assert_eq!(from_message.digest(), from_prehash.digest());
+    }
+
+    #[test]
+    fn signing_under_derived_key_works() -> Result<()> {
+        let quorum_size = 3;
+        let rng = &mut init_testing();
+        let sid = Identifier::random(rng);
+        let message = b"the quick brown fox jumped over the lazy dog";
+        let path: DerivationPath = "m/44/0/7".parse()?;
+
+        let configs = ParticipantConfig::random_quorum(quorum_size, rng)?;
+        let keygen_outputs = keygen::Output::simulate_set(&configs, rng);
+        let presign_records = PresignRecord::simulate_set(&keygen_outputs, rng);
+        let child_public_key = keygen_outputs[0].derive_child(&path)?.public_key()?;
+
+        // The presign records were generated for the parent key
+        let inputs = std::iter::zip(&keygen_outputs, presign_records)
+            .map(|(keygen, record)| {
+                sign::Input::new(message, record, keygen.public_key_shares().to_vec())
+                    .with_derivation_path(&keygen.chain_code(), &path)
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let mut quorum = std::iter::zip(configs, inputs)
+            .map(|(config, input)| {
+                SignParticipant::new(sid, config.id(), config.other_ids().to_vec(), input)
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let signatures = run_signing(&mut quorum, sid, rng)?;
+
+        // Everyone gets a signature that verifies under the child key, but not
+        // under the parent key
+        assert_eq!(signatures.len(), quorum_size);
+        for signature in &signatures {
+            assert!(child_public_key.verify(message, signature.as_ref()).is_ok());
+            assert!(keygen_outputs[0]
+                .public_key()?
+                .verify(message, signature.as_ref())
+                .is_err());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn signing_requires_record_for_signers() -> Result<()> {
+        let rng = &mut init_testing();
+        let sid = Identifier::random(rng);
+        let message = b"the quick brown fox jumped over the lazy dog";
+
+        let configs = ParticipantConfig::random_quorum(3, rng)?;
+        let keygen_outputs = keygen::Output::simulate_set(&configs, rng);
+
+        // A record generated by a different set of participants is rejected
+        let other_configs = ParticipantConfig::random_quorum(3, rng)?;
+        let other_outputs = keygen::Output::simulate_set(&other_configs, rng);
+        let record = PresignRecord::simulate_set(&other_outputs, rng).remove(0);
+        let input = sign::Input::new(
+            message,
+            record,
+            keygen_outputs[0].public_key_shares().to_vec(),
+        );
+        assert!(
+            SignParticipant::new(sid, configs[0].id(), configs[0].other_ids().to_vec(), input)
+                .is_err()
+        );
+
+        // A record generated by the signers is accepted
+        let record = PresignRecord::simulate_set(&keygen_outputs, rng).remove(0);
+        let input = sign::Input::new(
+            message,
+            record,
+            keygen_outputs[0].public_key_shares().to_vec(),
+        );
+        assert!(
+            SignParticipant::new(sid, configs[0].id(), configs[0].other_ids().to_vec(), input)
+                .is_ok()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn threshold_signers_must_be_a_quorum_of_key_holders() -> Result<()> {
+        let rng = &mut init_testing();
+        let sid = Identifier::random(rng);
+        let message = b"the quick brown fox jumped over the lazy dog";
+
+        let configs = ParticipantConfig::random_quorum(5, rng)?;
+        let keygen_outputs = keygen::Output::simulate_threshold_set(&configs, 3, rng);
+        let pids = configs
+            .iter()
+            .map(ParticipantConfig::id)
+            .collect::<Vec<_>>();
+
+        // Make a signing input for the first member of the quorum
+        let make_input = |quorum: &[ParticipantIdentifier], rng: &mut _| -> Result<_> {
+            let additive_outputs = keygen_outputs
+                .iter()
+                .filter(|output| quorum.contains(&output.private_pid().unwrap()))
+                .map(|output| output.to_additive_shares(quorum))
+                .collect::<Result<Vec<_>>>()?;
+            let record = PresignRecord::simulate_set(&additive_outputs, rng).remove(0);
+            Ok(sign::Input::new(
+                message,
+                record,
+                keygen_outputs[0].public_key_shares().to_vec(),
+            ))
+        };
+
+        // Any threshold of key holders can sign with the threshold public key shares
+        let quorum = &pids[1..4];
+        let input = make_input(quorum, rng)?;
+        assert_eq!(input.public_key()?, keygen_outputs[0].public_key()?);
+        assert!(SignParticipant::new(sid, quorum[0], quorum[1..].to_vec(), input).is_ok());
+
+        // A different quorum can't use the record, even though it could sign with
+        // a record of its own
+        let input = make_input(quorum, rng)?;
+        assert!(SignParticipant::new(sid, pids[0], pids[1..3].to_vec(), input).is_err());
+
+        // Fewer than a threshold of key holders can't
+        let input = make_input(&pids[..3], rng)?;
+        assert!(SignParticipant::new(sid, pids[0], vec![pids[1]], input).is_err());
+
+        // Signers that don't hold a key share can't
+        let input = make_input(&pids[..3], rng)?;
+        let outsider = ParticipantIdentifier::random(rng);
+        assert!(
+            SignParticipant::new(sid, pids[0], vec![pids[1], pids[2], outsider], input).is_err()
+        );
+        Ok(())
+    }
 }
@@ -11,6 +11,8 @@ use serde::{Deserialize, Serialize};
 use crate::{
     errors::{InternalError, Result},
     messages::{Message, MessageType, SignMessageType},
+    presign::ShareCommitment,
+    utils::CurvePoint,
 };
 
 /// A single participant's share of the signature.
@@ -22,6 +24,21 @@ impl SignatureShare {
     pub(super) fn new(share: Scalar) -> Self {
         Self(share)
     }
+
+    /// Check the share against the sender's presign commitments, i.e. that
+    /// `R^{σᵢ} = (R^{kᵢ})^m (R^{χᵢ})^r` for the mask point `R`, message digest
+    /// `m` and x-projection `r`.
+    pub(super) fn is_consistent_with(
+        &self,
+        commitment: &ShareCommitment,
+        mask_point: &CurvePoint,
+        digest: &Scalar,
+        x_projection: &Scalar,
+    ) -> bool {
+        mask_point.multiply_by_scalar(&self.0)
+            == commitment.mask().multiply_by_scalar(digest)
+                + commitment.masked_key().multiply_by_scalar(x_projection)
+    }
 }
 
 impl TryFrom<&Message> for SignatureShare {
@@ -9,13 +9,14 @@
 use crate::errors::{CallerError, InternalError, Result};
 use generic_array::GenericArray;
 use k256::{
+    ecdsa::VerifyingKey,
     elliptic_curve::{
         bigint::Encoding,
         group::{ff::PrimeField, GroupEncoding},
         point::AffineCoordinates,
         AffinePoint, Curve,
     },
-    EncodedPoint, FieldBytes, Scalar, Secp256k1,
+    EncodedPoint, Scalar, Secp256k1,
 };
 use libpaillier::unknown_order::BigNumber;
 use merlin::Transcript;
@@ -49,8 +50,25 @@ impl AsRef<CurvePoint> for CurvePoint {
 }
 
 impl CurvePoint {
-    pub fn x_affine(&self) -> FieldBytes {
-        self.0.to_affine().x()
+    /// Compute the x-projection of the point, as used for the `r` component of
+    /// an ECDSA signature.
+    pub(crate) fn x_projection(&self) -> Result<Scalar> {
+        let x = self.0.to_affine().x();
+        Option::from(Scalar::from_repr(x)).ok_or_else(|| {
+            error!("Unable to compute x-projection of curve point: failed to convert x coord to `Scalar`");
+            InternalError::InternalInvariantFailed
+        })
+    }
+    /// Use the point as an ECDSA verification key.
+    pub(crate) fn to_verifying_key(self) -> Result<VerifyingKey> {
+        VerifyingKey::from_affine(self.0.to_affine()).map_err(|_| {
+            error!("Point is not a valid verification key");
+            CallerError::BadInput.into()
+        })
+    }
+    /// Returns whether the y-coordinate of the point (in affine form) is odd.
+    pub(crate) fn y_is_odd(&self) -> bool {
+        self.0.to_affine().y_is_odd().into()
     }
     #[cfg(test)]
     pub(crate) fn random(rng: impl RngCore) -> Self {
@@ -78,6 +96,49 @@ impl CurvePoint {
         Self(self.0 * point)
     }
 
+    /// Compute the sum of `scalar * point` over all the `terms`, using a
+    /// single multi-scalar multiplication.
+    ///
+    /// This uses Straus' method with 4-bit windows, so the point doublings are
+    /// shared between all the terms. It isn't constant-time, so it must only
+    /// be used with public values, e.g. to verify proofs.
+    pub(crate) fn multiscalar_mul(terms: &[(Scalar, CurvePoint)]) -> Self {
+        use k256::ProjectivePoint;
+
+        // Precompute `0 * point, 1 * point, ..., 15 * point` for each term.
+        let tables = terms
+            .iter()
+            .map(|(_, point)| {
+                std::iter::successors(Some(ProjectivePoint::IDENTITY), |multiple| {
+                    Some(*multiple + point.0)
+                })
+                .take(16)
+                .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        // Big-endian encodings of the scalars.
+        let scalars = terms
+            .iter()
+            .map(|(scalar, _)| scalar.to_bytes())
+            .collect::<Vec<_>>();
+
+        let mut sum = ProjectivePoint::IDENTITY;
+        for byte in 0..32 {
+            for shift in [4, 0] {
+                for _ in 0..4 {
+                    sum = sum.double();
+                }
+                for (table, scalar) in tables.iter().zip(&scalars) {
+                    let window = (scalar[byte] >> shift) & 0xf;
+                    if window != 0 {
+                        sum += table[window as usize];
+                    }
+                }
+            }
+        }
+        Self(sum)
+    }
+
     /// Serialize the `CurvePoint` as an affine-encoded secp256k1 byte array.
     pub(crate) fn to_bytes(self) -> Vec<u8> {
         let mut generic_array = AffinePoint::<Secp256k1>::from(self.0).to_bytes();
@@ -143,7 +204,7 @@ impl<'de> Deserialize<'de> for CurvePoint {
 #[cfg(test)]
 mod curve_point_tests {
     use crate::utils::{testing::init_testing, CurvePoint};
-    use k256::elliptic_curve::Group;
+    use k256::elliptic_curve::{Field, Group};
 
     #[test]
     fn curve_point_byte_conversion_works() {
@@ -153,6 +214,27 @@ mod curve_point_tests {
         let reconstructed = CurvePoint::try_from_bytes(&bytes).unwrap();
         assert_eq!(point, reconstructed);
     }
+
+    #[test]
+    fn multiscalar_mul_matches_individual_multiplications() {
+        let rng = &mut init_testing();
+        for count in [0, 1, 2, 7] {
+            let terms = std::iter::repeat_with(|| {
+                (
+                    k256::Scalar::random(&mut *rng),
+                    CurvePoint(k256::ProjectivePoint::random(&mut *rng)),
+                )
+            })
+            .take(count)
+            .collect::<Vec<_>>();
+            let expected = terms
+                .iter()
+                .fold(CurvePoint::IDENTITY, |sum, (scalar, point)| {
+                    sum + point.multiply_by_scalar(scalar)
+                });
+            assert_eq!(CurvePoint::multiscalar_mul(&terms), expected);
+        }
+    }
 }
 
 /// Helper type for parsing byte array into slices.
@@ -370,6 +452,11 @@ pub(crate) fn bn_to_scalar(x: &BigNumber) -> Result<k256::Scalar> {
     Ok(ret)
 }
 
+/// Returns x: k256::Scalar as a BigNumber in the range `[0, k256_order)`
+pub(crate) fn scalar_to_bn(x: &Scalar) -> BigNumber {
+    BigNumber::from_slice(x.to_repr())
+}
+
 pub(crate) fn k256_order() -> BigNumber {
     // Set order = q
     let order_bytes: [u8; 32] = k256::Secp256k1::ORDER.to_be_bytes();
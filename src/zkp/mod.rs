@@ -14,10 +14,12 @@
 //! latter verifies the proof was constructed correctly.
 
 pub(crate) mod piaffg;
+pub(crate) mod pidec;
 pub(crate) mod pienc;
 pub(crate) mod pifac;
 pub(crate) mod pilog;
 pub(crate) mod pimod;
+pub(crate) mod pimul;
 pub(crate) mod piprm;
 pub(crate) mod pisch;
 
@@ -29,7 +31,9 @@ use serde::{de::DeserializeOwned, Serialize};
 /// A trait for constructing arbitrary system context.
 ///
 /// This context can be turned into bytes and appended to a [`Transcript`].
-pub(crate) trait ProofContext {
+/// It must be shareable between threads, so that proofs using the same
+/// context can be generated and verified in parallel.
+pub(crate) trait ProofContext: Sync {
     fn as_bytes(&self) -> Result<Vec<u8>>;
 }
 
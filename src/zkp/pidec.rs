@@ -0,0 +1,363 @@
+// Copyright (c) 2022-2023 Bolt Labs Holdings, Inc
+//
+// This source code is licensed under both the MIT license found in the
+// LICENSE-MIT file in the root directory of this source tree and the Apache
+// License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+// of this source tree.
+
+//! Implements a zero-knowledge proof that the plaintext of a Paillier
+//! ciphertext, reduced modulo the group order `q`, equals a public value.
+//!
+//! More precisely, given a ciphertext `C` under the prover's encryption key
+//! and a public value `x`, the prover shows it knows `y` and `ρ` such that `C
+//! = (1 + N)^y ρ^N mod N²` and `x = y mod q`.
+//!
+//! The proof is defined in Figure 30 of CGGMP[^cite], and uses a standard
+//! Fiat-Shamir transformation to make the proof non-interactive. The mask is
+//! sized for plaintexts of up to roughly `ℓ'` bits, which covers the values
+//! checked during the presign identification phase.
+//!
+//! [^cite]: Ran Canetti, Rosario Gennaro, Steven Goldfeder, Nikolaos Makriyannis, and Udi Peled.
+//! UC Non-Interactive, Proactive, Threshold ECDSA with Identifiable Aborts.
+//! [EPrint archive, 2021](https://eprint.iacr.org/2021/060.pdf).
+
+use crate::{
+    errors::*,
+    paillier::{Ciphertext, EncryptionKey, MaskedNonce, Nonce},
+    parameters::{ELL, ELL_PRIME, EPSILON},
+    ring_pedersen::{Commitment, MaskedRandomness, RingPedersen},
+    utils::{k256_order, plusminus_challenge_from_transcript, random_plusminus_by_size},
+    zkp::{Proof, ProofContext},
+};
+use libpaillier::unknown_order::BigNumber;
+use merlin::Transcript;
+use rand::{CryptoRng, RngCore};
+use serde::{Deserialize, Serialize};
+use std::fmt::Debug;
+use tracing::error;
+
+/// Proof that the plaintext of a ciphertext is congruent to a public value
+/// modulo the group order.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct PiDecProof {
+    /// Commitment to the (secret) plaintext (`S` in the paper).
+    plaintext_commit: Commitment,
+    /// Commitment to the mask value (`T` in the paper).
+    mask_commit: Commitment,
+    /// Paillier encryption of the mask value (`A` in the paper).
+    mask_ciphertext: Ciphertext,
+    /// The mask value reduced modulo the group order (`ɣ` in the paper).
+    reduced_mask: BigNumber,
+    /// Fiat-Shamir challenge (`e` in the paper).
+    challenge: BigNumber,
+    /// Response binding the (secret) plaintext with the mask value (`z1` in
+    /// the paper).
+    plaintext_response: BigNumber,
+    /// Response binding the commitment randomness of the two commitments
+    /// (`z2` in the paper).
+    randomness_response: MaskedRandomness,
+    /// Response binding the (secret) nonce with the nonce of
+    /// [`PiDecProof::mask_ciphertext`] (`w` in the paper).
+    nonce_response: MaskedNonce,
+}
+
+/// Common input and setup parameters known to both the prover and the verifier.
+///
+/// Copying/Cloning references is harmless and sometimes necessary. So we
+/// implement Clone and Copy for this type.
+#[derive(Serialize, Clone, Copy)]
+pub(crate) struct PiDecInput<'a> {
+    /// The verifier's ring-Pedersen commitment scheme (`(Nhat, s, t)` in the
+    /// paper).
+    ring_pedersen: &'a RingPedersen,
+    /// The prover's encryption key (`N_0` in the paper).
+    encryption_key: &'a EncryptionKey,
+    /// The ciphertext about which we are proving properties (`C` in the
+    /// paper).
+    ciphertext: &'a Ciphertext,
+    /// The claimed plaintext, modulo the group order (`x` in the paper).
+    reduced_plaintext: &'a BigNumber,
+}
+
+impl<'a> PiDecInput<'a> {
+    /// Collect common input for proving or verifying a [`PiDecProof`] that the
+    /// plaintext of `ciphertext` is congruent to `reduced_plaintext` modulo
+    /// the group order.
+    pub(crate) fn new(
+        verifier_ring_pedersen: &'a RingPedersen,
+        prover_encryption_key: &'a EncryptionKey,
+        ciphertext: &'a Ciphertext,
+        reduced_plaintext: &'a BigNumber,
+    ) -> Self {
+        Self {
+            ring_pedersen: verifier_ring_pedersen,
+            encryption_key: prover_encryption_key,
+            ciphertext,
+            reduced_plaintext,
+        }
+    }
+}
+
+/// The prover's secret knowledge.
+pub(crate) struct PiDecSecret<'a> {
+    /// The (unreduced) plaintext of the ciphertext (`y` in the paper).
+    plaintext: &'a BigNumber,
+    /// The nonce of the ciphertext (`ρ` in the paper).
+    nonce: &'a Nonce,
+}
+
+impl Debug for PiDecSecret<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("pidec::PiDecSecret")
+            .field("plaintext", &"[redacted]")
+            .field("nonce", &"[redacted]")
+            .finish()
+    }
+}
+
+impl<'a> PiDecSecret<'a> {
+    /// Collect prover secrets for proving a [`PiDecProof`].
+    pub(crate) fn new(plaintext: &'a BigNumber, nonce: &'a Nonce) -> Self {
+        Self { plaintext, nonce }
+    }
+}
+
+impl Proof for PiDecProof {
+    type CommonInput<'a> = PiDecInput<'a>;
+    type ProverSecret<'a> = PiDecSecret<'a>;
+
+    #[cfg_attr(feature = "flame_it", flame("PiDecProof"))]
+    fn prove<R: RngCore + CryptoRng>(
+        input: Self::CommonInput<'_>,
+        secret: Self::ProverSecret<'_>,
+        context: &impl ProofContext,
+        transcript: &mut Transcript,
+        rng: &mut R,
+    ) -> Result<Self> {
+        // Sample a mask for the plaintext (aka `ɑ`).
+        let mask = random_plusminus_by_size(rng, ELL_PRIME + EPSILON);
+
+        // Commit to the plaintext and to the mask (aka `S, μ` and `T, ν`).
+        let (plaintext_commit, plaintext_commit_randomness) =
+            input.ring_pedersen.commit(secret.plaintext, ELL, rng);
+        let (mask_commit, mask_commit_randomness) =
+            input.ring_pedersen.commit(&mask, ELL + EPSILON, rng);
+        // Encrypt the mask (aka `A, r`).
+        let (mask_ciphertext, mask_nonce) = input
+            .encryption_key
+            .encrypt(rng, &mask)
+            .map_err(|_| InternalError::InternalInvariantFailed)?;
+        // Reduce the mask (aka `ɣ`).
+        let reduced_mask = mask.nmod(&k256_order());
+
+        Self::fill_transcript(
+            transcript,
+            context,
+            &input,
+            &plaintext_commit,
+            &mask_commit,
+            &mask_ciphertext,
+            &reduced_mask,
+        )?;
+        let challenge = plusminus_challenge_from_transcript(transcript)?;
+
+        // Form the responses (aka `z1`, `z2` and `w`).
+        let plaintext_response = &mask + &challenge * secret.plaintext;
+        let randomness_response =
+            plaintext_commit_randomness.mask(&mask_commit_randomness, &challenge);
+        let nonce_response = input
+            .encryption_key
+            .mask(secret.nonce, &mask_nonce, &challenge);
+
+        Ok(Self {
+            plaintext_commit,
+            mask_commit,
+            mask_ciphertext,
+            reduced_mask,
+            challenge,
+            plaintext_response,
+            randomness_response,
+            nonce_response,
+        })
+    }
+
+    #[cfg_attr(feature = "flame_it", flame("PiDecProof"))]
+    fn verify(
+        self,
+        input: Self::CommonInput<'_>,
+        context: &impl ProofContext,
+        transcript: &mut Transcript,
+    ) -> Result<()> {
+        Self::fill_transcript(
+            transcript,
+            context,
+            &input,
+            &self.plaintext_commit,
+            &self.mask_commit,
+            &self.mask_ciphertext,
+            &self.reduced_mask,
+        )?;
+        let challenge = plusminus_challenge_from_transcript(transcript)?;
+        if challenge != self.challenge {
+            error!("Fiat-Shamir consistency check failed");
+            return Err(InternalError::ProtocolError(None));
+        }
+
+        // Check that `(1 + N_0)^{z1} w^{N_0} = A C^e`.
+        let ciphertext_is_valid = {
+            let lhs = input
+                .encryption_key
+                .encrypt_with_nonce(&self.plaintext_response, &self.nonce_response)
+                .map_err(|_| InternalError::ProtocolError(None))?;
+            let rhs = input
+                .encryption_key
+                .multiply_and_add(&self.challenge, input.ciphertext, &self.mask_ciphertext)
+                .map_err(|_| InternalError::ProtocolError(None))?;
+            lhs == rhs
+        };
+        if !ciphertext_is_valid {
+            error!("ciphertext check (first equality check) failed");
+            return Err(InternalError::ProtocolError(None));
+        }
+
+        // Check that `z1 = ɣ + e x mod q`.
+        let order = k256_order();
+        let reduction_is_valid = self.plaintext_response.nmod(&order)
+            == (&self.reduced_mask + &self.challenge * input.reduced_plaintext).nmod(&order);
+        if !reduction_is_valid {
+            error!("reduced plaintext check (second equality check) failed");
+            return Err(InternalError::ProtocolError(None));
+        }
+
+        // Check that `s^{z1} t^{z2} = T S^e`.
+        let commitment_is_valid = {
+            let lhs = input
+                .ring_pedersen
+                .reconstruct(&self.plaintext_response, &self.randomness_response);
+            let rhs = input.ring_pedersen.combine(
+                &self.mask_commit,
+                &self.plaintext_commit,
+                &self.challenge,
+            );
+            lhs == rhs
+        };
+        if !commitment_is_valid {
+            error!("ring-Pedersen commitment check (third equality check) failed");
+            return Err(InternalError::ProtocolError(None));
+        }
+
+        Ok(())
+    }
+}
+
+impl PiDecProof {
+    /// Update the [`Transcript`] with all the commitment values used in the
+    /// proof.
+    fn fill_transcript(
+        transcript: &mut Transcript,
+        context: &impl ProofContext,
+        input: &PiDecInput,
+        plaintext_commit: &Commitment,
+        mask_commit: &Commitment,
+        mask_ciphertext: &Ciphertext,
+        reduced_mask: &BigNumber,
+    ) -> Result<()> {
+        transcript.append_message(b"PiDec ProofContext", &context.as_bytes()?);
+        transcript.append_message(b"PiDec CommonInput", &serialize!(&input)?);
+        transcript.append_message(
+            b"(S, T, A, gamma)",
+            &[
+                plaintext_commit.to_bytes(),
+                mask_commit.to_bytes(),
+                mask_ciphertext.to_bytes(),
+                reduced_mask.to_bytes(),
+            ]
+            .concat(),
+        );
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        paillier::DecryptionKey, ring_pedersen::VerifiedRingPedersen, utils::testing::init_testing,
+        zkp::BadContext,
+    };
+
+    fn transcript() -> Transcript {
+        Transcript::new(b"PiDecProof Test")
+    }
+
+    /// Generate a [`PiDecProof`] about the encryption of a random `ℓ'`-bit
+    /// plaintext, claiming its plaintext is congruent to `y + offset mod q`,
+    /// and pass it to the `test_code` closure along with the input.
+    fn with_random_proof<R: RngCore + CryptoRng>(
+        rng: &mut R,
+        offset: u64,
+        mut test_code: impl FnMut(PiDecProof, PiDecInput) -> Result<()>,
+    ) -> Result<()> {
+        let (decryption_key, _, _) = DecryptionKey::new(rng).unwrap();
+        let pk = decryption_key.encryption_key();
+        let setup_params = VerifiedRingPedersen::gen(rng, &())?;
+
+        let y = random_plusminus_by_size(rng, ELL_PRIME);
+        let (C, rho) = pk.encrypt(rng, &y).unwrap();
+        let x = (&y + BigNumber::from(offset)).nmod(&k256_order());
+
+        let input = PiDecInput::new(setup_params.scheme(), &pk, &C, &x);
+        let proof = PiDecProof::prove(
+            input,
+            PiDecSecret::new(&y, &rho),
+            &(),
+            &mut transcript(),
+            rng,
+        )?;
+        test_code(proof, input)
+    }
+
+    #[test]
+    fn pidec_proof_verifies() -> Result<()> {
+        let mut rng = init_testing();
+        with_random_proof(&mut rng, 0, |proof, input| {
+            proof.verify(input, &(), &mut transcript())
+        })
+    }
+
+    #[test]
+    fn pidec_proof_context_must_be_correct() -> Result<()> {
+        let mut rng = init_testing();
+        with_random_proof(&mut rng, 0, |proof, input| {
+            assert!(proof
+                .verify(input, &BadContext {}, &mut transcript())
+                .is_err());
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn pidec_proof_requires_correct_reduced_plaintext() -> Result<()> {
+        let mut rng = init_testing();
+        with_random_proof(&mut rng, 1, |proof, input| {
+            assert!(proof.verify(input, &(), &mut transcript()).is_err());
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn pidec_proof_requires_correct_setup_parameters() -> Result<()> {
+        let mut rng = init_testing();
+        let bad_setup_params = VerifiedRingPedersen::gen(&mut rng, &())?;
+        with_random_proof(&mut rng, 0, |proof, input| {
+            let bad_input = PiDecInput::new(
+                bad_setup_params.scheme(),
+                input.encryption_key,
+                input.ciphertext,
+                input.reduced_plaintext,
+            );
+            assert!(proof.verify(bad_input, &(), &mut transcript()).is_err());
+            Ok(())
+        })
+    }
+}
@@ -34,6 +34,7 @@
 
 use crate::{
     errors::*,
+    parallel,
     utils::*,
     zkp::{Proof, ProofContext},
 };
@@ -127,30 +128,33 @@ impl Proof for PiModProof {
 
         Self::fill_transcript(transcript, context, &input, &w)?;
 
-        let elements = (0..LAMBDA)
-            .map(|_| {
-                let y = positive_challenge_from_transcript(transcript, input.modulus)?;
-                let (a, b, mut x) = y_prime_combinations(&w, &y, secret.p, secret.q)?;
-
-                let phi_n = (secret.p - 1) * (secret.q - 1);
-                let exp = input.modulus.invert(&phi_n).ok_or_else(|| {
-                    error!("Could not invert N");
-                    InternalError::InternalInvariantFailed
-                })?;
-                let z = modpow(&y, &exp, input.modulus);
-                let fourth_root_y = x.pop().ok_or_else(|| {
-                    error!("Expected to get a fourth root, but did not.");
-                    InternalError::InternalInvariantFailed
-                });
-                Ok(PiModProofElements {
-                    fourth_root: fourth_root_y?,
-                    sign_exponent: a,
-                    jacobi_exponent: b,
-                    challenge_secret_link: z,
-                    challenge: y,
-                })
+        let challenges = (0..LAMBDA)
+            .map(|_| positive_challenge_from_transcript(transcript, input.modulus))
+            .collect::<Result<Vec<_>>>()?;
+        // The elements for each challenge are independent of each other.
+        let elements = parallel::map(challenges, |y| -> Result<_> {
+            let (a, b, mut x) = y_prime_combinations(&w, &y, secret.p, secret.q)?;
+
+            let phi_n = (secret.p - 1) * (secret.q - 1);
+            let exp = input.modulus.invert(&phi_n).ok_or_else(|| {
+                error!("Could not invert N");
+                InternalError::InternalInvariantFailed
+            })?;
+            let z = modpow(&y, &exp, input.modulus);
+            let fourth_root_y = x.pop().ok_or_else(|| {
+                error!("Expected to get a fourth root, but did not.");
+                InternalError::InternalInvariantFailed
+            });
+            Ok(PiModProofElements {
+                fourth_root: fourth_root_y?,
+                sign_exponent: a,
+                jacobi_exponent: b,
+                challenge_secret_link: z,
+                challenge: y,
             })
-            .collect::<Result<Vec<PiModProofElements>>>()?;
+        })
+        .into_iter()
+        .collect::<Result<Vec<PiModProofElements>>>()?;
 
         let proof = Self {
             random_jacobi_one: w,
@@ -200,14 +204,17 @@ impl Proof for PiModProof {
         }
         Self::fill_transcript(transcript, context, &input, &self.random_jacobi_one)?;
 
+        // First, check that each y came from the Fiat-Shamir transcript...
         for elements in &self.elements {
-            // First, check that y came from Fiat-Shamir transcript
             let y = positive_challenge_from_transcript(transcript, input.modulus)?;
             if y != elements.challenge {
                 error!("y does not match Fiat-Shamir challenge");
                 return Err(InternalError::ProtocolError(None));
             }
+        }
 
+        // ... and then check the elements, which are independent of each other.
+        parallel::map(self.elements.iter().collect::<Vec<_>>(), |elements| {
             let y_candidate = modpow(
                 &elements.challenge_secret_link,
                 input.modulus,
@@ -239,9 +246,10 @@ impl Proof for PiModProof {
                 error!("x^4 != y' (mod N)");
                 return Err(InternalError::ProtocolError(None));
             }
-        }
-
-        Ok(())
+            Ok(())
+        })
+        .into_iter()
+        .collect()
     }
 }
 
@@ -0,0 +1,371 @@
+// Copyright (c) 2022-2023 Bolt Labs Holdings, Inc
+//
+// This source code is licensed under both the MIT license found in the
+// LICENSE-MIT file in the root directory of this source tree and the Apache
+// License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+// of this source tree.
+
+//! Implements a zero-knowledge proof that a Paillier ciphertext encrypts the
+//! product of the plaintexts of two other ciphertexts, where the prover knows
+//! the plaintext of one of them.
+//!
+//! More precisely, given ciphertexts `X = enc(x; ρₓ)`, `Y` and `C`, all under
+//! the prover's encryption key, the prover shows it knows `x`, `ρₓ` and `ρ`
+//! such that `C = Y^x ρ^N mod N²`.
+//!
+//! The proof is defined in Figure 29 of CGGMP[^cite], and uses a standard
+//! Fiat-Shamir transformation to make the proof non-interactive. Unlike the
+//! paper, which samples the mask `ɑ` from `ℤ_N`, we sample it from `± 2^{ℓ +
+//! ε}`; since `x` is always an element of the curve's scalar field, this
+//! hides `x` statistically.
+//!
+//! [^cite]: Ran Canetti, Rosario Gennaro, Steven Goldfeder, Nikolaos Makriyannis, and Udi Peled.
+//! UC Non-Interactive, Proactive, Threshold ECDSA with Identifiable Aborts.
+//! [EPrint archive, 2021](https://eprint.iacr.org/2021/060.pdf).
+
+use crate::{
+    errors::*,
+    paillier::{Ciphertext, EncryptionKey, MaskedNonce, Nonce},
+    parameters::{ELL, EPSILON},
+    utils::{plusminus_challenge_from_transcript, random_plusminus_by_size},
+    zkp::{Proof, ProofContext},
+};
+use libpaillier::unknown_order::BigNumber;
+use merlin::Transcript;
+use rand::{CryptoRng, RngCore};
+use serde::{Deserialize, Serialize};
+use std::fmt::Debug;
+use tracing::error;
+
+/// Proof that a ciphertext encrypts the product of the plaintexts of two other
+/// ciphertexts.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct PiMulProof {
+    /// Mask applied to the multiplicand ciphertext (`A` in the paper).
+    product_mask: Ciphertext,
+    /// Encryption of the mask value (`B` in the paper).
+    mask_ciphertext: Ciphertext,
+    /// Fiat-Shamir challenge (`e` in the paper).
+    challenge: BigNumber,
+    /// Response binding the (secret) multiplier with the mask value (`z` in
+    /// the paper).
+    plaintext_response: BigNumber,
+    /// Response binding the product's nonce with the nonce used in
+    /// [`PiMulProof::product_mask`] (`u` in the paper).
+    product_nonce_response: MaskedNonce,
+    /// Response binding the multiplier's nonce with the nonce used in
+    /// [`PiMulProof::mask_ciphertext`] (`v` in the paper).
+    multiplier_nonce_response: MaskedNonce,
+}
+
+/// Common input and setup parameters known to both the prover and the verifier.
+///
+/// Copying/Cloning references is harmless and sometimes necessary. So we
+/// implement Clone and Copy for this type.
+#[derive(Serialize, Clone, Copy)]
+pub(crate) struct PiMulInput<'a> {
+    /// The prover's encryption key (`N` in the paper).
+    encryption_key: &'a EncryptionKey,
+    /// Encryption of the (secret) multiplier (`X` in the paper).
+    multiplier: &'a Ciphertext,
+    /// The multiplicand ciphertext (`Y` in the paper).
+    multiplicand: &'a Ciphertext,
+    /// The claimed product (`C` in the paper).
+    product: &'a Ciphertext,
+}
+
+impl<'a> PiMulInput<'a> {
+    /// Collect common input for proving or verifying a [`PiMulProof`] that
+    /// `product` encrypts the product of the plaintexts of `multiplier` and
+    /// `multiplicand`, all under the prover's `encryption_key`.
+    pub(crate) fn new(
+        prover_encryption_key: &'a EncryptionKey,
+        multiplier: &'a Ciphertext,
+        multiplicand: &'a Ciphertext,
+        product: &'a Ciphertext,
+    ) -> Self {
+        Self {
+            encryption_key: prover_encryption_key,
+            multiplier,
+            multiplicand,
+            product,
+        }
+    }
+}
+
+/// The prover's secret knowledge.
+pub(crate) struct PiMulSecret<'a> {
+    /// The plaintext of the multiplier ciphertext (`x` in the paper).
+    multiplier: &'a BigNumber,
+    /// The nonce of the multiplier ciphertext (`ρₓ` in the paper).
+    multiplier_nonce: &'a Nonce,
+    /// The nonce applied to the product ciphertext (`ρ` in the paper).
+    product_nonce: &'a Nonce,
+}
+
+impl Debug for PiMulSecret<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("pimul::PiMulSecret")
+            .field("multiplier", &"[redacted]")
+            .field("multiplier_nonce", &"[redacted]")
+            .field("product_nonce", &"[redacted]")
+            .finish()
+    }
+}
+
+impl<'a> PiMulSecret<'a> {
+    /// Collect prover secrets for proving a [`PiMulProof`].
+    pub(crate) fn new(
+        multiplier: &'a BigNumber,
+        multiplier_nonce: &'a Nonce,
+        product_nonce: &'a Nonce,
+    ) -> Self {
+        Self {
+            multiplier,
+            multiplier_nonce,
+            product_nonce,
+        }
+    }
+}
+
+impl Proof for PiMulProof {
+    type CommonInput<'a> = PiMulInput<'a>;
+    type ProverSecret<'a> = PiMulSecret<'a>;
+
+    #[cfg_attr(feature = "flame_it", flame("PiMulProof"))]
+    fn prove<R: RngCore + CryptoRng>(
+        input: Self::CommonInput<'_>,
+        secret: Self::ProverSecret<'_>,
+        context: &impl ProofContext,
+        transcript: &mut Transcript,
+        rng: &mut R,
+    ) -> Result<Self> {
+        // Sample a mask for the multiplier (aka `ɑ`).
+        let mask = random_plusminus_by_size(rng, ELL + EPSILON);
+
+        // Multiply the multiplicand by the mask and rerandomize it (aka `A, r`).
+        let (zero_ciphertext, product_nonce_mask) = input
+            .encryption_key
+            .encrypt(rng, &BigNumber::zero())
+            .map_err(|_| InternalError::InternalInvariantFailed)?;
+        let product_mask = input
+            .encryption_key
+            .multiply_and_add(&mask, input.multiplicand, &zero_ciphertext)
+            .map_err(|_| InternalError::InternalInvariantFailed)?;
+        // Encrypt the mask (aka `B, s`).
+        let (mask_ciphertext, multiplier_nonce_mask) = input
+            .encryption_key
+            .encrypt(rng, &mask)
+            .map_err(|_| InternalError::InternalInvariantFailed)?;
+
+        Self::fill_transcript(transcript, context, &input, &product_mask, &mask_ciphertext)?;
+        let challenge = plusminus_challenge_from_transcript(transcript)?;
+
+        // Form the responses (aka `z`, `u` and `v`).
+        let plaintext_response = &mask + &challenge * secret.multiplier;
+        let product_nonce_response =
+            input
+                .encryption_key
+                .mask(secret.product_nonce, &product_nonce_mask, &challenge);
+        let multiplier_nonce_response =
+            input
+                .encryption_key
+                .mask(secret.multiplier_nonce, &multiplier_nonce_mask, &challenge);
+
+        Ok(Self {
+            product_mask,
+            mask_ciphertext,
+            challenge,
+            plaintext_response,
+            product_nonce_response,
+            multiplier_nonce_response,
+        })
+    }
+
+    #[cfg_attr(feature = "flame_it", flame("PiMulProof"))]
+    fn verify(
+        self,
+        input: Self::CommonInput<'_>,
+        context: &impl ProofContext,
+        transcript: &mut Transcript,
+    ) -> Result<()> {
+        Self::fill_transcript(
+            transcript,
+            context,
+            &input,
+            &self.product_mask,
+            &self.mask_ciphertext,
+        )?;
+        let challenge = plusminus_challenge_from_transcript(transcript)?;
+        if challenge != self.challenge {
+            error!("Fiat-Shamir consistency check failed");
+            return Err(InternalError::ProtocolError(None));
+        }
+
+        // Check that `Y^z u^N = A C^e`.
+        let product_is_valid = {
+            let rerandomizer = input
+                .encryption_key
+                .encrypt_with_nonce(&BigNumber::zero(), &self.product_nonce_response)
+                .map_err(|_| InternalError::ProtocolError(None))?;
+            let lhs = input
+                .encryption_key
+                .multiply_and_add(&self.plaintext_response, input.multiplicand, &rerandomizer)
+                .map_err(|_| InternalError::ProtocolError(None))?;
+            let rhs = input
+                .encryption_key
+                .multiply_and_add(&self.challenge, input.product, &self.product_mask)
+                .map_err(|_| InternalError::ProtocolError(None))?;
+            lhs == rhs
+        };
+        if !product_is_valid {
+            error!("product check (first equality check) failed");
+            return Err(InternalError::ProtocolError(None));
+        }
+
+        // Check that `(1 + N)^z v^N = B X^e`.
+        let multiplier_is_valid = {
+            let lhs = input
+                .encryption_key
+                .encrypt_with_nonce(&self.plaintext_response, &self.multiplier_nonce_response)
+                .map_err(|_| InternalError::ProtocolError(None))?;
+            let rhs = input
+                .encryption_key
+                .multiply_and_add(&self.challenge, input.multiplier, &self.mask_ciphertext)
+                .map_err(|_| InternalError::ProtocolError(None))?;
+            lhs == rhs
+        };
+        if !multiplier_is_valid {
+            error!("multiplier check (second equality check) failed");
+            return Err(InternalError::ProtocolError(None));
+        }
+
+        Ok(())
+    }
+}
+
+impl PiMulProof {
+    /// Update the [`Transcript`] with all the commitment values used in the
+    /// proof.
+    fn fill_transcript(
+        transcript: &mut Transcript,
+        context: &impl ProofContext,
+        input: &PiMulInput,
+        product_mask: &Ciphertext,
+        mask_ciphertext: &Ciphertext,
+    ) -> Result<()> {
+        transcript.append_message(b"PiMul ProofContext", &context.as_bytes()?);
+        transcript.append_message(b"PiMul CommonInput", &serialize!(&input)?);
+        transcript.append_message(
+            b"(A, B)",
+            &[product_mask.to_bytes(), mask_ciphertext.to_bytes()].concat(),
+        );
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        paillier::DecryptionKey,
+        utils::{k256_order, random_positive_bn, testing::init_testing},
+        zkp::BadContext,
+    };
+
+    fn transcript() -> Transcript {
+        Transcript::new(b"PiMulProof Test")
+    }
+
+    /// Generate a [`PiMulProof`] about `Y^x` for random `x` and `Y`, and pass
+    /// it to the `test_code` closure along with the input.
+    fn with_random_proof<R: RngCore + CryptoRng>(
+        rng: &mut R,
+        mut test_code: impl FnMut(PiMulProof, PiMulInput) -> Result<()>,
+    ) -> Result<()> {
+        let (decryption_key, _, _) = DecryptionKey::new(rng).unwrap();
+        let pk = decryption_key.encryption_key();
+
+        let x = random_positive_bn(rng, &k256_order());
+        let y = random_positive_bn(rng, &k256_order());
+        let (X, rho_x) = pk.encrypt(rng, &x).unwrap();
+        let (Y, _) = pk.encrypt(rng, &y).unwrap();
+        let (zero, rho) = pk.encrypt(rng, &BigNumber::zero()).unwrap();
+        let C = pk.multiply_and_add(&x, &Y, &zero).unwrap();
+
+        let input = PiMulInput::new(&pk, &X, &Y, &C);
+        let proof = PiMulProof::prove(
+            input,
+            PiMulSecret::new(&x, &rho_x, &rho),
+            &(),
+            &mut transcript(),
+            rng,
+        )?;
+        test_code(proof, input)
+    }
+
+    #[test]
+    fn pimul_proof_verifies() -> Result<()> {
+        let mut rng = init_testing();
+        with_random_proof(&mut rng, |proof, input| {
+            proof.verify(input, &(), &mut transcript())
+        })
+    }
+
+    #[test]
+    fn pimul_proof_context_must_be_correct() -> Result<()> {
+        let mut rng = init_testing();
+        with_random_proof(&mut rng, |proof, input| {
+            assert!(proof
+                .verify(input, &BadContext {}, &mut transcript())
+                .is_err());
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn pimul_proof_requires_correct_product() -> Result<()> {
+        let mut rng = init_testing();
+        let (decryption_key, _, _) = DecryptionKey::new(&mut rng).unwrap();
+        let pk = decryption_key.encryption_key();
+
+        let x = random_positive_bn(&mut rng, &k256_order());
+        let y = random_positive_bn(&mut rng, &k256_order());
+        let (X, rho_x) = pk.encrypt(&mut rng, &x).unwrap();
+        let (Y, _) = pk.encrypt(&mut rng, &y).unwrap();
+
+        // The "product" uses a different multiplier than the one encrypted in `X`
+        let wrong_x = &x + 1;
+        let (zero, rho) = pk.encrypt(&mut rng, &BigNumber::zero()).unwrap();
+        let bad_C = pk.multiply_and_add(&wrong_x, &Y, &zero).unwrap();
+
+        let input = PiMulInput::new(&pk, &X, &Y, &bad_C);
+        for multiplier in [&x, &wrong_x] {
+            let proof = PiMulProof::prove(
+                input,
+                PiMulSecret::new(multiplier, &rho_x, &rho),
+                &(),
+                &mut transcript(),
+                &mut rng,
+            )?;
+            assert!(proof.verify(input, &(), &mut transcript()).is_err());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn pimul_proof_requires_correct_ciphertexts() -> Result<()> {
+        let mut rng = init_testing();
+        with_random_proof(&mut rng, |proof, input| {
+            // Swap the multiplier and multiplicand
+            let bad_input = PiMulInput::new(
+                input.encryption_key,
+                input.multiplicand,
+                input.multiplier,
+                input.product,
+            );
+            assert!(proof.verify(bad_input, &(), &mut transcript()).is_err());
+            Ok(())
+        })
+    }
+}
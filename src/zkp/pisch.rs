@@ -29,9 +29,11 @@
 use crate::{
     errors::*,
     messages::{KeygenMessageType, Message, MessageType},
+    protocol::ParticipantIdentifier,
     utils::{self, k256_order, positive_challenge_from_transcript, random_positive_bn},
     zkp::{Proof, ProofContext},
 };
+use k256::Scalar;
 use libpaillier::unknown_order::BigNumber;
 use merlin::Transcript;
 use rand::{CryptoRng, RngCore};
@@ -42,7 +44,7 @@ use utils::CurvePoint;
 
 /// Proof of knowledge of discrete logarithm of a group element which is the
 /// commitment to the secret.
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub(crate) struct PiSchProof {
     /// Commitment to the secret (`A` in the paper).
     commitment: CurvePoint,
@@ -201,16 +203,84 @@ impl PiSchProof {
         };
         Ok(proof)
     }
+
+    /// Verify a batch of proofs at once.
+    ///
+    /// Each entry holds the participant that sent a proof, the proof itself,
+    /// and the common input and transcript that it would be verified with
+    /// using [`verify()`](Proof::verify). The
+    /// Fiat-Shamir challenges are checked one by one, but the verification
+    /// equations `g^z = A * X^e` are each multiplied by a random weight and
+    /// combined into a single multi-scalar multiplication. If any proof is
+    /// invalid, the combined equation only holds with negligible probability.
+    ///
+    /// If the batch doesn't verify, the proofs are verified one by one to
+    /// find an invalid one, and its sender is blamed.
+    pub(crate) fn verify_batch<R: RngCore + CryptoRng>(
+        batch: Vec<(ParticipantIdentifier, Self, CommonInput<'_>, Transcript)>,
+        context: &impl ProofContext,
+        rng: &mut R,
+    ) -> Result<()> {
+        let q = k256_order();
+        let mut generator_scalar = Scalar::ZERO;
+        let mut terms = Vec::with_capacity(2 * batch.len() + 1);
+        for (pid, proof, input, transcript) in &batch {
+            // First check Fiat-Shamir challenge consistency
+            let mut transcript = transcript.clone();
+            Self::fill_transcript(&mut transcript, context, input, &proof.commitment)?;
+            let challenge = positive_challenge_from_transcript(&mut transcript, &q)?;
+            if challenge != proof.challenge {
+                error!("Fiat-Shamir consistency check failed");
+                return Err(InternalError::ProtocolError(Some(*pid)));
+            }
+
+            // Then add `weight * (z * g - A - e * X)` to the combined equation
+            let weight = utils::bn_to_scalar(&random_positive_bn(rng, &q))?;
+            let challenge = utils::bn_to_scalar(&proof.challenge)?;
+            generator_scalar += weight * utils::bn_to_scalar(&proof.response)?;
+            terms.push((-weight, proof.commitment));
+            terms.push((-(weight * challenge), *input.x_commitment));
+        }
+        terms.push((generator_scalar, CurvePoint::GENERATOR));
+
+        if CurvePoint::multiscalar_mul(&terms) == CurvePoint::IDENTITY {
+            return Ok(());
+        }
+
+        for (pid, proof, input, mut transcript) in batch {
+            proof
+                .verify(input, context, &mut transcript)
+                .map_err(|err| match err {
+                    InternalError::ProtocolError(_) => InternalError::ProtocolError(Some(pid)),
+                    err => err,
+                })?;
+        }
+        error!("Batch verification failed, but each proof verified individually");
+        Err(InternalError::InternalInvariantFailed)
+    }
+
     pub(crate) fn from_message(message: &Message) -> Result<Self> {
         message.check_type(MessageType::Keygen(KeygenMessageType::R3Proof))?;
         let pisch_proof: PiSchProof = deserialize!(&message.unverified_bytes)?;
-        if pisch_proof.challenge >= k256_order() {
+        pisch_proof.check_ranges()?;
+        Ok(pisch_proof)
+    }
+
+    /// Get the commitment to the mask used in the proof (`A` in the paper).
+    pub(crate) fn commitment(&self) -> &CurvePoint {
+        &self.commitment
+    }
+
+    /// Check that the challenge and response of a deserialized proof are in
+    /// range.
+    pub(crate) fn check_ranges(&self) -> Result<()> {
+        if self.challenge >= k256_order() {
             return Err(InternalError::ProtocolError(None));
         }
-        if pisch_proof.response >= k256_order() {
+        if self.response >= k256_order() {
             return Err(InternalError::ProtocolError(None));
         }
-        Ok(pisch_proof)
+        Ok(())
     }
     fn fill_transcript(
         transcript: &mut Transcript,
@@ -465,4 +535,63 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn batch_verification_works() -> Result<()> {
+        let mut rng = init_testing();
+        let q = k256_order();
+        let g = CurvePoint::GENERATOR;
+
+        let secrets = std::iter::repeat_with(|| random_positive_bn(&mut rng, &q))
+            .take(5)
+            .collect::<Vec<_>>();
+        let commits = secrets
+            .iter()
+            .map(|x| g.multiply_by_bignum(x))
+            .collect::<Result<Vec<_>>>()?;
+        let proofs = secrets
+            .iter()
+            .zip(&commits)
+            .map(|(x, x_commit)| {
+                PiSchProof::prove(
+                    CommonInput::new(x_commit),
+                    ProverSecret::new(x),
+                    &(),
+                    &mut transcript(),
+                    &mut rng,
+                )
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let pids = std::iter::repeat_with(|| ParticipantIdentifier::random(&mut rng))
+            .take(5)
+            .collect::<Vec<_>>();
+        let batch = |proofs: Vec<PiSchProof>| {
+            proofs
+                .into_iter()
+                .zip(&commits)
+                .zip(&pids)
+                .map(|((proof, x_commit), pid)| {
+                    (*pid, proof, CommonInput::new(x_commit), transcript())
+                })
+                .collect::<Vec<_>>()
+        };
+
+        // A batch of valid proofs verifies
+        PiSchProof::verify_batch(batch(proofs.clone()), &(), &mut rng)?;
+        PiSchProof::verify_batch(vec![], &(), &mut rng)?;
+
+        // A batch with a single bad response doesn't, and its sender is blamed
+        let mut bad_proofs = proofs.clone();
+        bad_proofs[3].response = random_positive_bn(&mut rng, &q);
+        assert_eq!(
+            PiSchProof::verify_batch(batch(bad_proofs), &(), &mut rng),
+            Err(InternalError::ProtocolError(Some(pids[3])))
+        );
+
+        // Neither does a batch where two proofs were swapped
+        let mut swapped = proofs;
+        swapped.swap(0, 1);
+        assert!(PiSchProof::verify_batch(batch(swapped), &(), &mut rng).is_err());
+        Ok(())
+    }
 }